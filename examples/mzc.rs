@@ -0,0 +1,437 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// Standalone DHCP client:
+//
+//      mzc daemon <iface> [--script <path>] [--lease-dir <dir>]
+//
+// Runs DHCPv4 and DHCPv6 on the interface until SIGTERM or SIGINT, which
+// releases the leases. Leases are stored in `<lease-dir>/<iface>.lease4` and
+// `<lease-dir>/<iface>.lease6` and requested again on next start. The script
+// is invoked with dhclient-script compatible environment variables on every
+// lease change, and is in charge of applying the leases to the system.
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures::stream::StreamExt;
+use tokio::signal::unix::{signal, SignalKind};
+
+use mozim::{
+    DhcpDualStackClient, DhcpError, DhcpStateEvent, DhcpV4ClasslessRoute,
+    DhcpV4Config, DhcpV4Lease, DhcpV4LeaseBuilder, DhcpV4State, DhcpV6Config,
+    DhcpV6IaType, DhcpV6Lease, DhcpV6LeaseBuilder, DhcpV6State, ErrorKind,
+};
+
+const DEFAULT_LEASE_DIR: &str = "/var/lib/mozim";
+
+const USAGE: &str =
+    "Usage: mzc daemon <iface> [--script <path>] [--lease-dir <dir>]";
+
+#[derive(Debug)]
+struct DaemonConf {
+    iface: String,
+    script: Option<PathBuf>,
+    lease_dir: PathBuf,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    enable_log();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let conf = match parse_args(&args) {
+        Some(c) => c,
+        None => {
+            eprintln!("{USAGE}");
+            std::process::exit(1);
+        }
+    };
+    run_daemon(conf).await
+}
+
+fn parse_args(args: &[String]) -> Option<DaemonConf> {
+    if args.first().map(String::as_str) != Some("daemon") {
+        return None;
+    }
+    let mut conf = DaemonConf {
+        iface: args.get(1).filter(|i| !i.starts_with('-'))?.to_string(),
+        script: None,
+        lease_dir: PathBuf::from(DEFAULT_LEASE_DIR),
+    };
+    let mut remain = args[2..].iter();
+    while let Some(arg) = remain.next() {
+        match arg.as_str() {
+            "--script" => conf.script = Some(PathBuf::from(remain.next()?)),
+            "--lease-dir" => conf.lease_dir = PathBuf::from(remain.next()?),
+            _ => return None,
+        }
+    }
+    Some(conf)
+}
+
+async fn run_daemon(
+    conf: DaemonConf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(&conf.lease_dir)?;
+    let lease4_path = conf.lease_dir.join(format!("{}.lease4", conf.iface));
+    let lease6_path = conf.lease_dir.join(format!("{}.lease6", conf.iface));
+
+    let mut v4_config = DhcpV4Config::new(&conf.iface);
+    v4_config.set_timeout(0);
+    let mut v6_config =
+        DhcpV6Config::new(&conf.iface, DhcpV6IaType::NonTemporaryAddresses);
+    v6_config.set_timeout(0);
+
+    let v4_lease = load_lease(&lease4_path).and_then(|l| env_to_v4_lease(&l));
+    let v6_lease = load_lease(&lease6_path).and_then(|l| env_to_v6_lease(&l));
+    if v4_lease.is_some() {
+        run_script(&conf, "PREINIT", &[]);
+    }
+    if v6_lease.is_some() {
+        run_script(&conf, "PREINIT6", &[]);
+    }
+
+    let mut stream =
+        DhcpDualStackClient::init(v4_config, v6_config, v4_lease, v6_lease)?
+            .into_state_stream();
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+    let mut v4_reason = "BOUND";
+    let mut v6_reason = "BOUND6";
+
+    loop {
+        let event = tokio::select! {
+            _ = sigterm.recv() => break,
+            _ = sigint.recv() => break,
+            event = stream.next() => event,
+        };
+        match event {
+            Some(Ok(DhcpStateEvent::V4(state))) => match state {
+                DhcpV4State::Bound(lease) => {
+                    let env = v4_lease_to_env(&lease);
+                    save_lease(&lease4_path, &env);
+                    run_script(&conf, v4_reason, &prefix_env("new_", &env));
+                    v4_reason = "BOUND";
+                }
+                DhcpV4State::Renewing => v4_reason = "RENEW",
+                DhcpV4State::Rebinding => v4_reason = "REBIND",
+                DhcpV4State::Nacked(lease) => {
+                    let env = lease.as_ref().map(v4_lease_to_env);
+                    lease_lost(&conf, &lease4_path, "EXPIRE", env);
+                    v4_reason = "BOUND";
+                }
+                DhcpV4State::Expired(lease) => {
+                    let env = Some(v4_lease_to_env(&lease));
+                    lease_lost(&conf, &lease4_path, "EXPIRE", env);
+                    v4_reason = "BOUND";
+                }
+                _ => (),
+            },
+            Some(Ok(DhcpStateEvent::V6(state))) => match state {
+                DhcpV6State::Bound(lease) => {
+                    let env = v6_lease_to_env(&lease);
+                    save_lease(&lease6_path, &env);
+                    run_script(&conf, v6_reason, &prefix_env("new_", &env));
+                    v6_reason = "BOUND6";
+                }
+                DhcpV6State::Renewing => v6_reason = "RENEW6",
+                DhcpV6State::Rebinding => v6_reason = "REBIND6",
+                DhcpV6State::Nacked(lease) => {
+                    let env = lease.as_ref().map(v6_lease_to_env);
+                    lease_lost(&conf, &lease6_path, "EXPIRE6", env);
+                    v6_reason = "BOUND6";
+                }
+                DhcpV6State::Expired(lease) => {
+                    let env = Some(v6_lease_to_env(&lease));
+                    lease_lost(&conf, &lease6_path, "EXPIRE6", env);
+                    v6_reason = "BOUND6";
+                }
+                _ => (),
+            },
+            Some(Ok(_)) => (),
+            Some(Err(e)) if is_fatal(&e) => {
+                log::error!("{e}, quitting");
+                stream.close();
+                return Err(e.into());
+            }
+            Some(Err(e)) => log::error!("{e}"),
+            None => break,
+        }
+    }
+
+    log::info!("Releasing leases of {}", conf.iface);
+    let v4_env = stream.v4_lease().map(v4_lease_to_env);
+    let v6_env = stream.v6_lease().map(v6_lease_to_env);
+    if let Err(e) = stream.release() {
+        log::error!("Failed to release leases: {e}");
+    }
+    stream.close();
+    if let Some(env) = v4_env {
+        remove_lease(&lease4_path);
+        run_script(&conf, "RELEASE", &prefix_env("old_", &env));
+    }
+    if let Some(env) = v6_env {
+        remove_lease(&lease6_path);
+        run_script(&conf, "RELEASE6", &prefix_env("old_", &env));
+    }
+    Ok(())
+}
+
+// Errors the client cannot recover from by retrying
+fn is_fatal(e: &DhcpError) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::Bug
+            | ErrorKind::InvalidArgument
+            | ErrorKind::PermissionDenied
+            | ErrorKind::AddressInUse
+            | ErrorKind::RawSocketRequired
+    )
+}
+
+fn lease_lost(
+    conf: &DaemonConf,
+    lease_path: &Path,
+    reason: &str,
+    env: Option<Vec<(String, String)>>,
+) {
+    remove_lease(lease_path);
+    run_script(conf, reason, &prefix_env("old_", &env.unwrap_or_default()));
+}
+
+fn run_script(conf: &DaemonConf, reason: &str, env: &[(String, String)]) {
+    let script = match conf.script.as_ref() {
+        Some(s) => s,
+        None => return,
+    };
+    log::info!("Running {} with reason {reason}", script.display());
+    match Command::new(script)
+        .env("reason", reason)
+        .env("interface", &conf.iface)
+        .envs(env.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+        .status()
+    {
+        Ok(status) if !status.success() => {
+            log::warn!("{} exited with {status}", script.display())
+        }
+        Ok(_) => (),
+        Err(e) => log::error!("Failed to run {}: {e}", script.display()),
+    }
+}
+
+fn prefix_env(prefix: &str, env: &[(String, String)]) -> Vec<(String, String)> {
+    env.iter()
+        .map(|(k, v)| (format!("{prefix}{k}"), v.clone()))
+        .collect()
+}
+
+fn join_addrs<T: std::fmt::Display>(addrs: &[T]) -> String {
+    addrs
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// Variable names without `new_` or `old_` prefix of dhclient-script
+fn v4_lease_to_env(lease: &DhcpV4Lease) -> Vec<(String, String)> {
+    let mut env = vec![
+        ("ip_address", lease.yiaddr.to_string()),
+        ("subnet_mask", lease.subnet_mask.to_string()),
+        ("dhcp_server_identifier", lease.srv_id.to_string()),
+        ("dhcp_lease_time", lease.lease_time.to_string()),
+        ("dhcp_renewal_time", lease.t1.to_string()),
+        ("dhcp_rebinding_time", lease.t2.to_string()),
+        ("expiry", (now() + u64::from(lease.lease_time)).to_string()),
+    ];
+    if let Some(addr) = lease.broadcast_addr {
+        env.push(("broadcast_address", addr.to_string()));
+    }
+    if let Some(addrs) = lease.gateways.as_deref() {
+        env.push(("routers", join_addrs(addrs)));
+    }
+    if let Some(addrs) = lease.dns_srvs.as_deref() {
+        env.push(("domain_name_servers", join_addrs(addrs)));
+    }
+    if let Some(addrs) = lease.ntp_srvs.as_deref() {
+        env.push(("ntp_servers", join_addrs(addrs)));
+    }
+    if let Some(mtu) = lease.mtu {
+        env.push(("interface_mtu", mtu.to_string()));
+    }
+    if let Some(name) = lease.host_name.as_ref() {
+        env.push(("host_name", name.to_string()));
+    }
+    if let Some(name) = lease.domain_name.as_ref() {
+        env.push(("domain_name", name.to_string()));
+    }
+    if let Some(routes) = lease.classless_routes.as_deref() {
+        env.push(("rfc3442_classless_static_routes", rfc3442_routes(routes)));
+    }
+    env.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+// dhclient-script expects option 121 in wire format as decimal octets:
+// prefix length, significant octets of destination, then router, e.g.
+// `24 192 168 1 192 168 1 1`
+fn rfc3442_routes(routes: &[DhcpV4ClasslessRoute]) -> String {
+    let mut octets = Vec::new();
+    for route in routes {
+        octets.push(route.prefix_length);
+        let len = usize::from(route.prefix_length.min(32)).div_ceil(8);
+        octets.extend_from_slice(&route.destination.octets()[..len]);
+        octets.extend_from_slice(&route.router.octets());
+    }
+    octets
+        .iter()
+        .map(u8::to_string)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn v6_lease_to_env(lease: &DhcpV6Lease) -> Vec<(String, String)> {
+    let mut env = vec![
+        ("ip6_address", lease.addr.to_string()),
+        ("ip6_prefixlen", lease.prefix_len.to_string()),
+        ("dhcp6_server_id", hex_encode(&lease.srv_duid)),
+        ("dhcp6_client_id", hex_encode(&lease.cli_duid)),
+        ("iaid", lease.iaid.to_string()),
+        ("renew", lease.t1.to_string()),
+        ("rebind", lease.t2.to_string()),
+        ("preferred_life", lease.preferred_life.to_string()),
+        ("max_life", lease.valid_life.to_string()),
+        ("expiry", (now() + u64::from(lease.valid_life)).to_string()),
+    ];
+    if let Some(addrs) = lease.dns_srvs.as_deref() {
+        env.push(("dhcp6_name_servers", join_addrs(addrs)));
+    }
+    if let Some(domains) = lease.domain_list.as_deref() {
+        env.push(("dhcp6_domain_search", domains.join(" ")));
+    }
+    env.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+fn env_to_v4_lease(env: &HashMap<String, String>) -> Option<DhcpV4Lease> {
+    let get = |k: &str| env.get(k).map(String::as_str);
+    let addrs = |k: &str| -> Option<Vec<Ipv4Addr>> {
+        get(k)?.split(' ').map(|a| a.parse().ok()).collect()
+    };
+    let mut builder = DhcpV4LeaseBuilder::new(
+        get("ip_address")?.parse().ok()?,
+        get("subnet_mask")?.parse().ok()?,
+        get("dhcp_server_identifier")?.parse().ok()?,
+        get("dhcp_lease_time")?.parse().ok()?,
+    );
+    builder
+        .set_t1(get("dhcp_renewal_time")?.parse().ok()?)
+        .set_t2(get("dhcp_rebinding_time")?.parse().ok()?);
+    if let Some(addrs) = addrs("routers") {
+        builder.set_gateways(&addrs);
+    }
+    if let Some(addrs) = addrs("domain_name_servers") {
+        builder.set_dns_srvs(&addrs);
+    }
+    if let Some(addrs) = addrs("ntp_servers") {
+        builder.set_ntp_srvs(&addrs);
+    }
+    if let Some(mtu) = get("interface_mtu").and_then(|m| m.parse().ok()) {
+        builder.set_mtu(mtu);
+    }
+    if let Some(name) = get("host_name") {
+        builder.set_host_name(name);
+    }
+    if let Some(name) = get("domain_name") {
+        builder.set_domain_name(name);
+    }
+    builder.build().ok()
+}
+
+fn env_to_v6_lease(env: &HashMap<String, String>) -> Option<DhcpV6Lease> {
+    let get = |k: &str| env.get(k).map(String::as_str);
+    let mut builder = DhcpV6LeaseBuilder::new(
+        DhcpV6IaType::NonTemporaryAddresses,
+        get("ip6_address")?.parse().ok()?,
+        &hex_decode(get("dhcp6_server_id")?)?,
+        get("max_life")?.parse().ok()?,
+    );
+    builder
+        .set_prefix_len(get("ip6_prefixlen")?.parse().ok()?)
+        .set_cli_duid(&hex_decode(get("dhcp6_client_id")?)?)
+        .set_iaid(get("iaid")?.parse().ok()?)
+        .set_t1(get("renew")?.parse().ok()?)
+        .set_t2(get("rebind")?.parse().ok()?)
+        .set_preferred_life(get("preferred_life")?.parse().ok()?);
+    if let Some(addrs) = get("dhcp6_name_servers") {
+        let addrs: Vec<Ipv6Addr> = addrs
+            .split(' ')
+            .map(|a| a.parse().ok())
+            .collect::<Option<_>>()?;
+        builder.set_dns_srvs(&addrs);
+    }
+    if let Some(domains) = get("dhcp6_domain_search") {
+        builder.set_domain_list(&domains.split(' ').collect::<Vec<&str>>());
+    }
+    builder.build().ok()
+}
+
+fn hex_encode(data: &[u8]) -> String {
+    data.iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+fn hex_decode(data: &str) -> Option<Vec<u8>> {
+    data.split(':')
+        .map(|b| u8::from_str_radix(b, 16).ok())
+        .collect()
+}
+
+// Lease file contains `key=value` lines, expired lease is ignored.
+fn load_lease(path: &Path) -> Option<HashMap<String, String>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let env: HashMap<String, String> = content
+        .lines()
+        .filter_map(|l| l.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let expiry: u64 = env.get("expiry")?.parse().ok()?;
+    if expiry <= now() {
+        log::info!("Ignoring expired lease {}", path.display());
+        return None;
+    }
+    log::info!("Loaded lease {}", path.display());
+    Some(env)
+}
+
+fn save_lease(path: &Path, env: &[(String, String)]) {
+    let content: String =
+        env.iter().map(|(k, v)| format!("{k}={v}\n")).collect();
+    if let Err(e) = std::fs::write(path, content) {
+        log::error!("Failed to save lease {}: {e}", path.display());
+    }
+}
+
+fn remove_lease(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::error!("Failed to remove lease {}: {e}", path.display());
+        }
+    }
+}
+
+fn enable_log() {
+    env_logger::Builder::new()
+        .filter(Some("mozim"), log::LevelFilter::Info)
+        .filter(Some("mzc"), log::LevelFilter::Info)
+        .init();
+}
@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+// Wrapper of shared lease approval callback, allowing config to derive
+// `PartialEq` and `Debug`.
+#[derive(Clone)]
+pub(crate) struct DhcpLeaseApprover<T>(
+    pub(crate) Arc<dyn Fn(&T) -> bool + Send + Sync>,
+);
+
+impl<T> DhcpLeaseApprover<T> {
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub(crate) fn approve(&self, lease: &T) -> bool {
+        (self.0)(lease)
+    }
+}
+
+impl<T> std::fmt::Debug for DhcpLeaseApprover<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DhcpLeaseApprover({:p})", Arc::as_ptr(&self.0))
+    }
+}
+
+impl<T> PartialEq for DhcpLeaseApprover<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T> Eq for DhcpLeaseApprover<T> {}
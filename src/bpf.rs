@@ -1,7 +1,5 @@
 use crate::{DhcpError, ErrorKind};
 
-const DHCP_BPF_LEN: u16 = 11;
-
 // libc are setting these constant as u32 which make our life worse
 // as libc::sock_filter code is u16.
 const BPF_B: u16 = 0x10;
@@ -26,53 +24,62 @@ const IPPROTO_UDP: u32 = 17;
 
 const DHCPV4_DST_PORT: u32 = 68;
 const ETHER_TYPE_POS: u32 = 12;
-const IP_PROTO_POS: u32 = 23;
-const IP_FRAGMENT_POS: u32 = 20;
-const IP_HEADER_LEN_POS: u32 = 14;
 const ETHER_HEADER_LEN: u32 = 14;
+// Offsets within IPv4 header
+const IP_PROTO_POS: u32 = 9;
+const IP_FRAGMENT_POS: u32 = 6;
 const DST_PORT_IN_IP_POS: u32 = 2;
 
-const BPF_FILTER_RAW: [(u16, u8, u8, u32); DHCP_BPF_LEN as usize] = [
-    // Load protocol type to A
-    (BPF_LD | BPF_H | BPF_ABS, 0, 0, ETHER_TYPE_POS),
-    // Move on if ETHERTYPE_IP, otherwise drop package
-    (BPF_JMP | BPF_JEQ | BPF_K, 0, 8, ETHERTYPE_IP),
-    // Load IPv4 protocol type to A
-    (BPF_LD | BPF_B | BPF_ABS, 0, 0, IP_PROTO_POS),
-    // Move on if UDP, otherwise drop package
-    (BPF_JMP | BPF_JEQ | BPF_K, 0, 6, IPPROTO_UDP),
-    // Load IPv4 flag and fragment offset
-    (BPF_LD | BPF_H | BPF_ABS, 0, 0, IP_FRAGMENT_POS),
-    // Drop package which has MF(more fragment) set is 1 or is fragment
-    (BPF_JMP | BPF_JSET | BPF_K, 4, 0, 0x1fff),
-    // Store IP header length to X
-    (BPF_LDX | BPF_B | BPF_MSH, 0, 0, IP_HEADER_LEN_POS),
-    // Load UDP destination port number to A
-    (
-        BPF_LD | BPF_H | BPF_IND,
-        0,
-        0,
-        ETHER_HEADER_LEN + DST_PORT_IN_IP_POS,
-    ),
-    // Check whether destination port is DHCPV4_DST_PORT
-    (BPF_JMP | BPF_JEQ | BPF_K, 0, 1, DHCPV4_DST_PORT),
-    // Accept this package
-    (BPF_RET, 0, 0, u32::MAX),
-    // Drop this package
-    (BPF_RET, 0, 0, 0x00000000),
-];
-pub(crate) fn apply_dhcp_bpf(fd: libc::c_int) -> Result<(), DhcpError> {
-    let mut raw_filters = [libc::sock_filter {
-        code: 0,
-        jt: 0,
-        jf: 0,
-        k: 0,
-    }; DHCP_BPF_LEN as usize];
-    for (i, (code, jt, jf, k)) in BPF_FILTER_RAW.iter().enumerate() {
-        raw_filters[i].code = *code;
-        raw_filters[i].jt = *jt;
-        raw_filters[i].jf = *jf;
-        raw_filters[i].k = *k;
+// Socket bound to interface without link-layer header(e.g. PPP, wireguard)
+// receives IP package directly, hence no ethernet header to check or skip.
+fn gen_bpf_filter(with_eth_header: bool) -> Vec<(u16, u8, u8, u32)> {
+    let mut ret = Vec::new();
+    let ip_offset = if with_eth_header {
+        ret.extend_from_slice(&[
+            // Load protocol type to A
+            (BPF_LD | BPF_H | BPF_ABS, 0, 0, ETHER_TYPE_POS),
+            // Move on if ETHERTYPE_IP, otherwise drop package
+            (BPF_JMP | BPF_JEQ | BPF_K, 0, 8, ETHERTYPE_IP),
+        ]);
+        ETHER_HEADER_LEN
+    } else {
+        0
+    };
+    ret.extend_from_slice(&[
+        // Load IPv4 protocol type to A
+        (BPF_LD | BPF_B | BPF_ABS, 0, 0, ip_offset + IP_PROTO_POS),
+        // Move on if UDP, otherwise drop package
+        (BPF_JMP | BPF_JEQ | BPF_K, 0, 6, IPPROTO_UDP),
+        // Load IPv4 flag and fragment offset
+        (BPF_LD | BPF_H | BPF_ABS, 0, 0, ip_offset + IP_FRAGMENT_POS),
+        // Drop package which has MF(more fragment) set is 1 or is fragment
+        (BPF_JMP | BPF_JSET | BPF_K, 4, 0, 0x1fff),
+        // Store IP header length to X
+        (BPF_LDX | BPF_B | BPF_MSH, 0, 0, ip_offset),
+        // Load UDP destination port number to A
+        (
+            BPF_LD | BPF_H | BPF_IND,
+            0,
+            0,
+            ip_offset + DST_PORT_IN_IP_POS,
+        ),
+        // Check whether destination port is DHCPV4_DST_PORT
+        (BPF_JMP | BPF_JEQ | BPF_K, 0, 1, DHCPV4_DST_PORT),
+        // Accept this package
+        (BPF_RET, 0, 0, u32::MAX),
+        // Drop this package
+        (BPF_RET, 0, 0, 0x00000000),
+    ]);
+    ret
+}
+
+pub(crate) fn apply_dhcp_bpf(
+    fd: libc::c_int,
+    with_eth_header: bool,
+) -> Result<(), DhcpError> {
+    let mut raw_filters = Vec::new();
+    for (code, jt, jf, k) in gen_bpf_filter(with_eth_header) {
+        raw_filters.push(libc::sock_filter { code, jt, jf, k });
         log::debug!(
             "Registering BPF filter {:#04x}, {}, {}, {:#010x}",
             code,
@@ -82,8 +89,8 @@ pub(crate) fn apply_dhcp_bpf(fd: libc::c_int) -> Result<(), DhcpError> {
         );
     }
     let bpf_filter = libc::sock_fprog {
-        len: DHCP_BPF_LEN,
-        filter: raw_filters.as_ptr() as *mut _,
+        len: raw_filters.len() as u16,
+        filter: raw_filters.as_mut_ptr(),
     };
 
     let rc = unsafe {
@@ -96,13 +103,12 @@ pub(crate) fn apply_dhcp_bpf(fd: libc::c_int) -> Result<(), DhcpError> {
         )
     };
     if rc != 0 {
+        let errno = nix::errno::Errno::last();
         let e = DhcpError::new(
             ErrorKind::Bug,
-            format!(
-                "Failed to apply socket BPF filter, error: {:?}",
-                nix::errno::Errno::last()
-            ),
-        );
+            format!("Failed to apply socket BPF filter, error: {errno:?}"),
+        )
+        .with_source(std::io::Error::from(errno));
         log::error!("{}", e);
         Err(e)
     } else {
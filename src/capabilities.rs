@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{DhcpV4MessageType, DhcpV6MessageType};
+
+// Options decoded into DhcpV4Lease, sorted by code
+const V4_OPTION_CODES: [u8; 28] = [
+    1,   // Subnet Mask
+    2,   // Time Offset
+    3,   // Router
+    4,   // Time Server
+    6,   // Domain Name Server
+    12,  // Host Name
+    15,  // Domain Name
+    26,  // Interface MTU
+    28,  // Broadcast Address
+    42,  // NTP Servers
+    51,  // IP Address Lease Time
+    52,  // Option Overload
+    53,  // DHCP Message Type
+    54,  // Server Identifier
+    58,  // Renewal (T1) Time Value
+    59,  // Rebinding (T2) Time Value
+    61,  // Client Identifier
+    100, // PCode
+    101, // TCode
+    108, // IPv6-Only Preferred
+    114, // Captive Portal
+    120, // SIP Servers
+    121, // Classless Static Route
+    125, // Vendor-Identifying Vendor-Specific Information
+    161, // MUD URL
+    212, // 6RD
+    213, // Access Network Domain Name
+    249, // Microsoft Classless Static Route
+];
+
+// Options decoded into DhcpV6Lease, sorted by code
+const V6_OPTION_CODES: [u16; 19] = [
+    1,   // Client Identifier
+    2,   // Server Identifier
+    3,   // IA_NA
+    4,   // IA_TA
+    5,   // IA Address
+    12,  // Server Unicast
+    13,  // Status Code
+    21,  // SIP Servers Domain Name List
+    22,  // SIP Servers IPv6 Address List
+    23,  // DNS Recursive Name Server
+    24,  // Domain Search List
+    25,  // IA_PD
+    26,  // IA Prefix
+    31,  // SNTP Servers
+    32,  // Information Refresh Time
+    56,  // NTP Server
+    67,  // Prefix Exclude
+    103, // Captive Portal
+    112, // MUD URL
+];
+
+/// DHCP options, message types and cargo features supported by the linked
+/// build of mozim, returned by [capabilities()].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub struct DhcpCapabilities {
+    /// DHCPv4 option codes decoded from server reply. Other options are
+    /// only available via [crate::DhcpV4Config::register_option_codec()].
+    pub v4_option_codes: Vec<u8>,
+    /// DHCPv4 message types could be generated by
+    /// [crate::DhcpV4Message::to_dhcp_pkg()].
+    pub v4_message_types: Vec<DhcpV4MessageType>,
+    /// DHCPv6 option codes decoded from server reply. Other options are
+    /// only available via [crate::DhcpV6Config::register_option_codec()].
+    pub v6_option_codes: Vec<u16>,
+    /// DHCPv6 message types could be generated by
+    /// [crate::DhcpV6Message::to_dhcp_pkg()].
+    pub v6_message_types: Vec<DhcpV6MessageType>,
+    /// Cargo features enabled at build time.
+    pub features: Vec<&'static str>,
+}
+
+/// Query what the linked build of mozim supports, for management layers
+/// adapting behavior to the mozim version and cargo features in use.
+pub fn capabilities() -> DhcpCapabilities {
+    let features = [
+        ("validate-names", cfg!(feature = "validate-names")),
+        ("machine-id", cfg!(feature = "machine-id")),
+        ("pcap", cfg!(feature = "pcap")),
+        ("serde", cfg!(feature = "serde")),
+        ("ra", cfg!(feature = "ra")),
+        ("chaos", cfg!(feature = "chaos")),
+        ("tracing", cfg!(feature = "tracing")),
+        ("gateway-monitor", cfg!(feature = "gateway-monitor")),
+        ("client", cfg!(feature = "client")),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect();
+    DhcpCapabilities {
+        v4_option_codes: V4_OPTION_CODES.to_vec(),
+        v4_message_types: vec![
+            DhcpV4MessageType::Discovery,
+            DhcpV4MessageType::Request,
+            DhcpV4MessageType::Decline,
+            DhcpV4MessageType::Release,
+            DhcpV4MessageType::Inform,
+        ],
+        v6_option_codes: V6_OPTION_CODES.to_vec(),
+        v6_message_types: vec![
+            DhcpV6MessageType::SOLICIT,
+            DhcpV6MessageType::REQUEST,
+            DhcpV6MessageType::RENEW,
+            DhcpV6MessageType::REBIND,
+            DhcpV6MessageType::RELEASE,
+            DhcpV6MessageType::DECLINE,
+            DhcpV6MessageType::INFORMATION_REQUEST,
+        ],
+        features,
+    }
+}
@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::mpsc::{
+    sync_channel, Receiver, RecvError, RecvTimeoutError, TryRecvError,
+};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use futures::{
+    channel::oneshot,
+    executor::block_on,
+    future::{select, Either},
+    Stream, StreamExt,
+};
+
+use crate::{DhcpError, ErrorKind};
+
+/// Drive the lease stream(e.g. [crate::DhcpV4ClientAsync],
+/// [crate::DhcpV6ClientAsync] or [crate::DhcpDualStackClient]) in a
+/// background thread, forwarding each item to the returned
+/// [DhcpLeaseChannel], so synchronous application can consume lease updates
+/// without async runtime.
+///
+/// At most `bound` items are queued, the background thread blocks till
+/// receiver catches up. The thread quits once the stream ends or
+/// [DhcpLeaseChannel] closed or dropped.
+pub fn spawn_lease_channel<S, T>(
+    stream: S,
+    bound: usize,
+) -> Result<DhcpLeaseChannel<T>, DhcpError>
+where
+    S: Stream<Item = Result<T, DhcpError>> + Unpin + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = sync_channel(bound);
+    let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+    let handle = std::thread::Builder::new()
+        .name("mozim_channel".to_string())
+        .spawn(move || {
+            let mut stream = stream;
+            block_on(async {
+                loop {
+                    match select(stream.next(), &mut stop_receiver).await {
+                        Either::Left((Some(item), _)) => {
+                            if sender.send(item).is_err() {
+                                log::debug!(
+                                    "Lease receiver dropped, stopping channel"
+                                );
+                                break;
+                            }
+                        }
+                        Either::Left((None, _)) => break,
+                        Either::Right(_) => {
+                            log::debug!("Lease channel closed");
+                            break;
+                        }
+                    }
+                }
+            });
+            // Dropping the stream stops the client and closes its sockets
+            drop(stream);
+        })
+        .map_err(|e| {
+            let e = DhcpError::new(
+                ErrorKind::Bug,
+                format!("Failed to spawn lease channel thread: {e}"),
+            );
+            log::error!("{}", e);
+            e
+        })?;
+    Ok(DhcpLeaseChannel {
+        receiver: Some(receiver),
+        stop_sender: Some(stop_sender),
+        handle: Some(handle),
+    })
+}
+
+/// Receiving end of [spawn_lease_channel()] owning the background thread.
+/// Closing or dropping it stops the thread and the client driven by it
+/// immediately instead of waiting for the next lease update.
+#[derive(Debug)]
+pub struct DhcpLeaseChannel<T> {
+    receiver: Option<Receiver<Result<T, DhcpError>>>,
+    stop_sender: Option<oneshot::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<T> DhcpLeaseChannel<T> {
+    /// Block till next item received, [RecvError] if stream ended or
+    /// channel closed.
+    pub fn recv(&self) -> Result<Result<T, DhcpError>, RecvError> {
+        match self.receiver.as_ref() {
+            Some(r) => r.recv(),
+            None => Err(RecvError),
+        }
+    }
+
+    /// Please refer to [DhcpLeaseChannel::recv()], waiting at most specified
+    /// duration.
+    pub fn recv_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<Result<T, DhcpError>, RecvTimeoutError> {
+        match self.receiver.as_ref() {
+            Some(r) => r.recv_timeout(timeout),
+            None => Err(RecvTimeoutError::Disconnected),
+        }
+    }
+
+    /// Please refer to [DhcpLeaseChannel::recv()], without blocking.
+    pub fn try_recv(&self) -> Result<Result<T, DhcpError>, TryRecvError> {
+        match self.receiver.as_ref() {
+            Some(r) => r.try_recv(),
+            None => Err(TryRecvError::Disconnected),
+        }
+    }
+
+    /// Stop and join the background thread, the stream is dropped which
+    /// stops the client. Items not received yet are discarded. This is also
+    /// done when dropped.
+    pub fn close(&mut self) {
+        // Wake up the thread waiting for stream
+        if let Some(stop_sender) = self.stop_sender.take() {
+            stop_sender.send(()).ok();
+        }
+        // Unblock the thread waiting for queue space
+        self.receiver.take();
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                log::error!("BUG: lease channel thread panicked");
+            }
+        }
+    }
+}
+
+impl<T> Drop for DhcpLeaseChannel<T> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
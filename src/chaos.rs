@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::DhcpError;
+
+#[derive(Debug)]
+struct DhcpChaosState {
+    rng: StdRng,
+    // Packet held back by reordering, sent after the next one
+    held: Option<Vec<u8>>,
+}
+
+/// Faults injected into packets sent by client for testing retransmission
+/// and timeout handling, only available with `chaos` feature. Each fault
+/// is applied with the specified percentage of chance, decided by random
+/// number generator seeded by `seed` so failures are reproducible.
+///
+/// The policy is shared by clones, so the random sequence continues across
+/// sockets re-created by client.
+#[derive(Debug, Clone)]
+pub struct DhcpChaosPolicy {
+    seed: u64,
+    drop: u8,
+    duplicate: u8,
+    reorder: u8,
+    corrupt: u8,
+    delay: u8,
+    delay_time: Duration,
+    state: Arc<Mutex<DhcpChaosState>>,
+}
+
+impl PartialEq for DhcpChaosPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        self.seed == other.seed
+            && self.drop == other.drop
+            && self.duplicate == other.duplicate
+            && self.reorder == other.reorder
+            && self.corrupt == other.corrupt
+            && self.delay == other.delay
+            && self.delay_time == other.delay_time
+    }
+}
+
+impl Eq for DhcpChaosPolicy {}
+
+impl DhcpChaosPolicy {
+    /// Policy injecting no fault till any setter invoked.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            drop: 0,
+            duplicate: 0,
+            reorder: 0,
+            corrupt: 0,
+            delay: 0,
+            delay_time: Duration::ZERO,
+            state: Arc::new(Mutex::new(DhcpChaosState {
+                rng: StdRng::seed_from_u64(seed),
+                held: None,
+            })),
+        }
+    }
+
+    /// Packet is silently discarded.
+    pub fn set_drop(&mut self, percent: u8) -> &mut Self {
+        self.drop = percent.min(100);
+        self
+    }
+
+    /// Packet is sent twice.
+    pub fn set_duplicate(&mut self, percent: u8) -> &mut Self {
+        self.duplicate = percent.min(100);
+        self
+    }
+
+    /// Packet is held back and sent after the next packet, it is lost if
+    /// client sends nothing afterwards.
+    pub fn set_reorder(&mut self, percent: u8) -> &mut Self {
+        self.reorder = percent.min(100);
+        self
+    }
+
+    /// Random bits of one byte in DHCP message are flipped.
+    pub fn set_corrupt(&mut self, percent: u8) -> &mut Self {
+        self.corrupt = percent.min(100);
+        self
+    }
+
+    /// Sending is blocked for specified time before packet is sent.
+    pub fn set_delay(&mut self, percent: u8, time: Duration) -> &mut Self {
+        self.delay = percent.min(100);
+        self.delay_time = time;
+        self
+    }
+
+    // Send packet via `send` with faults injected, the DHCP message starts
+    // at `payload_offset` of the packet.
+    pub(crate) fn send_with<F>(
+        &self,
+        pkg: &[u8],
+        payload_offset: usize,
+        send: F,
+    ) -> Result<(), DhcpError>
+    where
+        F: Fn(&[u8]) -> Result<(), DhcpError>,
+    {
+        let mut state = match self.state.lock() {
+            Ok(s) => s,
+            Err(e) => e.into_inner(),
+        };
+        let mut hit = |percent: u8| state.rng.gen_range(0..100u8) < percent;
+        let is_drop = hit(self.drop);
+        let is_corrupt = hit(self.corrupt);
+        let is_duplicate = hit(self.duplicate);
+        let is_reorder = hit(self.reorder);
+        let is_delay = hit(self.delay);
+        if is_drop {
+            log::debug!("Chaos: dropping packet");
+            return Ok(());
+        }
+        let mut pkg = pkg.to_vec();
+        if is_corrupt && pkg.len() > payload_offset {
+            let index = state.rng.gen_range(payload_offset..pkg.len());
+            let flip = state.rng.gen_range(1..=u8::MAX);
+            log::debug!("Chaos: corrupting byte {index} of packet");
+            pkg[index] ^= flip;
+        }
+        if is_reorder && state.held.is_none() {
+            log::debug!("Chaos: holding packet for reordering");
+            state.held = Some(pkg);
+            return Ok(());
+        }
+        if is_delay {
+            log::debug!("Chaos: delaying packet for {:?}", self.delay_time);
+            std::thread::sleep(self.delay_time);
+        }
+        send(&pkg)?;
+        if is_duplicate {
+            log::debug!("Chaos: duplicating packet");
+            send(&pkg)?;
+        }
+        if let Some(held) = state.held.take() {
+            log::debug!("Chaos: sending reordered packet");
+            send(&held)?;
+        }
+        Ok(())
+    }
+}
@@ -1,19 +1,26 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use std::os::fd::BorrowedFd;
+use std::collections::VecDeque;
+use std::os::fd::{AsFd, BorrowedFd};
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 use futures::{
     task::{Context, Poll, Waker},
     Stream,
 };
-use nix::poll::{PollFd, PollFlags};
+use nix::{
+    poll::{PollFd, PollFlags},
+    sys::eventfd::{EfdFlags, EventFd},
+};
 
 use crate::{
-    DhcpError, DhcpV4Client, DhcpV4Config, DhcpV4Lease, DhcpV6Client,
-    DhcpV6Config, DhcpV6Lease, ErrorKind,
+    DhcpError, DhcpV4Client, DhcpV4Config, DhcpV4Event, DhcpV4Lease,
+    DhcpV4Snapshot, DhcpV4State, DhcpV6Client, DhcpV6Config, DhcpV6Event,
+    DhcpV6Lease, DhcpV6RetransmitStats, DhcpV6State, ErrorKind,
 };
 
 const POLL_TIMEOUT: u16 = 1000; // milliseconds
@@ -23,17 +30,256 @@ struct ShareState {
     waker: Option<Waker>,
 }
 
+// Thread waking up the async executor when DHCP client fd is readable,
+// stopped and joined by `PollThread::stop()`.
+#[derive(Debug)]
+struct PollThread {
+    share_state: Arc<Mutex<ShareState>>,
+    stop_fd: Arc<EventFd>,
+    handle: Option<JoinHandle<()>>,
+    stopped: bool,
+}
+
+impl PollThread {
+    fn new() -> Result<Self, DhcpError> {
+        let stop_fd =
+            EventFd::from_flags(EfdFlags::EFD_CLOEXEC | EfdFlags::EFD_NONBLOCK)
+                .map_err(|e| {
+                    let e = DhcpError::new(
+                        ErrorKind::Bug,
+                        format!("Failed to create eventfd: {e}"),
+                    );
+                    log::error!("{}", e);
+                    e
+                })?;
+        Ok(Self {
+            share_state: Arc::new(Mutex::new(ShareState { waker: None })),
+            stop_fd: Arc::new(stop_fd),
+            handle: None,
+            stopped: false,
+        })
+    }
+
+    // Store the waker to be woken up once `fd` is readable, start the thread
+    // if not running.
+    fn register_waker(
+        &mut self,
+        fd: RawFd,
+        waker: &Waker,
+    ) -> Result<(), DhcpError> {
+        match self.share_state.lock() {
+            Ok(mut s) => s.waker = Some(waker.clone()),
+            Err(e) => {
+                let e = DhcpError::new(
+                    ErrorKind::Bug,
+                    format!("Failed to acquire lock on share_state {e}"),
+                );
+                log::error!("{}", e);
+                return Err(e);
+            }
+        }
+        if let Some(handle) = self.handle.as_ref() {
+            if !handle.is_finished() {
+                return Ok(());
+            }
+            log::debug!("poll_thread quit, restarting it");
+            self.join();
+        }
+        let share_state = self.share_state.clone();
+        let stop_fd = self.stop_fd.clone();
+        self.handle = Some(
+            std::thread::Builder::new()
+                .name("mozim_poll".to_string())
+                .spawn(move || poll_thread(fd, share_state, stop_fd))
+                .map_err(|e| {
+                    let e = DhcpError::new(
+                        ErrorKind::Bug,
+                        format!("Failed to spawn poll thread: {e}"),
+                    );
+                    log::error!("{}", e);
+                    e
+                })?,
+        );
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if self.stopped {
+            return;
+        }
+        self.stopped = true;
+        if let Err(e) = self.stop_fd.write(1) {
+            log::error!("BUG: Failed to signal poll_thread to quit: {e}");
+        }
+        self.join();
+    }
+
+    fn join(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                log::error!("BUG: poll_thread panicked");
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DhcpV4ClientAsync {
     client: DhcpV4Client,
-    share_state: Arc<Mutex<ShareState>>,
+    poll_thread: PollThread,
 }
 
 impl DhcpV4ClientAsync {
     /// Release the lease acquired from DHCPv4 server.
+    /// This function blocks the executor between the retransmissions
+    /// requested by [crate::DhcpV4Config::set_release_retransmits()], please
+    /// use [DhcpV4ClientAsync::release_force()] if that is not acceptable.
     pub fn release(&mut self, lease: &DhcpV4Lease) -> Result<(), DhcpError> {
         self.client.release(lease)
     }
+
+    /// Please refer to [DhcpV4Client::release_force()].
+    pub fn release_force(
+        &mut self,
+        lease: &DhcpV4Lease,
+    ) -> Result<(), DhcpError> {
+        self.client.release_force(lease)
+    }
+
+    /// Please refer to [DhcpV4Client::notify_resumed()].
+    pub fn notify_resumed(&mut self) -> Result<(), DhcpError> {
+        self.client.notify_resumed()
+    }
+
+    /// Please refer to [DhcpV4Client::last_candidates()].
+    pub fn last_candidates(&self) -> &[DhcpV4Lease] {
+        self.client.last_candidates()
+    }
+
+    /// Please refer to [DhcpV4Client::pending_lease()].
+    pub fn pending_lease(&self) -> Option<&DhcpV4Lease> {
+        self.client.pending_lease()
+    }
+
+    /// Please refer to [DhcpV4Client::config()].
+    pub fn config(&self) -> &DhcpV4Config {
+        self.client.config()
+    }
+
+    /// Please refer to [DhcpV4Client::update_config()].
+    pub fn update_config(
+        &mut self,
+        config: &DhcpV4Config,
+    ) -> Result<(), DhcpError> {
+        self.client.update_config(config)
+    }
+
+    /// Please refer to [DhcpV4Client::xid()].
+    pub fn xid(&self) -> u32 {
+        self.client.xid()
+    }
+
+    /// Please refer to [DhcpV4Client::trans_elapsed()].
+    pub fn trans_elapsed(&self) -> Duration {
+        self.client.trans_elapsed()
+    }
+
+    /// Please refer to [DhcpV4Client::snapshot()].
+    pub fn snapshot(&self) -> Result<DhcpV4Snapshot, DhcpError> {
+        self.client.snapshot()
+    }
+
+    /// Please refer to [DhcpV4Client::timers()].
+    pub fn timers(&self) -> Result<Vec<(DhcpV4Event, Duration)>, DhcpError> {
+        self.client.timers()
+    }
+
+    /// Please refer to [DhcpV4Client::remaining()].
+    pub fn remaining(
+        &self,
+        event: DhcpV4Event,
+    ) -> Result<Option<Duration>, DhcpError> {
+        self.client.remaining(event)
+    }
+
+    /// Convert into stream of lease state transitions, which also reports
+    /// the loss of lease([DhcpV4State::Expired] and [DhcpV4State::Nacked])
+    /// not visible in the stream of leases.
+    pub fn into_state_stream(mut self) -> DhcpV4StateStream {
+        self.client.track_states();
+        DhcpV4StateStream {
+            client: self,
+            pending: VecDeque::new(),
+            pending_error: None,
+        }
+    }
+}
+
+/// Stream of [DhcpV4State] created by
+/// [DhcpV4ClientAsync::into_state_stream()].
+#[derive(Debug)]
+pub struct DhcpV4StateStream {
+    client: DhcpV4ClientAsync,
+    pending: VecDeque<DhcpV4State>,
+    // Error raised along with pending states, yielded after them
+    pending_error: Option<DhcpError>,
+}
+
+impl DhcpV4StateStream {
+    /// Please refer to [DhcpV4ClientAsync::release()].
+    pub fn release(&mut self, lease: &DhcpV4Lease) -> Result<(), DhcpError> {
+        self.client.release(lease)
+    }
+
+    /// Please refer to [DhcpV4ClientAsync::release_force()].
+    pub fn release_force(
+        &mut self,
+        lease: &DhcpV4Lease,
+    ) -> Result<(), DhcpError> {
+        self.client.release_force(lease)
+    }
+
+    /// Please refer to [DhcpV4ClientAsync::notify_resumed()].
+    pub fn notify_resumed(&mut self) -> Result<(), DhcpError> {
+        self.client.notify_resumed()
+    }
+
+    /// Please refer to [DhcpV4ClientAsync::close()].
+    pub fn close(&mut self) {
+        self.client.close()
+    }
+}
+
+impl Stream for DhcpV4StateStream {
+    type Item = Result<DhcpV4State, DhcpError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(state) = self.pending.pop_front() {
+                return Poll::Ready(Some(Ok(state)));
+            }
+            if let Some(e) = self.pending_error.take() {
+                return Poll::Ready(Some(Err(e)));
+            }
+            let poll = Pin::new(&mut self.client).poll_next(cx);
+            let states = self.client.client.take_states();
+            self.pending.extend(states);
+            match poll {
+                // Lease is reported as `DhcpV4State::Bound` already
+                Poll::Ready(Some(Ok(_))) => continue,
+                Poll::Pending if !self.pending.is_empty() => continue,
+                Poll::Ready(Some(Err(e))) => {
+                    self.pending_error = Some(e);
+                    continue;
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
 }
 
 impl Stream for DhcpV4ClientAsync {
@@ -43,6 +289,9 @@ impl Stream for DhcpV4ClientAsync {
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
+        if self.poll_thread.stopped {
+            return Poll::Ready(None);
+        }
         // Poll without wait
         match self.client.poll(0) {
             Ok(events) => {
@@ -64,27 +313,9 @@ impl Stream for DhcpV4ClientAsync {
             }
         }
 
-        let mut share_state = match self.share_state.lock() {
-            Ok(s) => s,
-            Err(e) => {
-                return Poll::Ready(Some(Err(DhcpError::new(
-                    ErrorKind::Bug,
-                    format!(
-                        "BUG: DhcpV4ClientAsync::poll_next() \
-                        Failed to acquire lock on share_state {e}",
-                    ),
-                ))));
-            }
-        };
-        if share_state.waker.is_none() {
-            share_state.waker = Some(cx.waker().clone());
-            drop(share_state);
-            let fd = self.client.as_raw_fd();
-            let share_state = self.share_state.clone();
-            std::thread::spawn(move || poll_thread(fd, share_state));
-        } else {
-            share_state.waker = Some(cx.waker().clone());
-            drop(share_state);
+        let fd = self.client.as_raw_fd();
+        if let Err(e) = self.poll_thread.register_waker(fd, cx.waker()) {
+            return Poll::Ready(Some(Err(e)));
         }
 
         Poll::Pending
@@ -96,45 +327,80 @@ impl DhcpV4ClientAsync {
         config: DhcpV4Config,
         lease: Option<DhcpV4Lease>,
     ) -> Result<Self, DhcpError> {
+        Self::from_client(DhcpV4Client::init(config, lease)?)
+    }
+
+    pub(crate) fn from_client(client: DhcpV4Client) -> Result<Self, DhcpError> {
         Ok(Self {
-            client: DhcpV4Client::init(config, lease)?,
-            share_state: Arc::new(Mutex::new(ShareState { waker: None })),
+            client,
+            poll_thread: PollThread::new()?,
         })
     }
+
+    /// Stop and join the background thread waking up the async executor,
+    /// the stream ends afterwards. This is also done when dropped.
+    pub fn close(&mut self) {
+        self.poll_thread.stop();
+    }
+
+    /// Please refer to [DhcpV4Client::restore()].
+    pub fn restore(
+        config: DhcpV4Config,
+        snapshot: DhcpV4Snapshot,
+    ) -> Result<Self, DhcpError> {
+        Self::from_client(DhcpV4Client::restore(config, snapshot)?)
+    }
 }
 
 impl std::ops::Drop for DhcpV4ClientAsync {
     fn drop(&mut self) {
-        if let Ok(mut s) = self.share_state.lock() {
-            // Signal `poll_thread()` to quit
-            s.waker = None;
-        }
+        // Join the thread before DHCP client fd closed
+        self.close();
     }
 }
 
 // This function will be invoked in a thread to notify the async executor
-// via `Waker::wake()`. Will quit when `poll()` failed (except EAGAIN).
-fn poll_thread(fd: RawFd, share_state: Arc<Mutex<ShareState>>) {
+// via `Waker::wake()`. Will quit when `stop_fd` is signaled or `poll()`
+// failed (except EAGAIN and EINTR).
+fn poll_thread(
+    fd: RawFd,
+    share_state: Arc<Mutex<ShareState>>,
+    stop_fd: Arc<EventFd>,
+) {
     let fd = unsafe { BorrowedFd::borrow_raw(fd) };
-    let mut poll_fds = [PollFd::new(
-        fd,
-        PollFlags::POLLIN
-            | PollFlags::POLLOUT
-            | PollFlags::POLLHUP
-            | PollFlags::POLLERR,
-    )];
     loop {
-        if share_state.lock().map(|s| s.waker.is_none()).ok() == Some(true) {
-            std::thread::sleep(std::time::Duration::from_millis(
-                POLL_TIMEOUT as u64,
+        // Only wait on stop_fd when nobody is waiting for DHCP client
+        let has_waker = match share_state.lock() {
+            Ok(s) => s.waker.is_some(),
+            Err(e) => {
+                log::error!("BUG: poll_thread() Failed to acquire lock: {e}");
+                return;
+            }
+        };
+        let mut poll_fds =
+            vec![PollFd::new(stop_fd.as_fd(), PollFlags::POLLIN)];
+        if has_waker {
+            poll_fds.push(PollFd::new(
+                fd,
+                PollFlags::POLLIN
+                    | PollFlags::POLLOUT
+                    | PollFlags::POLLHUP
+                    | PollFlags::POLLERR,
             ));
-        } else {
-            match nix::poll::poll(&mut poll_fds, POLL_TIMEOUT) {
-                // Timeout, let's check whether waker is None(DHCP client quit);
-                Ok(0) => {
-                    continue;
+        }
+        match nix::poll::poll(&mut poll_fds, POLL_TIMEOUT) {
+            // Timeout, let's check whether waker is set
+            Ok(0) => continue,
+            Ok(_) => {
+                if poll_fds[0]
+                    .revents()
+                    .map(|r| r.contains(PollFlags::POLLIN))
+                    .unwrap_or_default()
+                {
+                    log::debug!("poll_thread stopped");
+                    return;
                 }
-                Ok(_) => match share_state.lock() {
+                match share_state.lock() {
                     Ok(mut s) => {
                         if let Some(waker) = s.waker.take() {
                             log::debug!("poll_thread got event");
@@ -151,18 +417,15 @@ fn poll_thread(fd: RawFd, share_state: Arc<Mutex<ShareState>>) {
                         );
                         return;
                     }
-                },
-                Err(e) => {
-                    if e == nix::errno::Errno::EAGAIN {
-                        continue;
-                    } else {
-                        log::error!(
-                            "BUG: poll_thread() got error from poll(): {e}"
-                        );
-                        return;
-                    }
                 }
             }
+            Err(nix::errno::Errno::EAGAIN | nix::errno::Errno::EINTR) => {
+                continue
+            }
+            Err(e) => {
+                log::error!("BUG: poll_thread() got error from poll(): {e}");
+                return;
+            }
         }
     }
 }
@@ -170,7 +433,7 @@ fn poll_thread(fd: RawFd, share_state: Arc<Mutex<ShareState>>) {
 #[derive(Debug)]
 pub struct DhcpV6ClientAsync {
     client: DhcpV6Client,
-    share_state: Arc<Mutex<ShareState>>,
+    poll_thread: PollThread,
 }
 
 impl Stream for DhcpV6ClientAsync {
@@ -180,6 +443,9 @@ impl Stream for DhcpV6ClientAsync {
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
+        if self.poll_thread.stopped {
+            return Poll::Ready(None);
+        }
         // Poll without wait
         match self.client.poll(0) {
             Ok(events) => {
@@ -201,27 +467,9 @@ impl Stream for DhcpV6ClientAsync {
             }
         }
 
-        let mut share_state = match self.share_state.lock() {
-            Ok(s) => s,
-            Err(e) => {
-                return Poll::Ready(Some(Err(DhcpError::new(
-                    ErrorKind::Bug,
-                    format!(
-                        "BUG: DhcpV6ClientAsync::poll_next() \
-                        Failed to acquire lock on share_state {e}",
-                    ),
-                ))));
-            }
-        };
-        if share_state.waker.is_none() {
-            share_state.waker = Some(cx.waker().clone());
-            drop(share_state);
-            let fd = self.client.as_raw_fd();
-            let share_state = self.share_state.clone();
-            std::thread::spawn(move || poll_thread(fd, share_state));
-        } else {
-            share_state.waker = Some(cx.waker().clone());
-            drop(share_state);
+        let fd = self.client.as_raw_fd();
+        if let Err(e) = self.poll_thread.register_waker(fd, cx.waker()) {
+            return Poll::Ready(Some(Err(e)));
         }
 
         Poll::Pending
@@ -233,25 +481,175 @@ impl DhcpV6ClientAsync {
         config: DhcpV6Config,
         lease: Option<DhcpV6Lease>,
     ) -> Result<Self, DhcpError> {
+        Self::from_client(DhcpV6Client::init(config, lease)?)
+    }
+
+    pub(crate) fn from_client(client: DhcpV6Client) -> Result<Self, DhcpError> {
         Ok(Self {
-            client: DhcpV6Client::init(config, lease)?,
-            share_state: Arc::new(Mutex::new(ShareState { waker: None })),
+            client,
+            poll_thread: PollThread::new()?,
         })
     }
+
+    /// Stop and join the background thread waking up the async executor,
+    /// the stream ends afterwards. This is also done when dropped.
+    pub fn close(&mut self) {
+        self.poll_thread.stop();
+    }
 }
 
 impl std::ops::Drop for DhcpV6ClientAsync {
     fn drop(&mut self) {
-        if let Ok(mut s) = self.share_state.lock() {
-            // Signal `poll_thread()` to quit
-            s.waker = None;
-        }
+        // Join the thread before DHCP client fd closed
+        self.close();
     }
 }
 
 impl DhcpV6ClientAsync {
     /// Release the lease acquired from DHCPv6 server.
+    /// This function blocks the executor while waiting server reply, please
+    /// use [DhcpV6ClientAsync::release_force()] if that is not acceptable.
     pub fn release(&mut self, lease: &DhcpV6Lease) -> Result<(), DhcpError> {
         self.client.release(lease)
     }
+
+    /// Please refer to [DhcpV6Client::release_force()].
+    pub fn release_force(
+        &mut self,
+        lease: &DhcpV6Lease,
+    ) -> Result<(), DhcpError> {
+        self.client.release_force(lease)
+    }
+
+    /// Please refer to [DhcpV6Client::notify_resumed()].
+    pub fn notify_resumed(&mut self) -> Result<(), DhcpError> {
+        self.client.notify_resumed()
+    }
+
+    /// Please refer to [DhcpV6Client::last_candidates()].
+    pub fn last_candidates(&self) -> &[DhcpV6Lease] {
+        self.client.last_candidates()
+    }
+
+    /// Please refer to [DhcpV6Client::pending_lease()].
+    pub fn pending_lease(&self) -> Option<&DhcpV6Lease> {
+        self.client.pending_lease()
+    }
+
+    /// Please refer to [DhcpV6Client::config()].
+    pub fn config(&self) -> &DhcpV6Config {
+        self.client.config()
+    }
+
+    /// Please refer to [DhcpV6Client::update_config()].
+    pub fn update_config(
+        &mut self,
+        config: &DhcpV6Config,
+    ) -> Result<(), DhcpError> {
+        self.client.update_config(config)
+    }
+
+    /// Please refer to [DhcpV6Client::retransmit_stats()].
+    pub fn retransmit_stats(&self) -> DhcpV6RetransmitStats {
+        self.client.retransmit_stats()
+    }
+
+    /// Please refer to [DhcpV6Client::timers()].
+    pub fn timers(&self) -> Result<Vec<(DhcpV6Event, Duration)>, DhcpError> {
+        self.client.timers()
+    }
+
+    /// Please refer to [DhcpV6Client::remaining()].
+    pub fn remaining(
+        &self,
+        event: DhcpV6Event,
+    ) -> Result<Option<Duration>, DhcpError> {
+        self.client.remaining(event)
+    }
+
+    /// Please refer to [DhcpV6Client::next_retransmit_at()].
+    pub fn next_retransmit_at(
+        &self,
+    ) -> Result<Option<(Instant, Duration)>, DhcpError> {
+        self.client.next_retransmit_at()
+    }
+
+    /// Convert into stream of lease state transitions, which also reports
+    /// the loss of lease([DhcpV6State::Expired] and [DhcpV6State::Nacked])
+    /// not visible in the stream of leases.
+    pub fn into_state_stream(mut self) -> DhcpV6StateStream {
+        self.client.track_states();
+        DhcpV6StateStream {
+            client: self,
+            pending: VecDeque::new(),
+            pending_error: None,
+        }
+    }
+}
+
+/// Stream of [DhcpV6State] created by
+/// [DhcpV6ClientAsync::into_state_stream()].
+#[derive(Debug)]
+pub struct DhcpV6StateStream {
+    client: DhcpV6ClientAsync,
+    pending: VecDeque<DhcpV6State>,
+    // Error raised along with pending states, yielded after them
+    pending_error: Option<DhcpError>,
+}
+
+impl DhcpV6StateStream {
+    /// Please refer to [DhcpV6ClientAsync::release()].
+    pub fn release(&mut self, lease: &DhcpV6Lease) -> Result<(), DhcpError> {
+        self.client.release(lease)
+    }
+
+    /// Please refer to [DhcpV6ClientAsync::release_force()].
+    pub fn release_force(
+        &mut self,
+        lease: &DhcpV6Lease,
+    ) -> Result<(), DhcpError> {
+        self.client.release_force(lease)
+    }
+
+    /// Please refer to [DhcpV6ClientAsync::notify_resumed()].
+    pub fn notify_resumed(&mut self) -> Result<(), DhcpError> {
+        self.client.notify_resumed()
+    }
+
+    /// Please refer to [DhcpV6ClientAsync::close()].
+    pub fn close(&mut self) {
+        self.client.close()
+    }
+}
+
+impl Stream for DhcpV6StateStream {
+    type Item = Result<DhcpV6State, DhcpError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(state) = self.pending.pop_front() {
+                return Poll::Ready(Some(Ok(state)));
+            }
+            if let Some(e) = self.pending_error.take() {
+                return Poll::Ready(Some(Err(e)));
+            }
+            let poll = Pin::new(&mut self.client).poll_next(cx);
+            let states = self.client.client.take_states();
+            self.pending.extend(states);
+            match poll {
+                // Lease is reported as `DhcpV6State::Bound` already
+                Poll::Ready(Some(Ok(_))) => continue,
+                Poll::Pending if !self.pending.is_empty() => continue,
+                Poll::Ready(Some(Err(e))) => {
+                    self.pending_error = Some(e);
+                    continue;
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
 }
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::DhcpError;
+
+/// Typed value of DHCP option decoded by [DhcpOptionCodec], stored in
+/// [crate::DhcpV4Lease::custom_opts] or [crate::DhcpV6Lease::custom_opts].
+/// Use [DhcpOptionValue::as_any()] to downcast to the concrete type.
+/// Could also be sent to server via
+/// [crate::DhcpV4Config::add_custom_option()] or
+/// [crate::DhcpV6Config::add_custom_option()].
+pub trait DhcpOptionValue: std::fmt::Debug + Send + Sync {
+    /// Option data without code and length, used when sending the option
+    /// and for comparing two values.
+    fn encode(&self) -> Vec<u8>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn clone_box(&self) -> Box<dyn DhcpOptionValue>;
+}
+
+impl Clone for Box<dyn DhcpOptionValue> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl PartialEq for dyn DhcpOptionValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.encode() == other.encode()
+    }
+}
+
+impl Eq for dyn DhcpOptionValue {}
+
+/// Decoder of DHCP option not supported by this crate, registered for
+/// specific option code via
+/// [crate::DhcpV4Config::register_option_codec()] or
+/// [crate::DhcpV6Config::register_option_codec()].
+pub trait DhcpOptionCodec: std::fmt::Debug + Send + Sync {
+    /// Decode option data(without code and length) received from server.
+    /// Returning error discards the option with a warning logged.
+    fn decode(
+        &self,
+        data: &[u8],
+    ) -> Result<Box<dyn DhcpOptionValue>, DhcpError>;
+}
+
+// Wrapper of shared option codec, allowing config to derive `PartialEq`.
+#[derive(Debug, Clone)]
+pub(crate) struct DhcpCodec(pub(crate) Arc<dyn DhcpOptionCodec>);
+
+impl PartialEq for DhcpCodec {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for DhcpCodec {}
+
+impl DhcpCodec {
+    pub(crate) fn decode(
+        &self,
+        code: u16,
+        data: &[u8],
+    ) -> Option<Box<dyn DhcpOptionValue>> {
+        match self.0.decode(data) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                log::warn!("Failed to decode DHCP option {code}: {e}");
+                None
+            }
+        }
+    }
+}
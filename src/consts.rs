@@ -0,0 +1,7 @@
+// SPDX-License-Identifier: Apache-2.0
+
+pub(crate) const DEFAULT_SOCKET_TIMEOUT: u32 = 5;
+// Maximum size of IP package could be received
+pub(crate) const DEFAULT_MAX_MSG_SIZE: u16 = 1500;
+// RFC 2132: 9.10. Maximum DHCP Message Size
+pub(crate) const MIN_MAX_MSG_SIZE: u16 = 576;
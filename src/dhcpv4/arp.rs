@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv4Addr;
+
+use crate::mac::BROADCAST_MAC_ADDRESS;
+
+// RFC 826 An Ethernet Address Resolution Protocol
+const ARP_HW_TYPE_ETHERNET: u16 = 1;
+const ARP_OP_REQUEST: u16 = 1;
+const ARP_OP_REPLY: u16 = 2;
+const ETH_HEADER_LEN: usize = 14;
+const ARP_PKG_LEN: usize = 28;
+
+// Ethernet frame holding ARP request asking MAC address of `target_ip`
+pub(crate) fn gen_arp_request(
+    src_mac: &[u8; 6],
+    src_ip: &Ipv4Addr,
+    target_ip: &Ipv4Addr,
+) -> Vec<u8> {
+    gen_arp_pkg(
+        ARP_OP_REQUEST,
+        &BROADCAST_MAC_ADDRESS,
+        src_mac,
+        src_ip,
+        &[0u8; 6],
+        target_ip,
+    )
+}
+
+// Ethernet frame holding ARP reply to `target_mac` stating `src_ip` is at
+// `src_mac`
+pub(crate) fn gen_arp_reply(
+    src_mac: &[u8; 6],
+    src_ip: &Ipv4Addr,
+    target_mac: &[u8; 6],
+    target_ip: &Ipv4Addr,
+) -> Vec<u8> {
+    gen_arp_pkg(
+        ARP_OP_REPLY,
+        target_mac,
+        src_mac,
+        src_ip,
+        target_mac,
+        target_ip,
+    )
+}
+
+fn gen_arp_pkg(
+    op: u16,
+    eth_dst: &[u8; 6],
+    src_mac: &[u8; 6],
+    src_ip: &Ipv4Addr,
+    target_mac: &[u8; 6],
+    target_ip: &Ipv4Addr,
+) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(ETH_HEADER_LEN + ARP_PKG_LEN);
+    ret.extend_from_slice(eth_dst);
+    ret.extend_from_slice(src_mac);
+    ret.extend_from_slice(&(libc::ETH_P_ARP as u16).to_be_bytes());
+    ret.extend_from_slice(&ARP_HW_TYPE_ETHERNET.to_be_bytes());
+    ret.extend_from_slice(&(libc::ETH_P_IP as u16).to_be_bytes());
+    ret.push(libc::ETH_ALEN as u8);
+    ret.push(4);
+    ret.extend_from_slice(&op.to_be_bytes());
+    ret.extend_from_slice(src_mac);
+    ret.extend_from_slice(&src_ip.octets());
+    ret.extend_from_slice(target_mac);
+    ret.extend_from_slice(&target_ip.octets());
+    ret
+}
+
+// Return sender IP and MAC of ARP reply in ethernet frame, None if not ARP
+// reply.
+pub(crate) fn parse_arp_reply(data: &[u8]) -> Option<(Ipv4Addr, [u8; 6])> {
+    let (mac, ip, _) = parse_arp_pkg(data, ARP_OP_REPLY)?;
+    Some((ip, mac))
+}
+
+// Return sender MAC, sender IP and target IP of ARP request in ethernet
+// frame, None if not ARP request.
+pub(crate) fn parse_arp_request(
+    data: &[u8],
+) -> Option<([u8; 6], Ipv4Addr, Ipv4Addr)> {
+    parse_arp_pkg(data, ARP_OP_REQUEST)
+}
+
+fn parse_arp_pkg(
+    data: &[u8],
+    op: u16,
+) -> Option<([u8; 6], Ipv4Addr, Ipv4Addr)> {
+    let arp = data.get(ETH_HEADER_LEN..ETH_HEADER_LEN + ARP_PKG_LEN)?;
+    if data[12..14] != (libc::ETH_P_ARP as u16).to_be_bytes()
+        || arp[0..2] != ARP_HW_TYPE_ETHERNET.to_be_bytes()
+        || arp[2..4] != (libc::ETH_P_IP as u16).to_be_bytes()
+        || arp[6..8] != op.to_be_bytes()
+    {
+        return None;
+    }
+    let mut mac = [0u8; 6];
+    mac.copy_from_slice(&arp[8..14]);
+    Some((
+        mac,
+        Ipv4Addr::new(arp[14], arp[15], arp[16], arp[17]),
+        Ipv4Addr::new(arp[24], arp[25], arp[26], arp[27]),
+    ))
+}
@@ -1,17 +1,26 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::net::{Ipv4Addr, SocketAddr};
 use std::os::unix::io::{AsRawFd, RawFd};
-use std::time::Duration;
-
-use rand::Rng;
+use std::time::{Duration, Instant};
 
 use super::{
+    arp::{gen_arp_reply, gen_arp_request, parse_arp_reply, parse_arp_request},
+    conformance::apply_conformance_policy,
     event::DhcpV4Event,
+    snapshot::DhcpV4Snapshot,
+    socket::DhcpV4Socket,
+    state::{DhcpV4Progress, DhcpV4State},
     time::{gen_dhcp_request_delay, gen_renew_rebind_times},
 };
 use crate::{
     event::DhcpEventPool,
+    log_limit::DhcpLogLimiter,
+    mac::{mac_address_to_eth_mac_bytes, mac_str_to_u8_array},
+    random::gen_u32,
     socket::{DhcpRawSocket, DhcpSocket, DhcpUdpSocket},
+    time::boot_time,
+    trace::{trace_msg_received, DhcpTraceSpan},
     DhcpError, DhcpV4Config, DhcpV4Lease, DhcpV4Message, DhcpV4MessageType,
     ErrorKind,
 };
@@ -20,24 +29,31 @@ use crate::{
 // discovery phase
 const MAX_REQUEST_RETRY_COUNT: u32 = 4;
 
+const GATEWAY_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// RFC 8925: 3.4. Constants and Configuration Variables
+const MIN_V6ONLY_WAIT: u32 = 300;
+
+// RFC 2131: 3.1. Client-server interaction - allocating a network address
+//      The client SHOULD wait a minimum of ten seconds before restarting the
+//      configuration process to avoid excessive network traffic in case of
+//      looping.
+const DECLINE_RESTART_DELAY: Duration = Duration::from_secs(10);
+
 const NOT_RETRY: bool = false;
 const IS_RETRY: bool = true;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum DhcpV4Phase {
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum DhcpV4Phase {
     Done,
+    #[default]
     Discovery,
     Request,
     Renew,
     Rebind,
 }
 
-impl Default for DhcpV4Phase {
-    fn default() -> Self {
-        Self::Discovery
-    }
-}
-
 impl std::fmt::Display for DhcpV4Phase {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -54,16 +70,62 @@ impl std::fmt::Display for DhcpV4Phase {
     }
 }
 
+#[derive(Debug)]
+struct DhcpV4GatewayProbe {
+    gateway: Ipv4Addr,
+    gateway_mac: [u8; 6],
+    // None means ARP request not sent yet
+    socket: Option<DhcpRawSocket>,
+}
+
+// Periodic ARP probe of default gateway while lease is bound
+#[cfg(feature = "gateway-monitor")]
+#[derive(Debug)]
+struct DhcpV4GatewayMonitor {
+    gateway: Ipv4Addr,
+    interval: Duration,
+    max_failures: u32,
+    // Probes sent in a row without reply
+    failures: u32,
+    // Whether reply of the last probe is still awaited
+    waiting: bool,
+    socket: DhcpRawSocket,
+}
+
 #[derive(Debug)]
 pub struct DhcpV4Client {
     config: DhcpV4Config,
     event_pool: DhcpEventPool<DhcpV4Event>,
     lease: Option<DhcpV4Lease>,
+    // CLOCK_BOOTTIME when current lease was acquired
+    lease_begin: Option<Duration>,
+    // Lease offered by DHCP server and being requested
+    pending_lease: Option<DhcpV4Lease>,
+    // Lease acknowledged by DHCP server but not committed by caller yet
+    uncommitted_lease: Option<DhcpV4Lease>,
+    // All offers received since discovery started
+    candidates: Vec<DhcpV4Lease>,
+    gateway_probe: Option<DhcpV4GatewayProbe>,
+    #[cfg(feature = "gateway-monitor")]
+    gateway_monitor: Option<DhcpV4GatewayMonitor>,
+    // ARP socket answering requests of leased address in proxy mode
+    arp_responder: Option<(Ipv4Addr, DhcpRawSocket)>,
     phase: DhcpV4Phase,
-    raw_socket: Option<DhcpRawSocket>,
+    raw_socket: Option<DhcpV4Socket>,
     retry_count: u32,
     udp_socket: Option<DhcpUdpSocket>,
     xid: u32,
+    // When current address acquisition or renewal begins
+    trans_begin: Instant,
+    span: DhcpTraceSpan,
+    // State transitions not consumed yet, None when nobody subscribed
+    states: Option<Vec<DhcpV4State>>,
+    // Last state and bound lease reported by `process_detailed()`
+    last_state: Option<DhcpV4State>,
+    last_bound: Option<DhcpV4Lease>,
+    // Lease acquisitions restarted in a row since lease bound
+    restart_count: u32,
+    log_limiter: DhcpLogLimiter,
 }
 
 impl AsRawFd for DhcpV4Client {
@@ -73,56 +135,566 @@ impl AsRawFd for DhcpV4Client {
 }
 
 impl DhcpV4Client {
+    /// Create the DHCP client. No packet is sent by this function, the first
+    /// one is sent when processing the event returned by
+    /// [DhcpV4Client::poll()].
+    /// Requires CAP_NET_RAW unless [DhcpV4Config::set_udp_only()] enabled,
+    /// use [crate::check_privileges()] to check beforehand.
     pub fn init(
         mut config: DhcpV4Config,
         lease: Option<DhcpV4Lease>,
     ) -> Result<Self, DhcpError> {
         config.init()?;
-        let mut event_pool = DhcpEventPool::new()?;
-        event_pool.add_timer(
-            Duration::from_secs(config.timeout.into()),
-            DhcpV4Event::Timeout,
-        )?;
-        let raw_socket = DhcpRawSocket::new(&config)?;
-        event_pool
-            .add_socket(raw_socket.as_raw_fd(), DhcpV4Event::RawPackageIn)?;
-
-        let xid: u32 = rand::thread_rng().gen();
+        Self::init_with_resolved_config(config, lease)
+    }
 
-        let (dhcp_msg, phase) = if let Some(lease) = &lease {
-            event_pool.add_timer(
-                Duration::from_secs(gen_dhcp_request_delay(0).into()),
-                DhcpV4Event::RequestTimeout,
-            )?;
-            let mut dhcp_msg =
-                DhcpV4Message::new(&config, DhcpV4MessageType::Request, xid);
-            dhcp_msg.load_lease(lease.clone());
-            (dhcp_msg, DhcpV4Phase::Request)
+    // Create client with config already resolved by `DhcpV4Config::init()`
+    pub(crate) fn init_with_resolved_config(
+        config: DhcpV4Config,
+        lease: Option<DhcpV4Lease>,
+    ) -> Result<Self, DhcpError> {
+        let mut event_pool = DhcpEventPool::new()?;
+        let raw_socket = if config.udp_only {
+            check_udp_only(&config, lease.as_ref())?;
+            None
         } else {
-            event_pool.add_timer(
-                Duration::from_secs(gen_dhcp_request_delay(0).into()),
-                DhcpV4Event::DiscoveryTimeout,
+            start_acquisition_timers(&mut event_pool, &config)?;
+            let raw_socket = DhcpV4Socket::new(&config)?;
+            event_pool.add_socket(
+                raw_socket.as_raw_fd(),
+                DhcpV4Event::RawPackageIn,
             )?;
-            (
-                DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, xid),
-                DhcpV4Phase::Discovery,
-            )
+            Some(raw_socket)
         };
-        raw_socket.send(&dhcp_msg.to_eth_pkg_broadcast()?)?;
-        Ok(Self {
+
+        // Nothing is sent here, the first DISCOVER or REQUEST will be sent
+        // once caller start processing events.
+        let phase = if config.udp_only {
+            DhcpV4Phase::Done
+        } else if lease.is_some() {
+            event_pool
+                .add_timer(Duration::ZERO, DhcpV4Event::RequestTimeout)?;
+            DhcpV4Phase::Request
+        } else {
+            event_pool
+                .add_timer(Duration::ZERO, DhcpV4Event::DiscoveryTimeout)?;
+            DhcpV4Phase::Discovery
+        };
+        let xid = gen_u32();
+        let span = gen_trace_span(&config, phase, xid);
+        let mut ret = Self {
             config,
             event_pool,
             lease,
+            lease_begin: None,
+            pending_lease: None,
+            uncommitted_lease: None,
+            candidates: Vec::new(),
+            gateway_probe: None,
+            #[cfg(feature = "gateway-monitor")]
+            gateway_monitor: None,
+            arp_responder: None,
             phase,
             xid,
-            raw_socket: Some(raw_socket),
+            trans_begin: Instant::now(),
+            span,
+            raw_socket,
             retry_count: 0,
             udp_socket: None,
+            states: None,
+            last_state: None,
+            last_bound: None,
+            restart_count: 0,
+            log_limiter: DhcpLogLimiter::default(),
+        };
+        if ret.config.udp_only {
+            ret.schedule_udp_only_renew()?;
+        } else if let Err(e) = ret.init_gateway_probe() {
+            log::warn!("Skipping gateway probe due to error: {e}");
+        }
+        Ok(ret)
+    }
+
+    // How long the lease has been held is unknown, hence renew immediately
+    // while rebind and expiry are scheduled as if lease just granted.
+    fn schedule_udp_only_renew(&mut self) -> Result<(), DhcpError> {
+        let lease = match self.lease.clone() {
+            Some(l) => l,
+            None => return Ok(()),
+        };
+        self.set_renew_rebind_timer(&lease)?;
+        if lease.is_infinite() {
+            return Ok(());
+        }
+        self.event_pool.del_timer(DhcpV4Event::Renew)?;
+        self.event_pool.del_timer(DhcpV4Event::RenewTimeout)?;
+        self.event_pool
+            .add_timer(Duration::ZERO, DhcpV4Event::Renew)?;
+        if self.config.renew_timeout > 0 {
+            self.event_pool.add_timer(
+                Duration::from_secs(self.config.renew_timeout.into()),
+                DhcpV4Event::RenewTimeout,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Take snapshot of client state which could be used by
+    /// [DhcpV4Client::restore()] to resume this client after process
+    /// restart.
+    pub fn snapshot(&self) -> Result<DhcpV4Snapshot, DhcpError> {
+        Ok(DhcpV4Snapshot {
+            phase: self.phase,
+            lease: self.lease.clone(),
+            lease_begin: self.lease_begin,
+            pending_lease: self.pending_lease.clone(),
+            xid: self.xid,
+            retry_count: self.retry_count,
+            trans_elapsed: self.trans_begin.elapsed(),
+            timers: self
+                .event_pool
+                .timers()?
+                .into_iter()
+                // Gateway probe and monitor are not resumable
+                .filter(|(e, _)| {
+                    *e != DhcpV4Event::GatewayProbeTimeout
+                        && *e != DhcpV4Event::GatewayMonitorTimeout
+                })
+                .collect(),
+            taken_at: boot_time()?,
         })
     }
 
+    /// Create the DHCP client resuming from specified snapshot. Timers are
+    /// restored with time passed since snapshot taken deducted, timers
+    /// already expired will be triggered immediately. Reply to DHCP message
+    /// sent before snapshot taken is still accepted.
+    /// Requires CAP_NET_RAW, use [crate::check_privileges()] to check
+    /// beforehand.
+    pub fn restore(
+        mut config: DhcpV4Config,
+        snapshot: DhcpV4Snapshot,
+    ) -> Result<Self, DhcpError> {
+        let now = boot_time()?;
+        if now < snapshot.taken_at {
+            let e = DhcpError::new(
+                ErrorKind::InvalidArgument,
+                "The DHCPv4 client snapshot was taken before system reboot"
+                    .to_string(),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+        let passed = now - snapshot.taken_at;
+        config.init()?;
+        let mut event_pool = DhcpEventPool::new()?;
+        for (event, remain) in snapshot.timers {
+            event_pool.add_timer(remain.saturating_sub(passed), event)?;
+        }
+        // The UDP socket of renew phase is replaced by raw socket which could
+        // also receive the unicast reply.
+        let raw_socket = if snapshot.phase == DhcpV4Phase::Done {
+            None
+        } else {
+            let raw_socket = DhcpV4Socket::new(&config)?;
+            event_pool.add_socket(
+                raw_socket.as_raw_fd(),
+                DhcpV4Event::RawPackageIn,
+            )?;
+            Some(raw_socket)
+        };
+        let trans_elapsed = snapshot.trans_elapsed + passed;
+        let span = gen_trace_span(&config, snapshot.phase, snapshot.xid);
+        log::debug!(
+            "Restored DHCPv4 client in {} phase with xid {}",
+            snapshot.phase,
+            snapshot.xid
+        );
+        let mut ret = Self {
+            config,
+            event_pool,
+            lease: snapshot.lease,
+            lease_begin: snapshot.lease_begin,
+            pending_lease: snapshot.pending_lease,
+            uncommitted_lease: None,
+            candidates: Vec::new(),
+            gateway_probe: None,
+            #[cfg(feature = "gateway-monitor")]
+            gateway_monitor: None,
+            arp_responder: None,
+            phase: snapshot.phase,
+            xid: snapshot.xid,
+            span,
+            trans_begin: Instant::now()
+                .checked_sub(trans_elapsed)
+                .unwrap_or_else(Instant::now),
+            raw_socket,
+            retry_count: snapshot.retry_count,
+            udp_socket: None,
+            states: None,
+            last_state: None,
+            last_bound: None,
+            restart_count: 0,
+            log_limiter: DhcpLogLimiter::default(),
+        };
+        // Lease is held in other phases
+        if matches!(
+            ret.phase,
+            DhcpV4Phase::Done | DhcpV4Phase::Renew | DhcpV4Phase::Rebind
+        ) {
+            ret.start_arp_responder();
+            #[cfg(feature = "gateway-monitor")]
+            ret.start_gateway_monitor();
+        }
+        Ok(ret)
+    }
+
+    fn init_gateway_probe(&mut self) -> Result<(), DhcpError> {
+        let (gateway_mac, lease) = match (
+            self.config.fast_resume_gateway_mac.as_ref(),
+            self.lease.as_ref(),
+        ) {
+            (Some(m), Some(l)) => (m, l),
+            _ => return Ok(()),
+        };
+        if self.config.src_mac.is_empty() {
+            log::debug!("No ARP on interface without MAC, skip gateway probe");
+            return Ok(());
+        }
+        let gateway =
+            match lease.effective_routes().iter().find(|r| r.is_default()) {
+                Some(r) => r.router,
+                None => {
+                    log::debug!(
+                        "No default gateway in lease, skip gateway probe"
+                    );
+                    return Ok(());
+                }
+            };
+        self.gateway_probe = Some(DhcpV4GatewayProbe {
+            gateway,
+            gateway_mac: mac_address_to_eth_mac_bytes(gateway_mac)?,
+            socket: None,
+        });
+        self.event_pool
+            .add_timer(Duration::ZERO, DhcpV4Event::GatewayProbeTimeout)
+    }
+
+    fn stop_gateway_probe(&mut self) -> Result<(), DhcpError> {
+        self.event_pool.del_socket(DhcpV4Event::ArpPackageIn)?;
+        self.event_pool
+            .del_timer(DhcpV4Event::GatewayProbeTimeout)?;
+        self.gateway_probe = None;
+        Ok(())
+    }
+
+    // First timeout is for sending ARP request, second one is the timeout of
+    // waiting ARP reply.
+    fn process_gateway_probe_timeout(
+        &mut self,
+    ) -> Result<Option<DhcpV4Lease>, DhcpError> {
+        self.event_pool
+            .del_timer(DhcpV4Event::GatewayProbeTimeout)?;
+        let (probe, lease) =
+            match (self.gateway_probe.as_mut(), self.lease.as_ref()) {
+                (Some(p), Some(l)) => (p, l),
+                _ => return Ok(None),
+            };
+        if probe.socket.is_some() {
+            log::info!(
+                "Gateway {} does not reply ARP request, waiting DHCP server",
+                probe.gateway
+            );
+            self.stop_gateway_probe()?;
+            return Ok(None);
+        }
+        let socket = DhcpRawSocket::new_arp(&self.config)?;
+        socket.send(&gen_arp_request(
+            &mac_address_to_eth_mac_bytes(&self.config.src_mac)?,
+            &lease.yiaddr,
+            &probe.gateway,
+        ))?;
+        self.event_pool
+            .add_socket(socket.as_raw_fd(), DhcpV4Event::ArpPackageIn)?;
+        probe.socket = Some(socket);
+        self.event_pool.add_timer(
+            GATEWAY_PROBE_TIMEOUT,
+            DhcpV4Event::GatewayProbeTimeout,
+        )?;
+        Ok(None)
+    }
+
+    fn process_gateway_probe_recv(
+        &mut self,
+    ) -> Result<Option<DhcpV4Lease>, DhcpError> {
+        let probe = match self.gateway_probe.as_ref() {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+        let data = match probe.socket.as_ref().map(|s| s.recv()) {
+            Some(Ok(d)) => d,
+            _ => return Ok(None),
+        };
+        let (ip, mac) = match parse_arp_reply(&data) {
+            Some((ip, mac)) if ip == probe.gateway => (ip, mac),
+            _ => return Ok(None),
+        };
+        let is_expected = mac == probe.gateway_mac;
+        self.stop_gateway_probe()?;
+        if is_expected {
+            log::info!(
+                "Gateway {ip} replied with expected MAC, lease is considered \
+                bound while confirming with DHCP server"
+            );
+            Ok(self.lease.clone())
+        } else {
+            log::info!(
+                "Gateway {ip} replied with unexpected MAC {mac:02x?}, \
+                waiting DHCP server"
+            );
+            Ok(None)
+        }
+    }
+
+    // Probe default gateway of bound lease periodically. Failure is not
+    // fatal as DHCP itself is not affected.
+    #[cfg(feature = "gateway-monitor")]
+    fn start_gateway_monitor(&mut self) {
+        let (interval, max_failures) = match self.config.gateway_monitor {
+            Some(m) => m,
+            None => return,
+        };
+        if self.gateway_monitor.is_some() {
+            return;
+        }
+        if self.config.udp_only {
+            log::debug!("No ARP in UDP-only mode, skip gateway monitor");
+            return;
+        }
+        if self.config.src_mac.is_empty() {
+            log::debug!(
+                "No ARP on interface without MAC, skip gateway monitor"
+            );
+            return;
+        }
+        let gateway = match self.lease.as_ref().and_then(|l| {
+            l.effective_routes()
+                .iter()
+                .find(|r| r.is_default())
+                .map(|r| r.router)
+        }) {
+            Some(g) => g,
+            None => {
+                log::debug!(
+                    "No default gateway in lease, skip gateway monitor"
+                );
+                return;
+            }
+        };
+        let interval = Duration::from_secs(interval.into());
+        let socket = match DhcpRawSocket::new_arp(&self.config).and_then(|s| {
+            self.event_pool
+                .add_socket(s.as_raw_fd(), DhcpV4Event::GatewayMonitorArpIn)?;
+            self.event_pool
+                .add_timer(interval, DhcpV4Event::GatewayMonitorTimeout)?;
+            Ok(s)
+        }) {
+            Ok(s) => s,
+            Err(e) => {
+                log::warn!("Failed to start gateway monitor: {e}");
+                return;
+            }
+        };
+        log::debug!(
+            "Monitoring gateway {gateway} every {} seconds",
+            interval.as_secs()
+        );
+        self.gateway_monitor = Some(DhcpV4GatewayMonitor {
+            gateway,
+            interval,
+            max_failures,
+            failures: 0,
+            waiting: false,
+            socket,
+        });
+    }
+
+    #[cfg(feature = "gateway-monitor")]
+    fn stop_gateway_monitor(&mut self) -> Result<(), DhcpError> {
+        if self.gateway_monitor.take().is_some() {
+            self.event_pool
+                .del_socket(DhcpV4Event::GatewayMonitorArpIn)?;
+            self.event_pool
+                .del_timer(DhcpV4Event::GatewayMonitorTimeout)?;
+        }
+        Ok(())
+    }
+
+    // Count the unanswered probe and send new one, rebind once the gateway
+    // is considered unreachable.
+    #[cfg(feature = "gateway-monitor")]
+    fn process_gateway_monitor_timeout(
+        &mut self,
+    ) -> Result<Option<DhcpV4Lease>, DhcpError> {
+        self.event_pool
+            .del_timer(DhcpV4Event::GatewayMonitorTimeout)?;
+        let (monitor, lease) =
+            match (self.gateway_monitor.as_mut(), self.lease.as_ref()) {
+                (Some(m), Some(l)) => (m, l),
+                _ => return Ok(None),
+            };
+        if monitor.waiting {
+            monitor.failures += 1;
+            log::debug!(
+                "Gateway {} did not reply ARP probe, {} failures in a row",
+                monitor.gateway,
+                monitor.failures
+            );
+        }
+        if monitor.failures >= monitor.max_failures {
+            log::warn!(
+                "Gateway {} is unreachable after {} ARP probes",
+                monitor.gateway,
+                monitor.failures
+            );
+            self.stop_gateway_monitor()?;
+            // Already broadcasting for any DHCP server
+            if self.phase == DhcpV4Phase::Rebind {
+                return Ok(None);
+            }
+            log::info!("Rebinding DHCP lease early as gateway is unreachable");
+            self.event_pool.del_timer(DhcpV4Event::Renew)?;
+            self.event_pool.del_timer(DhcpV4Event::RenewRetry)?;
+            self.event_pool.del_timer(DhcpV4Event::Rebind)?;
+            if self.udp_socket.take().is_some() {
+                self.event_pool.del_socket(DhcpV4Event::UdpPackageIn)?;
+            }
+            return self.process_rebind(NOT_RETRY);
+        }
+        let interval = monitor.interval;
+        // Sending failure is counted as unanswered probe
+        monitor.waiting = true;
+        if let Err(e) = monitor.socket.send(&gen_arp_request(
+            &mac_address_to_eth_mac_bytes(&self.config.src_mac)?,
+            &lease.yiaddr,
+            &monitor.gateway,
+        )) {
+            log::debug!("Failed to send ARP probe to gateway: {e}");
+        }
+        self.event_pool
+            .add_timer(interval, DhcpV4Event::GatewayMonitorTimeout)?;
+        Ok(None)
+    }
+
+    #[cfg(feature = "gateway-monitor")]
+    fn process_gateway_monitor_recv(&mut self) -> Result<(), DhcpError> {
+        let monitor = match self.gateway_monitor.as_mut() {
+            Some(m) => m,
+            None => return Ok(()),
+        };
+        let data = match monitor.socket.recv() {
+            Ok(d) => d,
+            Err(e) => {
+                log::debug!("Failed to receive ARP reply: {e}");
+                return Ok(());
+            }
+        };
+        if let Some((ip, _)) = parse_arp_reply(&data) {
+            if ip == monitor.gateway {
+                monitor.waiting = false;
+                monitor.failures = 0;
+            }
+        }
+        Ok(())
+    }
+
+    // Answer ARP requests of leased address on behalf of proxied client.
+    // Failure is not fatal as DHCP itself is not affected.
+    fn start_arp_responder(&mut self) {
+        if !self.config.is_proxy || !self.config.proxy_arp {
+            return;
+        }
+        let ip = match self.lease.as_ref() {
+            Some(l) => l.yiaddr,
+            None => return,
+        };
+        if self.arp_responder.as_ref().map(|(i, _)| *i) == Some(ip) {
+            return;
+        }
+        if let Err(e) = self.stop_arp_responder() {
+            log::warn!("Failed to stop ARP responder: {e}");
+        }
+        let socket = match DhcpRawSocket::new_arp(&self.config) {
+            Ok(s) => s,
+            Err(e) => {
+                log::warn!("Failed to start ARP responder: {e}");
+                return;
+            }
+        };
+        if let Err(e) = self
+            .event_pool
+            .add_socket(socket.as_raw_fd(), DhcpV4Event::ArpRequestIn)
+        {
+            log::warn!("Failed to start ARP responder: {e}");
+            return;
+        }
+        log::info!(
+            "Answering ARP requests of {ip} with proxied MAC {}",
+            self.config.src_mac
+        );
+        self.arp_responder = Some((ip, socket));
+    }
+
+    fn stop_arp_responder(&mut self) -> Result<(), DhcpError> {
+        if self.arp_responder.take().is_some() {
+            self.event_pool.del_socket(DhcpV4Event::ArpRequestIn)?;
+        }
+        Ok(())
+    }
+
+    fn process_arp_request(&mut self) -> Result<(), DhcpError> {
+        let (ip, socket) = match self.arp_responder.as_ref() {
+            Some(r) => r,
+            None => return Ok(()),
+        };
+        let data = match socket.recv() {
+            Ok(d) => d,
+            Err(e) => {
+                log::debug!("Failed to receive ARP request: {e}");
+                return Ok(());
+            }
+        };
+        match parse_arp_request(&data) {
+            // Gratuitous ARP of the address is not answered
+            Some((sender_mac, sender_ip, target_ip))
+                if target_ip == *ip && sender_ip != *ip =>
+            {
+                log::debug!(
+                    "Replying ARP request of {ip} from {sender_ip} \
+                    {sender_mac:02x?}"
+                );
+                socket.send(&gen_arp_reply(
+                    &mac_address_to_eth_mac_bytes(&self.config.src_mac)?,
+                    ip,
+                    &sender_mac,
+                    &sender_ip,
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn clean_up(&mut self) {
         self.lease = None;
+        self.lease_begin = None;
+        self.pending_lease = None;
+        self.uncommitted_lease = None;
+        self.gateway_probe = None;
+        #[cfg(feature = "gateway-monitor")]
+        {
+            self.gateway_monitor = None;
+        }
+        self.arp_responder = None;
         self.retry_count = 0;
         self.phase = DhcpV4Phase::Done;
         self.event_pool.remove_all_event();
@@ -130,20 +702,126 @@ impl DhcpV4Client {
         self.udp_socket = None;
     }
 
+    // Close socket of previous exchange, DHCP 4o6 socket holds the DHCPv6
+    // client port
+    fn close_raw_socket(&mut self) -> Result<(), DhcpError> {
+        if self.raw_socket.take().is_some() {
+            self.event_pool.del_socket(DhcpV4Event::RawPackageIn)?;
+        }
+        Ok(())
+    }
+
     pub fn poll(&self, wait_time: u32) -> Result<Vec<DhcpV4Event>, DhcpError> {
         self.event_pool.poll(wait_time)
     }
 
+    /// All the leases offered by DHCP servers during last discovery, including
+    /// the ones not selected or not approved. Useful for detecting rogue or
+    /// misconfigured DHCP servers.
+    pub fn last_candidates(&self) -> &[DhcpV4Lease] {
+        self.candidates.as_slice()
+    }
+
+    /// The lease offered by DHCP server which client is requesting.
+    pub fn pending_lease(&self) -> Option<&DhcpV4Lease> {
+        self.pending_lease.as_ref()
+    }
+
+    /// Effective config with interface information resolved.
+    pub fn config(&self) -> &DhcpV4Config {
+        &self.config
+    }
+
+    /// Update config of running client, only fields listed below are
+    /// allowed to be different from [DhcpV4Client::config()], otherwise
+    /// [ErrorKind::InvalidArgument] is returned:
+    ///  * timeout, state timeout and renew timeout
+    ///  * restart policy
+    ///  * request options
+    ///  * extra options
+    ///  * host name
+    ///
+    /// Changes take effect at the next message exchange, timers already
+    /// started are not changed.
+    pub fn update_config(
+        &mut self,
+        config: &DhcpV4Config,
+    ) -> Result<(), DhcpError> {
+        self.config.update(config)
+    }
+
+    /// Transaction ID of current DHCP exchange. A new one is generated for
+    /// each address acquisition, renewal and rebinding.
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    /// Time passed since current DHCP exchange began, which is also sent
+    /// to server via the `secs` field of each (re)transmitted message.
+    pub fn trans_elapsed(&self) -> Duration {
+        self.trans_begin.elapsed()
+    }
+
+    /// Pending timers with time left before firing, sorted by time left.
+    /// Useful for external supervisors to align their own tasks with DHCP
+    /// timing, e.g. [DhcpV4Event::Renew] for the upcoming renewal.
+    pub fn timers(&self) -> Result<Vec<(DhcpV4Event, Duration)>, DhcpError> {
+        self.event_pool.timers()
+    }
+
+    /// Time left before specified timer fires, `None` if not scheduled.
+    pub fn remaining(
+        &self,
+        event: DhcpV4Event,
+    ) -> Result<Option<Duration>, DhcpError> {
+        Ok(self
+            .event_pool
+            .timers()?
+            .into_iter()
+            .find_map(|(e, t)| (e == event).then_some(t)))
+    }
+
+    // Start recording state transitions for `take_states()`
+    pub(crate) fn track_states(&mut self) {
+        self.states.get_or_insert_with(Vec::new);
+    }
+
+    pub(crate) fn take_states(&mut self) -> Vec<DhcpV4State> {
+        self.states.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    fn push_state(&mut self, state: DhcpV4State) {
+        if let Some(states) = self.states.as_mut() {
+            states.push(state);
+        }
+    }
+
+    fn new_transaction(&mut self, phase: DhcpV4Phase) {
+        self.xid = gen_u32();
+        self.trans_begin = Instant::now();
+        self.span = gen_trace_span(&self.config, phase, self.xid);
+    }
+
+    fn gen_msg(&self, msg_type: DhcpV4MessageType) -> DhcpV4Message {
+        let mut dhcp_msg = DhcpV4Message::new(&self.config, msg_type, self.xid);
+        // RFC 2131: Seconds elapsed since client began address acquisition
+        // or renewal process.
+        dhcp_msg.set_secs(
+            self.trans_begin
+                .elapsed()
+                .as_secs()
+                .try_into()
+                .unwrap_or(u16::MAX),
+        );
+        dhcp_msg
+    }
+
     fn gen_discovery_pkg(&self) -> DhcpV4Message {
-        DhcpV4Message::new(&self.config, DhcpV4MessageType::Discovery, self.xid)
+        self.gen_msg(DhcpV4MessageType::Discovery)
     }
 
     fn gen_request_pkg(&self, lease: &DhcpV4Lease) -> DhcpV4Message {
-        let mut dhcp_msg = DhcpV4Message::new(
-            &self.config,
-            DhcpV4MessageType::Request,
-            self.xid,
-        );
+        let mut dhcp_msg = self.gen_msg(DhcpV4MessageType::Request);
         dhcp_msg.load_lease(lease.clone());
         dhcp_msg
     }
@@ -160,17 +838,46 @@ impl DhcpV4Client {
             log::error!("{}", e);
             return Err(e);
         };
-        let lease =
-            match recv_dhcp_msg(socket, DhcpV4MessageType::Offer, self.xid) {
-                Ok(Some(l)) => l,
-                Ok(None) => return Ok(None),
-                Err(e) => {
-                    log::info!("Ignoring invalid DHCP package: {e}");
-                    return Ok(None);
-                }
-            };
+        let lease = match recv_dhcp_msg(
+            socket,
+            &self.config,
+            DhcpV4MessageType::Offer,
+            self.xid,
+            Some(&mut self.candidates),
+            None,
+        ) {
+            Ok(Some(l)) => l,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                self.log_limiter.log(
+                    log::Level::Info,
+                    "Ignoring invalid DHCP package",
+                    &e,
+                );
+                return Ok(None);
+            }
+        };
+        if let Some(wait) = self.ipv6_only_wait(&lease) {
+            return self.process_ipv6_only(wait);
+        }
+        if let Some(approver) = self.config.lease_approver.as_ref() {
+            if !approver.approve(&lease) {
+                log::info!(
+                    "DHCP offer {} from server {} is not approved, \
+                    waiting for other offers",
+                    lease.yiaddr,
+                    lease.srv_id
+                );
+                return Ok(None);
+            }
+        }
         self.phase = DhcpV4Phase::Request;
-        socket.send(&self.gen_request_pkg(&lease).to_eth_pkg_broadcast()?)?;
+        reset_state_timer(&mut self.event_pool, &self.config)?;
+        let eth_pkg = self.gen_request_pkg(&lease).to_eth_pkg_broadcast()?;
+        // Keep the state consistent even sending failed, so retransmission
+        // could recover from transient failure.
+        self.pending_lease = Some(lease);
+        socket.send(&eth_pkg)?;
         Ok(None)
     }
 
@@ -178,23 +885,34 @@ impl DhcpV4Client {
         &mut self,
         lease: &DhcpV4Lease,
     ) -> Result<(), DhcpError> {
-        let t = gen_renew_rebind_times(lease.t1, lease.t2, lease.lease_time);
-        self.event_pool
-            .add_timer(Duration::from_secs(t[0].into()), DhcpV4Event::Renew)?;
-        self.event_pool.add_timer(
-            Duration::from_secs(t[1].into()),
-            DhcpV4Event::RenewRetry,
-        )?;
-        self.event_pool
-            .add_timer(Duration::from_secs(t[2].into()), DhcpV4Event::Rebind)?;
-        self.event_pool.add_timer(
-            Duration::from_secs(t[3].into()),
-            DhcpV4Event::RebindRetry,
-        )?;
+        self.lease_begin = Some(boot_time()?);
+        if lease.is_infinite() {
+            log::info!(
+                "DHCP lease {} is infinite, no renew or rebind scheduled",
+                lease.yiaddr
+            );
+            return Ok(());
+        }
+        let t1 = self.config.renew_policy.renew_time(
+            lease.t1,
+            lease.t2,
+            lease.lease_time,
+        );
+        let t = gen_renew_rebind_times(t1, lease.t2, lease.lease_time);
+        self.event_pool.add_timer(t[0], DhcpV4Event::Renew)?;
+        self.event_pool.add_timer(t[1], DhcpV4Event::RenewRetry)?;
+        self.event_pool.add_timer(t[2], DhcpV4Event::Rebind)?;
+        self.event_pool.add_timer(t[3], DhcpV4Event::RebindRetry)?;
         self.event_pool.add_timer(
             Duration::from_secs(lease.lease_time.into()),
             DhcpV4Event::LeaseExpired,
         )?;
+        if self.config.renew_timeout > 0 {
+            self.event_pool.add_timer(
+                t[0] + Duration::from_secs(self.config.renew_timeout.into()),
+                DhcpV4Event::RenewTimeout,
+            )?;
+        }
         Ok(())
     }
 
@@ -204,61 +922,199 @@ impl DhcpV4Client {
         } else {
             self.clean_up();
             let e = DhcpError::new(
-                ErrorKind::Bug,
-                "process_request(): No Raw socket".to_string(),
+                ErrorKind::Bug,
+                "process_request(): No Raw socket".to_string(),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        };
+        let lease = match recv_dhcp_msg(
+            socket,
+            &self.config,
+            DhcpV4MessageType::Ack,
+            self.xid,
+            Some(&mut self.candidates),
+            None,
+        ) {
+            Ok(Some(l)) => l,
+            Ok(None) => return Ok(None),
+            Err(e) if e.kind() == ErrorKind::NoLease => {
+                return self.process_nack(e);
+            }
+            Err(e) => {
+                self.log_limiter.log(
+                    log::Level::Info,
+                    "Ignoring invalid DHCP package",
+                    &e,
+                );
+                return Ok(None);
+            }
+        };
+        if let Some(wait) = self.ipv6_only_wait(&lease) {
+            return self.process_ipv6_only(wait);
+        }
+        self.clean_up();
+        if self.config.manual_commit {
+            log::info!(
+                "DHCP lease {} acknowledged, waiting caller to commit",
+                lease.yiaddr
+            );
+            self.uncommitted_lease = Some(lease.clone());
+            return Ok(Some(lease));
+        }
+        self.bind_lease(&lease)?;
+        Ok(Some(lease))
+    }
+
+    fn bind_lease(&mut self, lease: &DhcpV4Lease) -> Result<(), DhcpError> {
+        self.lease = Some(lease.clone());
+        self.set_renew_rebind_timer(lease)?;
+        self.restart_count = 0;
+        self.push_state(DhcpV4State::Bound(lease.clone()));
+        self.start_arp_responder();
+        #[cfg(feature = "gateway-monitor")]
+        self.start_gateway_monitor();
+        Ok(())
+    }
+
+    /// Lease returned by [DhcpV4Client::process()] but not committed yet
+    /// when [DhcpV4Config::set_manual_commit()] enabled.
+    pub fn uncommitted_lease(&self) -> Option<&DhcpV4Lease> {
+        self.uncommitted_lease.as_ref()
+    }
+
+    /// Confirm the lease has been applied to the system, the client then
+    /// binds the lease and schedules renewal.
+    /// Only needed when [DhcpV4Config::set_manual_commit()] enabled.
+    pub fn commit_lease(
+        &mut self,
+        lease: &DhcpV4Lease,
+    ) -> Result<(), DhcpError> {
+        let lease = self.take_uncommitted_lease(lease)?;
+        log::info!("DHCP lease {} committed", lease.yiaddr);
+        self.bind_lease(&lease)
+    }
+
+    /// Report the lease failed to be applied to the system(e.g. address
+    /// conflict), the client sends DHCPDECLINE and starts discovery again
+    /// after 10 seconds as RFC 2131 suggested, unless disallowed by
+    /// [DhcpV4Config::set_restart_policy()].
+    /// Only needed when [DhcpV4Config::set_manual_commit()] enabled.
+    /// The client is stopped if DHCPDECLINE cannot be sent, e.g.
+    /// [ErrorKind::RawSocketRequired] in UDP-only mode.
+    pub fn decline_lease(
+        &mut self,
+        lease: &DhcpV4Lease,
+    ) -> Result<(), DhcpError> {
+        let lease = self.take_uncommitted_lease(lease)?;
+        let mut dhcp_msg = self.gen_msg(DhcpV4MessageType::Decline);
+        dhcp_msg.load_lease(lease.clone());
+        // DECLINE is broadcast, hence fails in UDP-only mode
+        if let Err(e) = DhcpV4Socket::new(&self.config)
+            .and_then(|s| s.send(&dhcp_msg.to_eth_pkg_broadcast()?))
+        {
+            self.clean_up();
+            log::error!("Failed to send DHCPDECLINE of {}: {e}", lease.yiaddr);
+            return Err(e);
+        }
+        log::info!(
+            "DHCPDECLINE of {} sent to server {}",
+            lease.yiaddr,
+            lease.srv_id
+        );
+        if !self.restart_allowed() {
+            self.clean_up();
+            let e = DhcpError::new(
+                ErrorKind::NoLease,
+                format!(
+                    "DHCP lease {} declined, not restarting discovery as \
+                    per restart policy",
+                    lease.yiaddr
+                ),
             );
             log::error!("{}", e);
             return Err(e);
-        };
-        let lease =
-            match recv_dhcp_msg(socket, DhcpV4MessageType::Ack, self.xid) {
-                Ok(Some(l)) => l,
-                Ok(None) => return Ok(None),
-                Err(e) => {
-                    log::info!("Ignoring invalid DHCP package: {e}");
-                    return Ok(None);
-                }
-            };
+        }
+        self.restart_discovery()?;
+        self.event_pool.del_timer(DhcpV4Event::DiscoveryTimeout)?;
+        self.event_pool
+            .add_timer(DECLINE_RESTART_DELAY, DhcpV4Event::DiscoveryTimeout)
+    }
+
+    fn take_uncommitted_lease(
+        &mut self,
+        lease: &DhcpV4Lease,
+    ) -> Result<DhcpV4Lease, DhcpError> {
+        match self.uncommitted_lease.take() {
+            Some(l) if l.yiaddr == lease.yiaddr => Ok(l),
+            l => {
+                self.uncommitted_lease = l;
+                let e = DhcpError::new(
+                    ErrorKind::InvalidArgument,
+                    format!(
+                        "DHCP lease {} is not waiting for commit",
+                        lease.yiaddr
+                    ),
+                );
+                log::error!("{}", e);
+                Err(e)
+            }
+        }
+    }
+
+    // RFC 8925: 3.2. DHCPv4 Client Behavior
+    //      The client MUST NOT act on the option unless it included the
+    //      option in the Parameter Request List. V6ONLY_WAIT lower than
+    //      MIN_V6ONLY_WAIT should be treated as MIN_V6ONLY_WAIT.
+    fn ipv6_only_wait(&self, lease: &DhcpV4Lease) -> Option<Duration> {
+        if self.config.ipv6_only_preferred {
+            lease
+                .ipv6_only_wait
+                .map(|w| Duration::from_secs(w.max(MIN_V6ONLY_WAIT).into()))
+        } else {
+            None
+        }
+    }
+
+    // Stop the client as IPv4 should be disabled for specified period
+    fn process_ipv6_only(
+        &mut self,
+        wait: Duration,
+    ) -> Result<Option<DhcpV4Lease>, DhcpError> {
         self.clean_up();
-        self.lease = Some(lease.clone());
-        self.set_renew_rebind_timer(&lease)?;
-        Ok(Some(lease))
+        self.push_state(DhcpV4State::Ipv6OnlyPreferred(wait));
+        let e = DhcpError::new(
+            ErrorKind::Ipv6OnlyPreferred,
+            format!(
+                "DHCP server indicated IPv6-only preferred, IPv4 should be \
+                disabled for {} seconds",
+                wait.as_secs()
+            ),
+        );
+        log::info!("{}", e);
+        Err(e)
     }
 
     // RFC 2131 suggests four times(60 seconds) retry before fallback to
-    // discovery phase
+    // discovery phase.
+    // The first REQUEST is also sent by this function, hence `retry_count`
+    // is the number of REQUEST sent so far and the first REQUEST plus
+    // MAX_REQUEST_RETRY_COUNT retransmissions are sent before fallback.
     fn process_request_timeout(
         &mut self,
     ) -> Result<Option<DhcpV4Lease>, DhcpError> {
         self.event_pool.del_timer(DhcpV4Event::RequestTimeout)?;
-        if self.retry_count >= MAX_REQUEST_RETRY_COUNT {
-            self.retry_count = 0;
-            self.phase = DhcpV4Phase::Discovery;
-            self.event_pool.add_timer(
-                Duration::from_secs(
-                    gen_dhcp_request_delay(self.retry_count).into(),
-                ),
-                DhcpV4Event::DiscoveryTimeout,
-            )?;
-            if let Some(raw_socket) = &self.raw_socket {
-                raw_socket
-                    .send(&self.gen_discovery_pkg().to_eth_pkg_broadcast()?)?;
-                Ok(None)
-            } else {
-                self.clean_up();
-                let e =
-                    DhcpError::new(ErrorKind::Bug, "No RAW socket".to_string());
-                log::error!("{}", e);
-                Err(e)
-            }
+        if self.retry_count > MAX_REQUEST_RETRY_COUNT {
+            self.fallback_to_discovery()?;
+            Ok(None)
         } else {
-            self.retry_count += 1;
             self.event_pool.add_timer(
                 Duration::from_secs(
                     gen_dhcp_request_delay(self.retry_count).into(),
                 ),
                 DhcpV4Event::RequestTimeout,
             )?;
+            self.retry_count += 1;
             if let Some(raw_socket) = &self.raw_socket {
                 if let Some(lease) = &self.lease {
                     raw_socket.send(
@@ -283,17 +1139,53 @@ impl DhcpV4Client {
         }
     }
 
+    // Start discovery again within the same lease acquisition
+    fn fallback_to_discovery(&mut self) -> Result<(), DhcpError> {
+        self.retry_count = 0;
+        self.new_transaction(DhcpV4Phase::Discovery);
+        self.candidates.clear();
+        self.pending_lease = None;
+        self.phase = DhcpV4Phase::Discovery;
+        reset_state_timer(&mut self.event_pool, &self.config)?;
+        self.event_pool
+            .add_timer(Duration::ZERO, DhcpV4Event::DiscoveryTimeout)
+    }
+
+    // RFC 2131: If the client receives a DHCPNAK message, the client restarts
+    // the configuration process.
+    fn process_nack(
+        &mut self,
+        e: DhcpError,
+    ) -> Result<Option<DhcpV4Lease>, DhcpError> {
+        self.push_state(DhcpV4State::Nacked(self.lease.clone()));
+        self.stop_arp_responder()?;
+        if !self.restart_allowed() {
+            self.clean_up();
+            log::error!("{}", e);
+            return Err(e);
+        }
+        log::warn!("{e}, restarting discovery");
+        if self.phase == DhcpV4Phase::Request {
+            self.event_pool.del_timer(DhcpV4Event::RequestTimeout)?;
+            self.lease = None;
+            self.fallback_to_discovery()?;
+        } else {
+            self.restart_discovery()?;
+        }
+        Ok(None)
+    }
+
     fn process_discovery_timeout(
         &mut self,
     ) -> Result<Option<DhcpV4Lease>, DhcpError> {
-        self.event_pool.del_timer(DhcpV4Event::RequestTimeout)?;
-        self.retry_count += 1;
+        self.event_pool.del_timer(DhcpV4Event::DiscoveryTimeout)?;
         self.event_pool.add_timer(
             Duration::from_secs(
                 gen_dhcp_request_delay(self.retry_count).into(),
             ),
             DhcpV4Event::DiscoveryTimeout,
         )?;
+        self.retry_count += 1;
         if let Some(raw_socket) = &self.raw_socket {
             raw_socket
                 .send(&self.gen_discovery_pkg().to_eth_pkg_broadcast()?)?;
@@ -306,9 +1198,35 @@ impl DhcpV4Client {
         }
     }
 
-    fn process_timeout(&mut self) -> Result<Option<DhcpV4Lease>, DhcpError> {
+    fn process_timeout(
+        &mut self,
+        event: DhcpV4Event,
+    ) -> Result<Option<DhcpV4Lease>, DhcpError> {
+        let phase = self.phase;
         self.clean_up();
-        let e = DhcpError::new(ErrorKind::Timeout, "Timeout".to_string());
+        let e = match event {
+            DhcpV4Event::StateTimeout => DhcpError::new(
+                ErrorKind::StateTimeout,
+                format!(
+                    "Timeout on {phase} state after {} seconds",
+                    self.config.state_timeout
+                ),
+            ),
+            DhcpV4Event::RenewTimeout => DhcpError::new(
+                ErrorKind::RenewTimeout,
+                format!(
+                    "Failed to renew lease within {} seconds",
+                    self.config.renew_timeout
+                ),
+            ),
+            _ => DhcpError::new(
+                ErrorKind::Timeout,
+                format!(
+                    "Failed to acquire lease within {} seconds",
+                    self.config.timeout
+                ),
+            ),
+        };
         log::error!("{}", e);
         Err(e)
     }
@@ -333,6 +1251,11 @@ impl DhcpV4Client {
             return Ok(None);
         }
 
+        if !is_retry {
+            self.new_transaction(DhcpV4Phase::Renew);
+        }
+        // The span entered by `process()` is the previous transaction
+        let _span = self.span.enter();
         let lease = if let Some(l) = self.lease.as_ref() {
             l
         } else {
@@ -344,49 +1267,97 @@ impl DhcpV4Client {
             log::error!("{}", e);
             return Err(e);
         };
-        let udp_socket = DhcpUdpSocket::new(
-            self.config.iface_name.as_str(),
-            &lease.yiaddr,
-            &lease.siaddr,
-            self.config.socket_timeout,
-        )?;
-
-        let mut dhcp_msg = DhcpV4Message::new(
-            &self.config,
-            DhcpV4MessageType::Request,
-            self.xid,
-        );
-        dhcp_msg.load_lease(lease.clone());
+        let mut dhcp_msg = self.gen_request_pkg(lease);
         dhcp_msg.renew_or_rebind(true);
-        udp_socket.send(&dhcp_msg.to_dhcp_pkg()?)?;
-        self.event_pool
-            .add_socket(udp_socket.as_raw_fd(), DhcpV4Event::UdpPackageIn)?;
-        self.udp_socket = Some(udp_socket);
+        // Close socket of previous renew attempt which holds the client port
+        if self.udp_socket.take().is_some() {
+            self.event_pool.del_socket(DhcpV4Event::UdpPackageIn)?;
+        }
+        // DHCP 4o6 indicates unicast by flag of DHCPv4-query instead
+        let udp_socket = if self.config.is_udp_socket_disabled() {
+            None
+        } else {
+            match DhcpUdpSocket::new(
+                &self.config,
+                &lease.yiaddr,
+                &lease.srv_ip(),
+            ) {
+                Ok(s) => Some(s),
+                Err(e) if e.kind() == ErrorKind::AddressInUse => {
+                    log::warn!("{e}, fallback to raw socket for renewing");
+                    None
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        if let Some(udp_socket) = udp_socket {
+            #[cfg(feature = "pcap")]
+            let udp_socket =
+                udp_socket.with_pcap(self.config.pcap_file.as_deref())?;
+            udp_socket.send(&dhcp_msg.to_dhcp_pkg()?)?;
+            self.event_pool.add_socket(
+                udp_socket.as_raw_fd(),
+                DhcpV4Event::UdpPackageIn,
+            )?;
+            self.udp_socket = Some(udp_socket);
+        } else {
+            self.close_raw_socket()?;
+            let raw_socket = DhcpV4Socket::new(&self.config)?;
+            raw_socket.send(&dhcp_msg.to_proxy_eth_pkg_unicast()?)?;
+            self.event_pool.add_socket(
+                raw_socket.as_raw_fd(),
+                DhcpV4Event::RawPackageIn,
+            )?;
+            self.raw_socket = Some(raw_socket);
+        }
         self.phase = DhcpV4Phase::Renew;
         self.retry_count = u32::from(is_retry);
+        if !is_retry {
+            self.push_state(DhcpV4State::Renewing);
+        }
         Ok(None)
     }
 
     fn process_renew_recv(&mut self) -> Result<Option<DhcpV4Lease>, DhcpError> {
-        let socket = if let Some(s) = self.udp_socket.as_ref() {
-            s
+        // Renew is unicast to the DHCP server granted current lease, reply
+        // from elsewhere is not trustworthy.
+        let srv_ip = self.lease.as_ref().map(|l| l.srv_ip());
+        // Raw socket is used when UDP client port is used by others
+        let reply = if let Some(s) = self.udp_socket.as_ref() {
+            recv_dhcp_msg(
+                s,
+                &self.config,
+                DhcpV4MessageType::Ack,
+                self.xid,
+                None,
+                srv_ip,
+            )
+        } else if let Some(s) = self.raw_socket.as_ref() {
+            recv_dhcp_msg(
+                s,
+                &self.config,
+                DhcpV4MessageType::Ack,
+                self.xid,
+                None,
+                srv_ip,
+            )
         } else {
             self.clean_up();
             let e = DhcpError::new(
                 ErrorKind::Bug,
-                "process_renew_recv(): No UDP socket".to_string(),
+                "process_renew_recv(): No socket".to_string(),
             );
             log::error!("{}", e);
             return Err(e);
         };
-        match recv_dhcp_msg(socket, DhcpV4MessageType::Ack, self.xid) {
+        match reply {
             Ok(Some(lease)) => {
                 self.clean_up();
-                self.lease = Some(lease.clone());
-                self.set_renew_rebind_timer(&lease)?;
+                self.bind_lease(&lease)?;
                 Ok(Some(lease))
             }
             Ok(None) => Ok(None),
+            Err(e) if e.kind() == ErrorKind::NoLease => self.process_nack(e),
             Err(e) => {
                 if self.retry_count == 0 {
                     log::warn!("DHCP renew failed: {}, will try", e);
@@ -408,6 +1379,14 @@ impl DhcpV4Client {
         } else {
             self.event_pool.del_timer(DhcpV4Event::Rebind)?;
         }
+        if self.config.udp_only {
+            self.clean_up();
+            return Err(udp_only_error("Rebinding DHCP lease"));
+        }
+        if !is_retry {
+            self.new_transaction(DhcpV4Phase::Rebind);
+        }
+        let _span = self.span.enter();
         let lease = if let Some(l) = self.lease.as_ref() {
             l
         } else {
@@ -419,20 +1398,21 @@ impl DhcpV4Client {
             log::error!("{}", e);
             return Err(e);
         };
-        let raw_socket = DhcpRawSocket::new(&self.config)?;
-        let mut dhcp_msg = DhcpV4Message::new(
-            &self.config,
-            DhcpV4MessageType::Request,
-            self.xid,
-        );
-        dhcp_msg.load_lease(lease.clone());
+        let mut dhcp_msg = self.gen_request_pkg(lease);
         dhcp_msg.renew_or_rebind(true);
-        raw_socket.send(&dhcp_msg.to_eth_pkg_broadcast()?)?;
+        self.close_raw_socket()?;
+        let raw_socket = DhcpV4Socket::new(&self.config)?;
         self.event_pool
             .add_socket(raw_socket.as_raw_fd(), DhcpV4Event::RawPackageIn)?;
-        self.raw_socket = Some(raw_socket);
         self.phase = DhcpV4Phase::Rebind;
         self.retry_count = u32::from(is_retry);
+        if !is_retry {
+            self.push_state(DhcpV4State::Rebinding);
+        }
+        // Keep the state consistent even sending failed, so retransmission
+        // could recover from transient failure.
+        let raw_socket = self.raw_socket.insert(raw_socket);
+        raw_socket.send(&dhcp_msg.to_eth_pkg_broadcast()?)?;
         Ok(None)
     }
 
@@ -450,14 +1430,21 @@ impl DhcpV4Client {
             log::error!("{}", e);
             return Err(e);
         };
-        match recv_dhcp_msg(socket, DhcpV4MessageType::Ack, self.xid) {
+        match recv_dhcp_msg(
+            socket,
+            &self.config,
+            DhcpV4MessageType::Ack,
+            self.xid,
+            None,
+            None,
+        ) {
             Ok(Some(lease)) => {
                 self.clean_up();
-                self.lease = Some(lease.clone());
-                self.set_renew_rebind_timer(&lease)?;
+                self.bind_lease(&lease)?;
                 Ok(Some(lease))
             }
             Ok(None) => Ok(None),
+            Err(e) if e.kind() == ErrorKind::NoLease => self.process_nack(e),
             Err(e) => {
                 if self.retry_count == 0 {
                     log::warn!("DHCP rebind failed: {}, will try", e);
@@ -477,27 +1464,91 @@ impl DhcpV4Client {
     fn process_lease_expired(
         &mut self,
     ) -> Result<Option<DhcpV4Lease>, DhcpError> {
+        if let Some(lease) = self.lease.clone() {
+            self.push_state(DhcpV4State::Expired(lease));
+        }
+        self.stop_arp_responder()?;
+        if !self.restart_allowed() {
+            self.clean_up();
+            let e = DhcpError::new(
+                ErrorKind::LeaseExpired,
+                "DHCP lease expired, not restarting discovery as per \
+                restart policy"
+                    .to_string(),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+        self.restart_discovery()?;
+        Ok(None)
+    }
+
+    fn restart_allowed(&mut self) -> bool {
+        if self.config.restart_policy.allows(self.restart_count) {
+            self.restart_count += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Drop current lease and start new lease acquisition
+    fn restart_discovery(&mut self) -> Result<(), DhcpError> {
         self.clean_up();
-        self.event_pool.add_timer(
-            Duration::from_secs(self.config.timeout.into()),
-            DhcpV4Event::Timeout,
-        )?;
-        let raw_socket = DhcpRawSocket::new(&self.config)?;
+        if self.config.udp_only {
+            return Err(udp_only_error("DHCP lease acquisition"));
+        }
+        start_acquisition_timers(&mut self.event_pool, &self.config)?;
+        let raw_socket = DhcpV4Socket::new(&self.config)?;
         self.event_pool
             .add_socket(raw_socket.as_raw_fd(), DhcpV4Event::RawPackageIn)?;
-        self.event_pool.add_timer(
-            Duration::from_secs(gen_dhcp_request_delay(0).into()),
-            DhcpV4Event::DiscoveryTimeout,
-        )?;
-        let dhcp_msg = DhcpV4Message::new(
-            &self.config,
-            DhcpV4MessageType::Discovery,
-            self.xid,
-        );
-        raw_socket.send(&dhcp_msg.to_eth_pkg_broadcast()?)?;
+        self.event_pool
+            .add_timer(Duration::ZERO, DhcpV4Event::DiscoveryTimeout)?;
+        self.candidates.clear();
+        self.new_transaction(DhcpV4Phase::Discovery);
         self.raw_socket = Some(raw_socket);
         self.phase = DhcpV4Phase::Discovery;
-        Ok(None)
+        Ok(())
+    }
+
+    /// Same as [DhcpV4Client::process()] but report what changed, so
+    /// caller does not need to compare leases to know when to reconfigure
+    /// the system. State transitions happened without returning lease
+    /// (e.g. entering renewing) are also included.
+    pub fn process_detailed(
+        &mut self,
+        event: DhcpV4Event,
+    ) -> Result<Vec<DhcpV4Progress>, DhcpError> {
+        self.track_states();
+        self.process(event)?;
+        let mut ret = Vec::new();
+        for state in self.take_states() {
+            let old_state = self.last_state.replace(state.clone());
+            match &state {
+                DhcpV4State::Bound(lease) => {
+                    let progress = match self.last_bound.replace(lease.clone())
+                    {
+                        Some(old) if old.yiaddr == lease.yiaddr => {
+                            DhcpV4Progress::LeaseRenewed {
+                                t1: Duration::from_secs(lease.t1.into()),
+                                t2: Duration::from_secs(lease.t2.into()),
+                            }
+                        }
+                        _ => DhcpV4Progress::LeaseAcquired(lease.clone()),
+                    };
+                    ret.push(DhcpV4Progress::StateChanged(old_state, state));
+                    ret.push(progress);
+                }
+                DhcpV4State::Nacked(_) | DhcpV4State::Expired(_) => {
+                    self.last_bound = None;
+                    ret.push(DhcpV4Progress::StateChanged(old_state, state));
+                }
+                _ => {
+                    ret.push(DhcpV4Progress::StateChanged(old_state, state));
+                }
+            }
+        }
+        Ok(ret)
     }
 
     pub fn process(
@@ -505,46 +1556,139 @@ impl DhcpV4Client {
         event: DhcpV4Event,
     ) -> Result<Option<DhcpV4Lease>, DhcpError> {
         log::debug!("Processing event {:?}", event);
+        let _span = self.span.enter();
         match event {
             DhcpV4Event::RawPackageIn => match self.phase {
                 DhcpV4Phase::Discovery => self.process_discovery(),
                 DhcpV4Phase::Request => self.process_request(),
+                DhcpV4Phase::Renew => self.process_renew_recv(),
                 DhcpV4Phase::Rebind => self.process_rebind_recv(),
-                _ => {
-                    log::error!(
-                        "BUG: Got in-coming packet on raw socket \
-                        with unexpected phase {}",
-                        self.phase
+                // Only timers should be armed once lease bound, stop waking
+                // up for packets matched by the BPF filter.
+                DhcpV4Phase::Done => {
+                    log::debug!(
+                        "Removing raw socket from event pool as no DHCP \
+                        exchange in progress"
                     );
+                    self.close_raw_socket()?;
                     Ok(None)
                 }
             },
             DhcpV4Event::UdpPackageIn => match self.phase {
                 DhcpV4Phase::Renew => self.process_renew_recv(),
+                // UDP socket is only used for renewing, stray packet would
+                // otherwise keep waking up the client.
                 _ => {
-                    log::error!(
-                        "BUG: Got in-coming packet on UDP socket \
-                        with unexpected phase {}",
+                    log::debug!(
+                        "Removing UDP socket from event pool as no renew \
+                        in progress, current phase {}",
                         self.phase
                     );
+                    if self.udp_socket.take().is_some() {
+                        self.event_pool
+                            .del_socket(DhcpV4Event::UdpPackageIn)?;
+                    }
                     Ok(None)
                 }
             },
             DhcpV4Event::RequestTimeout => self.process_request_timeout(),
             DhcpV4Event::DiscoveryTimeout => self.process_discovery_timeout(),
-            DhcpV4Event::Timeout => self.process_timeout(),
+            DhcpV4Event::Timeout
+            | DhcpV4Event::StateTimeout
+            | DhcpV4Event::RenewTimeout => self.process_timeout(event),
             DhcpV4Event::Renew => self.process_renew(NOT_RETRY),
             DhcpV4Event::RenewRetry => self.process_renew(IS_RETRY),
             DhcpV4Event::Rebind => self.process_rebind(NOT_RETRY),
             DhcpV4Event::RebindRetry => self.process_rebind(IS_RETRY),
             DhcpV4Event::LeaseExpired => self.process_lease_expired(),
+            DhcpV4Event::ArpPackageIn => self.process_gateway_probe_recv(),
+            DhcpV4Event::ArpRequestIn => {
+                self.process_arp_request()?;
+                Ok(None)
+            }
+            DhcpV4Event::GatewayProbeTimeout => {
+                self.process_gateway_probe_timeout()
+            }
+            #[cfg(feature = "gateway-monitor")]
+            DhcpV4Event::GatewayMonitorTimeout => {
+                self.process_gateway_monitor_timeout()
+            }
+            #[cfg(feature = "gateway-monitor")]
+            DhcpV4Event::GatewayMonitorArpIn => {
+                self.process_gateway_monitor_recv()?;
+                Ok(None)
+            }
+            #[cfg(not(feature = "gateway-monitor"))]
+            DhcpV4Event::GatewayMonitorTimeout
+            | DhcpV4Event::GatewayMonitorArpIn => Ok(None),
+        }
+    }
+
+    /// Notify the client that system has just resumed from suspend.
+    /// The lease lifetime will be revalidated against the time elapsed since
+    /// lease acquired(suspended time included). If the lease has expired, the
+    /// client starts DHCP discovery again; if T2 has passed, the client jumps
+    /// straight to rebind.
+    pub fn notify_resumed(&mut self) -> Result<(), DhcpError> {
+        let (lease, lease_begin) = match (self.lease.as_ref(), self.lease_begin)
+        {
+            (Some(l), Some(b)) if !l.is_infinite() => (l, b),
+            _ => return Ok(()),
+        };
+        let elapsed = boot_time()?.saturating_sub(lease_begin);
+        if elapsed >= Duration::from_secs(lease.lease_time.into()) {
+            log::info!(
+                "DHCP lease {} expired during suspend, starting discovery",
+                lease.yiaddr
+            );
+            self.process_lease_expired()?;
+        } else if elapsed >= Duration::from_secs(lease.t2.into())
+            && self.phase != DhcpV4Phase::Rebind
+        {
+            log::info!(
+                "DHCP lease {} passed T2 during suspend, starting rebind",
+                lease.yiaddr
+            );
+            self.event_pool.del_timer(DhcpV4Event::Renew)?;
+            self.event_pool.del_timer(DhcpV4Event::RenewRetry)?;
+            self.event_pool.del_socket(DhcpV4Event::UdpPackageIn)?;
+            self.udp_socket = None;
+            self.process_rebind(NOT_RETRY)?;
         }
+        Ok(())
     }
 
     /// Release the DHCPv4 lease.
+    /// The DHCPRELEASE is retransmitted if requested by
+    /// [DhcpV4Config::set_release_retransmits()]. Error is only returned when
+    /// none of the transmissions was sent.
     /// To request new lease once released, please create new instance of
     /// [DhcpV4Client].
+    /// The calling thread is blocked between the retransmissions, please use
+    /// [DhcpV4Client::release_force()] if that is not acceptable.
     pub fn release(&mut self, lease: &DhcpV4Lease) -> Result<(), DhcpError> {
+        let result = self.send_releases(lease, self.config.release_retransmits);
+        self.clean_up();
+        result
+    }
+
+    /// Send DHCPRELEASE once without retransmission regardless of
+    /// [DhcpV4Config::set_release_retransmits()], intended for shutdown path
+    /// which cannot afford blocking.
+    pub fn release_force(
+        &mut self,
+        lease: &DhcpV4Lease,
+    ) -> Result<(), DhcpError> {
+        let result = self.send_releases(lease, 0);
+        self.clean_up();
+        result
+    }
+
+    fn send_releases(
+        &mut self,
+        lease: &DhcpV4Lease,
+        retransmits: u8,
+    ) -> Result<(), DhcpError> {
         let mut dhcp_msg = DhcpV4Message::new(
             &self.config,
             DhcpV4MessageType::Release,
@@ -552,19 +1696,66 @@ impl DhcpV4Client {
         );
         dhcp_msg.load_lease(lease.clone());
 
-        if self.config.is_proxy {
-            let raw_socket = DhcpRawSocket::new(&self.config)?;
+        let tries = u32::from(retransmits) + 1;
+        let mut sent = 0;
+        let mut last_error = None;
+        for i in 0..tries {
+            if i > 0 {
+                std::thread::sleep(Duration::from_secs(
+                    gen_dhcp_request_delay(i - 1).into(),
+                ));
+            }
+            match self.send_release(&dhcp_msg, lease) {
+                Ok(()) => {
+                    sent += 1;
+                    log::info!(
+                        "DHCPRELEASE of {} sent to server {} ({}/{tries})",
+                        lease.yiaddr,
+                        lease.srv_id,
+                        i + 1
+                    );
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Failed to send DHCPRELEASE of {} ({}/{tries}): {e}",
+                        lease.yiaddr,
+                        i + 1
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) if sent == 0 => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn send_release(
+        &mut self,
+        dhcp_msg: &DhcpV4Message,
+        lease: &DhcpV4Lease,
+    ) -> Result<(), DhcpError> {
+        if self.config.is_udp_socket_disabled() {
+            self.close_raw_socket()?;
+            let raw_socket = DhcpV4Socket::new(&self.config)?;
             raw_socket.send(&dhcp_msg.to_proxy_eth_pkg_unicast()?)?;
         } else {
+            // Release the client port held by renew
+            if self.udp_socket.take().is_some() {
+                self.event_pool.del_socket(DhcpV4Event::UdpPackageIn)?;
+            }
             // Cannot create UDP socket when interface does not have DHCP IP
             // assigned, so we fallback to RAW socket
             match DhcpUdpSocket::new(
-                self.config.iface_name.as_str(),
+                &self.config,
                 &lease.yiaddr,
-                &lease.siaddr,
-                self.config.socket_timeout,
+                &lease.srv_ip(),
             ) {
                 Ok(udp_socket) => {
+                    #[cfg(feature = "pcap")]
+                    let udp_socket = udp_socket
+                        .with_pcap(self.config.pcap_file.as_deref())?;
                     udp_socket.send(&dhcp_msg.to_dhcp_pkg()?)?;
                 }
                 Err(e) => {
@@ -572,27 +1763,134 @@ impl DhcpV4Client {
                         "Failed to create UDP socket to release lease {e}, \
                         fallback to RAW socket"
                     );
-                    let raw_socket = DhcpRawSocket::new(&self.config)?;
+                    let raw_socket = DhcpV4Socket::new(&self.config)?;
                     raw_socket.send(&dhcp_msg.to_proxy_eth_pkg_unicast()?)?;
                 }
             }
         }
-        self.clean_up();
         Ok(())
     }
 }
 
+// UDP-only mode could only renew the specified lease via UDP socket
+fn check_udp_only(
+    config: &DhcpV4Config,
+    lease: Option<&DhcpV4Lease>,
+) -> Result<(), DhcpError> {
+    if lease.is_none() {
+        return Err(udp_only_error("DHCP lease acquisition"));
+    }
+    if config.is_udp_socket_disabled() && config.memory_socket.is_none() {
+        let e = DhcpError::new(
+            ErrorKind::InvalidArgument,
+            "UDP-only mode is not supported in proxy mode or DHCP 4o6"
+                .to_string(),
+        );
+        log::error!("{}", e);
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn udp_only_error(action: &str) -> DhcpError {
+    let e = DhcpError::new(
+        ErrorKind::RawSocketRequired,
+        format!(
+            "{action} requires raw socket which is disallowed in UDP-only mode"
+        ),
+    );
+    log::error!("{}", e);
+    e
+}
+
+// Overall timeout and per-state timeout on beginning of lease acquisition
+fn start_acquisition_timers(
+    event_pool: &mut DhcpEventPool<DhcpV4Event>,
+    config: &DhcpV4Config,
+) -> Result<(), DhcpError> {
+    if config.timeout > 0 {
+        event_pool.add_timer(
+            Duration::from_secs(config.timeout.into()),
+            DhcpV4Event::Timeout,
+        )?;
+    }
+    reset_state_timer(event_pool, config)
+}
+
+// Restart per-state timeout on entering new lease acquisition state
+fn reset_state_timer(
+    event_pool: &mut DhcpEventPool<DhcpV4Event>,
+    config: &DhcpV4Config,
+) -> Result<(), DhcpError> {
+    event_pool.del_timer(DhcpV4Event::StateTimeout)?;
+    if config.state_timeout > 0 {
+        event_pool.add_timer(
+            Duration::from_secs(config.state_timeout.into()),
+            DhcpV4Event::StateTimeout,
+        )?;
+    }
+    Ok(())
+}
+
+fn gen_trace_span(
+    config: &DhcpV4Config,
+    phase: DhcpV4Phase,
+    xid: u32,
+) -> DhcpTraceSpan {
+    DhcpTraceSpan::new(
+        "dhcpv4",
+        phase,
+        &config.iface_name,
+        format!("{xid:#010x}"),
+    )
+}
+
 fn recv_dhcp_msg(
     socket: &impl DhcpSocket,
+    config: &DhcpV4Config,
     expected: DhcpV4MessageType,
     xid: u32,
+    candidates: Option<&mut Vec<DhcpV4Lease>>,
+    srv_ip: Option<Ipv4Addr>,
 ) -> Result<Option<DhcpV4Lease>, DhcpError> {
-    let buffer: Vec<u8> = socket.recv()?;
-    let reply_dhcp_msg = if socket.is_raw() {
-        DhcpV4Message::from_eth_pkg(&buffer)?
+    let (buffer, src_addr) = socket.recv_from()?;
+    let mut reply_dhcp_msg = if socket.is_raw() {
+        DhcpV4Message::from_eth_pkg(&buffer, config.name_policy)?
     } else {
-        DhcpV4Message::from_dhcp_pkg(&buffer)?
+        DhcpV4Message::from_dhcp_pkg(&buffer, config.name_policy)?
     };
+    if let Some(lease) = reply_dhcp_msg.lease.as_mut() {
+        if let Some(SocketAddr::V4(src_addr)) = src_addr {
+            lease.src_addr = Some(src_addr);
+        }
+        lease.decode_custom_opts(config, &reply_dhcp_msg.raw_opts);
+    }
+    trace_msg_received(
+        &reply_dhcp_msg.msg_type,
+        format!("{:#010x}", reply_dhcp_msg.xid),
+        reply_dhcp_msg.lease.as_ref().map(|l| l.srv_id),
+    );
+    if let Some(src_addr) =
+        reply_dhcp_msg.lease.as_ref().and_then(|l| l.src_addr)
+    {
+        // Reply from both DHCP server and relay agent is sent from server
+        // port
+        if src_addr.port() != dhcproto::v4::SERVER_PORT {
+            log::debug!(
+                "Dropping DHCP message from {src_addr} which is not server \
+                port {}",
+                dhcproto::v4::SERVER_PORT
+            );
+            return Ok(None);
+        }
+        if let Some(srv_ip) = srv_ip.filter(|ip| ip != src_addr.ip()) {
+            log::debug!(
+                "Dropping DHCP message from {src_addr}, expecting reply from \
+                DHCP server {srv_ip}"
+            );
+            return Ok(None);
+        }
+    }
     if reply_dhcp_msg.xid != xid {
         log::debug!(
             "Dropping DHCP message due to xid miss-match. \
@@ -602,6 +1900,64 @@ fn recv_dhcp_msg(
         );
         return Ok(None);
     }
+    // Multiple clients on the same bridge may share the same xid by chance,
+    // the raw socket receives replies for all of them.
+    if !config.is_proxy && !config.src_mac.is_empty() {
+        let src_mac = mac_str_to_u8_array(&config.src_mac);
+        if !reply_dhcp_msg.chaddr.starts_with(&src_mac) {
+            log::debug!(
+                "Dropping DHCP message due to chaddr miss-match. \
+                Expecting {}, got {:?}",
+                config.src_mac,
+                reply_dhcp_msg.chaddr
+            );
+            return Ok(None);
+        }
+    }
+    if reply_dhcp_msg.is_client_id_mismatch(config) {
+        log::debug!("Dropping DHCP message due to client identifier mismatch");
+        return Ok(None);
+    }
+    if let Err(e) = apply_conformance_policy(
+        config.conformance_policy,
+        ErrorKind::InvalidDhcpServerReply,
+        &format!("DHCP {}", reply_dhcp_msg.msg_type),
+        &reply_dhcp_msg.violations,
+    ) {
+        log::debug!("Dropping DHCP message: {e}");
+        return Ok(None);
+    }
+    if let (Some(candidates), Some(lease)) =
+        (candidates, reply_dhcp_msg.lease.as_ref())
+    {
+        if reply_dhcp_msg.msg_type == DhcpV4MessageType::Offer {
+            let mut lease = lease.clone();
+            if let Err(e) = lease.validate_times(config) {
+                log::debug!("Dropping DHCP offer: {e}");
+                return Ok(None);
+            }
+            lease.sanitize(config);
+            // Replace retransmitted offer from the same server
+            candidates.retain(|l| l.srv_id != lease.srv_id);
+            candidates.push(lease);
+        }
+    }
+    if expected == DhcpV4MessageType::Ack
+        && reply_dhcp_msg.msg_type == DhcpV4MessageType::Nack
+    {
+        let e = DhcpError::new(
+            ErrorKind::NoLease,
+            format!(
+                "DHCP server {} refused the requested lease",
+                reply_dhcp_msg
+                    .lease
+                    .as_ref()
+                    .map(|l| l.srv_id.to_string())
+                    .unwrap_or_default()
+            ),
+        );
+        return Err(e);
+    }
     if reply_dhcp_msg.msg_type != expected {
         log::debug!(
             "Dropping DHCP message due to type miss-match.
@@ -611,7 +1967,12 @@ fn recv_dhcp_msg(
         );
         return Ok(None);
     }
-    if let Some(lease) = reply_dhcp_msg.lease {
+    if let Some(mut lease) = reply_dhcp_msg.lease {
+        if let Err(e) = lease.validate_times(config) {
+            log::warn!("Dropping DHCP {}: {e}", reply_dhcp_msg.msg_type);
+            return Ok(None);
+        }
+        lease.sanitize(config);
         Ok(Some(lease))
     } else {
         log::debug!(
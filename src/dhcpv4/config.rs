@@ -1,14 +1,32 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::net::{Ipv4Addr, Ipv6Addr};
+#[cfg(feature = "client")]
+use std::os::unix::io::RawFd;
+use std::sync::Arc;
+
+use crate::{
+    approver::DhcpLeaseApprover,
+    codec::DhcpCodec,
+    consts::{DEFAULT_SOCKET_TIMEOUT, MIN_MAX_MSG_SIZE},
+    identity::DhcpIdentity,
+    mac::mac_str_to_u8_array,
+    DhcpError, DhcpIdentityProvider, DhcpOptionCodec, DhcpOptionValue,
+    DhcpV4ClientProfile, DhcpV4Lease, ErrorKind, NamePolicy, RenewPolicy,
+    RestartPolicy,
+};
+#[cfg(feature = "client")]
 use crate::{
-    mac::mac_str_to_u8_array, nispor::get_nispor_iface,
-    socket::DEFAULT_SOCKET_TIMEOUT, DhcpError,
+    mac::is_eth_mac_address,
+    nispor::{get_ipv6_addr_of_iface, get_nispor_iface},
+    DhcpMemorySocket,
 };
 
 // https://www.iana.org/assignments/arp-parameters/arp-parameters.xhtml#arp-parameters-2
 const ARP_HW_TYPE_ETHERNET: u8 = 1;
 
 const DEFAULT_TIMEOUT: u32 = 120;
+const MAX_RELEASE_RETRANSMITS: u8 = 2;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct DhcpV4Config {
@@ -16,11 +34,62 @@ pub struct DhcpV4Config {
     pub(crate) iface_index: u32,
     pub(crate) src_mac: String,
     pub(crate) client_id: Vec<u8>,
+    pub(crate) send_client_id: bool,
+    pub(crate) verify_client_id: bool,
     pub(crate) host_name: String,
     // TODO: Support allow list and deny list for DHCP servers.
     pub(crate) timeout: u32,
+    pub(crate) state_timeout: u32,
+    pub(crate) renew_timeout: u32,
+    pub(crate) release_retransmits: u8,
+    pub(crate) restart_policy: RestartPolicy,
+    pub(crate) manual_commit: bool,
+    pub(crate) renew_policy: RenewPolicy,
     pub(crate) socket_timeout: u32,
     pub(crate) is_proxy: bool,
+    pub(crate) proxy_arp: bool,
+    pub(crate) lease_check_policy: DhcpV4LeaseCheckPolicy,
+    pub(crate) lease_validation: DhcpV4LeaseValidation,
+    pub(crate) conformance_policy: DhcpV4ConformancePolicy,
+    pub(crate) option_order: DhcpV4OptionOrder,
+    pub(crate) request_opts: Option<Vec<u8>>,
+    pub(crate) allow_default_route_on_link: bool,
+    pub(crate) name_policy: NamePolicy,
+    pub(crate) lease_approver: Option<DhcpLeaseApprover<DhcpV4Lease>>,
+    pub(crate) fast_resume_gateway_mac: Option<String>,
+    // Probe interval in seconds and unanswered probes allowed
+    #[cfg(feature = "gateway-monitor")]
+    pub(crate) gateway_monitor: Option<(u32, u32)>,
+    pub(crate) extra_opts: Vec<(u8, Vec<u8>)>,
+    pub(crate) dscp: u8,
+    pub(crate) socket_priority: Option<i32>,
+    pub(crate) udp_reuse_addr: bool,
+    #[cfg(feature = "client")]
+    pub(crate) raw_socket_fd: Option<RawFd>,
+    #[cfg(feature = "client")]
+    pub(crate) udp_socket_fd: Option<RawFd>,
+    #[cfg(feature = "client")]
+    pub(crate) udp_only: bool,
+    pub(crate) max_msg_size: Option<u16>,
+    pub(crate) preferred_ip: Option<Ipv4Addr>,
+    pub(crate) ipv6_only_preferred: bool,
+    pub(crate) request_timezone: bool,
+    pub(crate) request_6rd: bool,
+    pub(crate) request_sip_srvs: bool,
+    pub(crate) request_captive_portal: bool,
+    pub(crate) mud_url: Option<String>,
+    pub(crate) request_vendor_specific_infos: bool,
+    pub(crate) transport: DhcpV4Transport,
+    // IPv6 address of interface used by DHCP 4o6 transport
+    pub(crate) src_ip6: Ipv6Addr,
+    #[cfg(feature = "client")]
+    pub(crate) memory_socket: Option<DhcpMemorySocket>,
+    pub(crate) identity_provider: Option<DhcpIdentity>,
+    pub(crate) option_codecs: Vec<(u8, DhcpCodec)>,
+    #[cfg(feature = "chaos")]
+    pub(crate) chaos_policy: Option<crate::DhcpChaosPolicy>,
+    #[cfg(feature = "pcap")]
+    pub(crate) pcap_file: Option<String>,
 }
 
 impl Default for DhcpV4Config {
@@ -30,10 +99,59 @@ impl Default for DhcpV4Config {
             iface_index: 0,
             src_mac: String::new(),
             client_id: Vec::new(),
+            send_client_id: true,
+            verify_client_id: true,
             host_name: String::new(),
             timeout: DEFAULT_TIMEOUT,
+            state_timeout: 0,
+            renew_timeout: 0,
+            release_retransmits: 0,
+            restart_policy: RestartPolicy::default(),
+            manual_commit: false,
+            renew_policy: RenewPolicy::default(),
             socket_timeout: DEFAULT_SOCKET_TIMEOUT,
             is_proxy: false,
+            proxy_arp: false,
+            lease_check_policy: DhcpV4LeaseCheckPolicy::default(),
+            lease_validation: DhcpV4LeaseValidation::default(),
+            conformance_policy: DhcpV4ConformancePolicy::default(),
+            option_order: DhcpV4OptionOrder::default(),
+            request_opts: None,
+            allow_default_route_on_link: false,
+            name_policy: NamePolicy::default(),
+            lease_approver: None,
+            fast_resume_gateway_mac: None,
+            #[cfg(feature = "gateway-monitor")]
+            gateway_monitor: None,
+            extra_opts: Vec::new(),
+            dscp: 0,
+            socket_priority: None,
+            udp_reuse_addr: false,
+            #[cfg(feature = "client")]
+            raw_socket_fd: None,
+            #[cfg(feature = "client")]
+            udp_socket_fd: None,
+            #[cfg(feature = "client")]
+            udp_only: false,
+            max_msg_size: None,
+            preferred_ip: None,
+            ipv6_only_preferred: false,
+            request_timezone: false,
+            request_6rd: false,
+            request_sip_srvs: false,
+            request_captive_portal: false,
+            mud_url: None,
+            request_vendor_specific_infos: false,
+            transport: DhcpV4Transport::default(),
+            src_ip6: Ipv6Addr::UNSPECIFIED,
+            #[cfg(feature = "client")]
+            memory_socket: None,
+            identity_provider: None,
+            option_codecs: Vec::new(),
+            #[cfg(feature = "chaos")]
+            chaos_policy: None,
+            #[cfg(feature = "pcap")]
+            pcap_file: None,
         }
     }
 }
@@ -46,12 +164,147 @@ impl DhcpV4Config {
         }
     }
 
+    pub fn iface_name(&self) -> &str {
+        self.iface_name.as_str()
+    }
+
+    /// Interface index, resolved when client initialized.
+    pub fn iface_index(&self) -> u32 {
+        self.iface_index
+    }
+
+    /// MAC address of interface(or the proxied MAC address in proxy mode),
+    /// resolved when client initialized.
+    pub fn mac_address(&self) -> &str {
+        self.src_mac.as_str()
+    }
+
+    pub fn client_id(&self) -> &[u8] {
+        self.client_id.as_slice()
+    }
+
+    pub fn host_name(&self) -> &str {
+        self.host_name.as_str()
+    }
+
+    // Apply the fields allowed to be changed on running client: timeouts,
+    // restart policy, request options, host name and extra options.
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub(crate) fn update(&mut self, new: &Self) -> Result<(), DhcpError> {
+        let mut config = self.clone();
+        config.timeout = new.timeout;
+        config.state_timeout = new.state_timeout;
+        config.renew_timeout = new.renew_timeout;
+        config.restart_policy = new.restart_policy;
+        config.request_opts.clone_from(&new.request_opts);
+        config.host_name.clone_from(&new.host_name);
+        config.extra_opts.clone_from(&new.extra_opts);
+        if &config != new {
+            let e = DhcpError::new(
+                ErrorKind::InvalidArgument,
+                "Only timeouts, restart policy, request options, host name \
+                and extra options are allowed to be changed on running DHCP \
+                client"
+                    .to_string(),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+        *self = config;
+        Ok(())
+    }
+
+    #[cfg(feature = "client")]
     // Check whether interface exists and resolve iface_index and MAC
     pub(crate) fn init(&mut self) -> Result<(), DhcpError> {
-        let np_iface = get_nispor_iface(self.iface_name.as_str(), false)?;
+        if self.memory_socket.is_some() {
+            let mac = self.src_mac.clone();
+            return self.init_identity(&mac);
+        }
+        let np_iface = get_nispor_iface(
+            self.iface_name.as_str(),
+            self.transport != DhcpV4Transport::Native,
+        )?;
+        self.init_with_iface(&np_iface)
+    }
+
+    #[cfg(feature = "client")]
+    // Resolve iface_index and MAC(and IPv6 address for DHCP 4o6) from
+    // interface information retrieved by caller
+    pub(crate) fn init_with_iface(
+        &mut self,
+        np_iface: &nispor::Iface,
+    ) -> Result<(), DhcpError> {
+        if self.memory_socket.is_some() {
+            let mac = self.src_mac.clone();
+            return self.init_identity(&mac);
+        }
         self.iface_index = np_iface.index;
+        if self.transport != DhcpV4Transport::Native {
+            if self.is_proxy {
+                let e = DhcpError::new(
+                    ErrorKind::InvalidArgument,
+                    "DHCP 4o6 transport is not supported in proxy mode"
+                        .to_string(),
+                );
+                log::error!("{}", e);
+                return Err(e);
+            }
+            self.src_ip6 = get_ipv6_addr_of_iface(np_iface)?;
+        }
+        let mac = if self.is_proxy {
+            self.src_mac.clone()
+        } else if is_eth_mac_address(&np_iface.mac_address) {
+            np_iface.mac_address.clone()
+        } else {
+            String::new()
+        };
+        self.init_identity(&mac)?;
         if !self.is_proxy {
-            self.src_mac = np_iface.mac_address;
+            if is_eth_mac_address(&np_iface.mac_address) {
+                self.src_mac = np_iface.mac_address.clone();
+            } else {
+                // RFC 2131: 4.2 DHCP server administrative controls
+                //      the client identifier MUST be unique ...
+                // Without hardware address, server could only identify
+                // client via client identifier.
+                if self.client_id.is_empty() || !self.send_client_id {
+                    let e = DhcpError::new(
+                        ErrorKind::InvalidArgument,
+                        format!(
+                            "Interface {} has no ethernet MAC address, please \
+                            define client identifier via \
+                            DhcpV4Config::set_client_id() or \
+                            DhcpV4Config::use_host_name_as_client_id()",
+                            self.iface_name
+                        ),
+                    );
+                    log::error!("{}", e);
+                    return Err(e);
+                }
+                log::info!(
+                    "Interface {} has no ethernet MAC address, using client \
+                    identifier only",
+                    self.iface_name
+                );
+                self.src_mac = String::new();
+            }
+        }
+        Ok(())
+    }
+
+    // Query client identifier from identity provider unless defined by
+    // `set_client_id()` or omitted by `clear_client_id()`
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    fn init_identity(&mut self, mac: &str) -> Result<(), DhcpError> {
+        if let Some(provider) = self.identity_provider.as_ref() {
+            if self.send_client_id && self.client_id.is_empty() {
+                if let Some(client_id) =
+                    provider.0.client_id(&self.iface_name, mac)?
+                {
+                    self.client_id = client_id;
+                }
+            }
         }
         Ok(())
     }
@@ -65,18 +318,88 @@ impl DhcpV4Config {
         }
     }
 
-    // Set timeout in seconds
+    /// In proxy mode, reply ARP requests for the leased address with the
+    /// proxied MAC address while lease is held, so that the address is
+    /// reachable without anything else answering ARP for it. Ignored when
+    /// not in proxy mode. Default is false.
+    pub fn set_proxy_arp(&mut self, enabled: bool) -> &mut Self {
+        self.proxy_arp = enabled;
+        self
+    }
+
+    /// Set overall timeout in seconds of lease acquisition, counting from
+    /// client initialized or lease expired till lease acquired. Renewing and
+    /// rebinding are not included. On expiry, [ErrorKind::Timeout] is
+    /// returned by [crate::DhcpV4Client::process()]. 0 means no timeout.
+    /// Default is 120.
     pub fn set_timeout(&mut self, timeout: u32) -> &mut Self {
         self.timeout = timeout;
         self
     }
 
+    /// Set timeout in seconds of each lease acquisition state(discovery or
+    /// request), restarted on entering new state. On expiry,
+    /// [ErrorKind::StateTimeout] is returned by
+    /// [crate::DhcpV4Client::process()]. 0 means no timeout. Default is 0.
+    pub fn set_state_timeout(&mut self, timeout: u32) -> &mut Self {
+        self.state_timeout = timeout;
+        self
+    }
+
+    /// Set timeout in seconds of lease renewal, counting from T1. If lease
+    /// is not extended via renewing or rebinding within this time,
+    /// [ErrorKind::RenewTimeout] is returned by
+    /// [crate::DhcpV4Client::process()]. 0 means no timeout, client starts
+    /// discovery once lease expired. Default is 0.
+    pub fn set_renew_timeout(&mut self, timeout: u32) -> &mut Self {
+        self.renew_timeout = timeout;
+        self
+    }
+
+    /// Set how many times DHCPRELEASE is retransmitted by
+    /// [crate::DhcpV4Client::release()], spaced by RFC 2131 retransmission
+    /// delay(about 4 seconds). As server never replies to DHCPRELEASE,
+    /// retransmitting helps server missing the first one to free the address
+    /// at the cost of blocking the release. Value bigger than 2 is treated
+    /// as 2. Default is 0.
+    pub fn set_release_retransmits(&mut self, count: u8) -> &mut Self {
+        self.release_retransmits = count.min(MAX_RELEASE_RETRANSMITS);
+        self
+    }
+
+    /// Set policy on whether client starts discovery again once lease
+    /// expired or refused by server. Default is [RestartPolicy::Forever].
+    pub fn set_restart_policy(&mut self, policy: RestartPolicy) -> &mut Self {
+        self.restart_policy = policy;
+        self
+    }
+
+    /// When enabled, newly acquired lease returned by
+    /// [crate::DhcpV4Client::process()] is not bound till caller applied it
+    /// to the system and invoked [crate::DhcpV4Client::commit_lease()]: no
+    /// renew timer is scheduled and no ARP is answered in the meantime. If
+    /// applying failed, [crate::DhcpV4Client::decline_lease()] sends
+    /// DHCPDECLINE and starts discovery again. Lease extended by renewing
+    /// or rebinding is bound immediately. Default is false.
+    pub fn set_manual_commit(&mut self, enabled: bool) -> &mut Self {
+        self.manual_commit = enabled;
+        self
+    }
+
+    /// Set when to start lease renewal instead of T1 from DHCP server.
+    /// Default is [RenewPolicy::ServerT1].
+    pub fn set_renew_policy(&mut self, policy: RenewPolicy) -> &mut Self {
+        self.renew_policy = policy;
+        self
+    }
+
     pub fn set_host_name(&mut self, host_name: &str) -> &mut Self {
         self.host_name = host_name.to_string();
         self
     }
 
     pub fn use_mac_as_client_id(&mut self) -> &mut Self {
+        self.send_client_id = true;
         self.client_id = vec![ARP_HW_TYPE_ETHERNET];
         self.client_id
             .append(&mut mac_str_to_u8_array(&self.src_mac));
@@ -101,8 +424,469 @@ impl DhcpV4Config {
         client_id: &[u8],
     ) -> &mut Self {
         // RFC 2132: 9.14. Client-identifier
+        self.send_client_id = true;
         self.client_id = vec![client_id_type];
         self.client_id.extend_from_slice(client_id);
         self
     }
+
+    /// Omit the Client Identifier(61) option, server identifies client by
+    /// hardware address(chaddr) only. Useful for servers mishandling this
+    /// option. Interface without ethernet MAC address cannot use this mode.
+    /// Invoking [DhcpV4Config::set_client_id()] or
+    /// [DhcpV4Config::use_mac_as_client_id()] afterwards sends the option
+    /// again.
+    pub fn clear_client_id(&mut self) -> &mut Self {
+        self.send_client_id = false;
+        self.client_id = Vec::new();
+        self
+    }
+
+    /// Whether to drop server reply holding Client Identifier(61) option
+    /// not identical to the one sent, as required by RFC 6842. Reply without
+    /// this option is always accepted. Disable it for servers echoing
+    /// altered client identifier. Default is `true`.
+    pub fn set_verify_client_id(&mut self, enabled: bool) -> &mut Self {
+        self.verify_client_id = enabled;
+        self
+    }
+
+    /// Query client identifier from specified provider when client
+    /// initialized, unless defined by [DhcpV4Config::set_client_id()] or
+    /// omitted by [DhcpV4Config::clear_client_id()].
+    pub fn set_identity_provider(
+        &mut self,
+        provider: Arc<dyn DhcpIdentityProvider>,
+    ) -> &mut Self {
+        self.identity_provider = Some(DhcpIdentity(provider));
+        self
+    }
+
+    /// Decode option of specified code in DHCP server reply using the
+    /// codec, stored as [crate::DhcpV4Option::Custom] in
+    /// [DhcpV4Lease::custom_opts]. The code is also requested in Parameter
+    /// Request List(55). Registering the same code again replaces the
+    /// previous codec.
+    pub fn register_option_codec(
+        &mut self,
+        code: u8,
+        codec: Arc<dyn DhcpOptionCodec>,
+    ) -> &mut Self {
+        self.option_codecs.retain(|(c, _)| *c != code);
+        self.option_codecs.push((code, DhcpCodec(codec)));
+        self
+    }
+
+    /// Set how to handle invalid MTU and classless routes in server reply.
+    /// Default is [DhcpV4LeaseCheckPolicy::Warn].
+    pub fn set_lease_check_policy(
+        &mut self,
+        policy: DhcpV4LeaseCheckPolicy,
+    ) -> &mut Self {
+        self.lease_check_policy = policy;
+        self
+    }
+
+    /// Set how to handle server reply missing Renewal Time(58) or Rebinding
+    /// Time(59) option, or not meeting T1 <= T2 <= lease time.
+    /// Default is [DhcpV4LeaseValidation::Permissive].
+    pub fn set_lease_validation(
+        &mut self,
+        validation: DhcpV4LeaseValidation,
+    ) -> &mut Self {
+        self.lease_validation = validation;
+        self
+    }
+
+    /// Set how to handle fixed header fields(`op`, `ciaddr`, `yiaddr`,
+    /// `siaddr`, `giaddr`, `secs` and `flags`) not conforming to RFC 2131
+    /// in emitted and received DHCP messages.
+    /// Default is [DhcpV4ConformancePolicy::Permissive].
+    pub fn set_conformance_policy(
+        &mut self,
+        policy: DhcpV4ConformancePolicy,
+    ) -> &mut Self {
+        self.conformance_policy = policy;
+        self
+    }
+
+    /// Whether to allow default route via 0.0.0.0 in Classless Static
+    /// Route option. Default is false.
+    pub fn set_allow_default_route_on_link(
+        &mut self,
+        value: bool,
+    ) -> &mut Self {
+        self.allow_default_route_on_link = value;
+        self
+    }
+
+    /// Set how to handle Host Name(12) and Domain Name(15) option in server
+    /// reply which is not valid UTF-8 or not safe to be used as DNS name.
+    /// Default is [NamePolicy::Raw].
+    pub fn set_name_policy(&mut self, policy: NamePolicy) -> &mut Self {
+        self.name_policy = policy;
+        self
+    }
+
+    /// Set callback to approve the lease offered by DHCP server before
+    /// requesting it. When callback returns false, the offer is ignored and
+    /// client keeps waiting for offers from other DHCP servers.
+    pub fn set_lease_approver(
+        &mut self,
+        approver: Arc<dyn Fn(&DhcpV4Lease) -> bool + Send + Sync>,
+    ) -> &mut Self {
+        self.lease_approver = Some(DhcpLeaseApprover(approver));
+        self
+    }
+
+    /// When initializing client with previous lease, probe the default
+    /// gateway of that lease via ARP. If gateway replied with specified MAC
+    /// address, the lease is returned as bound immediately while the client
+    /// keeps confirming the lease with DHCP server in the background.
+    pub fn set_fast_resume(&mut self, gateway_mac: &str) -> &mut Self {
+        self.fast_resume_gateway_mac = Some(gateway_mac.to_string());
+        self
+    }
+
+    /// Probe the default gateway of bound lease via ARP every `interval`
+    /// seconds. Once `max_failures`(at least 1) probes in a row are not
+    /// answered, the client rebinds the lease immediately instead of
+    /// waiting for T2, so it recovers quickly when upstream failed over to
+    /// another DHCP server. Interval 0 disables the monitor. Default is
+    /// disabled.
+    #[cfg(feature = "gateway-monitor")]
+    pub fn set_gateway_monitor(
+        &mut self,
+        interval: u32,
+        max_failures: u32,
+    ) -> &mut Self {
+        self.gateway_monitor =
+            (interval > 0).then_some((interval, max_failures.max(1)));
+        self
+    }
+
+    /// Set DSCP(0-63) of outgoing DHCP packets. Default is 0.
+    pub fn set_dscp(&mut self, dscp: u8) -> &mut Self {
+        self.dscp = dscp & 0x3f;
+        self
+    }
+
+    /// Set SO_PRIORITY of raw socket used for sending DHCP packets, which
+    /// could be mapped to VLAN priority or traffic control class.
+    pub fn set_socket_priority(&mut self, priority: i32) -> &mut Self {
+        self.socket_priority = Some(priority);
+        self
+    }
+
+    /// Set SO_REUSEADDR and SO_REUSEPORT on the UDP socket bound to DHCP
+    /// client port 68 for renewing lease, allowing port shared with other
+    /// DHCP clients. When the port is still used by others, client falls back
+    /// to raw socket for renewing. Default is false.
+    pub fn set_udp_reuse_addr(&mut self, value: bool) -> &mut Self {
+        self.udp_reuse_addr = value;
+        self
+    }
+
+    #[cfg(feature = "client")]
+    /// Use pre-opened `AF_PACKET` raw socket instead of creating one, so
+    /// client could run without `CAP_NET_RAW` when socket is passed from
+    /// privileged helper or systemd socket activation. The socket should be
+    /// bound to the interface already, the client will attach its BPF filter
+    /// to it. The fd is duplicated on use, caller should keep it open till
+    /// client is dropped. The ARP socket used by
+    /// [crate::DhcpV4LeaseCheckPolicy] is still created by client.
+    pub fn set_raw_socket_fd(&mut self, fd: RawFd) -> &mut Self {
+        self.raw_socket_fd = Some(fd);
+        self
+    }
+
+    #[cfg(feature = "client")]
+    /// Use pre-opened UDP socket bound to DHCP client port 68 for renewing
+    /// and releasing lease instead of creating one, so client could run
+    /// without `CAP_NET_BIND_SERVICE`. The fd is duplicated on use, caller
+    /// should keep it open till client is dropped.
+    pub fn set_udp_socket_fd(&mut self, fd: RawFd) -> &mut Self {
+        self.udp_socket_fd = Some(fd);
+        self
+    }
+
+    #[cfg(feature = "client")]
+    /// Never open `AF_PACKET` raw socket, so client holding a valid lease
+    /// could renew it without `CAP_NET_RAW`, e.g. short-lived process
+    /// refreshing lease persisted by others. The lease passed to
+    /// [crate::DhcpV4Client::init()] is renewed immediately via UDP socket,
+    /// while rebinding, lease acquisition after DHCPNAK or lease expiry and
+    /// gateway monitor fail with [ErrorKind::RawSocketRequired]. Not
+    /// supported in proxy mode or with [DhcpV4Transport::Dhcp4o6].
+    /// Default is false.
+    pub fn set_udp_only(&mut self, enabled: bool) -> &mut Self {
+        self.udp_only = enabled;
+        self
+    }
+
+    /// Set the maximum size of IP package could be received, e.g. 9000 for
+    /// jumbo frame network. The value is also sent to DHCP server via
+    /// Maximum DHCP Message Size(57) option. Value smaller than 576 is
+    /// treated as 576. Default is 1500 without option 57 sent.
+    pub fn set_max_msg_size(&mut self, size: u16) -> &mut Self {
+        self.max_msg_size = Some(size.max(MIN_MAX_MSG_SIZE));
+        self
+    }
+
+    /// Request the specified IPv4 address via Requested IP Address(50)
+    /// option in DHCPDISCOVER, e.g. the address used before when only the
+    /// address instead of the whole [DhcpV4Lease] is remembered. Server may
+    /// still offer a different address. Default is not requesting any.
+    pub fn set_preferred_ip(&mut self, ip: Ipv4Addr) -> &mut Self {
+        self.preferred_ip = Some(ip);
+        self
+    }
+
+    /// Send RFC 8520 Manufacturer Usage Description URL(161) option in
+    /// DHCPDISCOVER and DHCPREQUEST. The URL should use https scheme and be
+    /// no longer than 255 characters, otherwise
+    /// [ErrorKind::InvalidArgument] is returned when generating the message.
+    pub fn set_mud_url(&mut self, url: &str) -> &mut Self {
+        self.mud_url = Some(url.to_string());
+        self
+    }
+
+    /// Request RFC 8925 IPv6-Only Preferred(108) option, indicating this
+    /// host could operate in IPv6-only mode. When DHCP server offers it,
+    /// client stops with [crate::DhcpV4State::Ipv6OnlyPreferred] and
+    /// [ErrorKind::Ipv6OnlyPreferred] instead of requesting the lease.
+    /// Default is false.
+    pub fn set_ipv6_only_preferred(&mut self, enabled: bool) -> &mut Self {
+        self.ipv6_only_preferred = enabled;
+        self
+    }
+
+    /// Set the order of options in messages sent, e.g. mimicking other DHCP
+    /// clients for middleboxes fingerprinting them. Default is
+    /// [DhcpV4OptionOrder::Sorted].
+    pub fn set_option_order(&mut self, order: DhcpV4OptionOrder) -> &mut Self {
+        self.option_order = order;
+        self
+    }
+
+    /// Mimic well known DHCP client by overriding the option order, options
+    /// requested, Vendor Class Identifier(60) and host name behavior, please
+    /// refer to [DhcpV4ClientProfile] for detail. Setters invoked afterwards
+    /// could still override the profile.
+    pub fn set_client_profile(
+        &mut self,
+        profile: DhcpV4ClientProfile,
+    ) -> &mut Self {
+        profile.apply(self);
+        self
+    }
+
+    /// Set the options to request in Parameter Request List(55) option in
+    /// the given order, replacing the built-in list. Options enabled by
+    /// other setters like [DhcpV4Config::set_request_timezone()] or
+    /// registered codecs are appended if not included.
+    pub fn set_request_opts(&mut self, opts: &[u8]) -> &mut Self {
+        self.request_opts = Some(opts.to_vec());
+        self
+    }
+
+    /// Request RFC 4833 PCode(100) and TCode(101) timezone options, stored
+    /// in [DhcpV4Lease::tz_posix] and [DhcpV4Lease::tz_database]. Default
+    /// is false.
+    pub fn set_request_timezone(&mut self, enabled: bool) -> &mut Self {
+        self.request_timezone = enabled;
+        self
+    }
+
+    /// Request RFC 5969 6rd(212) option, stored in
+    /// [DhcpV4Lease::six_rd]. Default is false.
+    pub fn set_request_6rd(&mut self, enabled: bool) -> &mut Self {
+        self.request_6rd = enabled;
+        self
+    }
+
+    /// Request RFC 3361 SIP Servers(120) option, stored in
+    /// [DhcpV4Lease::sip_srvs]. Default is false.
+    pub fn set_request_sip_srvs(&mut self, enabled: bool) -> &mut Self {
+        self.request_sip_srvs = enabled;
+        self
+    }
+
+    /// Request RFC 8910 Captive-Portal(114) option, stored in
+    /// [DhcpV4Lease::captive_portal]. Default is false.
+    pub fn set_request_captive_portal(&mut self, enabled: bool) -> &mut Self {
+        self.request_captive_portal = enabled;
+        self
+    }
+
+    /// Request RFC 3925 Vendor-Identifying Vendor-Specific Information(125)
+    /// option, stored in [DhcpV4Lease::vendor_specific_infos]. Default is
+    /// false.
+    pub fn set_request_vendor_specific_infos(
+        &mut self,
+        enabled: bool,
+    ) -> &mut Self {
+        self.request_vendor_specific_infos = enabled;
+        self
+    }
+
+    /// Store every DHCP packet sent or received by client into specified
+    /// pcapng file, appending new section if file already exists. Packets
+    /// sent or received via UDP socket are stored with generated IP and UDP
+    /// headers.
+    #[cfg(feature = "pcap")]
+    pub fn set_pcap_file(&mut self, path: &str) -> &mut Self {
+        self.pcap_file = Some(path.to_string());
+        self
+    }
+
+    /// Set how DHCPv4 messages are transported, default is
+    /// [DhcpV4Transport::Native].
+    pub fn set_transport(&mut self, transport: DhcpV4Transport) -> &mut Self {
+        self.transport = transport;
+        self
+    }
+
+    #[cfg(feature = "client")]
+    /// Exchange DHCP messages via in-memory socket instead of network
+    /// interface, the interface is not queried and specified MAC address is
+    /// used. Intended for testing client against scripted DHCP server
+    /// replies, please refer to [DhcpMemorySocket].
+    pub fn set_memory_socket(
+        &mut self,
+        socket: DhcpMemorySocket,
+        mac_address: &str,
+    ) -> &mut Self {
+        self.memory_socket = Some(socket);
+        self.src_mac = mac_address.to_string();
+        self
+    }
+
+    /// Inject faults into DHCPv4 messages sent by client, only intended for
+    /// testing.
+    #[cfg(feature = "chaos")]
+    pub fn set_chaos_policy(
+        &mut self,
+        policy: crate::DhcpChaosPolicy,
+    ) -> &mut Self {
+        self.chaos_policy = Some(policy);
+        self
+    }
+
+    #[cfg(feature = "client")]
+    // Whether all messages including unicast ones are sent via
+    // `DhcpV4Socket`, UDP socket is not used.
+    pub(crate) fn is_udp_socket_disabled(&self) -> bool {
+        #[cfg(feature = "chaos")]
+        if self.chaos_policy.is_some() {
+            return true;
+        }
+        self.is_proxy
+            || self.transport != DhcpV4Transport::Native
+            || self.memory_socket.is_some()
+    }
+
+    /// Include specified option in DISCOVER, REQUEST and RELEASE messages,
+    /// overriding the built-in option with the same code. Option data longer
+    /// than 255 bytes is split into multiple option instances as RFC 3396
+    /// required. Adding the same option code again replaces its data.
+    pub fn add_extra_option(&mut self, code: u8, data: &[u8]) -> &mut Self {
+        if let Some((_, exist)) =
+            self.extra_opts.iter_mut().find(|(c, _)| *c == code)
+        {
+            *exist = data.to_vec();
+        } else {
+            self.extra_opts.push((code, data.to_vec()));
+        }
+        self
+    }
+
+    /// Include option encoded by [DhcpOptionValue::encode()], please refer
+    /// to [DhcpV4Config::add_extra_option()] for details.
+    pub fn add_custom_option(
+        &mut self,
+        code: u8,
+        value: Box<dyn DhcpOptionValue>,
+    ) -> &mut Self {
+        self.add_extra_option(code, &value.encode())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum DhcpV4Transport {
+    /// Broadcast via raw socket, unicast via UDP socket
+    #[default]
+    Native,
+    /// RFC 7341 DHCPv4-over-DHCPv6(DHCP 4o6) for IPv6-only access network:
+    /// DHCPv4 messages are encapsulated in DHCPv4-query sent from DHCPv6
+    /// client port to the specified DHCP 4o6 server, or to
+    /// All_DHCP_Relay_Agents_and_Servers(ff02::1:2) when not specified.
+    /// As DHCPv6 client port is used, this cannot run along with
+    /// [crate::DhcpV6Client] on the same interface.
+    Dhcp4o6(Option<Ipv6Addr>),
+}
+
+/// Policy on values of DHCP server reply failed the sanity check, the
+/// violations are always stored in [crate::DhcpV4Lease::warnings] unless
+/// [DhcpV4LeaseCheckPolicy::Ignore] is used.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum DhcpV4LeaseCheckPolicy {
+    /// Remove invalid value from lease
+    Strip,
+    /// Keep invalid value in lease
+    #[default]
+    Warn,
+    /// Do not check at all
+    Ignore,
+}
+
+/// Order of options in DHCP messages sent by client. DHCP Message Type(53)
+/// is always the first and Relay Agent Information(82) is always the last
+/// as RFC 3046 required.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[non_exhaustive]
+pub enum DhcpV4OptionOrder {
+    /// Ascending order of option code, reproducible regardless of how
+    /// options are added
+    #[default]
+    Sorted,
+    /// Built-in options in the order of message construction, followed by
+    /// options added by [DhcpV4Config::add_extra_option()] and
+    /// [crate::DhcpV4Message::add_option()] in the order of adding
+    Insertion,
+    /// Specified option codes first in given order, then others in the
+    /// order of [DhcpV4OptionOrder::Insertion]
+    Custom(Vec<u8>),
+}
+
+/// Handling of DHCP message fields not conforming to RFC 2131, e.g.
+/// non-zero `ciaddr` in DHCPDISCOVER or non-zero `secs` in server reply.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum DhcpV4ConformancePolicy {
+    /// No validation
+    #[default]
+    Permissive,
+    /// Log violations as warning
+    Warn,
+    /// Refuse to emit message with violation and drop received one
+    Strict,
+}
+
+/// Validation level of lease timers in DHCP server reply. Reply without IP
+/// Address Lease Time(51) option is always dropped.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum DhcpV4LeaseValidation {
+    /// Drop reply missing Renewal Time(58) or Rebinding Time(59) option or
+    /// not meeting T1 <= T2 <= lease time
+    Strict,
+    /// Use RFC 2131 default T1 of 50% and T2 of 87.5% of lease time when
+    /// Renewal Time(58) or Rebinding Time(59) option is missing, cap T1 and
+    /// T2 with [crate::DhcpV4LeaseWarning::InvalidTimers] recorded when
+    /// not meeting T1 <= T2 <= lease time
+    #[default]
+    Permissive,
 }
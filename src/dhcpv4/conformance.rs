@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv4Addr;
+
+use dhcproto::v4;
+
+use crate::{DhcpError, DhcpV4ConformancePolicy, ErrorKind};
+
+// RFC 2131: 2. Protocol Summary
+//      The remaining bits of the flags field are reserved for future use.
+//      They MUST be set to zero by clients and ignored by servers and relay
+//      agents.
+const RESERVED_FLAGS_MASK: u16 = 0x7fff;
+
+// RFC 2131: 4.4.1 Table 5: Fields and options used by DHCP clients
+// The `with_ciaddr` indicates client holds the address: REQUEST in BOUND,
+// RENEWING or REBINDING state, RELEASE and INFORM.
+pub(crate) fn check_client_msg(
+    msg: &v4::Message,
+    with_ciaddr: bool,
+) -> Vec<String> {
+    let mut ret = Vec::new();
+    let msg_type = msg.opts().msg_type();
+    if msg.opcode() != v4::Opcode::BootRequest {
+        ret.push(format!("op {:?} is not BOOTREQUEST", msg.opcode()));
+    }
+    if with_ciaddr && msg.ciaddr().is_unspecified() {
+        ret.push("ciaddr is 0 while client holds the address".to_string());
+    } else if !with_ciaddr && !msg.ciaddr().is_unspecified() {
+        ret.push(format!(
+            "ciaddr {} is not 0 while client holds no address",
+            msg.ciaddr()
+        ));
+    }
+    for (name, ip) in [
+        ("yiaddr", msg.yiaddr()),
+        ("siaddr", msg.siaddr()),
+        ("giaddr", msg.giaddr()),
+    ] {
+        check_zero_ip(&mut ret, name, ip);
+    }
+    let flags = u16::from(msg.flags());
+    if flags & RESERVED_FLAGS_MASK != 0 {
+        ret.push(format!("reserved bits of flags {flags:#06x} are not 0"));
+    }
+    if matches!(
+        msg_type,
+        Some(v4::MessageType::Decline | v4::MessageType::Release)
+    ) {
+        if msg.secs() != 0 {
+            ret.push(format!("secs {} is not 0", msg.secs()));
+        }
+        if flags != 0 {
+            ret.push(format!("flags {flags:#06x} is not 0"));
+        }
+    }
+    ret
+}
+
+// RFC 2131: 4.3.1 Table 3: Fields and options used by DHCP servers
+pub(crate) fn check_server_msg(msg: &v4::Message) -> Vec<String> {
+    let mut ret = Vec::new();
+    if msg.opcode() != v4::Opcode::BootReply {
+        ret.push(format!("op {:?} is not BOOTREPLY", msg.opcode()));
+    }
+    if msg.secs() != 0 {
+        ret.push(format!("secs {} is not 0", msg.secs()));
+    }
+    match msg.opts().msg_type() {
+        Some(v4::MessageType::Offer) => {
+            check_zero_ip(&mut ret, "ciaddr", msg.ciaddr());
+            if msg.yiaddr().is_unspecified() {
+                ret.push("yiaddr of DHCPOFFER is 0".to_string());
+            }
+        }
+        Some(v4::MessageType::Nak) => {
+            for (name, ip) in [
+                ("ciaddr", msg.ciaddr()),
+                ("yiaddr", msg.yiaddr()),
+                ("siaddr", msg.siaddr()),
+            ] {
+                check_zero_ip(&mut ret, name, ip);
+            }
+        }
+        _ => (),
+    }
+    ret
+}
+
+fn check_zero_ip(violations: &mut Vec<String>, name: &str, ip: Ipv4Addr) {
+    if !ip.is_unspecified() {
+        violations.push(format!("{name} {ip} is not 0"));
+    }
+}
+
+// Log the violations or turn them into error as per policy
+pub(crate) fn apply_conformance_policy(
+    policy: DhcpV4ConformancePolicy,
+    kind: ErrorKind,
+    msg_desc: &str,
+    violations: &[String],
+) -> Result<(), DhcpError> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    match policy {
+        DhcpV4ConformancePolicy::Permissive => Ok(()),
+        DhcpV4ConformancePolicy::Warn => {
+            log::warn!(
+                "{msg_desc} does not conform to RFC 2131: {}",
+                violations.join(", ")
+            );
+            Ok(())
+        }
+        DhcpV4ConformancePolicy::Strict => Err(DhcpError::new(
+            kind,
+            format!(
+                "{msg_desc} does not conform to RFC 2131: {}",
+                violations.join(", ")
+            ),
+        )),
+    }
+}
@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use crate::{
+    socket::{sealed::Sealed, DhcpSocket, DhcpUdpSocket},
+    DhcpError, DhcpV4Config, ErrorKind,
+};
+
+// RFC 7341: 6. New DHCPv6 Messages
+const DHCPV4_QUERY: u8 = 20;
+const DHCPV4_RESPONSE: u8 = 21;
+// RFC 7341: 7.1. DHCPv4 Message Option Format
+const OPTION_DHCPV4_MSG: u16 = 87;
+// RFC 7341: 6.2. The "flags" field of DHCPv4-query
+//      U: Unicast flag. If set to 1, this indicates that the DHCPv4 message
+//      encapsulated within the DHCPv4-query message would be sent to a
+//      unicast address if it were sent using IPv4.
+const DHCPV4_QUERY_FLAG_UNICAST: u8 = 0x80;
+const DHCPV4_QUERY_HEADER_LEN: usize = 4;
+
+const ALL_DHCP_RELAY_AGENTS_AND_SERVERS: Ipv6Addr =
+    Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 1, 2);
+
+// RFC 7341 DHCPv4-over-DHCPv6 transport. Like raw socket, it takes ethernet
+// frame to send, but DHCPv4 message without any header is received.
+#[derive(Debug)]
+pub(crate) struct Dhcp4o6Socket {
+    socket: DhcpUdpSocket,
+    srv_ip: Ipv6Addr,
+}
+
+impl AsRawFd for Dhcp4o6Socket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl Dhcp4o6Socket {
+    pub(crate) fn new(
+        config: &DhcpV4Config,
+        srv_ip: Option<Ipv6Addr>,
+    ) -> Result<Self, DhcpError> {
+        let socket = DhcpUdpSocket::new_dhcp4o6(config)?;
+        #[cfg(feature = "pcap")]
+        let socket = socket.with_pcap(config.pcap_file.as_deref())?;
+        Ok(Self {
+            socket,
+            srv_ip: srv_ip.unwrap_or(ALL_DHCP_RELAY_AGENTS_AND_SERVERS),
+        })
+    }
+}
+
+impl Sealed for Dhcp4o6Socket {}
+
+impl DhcpSocket for Dhcp4o6Socket {
+    fn is_raw(&self) -> bool {
+        false
+    }
+
+    fn send(&self, eth_pkg: &[u8]) -> Result<(), DhcpError> {
+        let pkg = etherparse::SlicedPacket::from_ethernet(eth_pkg).map_err(
+            |error| {
+                let e = DhcpError::new(
+                    ErrorKind::Bug,
+                    format!(
+                        "Failed to parse ethernet package for DHCP 4o6: \
+                        {error}"
+                    ),
+                );
+                log::error!("{}", e);
+                e
+            },
+        )?;
+        let unicast = match pkg.ip.as_ref() {
+            Some(etherparse::InternetSlice::Ipv4(ip_header, _)) => {
+                ip_header.destination_addr() != Ipv4Addr::BROADCAST
+            }
+            _ => false,
+        };
+        self.socket
+            .send_to_v6(&self.srv_ip, &gen_dhcpv4_query(pkg.payload, unicast))
+    }
+
+    fn recv(&self) -> Result<Vec<u8>, DhcpError> {
+        Ok(self.recv_from()?.0)
+    }
+
+    fn recv_from(&self) -> Result<(Vec<u8>, Option<SocketAddr>), DhcpError> {
+        let (buffer, src) = self.socket.recv_from()?;
+        Ok((parse_dhcpv4_response(&buffer)?, src))
+    }
+}
+
+// Encapsulate DHCPv4 message into DHCPv4-query message
+pub(crate) fn gen_dhcpv4_query(dhcp_pkg: &[u8], unicast: bool) -> Vec<u8> {
+    let mut ret = vec![
+        DHCPV4_QUERY,
+        if unicast {
+            DHCPV4_QUERY_FLAG_UNICAST
+        } else {
+            0
+        },
+        0,
+        0,
+    ];
+    ret.extend_from_slice(&OPTION_DHCPV4_MSG.to_be_bytes());
+    ret.extend_from_slice(
+        &u16::try_from(dhcp_pkg.len())
+            .unwrap_or(u16::MAX)
+            .to_be_bytes(),
+    );
+    ret.extend_from_slice(dhcp_pkg);
+    ret
+}
+
+// Take DHCPv4 message out of DHCPv4-response message
+pub(crate) fn parse_dhcpv4_response(data: &[u8]) -> Result<Vec<u8>, DhcpError> {
+    if data.first() != Some(&DHCPV4_RESPONSE) {
+        let e = DhcpError::new(
+            ErrorKind::InvalidDhcpServerReply,
+            format!(
+                "Expecting DHCPv4-response message({DHCPV4_RESPONSE}), got \
+                message type {:?}",
+                data.first()
+            ),
+        );
+        log::debug!("{}", e);
+        return Err(e);
+    }
+    let mut opts = data.get(DHCPV4_QUERY_HEADER_LEN..).unwrap_or_default();
+    while opts.len() >= 4 {
+        let code = u16::from_be_bytes([opts[0], opts[1]]);
+        let len = usize::from(u16::from_be_bytes([opts[2], opts[3]]));
+        let Some(opt_data) = opts.get(4..4 + len) else {
+            break;
+        };
+        if code == OPTION_DHCPV4_MSG {
+            return Ok(opt_data.to_vec());
+        }
+        opts = &opts[4 + len..];
+    }
+    let e = DhcpError::new(
+        ErrorKind::InvalidDhcpServerReply,
+        "No valid DHCPv4 Message option in DHCPv4-response".to_string(),
+    );
+    log::debug!("{}", e);
+    Err(e)
+}
@@ -5,6 +5,7 @@ use std::convert::TryFrom;
 use crate::{event::DhcpEvent, DhcpError, ErrorKind};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DhcpV4Event {
     RawPackageIn = 1,
     UdpPackageIn,
@@ -16,6 +17,13 @@ pub enum DhcpV4Event {
     Rebind,
     RebindRetry,
     LeaseExpired,
+    ArpPackageIn,
+    GatewayProbeTimeout,
+    StateTimeout,
+    RenewTimeout,
+    ArpRequestIn,
+    GatewayMonitorTimeout,
+    GatewayMonitorArpIn,
 }
 
 impl From<DhcpV4Event> for u64 {
@@ -40,6 +48,19 @@ impl TryFrom<u64> for DhcpV4Event {
             x if x == Self::Rebind as u64 => Ok(Self::Rebind),
             x if x == Self::RebindRetry as u64 => Ok(Self::RebindRetry),
             x if x == Self::LeaseExpired as u64 => Ok(Self::LeaseExpired),
+            x if x == Self::ArpPackageIn as u64 => Ok(Self::ArpPackageIn),
+            x if x == Self::GatewayProbeTimeout as u64 => {
+                Ok(Self::GatewayProbeTimeout)
+            }
+            x if x == Self::StateTimeout as u64 => Ok(Self::StateTimeout),
+            x if x == Self::RenewTimeout as u64 => Ok(Self::RenewTimeout),
+            x if x == Self::ArpRequestIn as u64 => Ok(Self::ArpRequestIn),
+            x if x == Self::GatewayMonitorTimeout as u64 => {
+                Ok(Self::GatewayMonitorTimeout)
+            }
+            x if x == Self::GatewayMonitorArpIn as u64 => {
+                Ok(Self::GatewayMonitorArpIn)
+            }
             _ => {
                 let e = DhcpError::new(
                     ErrorKind::Bug,
@@ -68,6 +89,13 @@ impl std::fmt::Display for DhcpV4Event {
                 Self::Rebind => "Rebind",
                 Self::RebindRetry => "RebindRetry",
                 Self::LeaseExpired => "LeaseExpired",
+                Self::ArpPackageIn => "ArpPackageIn",
+                Self::GatewayProbeTimeout => "GatewayProbeTimeout",
+                Self::StateTimeout => "StateTimeout",
+                Self::RenewTimeout => "RenewTimeout",
+                Self::ArpRequestIn => "ArpRequestIn",
+                Self::GatewayMonitorTimeout => "GatewayMonitorTimeout",
+                Self::GatewayMonitorArpIn => "GatewayMonitorArpIn",
             }
         )
     }
@@ -1,15 +1,57 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4};
 
 use dhcproto::{v4, v4::DhcpOption};
 
-use crate::DhcpError;
+use crate::{
+    mud::{parse_mud_url, OPTION_V4_MUD_URL},
+    name::decode_dns_names,
+    DhcpError, DhcpLifetime, DhcpOptionValue, DhcpV4Config,
+    DhcpV4LeaseCheckPolicy, DhcpV4LeaseValidation, ErrorKind,
+};
+
+// RFC 791: Every internet module must be able to forward a datagram of 68
+// octets without further fragmentation, and every internet destination must
+// be able to receive a datagram of 576 octets either in one piece or in
+// fragments to be reassembled. We treat anything lower as server mistake.
+const MIN_MTU: u16 = 576;
+
+// Microsoft Classless Static Route Option, identical format to RFC 3442
+pub(crate) const OPTION_MS_CLASSLESS_STATIC_ROUTE: u8 = 249;
+
+// RFC 8925 IPv6-Only Preferred Option. dhcproto wrongly assigns code 106
+// to it.
+pub(crate) const OPTION_IPV6_ONLY_PREFERRED: u8 = 108;
+
+// RFC 4833 Timezone Options
+pub(crate) const OPTION_PCODE: u8 = 100;
+pub(crate) const OPTION_TCODE: u8 = 101;
+
+// RFC 5969 IPv6 Rapid Deployment on IPv4 Infrastructures(6rd) Option
+pub(crate) const OPTION_6RD: u8 = 212;
+// RFC 5986 Access Network Domain Name Option
+const OPTION_V4_ACCESS_DOMAIN: u8 = 213;
+
+// RFC 3361 SIP Servers DHCP Option
+pub(crate) const OPTION_SIP_SERVERS: u8 = 120;
+// RFC 3925 Vendor-Identifying Vendor-Specific Information Option
+pub(crate) const OPTION_VENDOR_IDENTIFYING_VENDOR_SPECIFIC_INFO: u8 = 125;
+
+// RFC 8910: 2. Captive Portal API URI indicating no captive portal
+const CAPTIVE_PORTAL_UNRESTRICTED: &str =
+    "urn:ietf:params:capport:unrestricted";
+
+// RFC 3361: Encoding byte of SIP Servers option
+const SIP_SERVERS_ENC_DOMAIN: u8 = 0;
+const SIP_SERVERS_ENC_ADDRESS: u8 = 1;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DhcpV4Lease {
     // Required for sending DHCPRELEASE in proxy mode
     pub(crate) srv_mac: [u8; 6],
+    pub(crate) xid: u32,
     pub siaddr: Ipv4Addr,
     pub yiaddr: Ipv4Addr,
     pub t1: u32,
@@ -21,9 +63,57 @@ pub struct DhcpV4Lease {
     pub dns_srvs: Option<Vec<Ipv4Addr>>,
     pub gateways: Option<Vec<Ipv4Addr>>,
     pub ntp_srvs: Option<Vec<Ipv4Addr>>,
+    /// RFC 868 Time Servers(4)
+    pub time_srvs: Option<Vec<Ipv4Addr>>,
+    /// Time Offset(2) of client subnet from UTC in seconds
+    pub time_offset: Option<i32>,
+    /// RFC 4833 PCode(100): IEEE 1003.1 TZ string, e.g.
+    /// `EST5EDT4,M3.2.0/02:00,M11.1.0/02:00`. Only received when requested
+    /// by [DhcpV4Config::set_request_timezone()].
+    pub tz_posix: Option<String>,
+    /// RFC 4833 TCode(101): Name of entry in TZ database, e.g.
+    /// `Europe/Zurich`. Only received when requested by
+    /// [DhcpV4Config::set_request_timezone()].
+    pub tz_database: Option<String>,
     pub mtu: Option<u16>,
     pub host_name: Option<String>,
     pub domain_name: Option<String>,
+    /// RFC 3442 Classless Static Route(121)
+    pub classless_routes: Option<Vec<DhcpV4ClasslessRoute>>,
+    /// Microsoft Classless Static Route(249), only used by
+    /// [DhcpV4Lease::effective_routes()] when option 121 is absent.
+    pub ms_classless_routes: Option<Vec<DhcpV4ClasslessRoute>>,
+    /// RFC 3361 SIP Servers(120), only received when requested by
+    /// [DhcpV4Config::set_request_sip_srvs()].
+    pub sip_srvs: Option<DhcpV4SipServers>,
+    /// RFC 3925 Vendor-Identifying Vendor-Specific Information(125), only
+    /// received when requested by
+    /// [DhcpV4Config::set_request_vendor_specific_infos()].
+    pub vendor_specific_infos: Option<Vec<DhcpV4VendorSpecificInfo>>,
+    /// RFC 8520 Manufacturer Usage Description URL(161)
+    pub mud_url: Option<String>,
+    /// RFC 5969 6rd(212), only received when requested by
+    /// [DhcpV4Config::set_request_6rd()].
+    pub six_rd: Option<DhcpV4SixRd>,
+    /// RFC 5986 Access Network Domain Name(213)
+    pub access_domain: Option<String>,
+    /// RFC 8925 IPv6-Only Preferred(108) V6ONLY_WAIT in seconds, only
+    /// received when requested by
+    /// [DhcpV4Config::set_ipv6_only_preferred()].
+    pub ipv6_only_wait: Option<u32>,
+    /// RFC 8910 Captive-Portal(114) API URI, only received when requested
+    /// by [DhcpV4Config::set_request_captive_portal()].
+    pub captive_portal: Option<String>,
+    /// Options decoded by codecs registered via
+    /// [DhcpV4Config::register_option_codec()].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub custom_opts: Vec<DhcpV4Option>,
+    /// Problems found in the DHCP server reply when sanity checking the
+    /// lease against [DhcpV4Config] policy.
+    pub warnings: Vec<DhcpV4LeaseWarning>,
+    /// Source IP address and UDP port of the DHCP server reply carrying
+    /// this lease.
+    pub src_addr: Option<SocketAddrV4>,
     // TODO: We should save the unsupported DHCP options for external parser.
     //pub other_dhcp_opts: Vec<DhcpV4UnknownOption>,
 }
@@ -32,6 +122,7 @@ impl Default for DhcpV4Lease {
     fn default() -> Self {
         Self {
             srv_mac: [u8::MAX; 6],
+            xid: 0,
             siaddr: Ipv4Addr::new(0, 0, 0, 0),
             yiaddr: Ipv4Addr::new(0, 0, 0, 0),
             t1: 0,
@@ -43,9 +134,25 @@ impl Default for DhcpV4Lease {
             dns_srvs: None,
             gateways: None,
             ntp_srvs: None,
+            time_srvs: None,
+            time_offset: None,
+            tz_posix: None,
+            tz_database: None,
             mtu: None,
             host_name: None,
             domain_name: None,
+            classless_routes: None,
+            ms_classless_routes: None,
+            sip_srvs: None,
+            vendor_specific_infos: None,
+            mud_url: None,
+            captive_portal: None,
+            ipv6_only_wait: None,
+            six_rd: None,
+            access_domain: None,
+            custom_opts: Vec::new(),
+            warnings: Vec::new(),
+            src_addr: None,
         }
     }
 }
@@ -54,6 +161,7 @@ impl std::convert::TryFrom<&v4::Message> for DhcpV4Lease {
     type Error = DhcpError;
     fn try_from(v4_dhcp_msg: &v4::Message) -> Result<Self, Self::Error> {
         let mut ret = Self {
+            xid: v4_dhcp_msg.xid(),
             siaddr: v4_dhcp_msg.siaddr(),
             yiaddr: v4_dhcp_msg.yiaddr(),
             ..Default::default()
@@ -91,18 +199,770 @@ impl std::convert::TryFrom<&v4::Message> for DhcpV4Lease {
                 DhcpOption::NtpServers(v) => {
                     ret.ntp_srvs = Some(v.clone());
                 }
+                DhcpOption::TimeServer(v) => {
+                    ret.time_srvs = Some(v.clone());
+                }
+                DhcpOption::TimeOffset(v) => {
+                    ret.time_offset = Some(*v);
+                }
                 DhcpOption::Hostname(v) => {
                     ret.host_name = Some(v.to_string());
                 }
                 DhcpOption::DomainName(v) => {
                     ret.domain_name = Some(v.to_string());
                 }
+                DhcpOption::ClasslessStaticRoute(v) => {
+                    ret.classless_routes = Some(
+                        v.iter()
+                            .map(|(dst, router)| {
+                                DhcpV4ClasslessRoute::new(
+                                    dst.addr(),
+                                    dst.prefix_len(),
+                                    *router,
+                                )
+                            })
+                            .collect(),
+                    );
+                }
+                DhcpOption::Unknown(v)
+                    if u8::from(v.code())
+                        == OPTION_MS_CLASSLESS_STATIC_ROUTE =>
+                {
+                    ret.ms_classless_routes = parse_classless_routes(v.data());
+                }
+                DhcpOption::Unknown(v)
+                    if u8::from(v.code()) == OPTION_PCODE =>
+                {
+                    ret.tz_posix = parse_tz_str(v.data());
+                }
+                DhcpOption::Unknown(v)
+                    if u8::from(v.code()) == OPTION_TCODE =>
+                {
+                    ret.tz_database = parse_tz_str(v.data());
+                }
+                DhcpOption::Unknown(v) if u8::from(v.code()) == OPTION_6RD => {
+                    ret.six_rd = parse_6rd(v.data());
+                }
+                DhcpOption::Unknown(v)
+                    if u8::from(v.code()) == OPTION_V4_ACCESS_DOMAIN =>
+                {
+                    ret.access_domain = parse_dns_names(v.data())
+                        .and_then(|names| names.into_iter().next());
+                }
+                DhcpOption::Unknown(v)
+                    if u8::from(v.code()) == OPTION_SIP_SERVERS =>
+                {
+                    ret.sip_srvs = parse_sip_srvs(v.data());
+                }
+                DhcpOption::Unknown(v)
+                    if u8::from(v.code())
+                        == OPTION_VENDOR_IDENTIFYING_VENDOR_SPECIFIC_INFO =>
+                {
+                    ret.vendor_specific_infos =
+                        parse_vendor_specific_infos(v.data());
+                }
+                DhcpOption::Unknown(v)
+                    if u8::from(v.code()) == OPTION_IPV6_ONLY_PREFERRED =>
+                {
+                    ret.ipv6_only_wait = <[u8; 4]>::try_from(v.data())
+                        .ok()
+                        .map(u32::from_be_bytes);
+                }
+                DhcpOption::Unknown(v)
+                    if u8::from(v.code()) == OPTION_V4_MUD_URL =>
+                {
+                    ret.mud_url = parse_mud_url(v.data());
+                }
                 v => {
                     log::debug!("Unsupported DHCP opt {:?}", v);
                 }
             }
         }
-        // TODO: Validate T1 < T2 < lease_time.
         Ok(ret)
     }
 }
+
+impl DhcpV4Lease {
+    /// Transaction ID of the DHCP reply carrying this lease.
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    // Address of DHCP server granted this lease
+    pub(crate) fn srv_ip(&self) -> Ipv4Addr {
+        if self.srv_id.is_unspecified() {
+            self.siaddr
+        } else {
+            self.srv_id
+        }
+    }
+
+    /// Whether lease time is infinite(0xffffffff), no renew or rebind will
+    /// be scheduled for it.
+    pub fn is_infinite(&self) -> bool {
+        self.lease_lifetime().is_infinite()
+    }
+
+    /// [DhcpV4Lease::lease_time] with infinity represented explicitly.
+    pub fn lease_lifetime(&self) -> DhcpLifetime {
+        self.lease_time.into()
+    }
+
+    /// [DhcpV4Lease::t1] with infinity represented explicitly.
+    pub fn t1_lifetime(&self) -> DhcpLifetime {
+        self.t1.into()
+    }
+
+    /// [DhcpV4Lease::t2] with infinity represented explicitly.
+    pub fn t2_lifetime(&self) -> DhcpLifetime {
+        self.t2.into()
+    }
+
+    /// URI of captive portal API, `None` if not provided by DHCP server or
+    /// server indicated no captive portal.
+    pub fn captive_portal_url(&self) -> Option<&str> {
+        self.captive_portal
+            .as_deref()
+            .filter(|u| *u != CAPTIVE_PORTAL_UNRESTRICTED)
+    }
+
+    /// MAC address of the DHCP server(or relay) sending this lease,
+    /// `ff:ff:ff:ff:ff:ff` when unknown(e.g. received from UDP socket).
+    pub fn server_mac(&self) -> [u8; 6] {
+        self.srv_mac
+    }
+
+    /// Set MAC address of the DHCP server(or relay) granted this lease, for
+    /// restoring externally persisted lease which should be released in
+    /// proxy mode, as DHCPRELEASE is unicast to this MAC address.
+    pub fn with_server_mac(mut self, mac: [u8; 6]) -> Self {
+        self.srv_mac = mac;
+        self
+    }
+
+    /// Routes should be applied to system following RFC 3442:
+    ///  * When Classless Static Route(121) exists, Router(3) option is
+    ///    ignored.
+    ///  * Fallback to Microsoft Classless Static Route(249) if option 121
+    ///    not found.
+    ///  * Otherwise, default routes via each router in Router(3) option.
+    pub fn effective_routes(&self) -> Vec<DhcpV4ClasslessRoute> {
+        if let Some(routes) = self
+            .classless_routes
+            .as_ref()
+            .or(self.ms_classless_routes.as_ref())
+        {
+            routes.clone()
+        } else if let Some(gateways) = self.gateways.as_ref() {
+            gateways
+                .iter()
+                .map(|gw| {
+                    DhcpV4ClasslessRoute::new(Ipv4Addr::UNSPECIFIED, 0, *gw)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    // Decode options in DHCP server reply with registered codecs
+    pub(crate) fn decode_custom_opts(
+        &mut self,
+        config: &DhcpV4Config,
+        raw_opts: &[(u8, Vec<u8>)],
+    ) {
+        self.custom_opts = config
+            .option_codecs
+            .iter()
+            .filter_map(|(code, codec)| {
+                raw_opts
+                    .iter()
+                    .find(|(c, _)| c == code)
+                    .and_then(|(_, data)| codec.decode((*code).into(), data))
+                    .map(|v| DhcpV4Option::Custom(*code, v))
+            })
+            .collect();
+    }
+
+    // Check T1, T2 and lease time against validation level defined in
+    // config. In permissive mode, missing T1 and T2 are filled with RFC 2131
+    // defaults and T1 > T2 > lease time is normalized with warning.
+    pub(crate) fn validate_times(
+        &mut self,
+        config: &DhcpV4Config,
+    ) -> Result<(), DhcpError> {
+        // Lease will not be used when RFC 8925 IPv6-Only Preferred applies
+        if config.ipv6_only_preferred && self.ipv6_only_wait.is_some() {
+            return Ok(());
+        }
+        let msg = if self.lease_time == 0 {
+            Some("DHCP server reply has no lease time".to_string())
+        } else if config.lease_validation == DhcpV4LeaseValidation::Strict
+            && (self.t1 == 0 || self.t2 == 0)
+        {
+            Some(format!(
+                "DHCP server reply has no T1({}) or T2({})",
+                self.t1, self.t2
+            ))
+        } else {
+            None
+        };
+        if let Some(msg) = msg {
+            return Err(DhcpError::new(ErrorKind::InvalidDhcpServerReply, msg));
+        }
+        if self.t1 == 0 {
+            log::debug!("Using default T1 as DHCP server reply has none");
+            self.t1 = if self.is_infinite() {
+                DhcpLifetime::Infinite.into()
+            } else {
+                self.lease_time / 2
+            };
+        }
+        if self.t2 == 0 {
+            log::debug!("Using default T2 as DHCP server reply has none");
+            self.t2 = if self.is_infinite() {
+                DhcpLifetime::Infinite.into()
+            } else {
+                u32::try_from(u64::from(self.lease_time) * 7 / 8).unwrap_or(0)
+            };
+        }
+        if self.t1 > self.t2 || self.t2 > self.lease_time {
+            if config.lease_validation == DhcpV4LeaseValidation::Strict {
+                return Err(DhcpError::new(
+                    ErrorKind::InvalidDhcpServerReply,
+                    format!(
+                        "DHCP server reply should have T1({}) <= T2({}) <= \
+                        lease time({})",
+                        self.t1, self.t2, self.lease_time
+                    ),
+                ));
+            }
+            self.add_warning(DhcpV4LeaseWarning::InvalidTimers {
+                t1: self.t1,
+                t2: self.t2,
+                lease_time: self.lease_time,
+            });
+            if self.t2 > self.lease_time {
+                self.t2 = u32::try_from(u64::from(self.lease_time) * 7 / 8)
+                    .unwrap_or(0);
+            }
+            if self.t1 > self.t2 {
+                self.t1 = (self.lease_time / 2).min(self.t2);
+            }
+        }
+        Ok(())
+    }
+
+    // Check the lease against policy defined in config, invalid values are
+    // recorded in `warnings` and removed from lease when policy requested so.
+    pub(crate) fn sanitize(&mut self, config: &DhcpV4Config) {
+        if config.lease_check_policy == DhcpV4LeaseCheckPolicy::Ignore {
+            return;
+        }
+        let strip = config.lease_check_policy == DhcpV4LeaseCheckPolicy::Strip;
+
+        if let Some(mtu) = self.mtu {
+            if mtu < MIN_MTU {
+                self.add_warning(DhcpV4LeaseWarning::InvalidMtu(mtu));
+                if strip {
+                    self.mtu = None;
+                }
+            }
+        }
+
+        #[cfg(feature = "validate-names")]
+        self.sanitize_names(strip);
+
+        if let Some(routes) = self.classless_routes.take() {
+            self.classless_routes = Some(self.sanitize_routes(routes, config));
+        }
+        if let Some(routes) = self.ms_classless_routes.take() {
+            self.ms_classless_routes =
+                Some(self.sanitize_routes(routes, config));
+        }
+        if self.classless_routes.is_some() && self.gateways.is_some() {
+            log::debug!(
+                "Router option is ignored for route as DHCP server \
+                replied with Classless Static Route option"
+            );
+        }
+    }
+
+    fn sanitize_routes(
+        &mut self,
+        routes: Vec<DhcpV4ClasslessRoute>,
+        config: &DhcpV4Config,
+    ) -> Vec<DhcpV4ClasslessRoute> {
+        let strip = config.lease_check_policy == DhcpV4LeaseCheckPolicy::Strip;
+        let mut valid_routes = Vec::new();
+        for route in routes.iter() {
+            let warning = if route.is_default() && route.router.is_unspecified()
+            {
+                if config.allow_default_route_on_link {
+                    None
+                } else {
+                    Some(DhcpV4LeaseWarning::DefaultRouteOnLink)
+                }
+            } else if !self.is_on_link(&route.router, &routes) {
+                Some(DhcpV4LeaseWarning::RouterNotOnLink(route.clone()))
+            } else {
+                None
+            };
+            if let Some(warning) = warning {
+                self.add_warning(warning);
+                if strip {
+                    continue;
+                }
+            }
+            valid_routes.push(route.clone());
+        }
+        valid_routes
+    }
+
+    #[cfg(feature = "validate-names")]
+    fn sanitize_names(&mut self, strip: bool) {
+        for name in [&mut self.host_name, &mut self.domain_name] {
+            if let Some(n) = name.as_ref() {
+                if !crate::name::is_valid_dns_name(n) {
+                    let warning = DhcpV4LeaseWarning::InvalidName(n.clone());
+                    log::warn!("DHCP lease of {}: {}", self.yiaddr, warning);
+                    self.warnings.push(warning);
+                    if strip {
+                        *name = None;
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_warning(&mut self, warning: DhcpV4LeaseWarning) {
+        log::warn!("DHCP lease of {}: {}", self.yiaddr, warning);
+        self.warnings.push(warning);
+    }
+
+    // Router is on-link when it is unspecified(RFC 3442 on-link route), in
+    // the same subnet with our address or covered by other on-link routes.
+    fn is_on_link(
+        &self,
+        router: &Ipv4Addr,
+        routes: &[DhcpV4ClasslessRoute],
+    ) -> bool {
+        router.is_unspecified()
+            || is_same_subnet(router, &self.yiaddr, &self.subnet_mask)
+            || routes.iter().any(|r| {
+                r.router.is_unspecified()
+                    && !r.is_default()
+                    && r.contains(router)
+            })
+    }
+}
+
+/// Build [DhcpV4Lease] without DHCP server involved, e.g. for unit test of
+/// projects using this crate.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DhcpV4LeaseBuilder {
+    lease: DhcpV4Lease,
+}
+
+impl DhcpV4LeaseBuilder {
+    /// When not set, T1 is 50% and T2 is 87.5% of `lease_time` as suggested
+    /// by RFC 2131.
+    pub fn new(
+        yiaddr: Ipv4Addr,
+        subnet_mask: Ipv4Addr,
+        srv_id: Ipv4Addr,
+        lease_time: u32,
+    ) -> Self {
+        Self {
+            lease: DhcpV4Lease {
+                yiaddr,
+                subnet_mask,
+                srv_id,
+                lease_time,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn set_t1(&mut self, t1: u32) -> &mut Self {
+        self.lease.t1 = t1;
+        self
+    }
+
+    pub fn set_t2(&mut self, t2: u32) -> &mut Self {
+        self.lease.t2 = t2;
+        self
+    }
+
+    pub fn set_xid(&mut self, xid: u32) -> &mut Self {
+        self.lease.xid = xid;
+        self
+    }
+
+    pub fn set_server_mac(&mut self, mac: [u8; 6]) -> &mut Self {
+        self.lease.srv_mac = mac;
+        self
+    }
+
+    pub fn set_gateways(&mut self, gateways: &[Ipv4Addr]) -> &mut Self {
+        self.lease.gateways = Some(gateways.to_vec());
+        self
+    }
+
+    pub fn set_dns_srvs(&mut self, srvs: &[Ipv4Addr]) -> &mut Self {
+        self.lease.dns_srvs = Some(srvs.to_vec());
+        self
+    }
+
+    pub fn set_ntp_srvs(&mut self, srvs: &[Ipv4Addr]) -> &mut Self {
+        self.lease.ntp_srvs = Some(srvs.to_vec());
+        self
+    }
+
+    pub fn set_mtu(&mut self, mtu: u16) -> &mut Self {
+        self.lease.mtu = Some(mtu);
+        self
+    }
+
+    pub fn set_host_name(&mut self, host_name: &str) -> &mut Self {
+        self.lease.host_name = Some(host_name.to_string());
+        self
+    }
+
+    pub fn set_domain_name(&mut self, domain_name: &str) -> &mut Self {
+        self.lease.domain_name = Some(domain_name.to_string());
+        self
+    }
+
+    pub fn set_classless_routes(
+        &mut self,
+        routes: &[DhcpV4ClasslessRoute],
+    ) -> &mut Self {
+        self.lease.classless_routes = Some(routes.to_vec());
+        self
+    }
+
+    /// Return [ErrorKind::InvalidArgument] if address is unspecified or
+    /// not meeting T1 <= T2 <= lease time.
+    pub fn build(&self) -> Result<DhcpV4Lease, DhcpError> {
+        let mut lease = self.lease.clone();
+        if lease.t1 == 0 {
+            lease.t1 = if lease.is_infinite() {
+                DhcpLifetime::Infinite.into()
+            } else {
+                lease.lease_time / 2
+            };
+        }
+        if lease.t2 == 0 {
+            lease.t2 = if lease.is_infinite() {
+                DhcpLifetime::Infinite.into()
+            } else {
+                u32::try_from(u64::from(lease.lease_time) * 7 / 8).unwrap_or(0)
+            };
+        }
+        let e = if lease.yiaddr.is_unspecified() {
+            Some("Lease address should not be 0.0.0.0".to_string())
+        } else if lease.t1 > lease.t2 || lease.t2 > lease.lease_time {
+            Some(format!(
+                "Lease should have T1({}) <= T2({}) <= lease time({})",
+                lease.t1, lease.t2, lease.lease_time
+            ))
+        } else {
+            None
+        };
+        if let Some(msg) = e {
+            let e = DhcpError::new(ErrorKind::InvalidArgument, msg);
+            log::error!("{}", e);
+            return Err(e);
+        }
+        Ok(lease)
+    }
+}
+
+// RFC 3442 encoding: destination prefix length, significant octets of
+// destination and then the router.
+fn parse_classless_routes(data: &[u8]) -> Option<Vec<DhcpV4ClasslessRoute>> {
+    let mut ret = Vec::new();
+    let mut data = data;
+    while let Some((&prefix_length, remain)) = data.split_first() {
+        if prefix_length > 32 {
+            log::debug!(
+                "Invalid classless route prefix length {prefix_length}"
+            );
+            return None;
+        }
+        let sig_octets = (prefix_length as usize).div_ceil(8);
+        if remain.len() < sig_octets + 4 {
+            log::debug!("Truncated classless route option {data:?}");
+            return None;
+        }
+        let mut dst = [0u8; 4];
+        dst[..sig_octets].copy_from_slice(&remain[..sig_octets]);
+        let mut router = [0u8; 4];
+        router.copy_from_slice(&remain[sig_octets..sig_octets + 4]);
+        ret.push(DhcpV4ClasslessRoute::new(
+            Ipv4Addr::from(dst),
+            prefix_length,
+            Ipv4Addr::from(router),
+        ));
+        data = &remain[sig_octets + 4..];
+    }
+    Some(ret)
+}
+
+// RFC 4833: 3. The string is NVT ASCII and not terminated by NULL, while
+// some servers still append one.
+fn parse_tz_str(data: &[u8]) -> Option<String> {
+    let data = data.strip_suffix(&[0]).unwrap_or(data);
+    if !data.is_empty() && data.iter().all(|c| c.is_ascii_graphic()) {
+        Some(String::from_utf8_lossy(data).to_string())
+    } else {
+        log::debug!("Invalid timezone option {data:?}");
+        None
+    }
+}
+
+// RFC 5969: 7.1.1. 6rd DHCPv4 Option Format
+//      IPv4MaskLen(1), 6rdPrefixLen(1), 6rdPrefix(16) followed by one or
+//      more 6rdBRIPv4Address(4)
+fn parse_6rd(data: &[u8]) -> Option<DhcpV4SixRd> {
+    if data.len() < 22 || !(data.len() - 18).is_multiple_of(4) {
+        log::debug!("Invalid 6rd option length {}", data.len());
+        return None;
+    }
+    let ipv4_mask_len = data[0];
+    let prefix_len = data[1];
+    // The delegated prefix is 6rd prefix followed by the IPv4 address
+    // without the common leading `ipv4_mask_len` bits.
+    if ipv4_mask_len > 32
+        || u16::from(prefix_len) + u16::from(32 - ipv4_mask_len) > 128
+    {
+        log::debug!(
+            "Invalid 6rd option with IPv4 mask length {ipv4_mask_len} and \
+            prefix length {prefix_len}"
+        );
+        return None;
+    }
+    let mut prefix = [0u8; 16];
+    prefix.copy_from_slice(&data[2..18]);
+    Some(DhcpV4SixRd {
+        ipv4_mask_len,
+        prefix: Ipv6Addr::from(prefix),
+        prefix_len,
+        border_relays: data[18..]
+            .chunks_exact(4)
+            .map(|c| Ipv4Addr::new(c[0], c[1], c[2], c[3]))
+            .collect(),
+    })
+}
+
+// Domain names in DNS wire format(RFC 1035 section 3.1)
+fn parse_dns_names(data: &[u8]) -> Option<Vec<String>> {
+    match decode_dns_names(data) {
+        Some(names) if !names.is_empty() => Some(names),
+        _ => {
+            log::debug!("Invalid domain names {data:?}");
+            None
+        }
+    }
+}
+
+fn parse_sip_srvs(data: &[u8]) -> Option<DhcpV4SipServers> {
+    match data.split_first() {
+        Some((&SIP_SERVERS_ENC_DOMAIN, names)) => {
+            parse_dns_names(names).map(DhcpV4SipServers::Domains)
+        }
+        Some((&SIP_SERVERS_ENC_ADDRESS, addrs))
+            if !addrs.is_empty() && addrs.len() % 4 == 0 =>
+        {
+            Some(DhcpV4SipServers::Addresses(
+                addrs
+                    .chunks_exact(4)
+                    .map(|c| Ipv4Addr::new(c[0], c[1], c[2], c[3]))
+                    .collect(),
+            ))
+        }
+        _ => {
+            log::debug!("Invalid SIP Servers option {data:?}");
+            None
+        }
+    }
+}
+
+// RFC 3925: Each enterprise is encoded as 4 bytes enterprise number, 1 byte
+// length and its sub-options in code, length and value format.
+fn parse_vendor_specific_infos(
+    data: &[u8],
+) -> Option<Vec<DhcpV4VendorSpecificInfo>> {
+    let mut ret = Vec::new();
+    let mut data = data;
+    while !data.is_empty() {
+        if data.len() < 5 || data.len() < 5 + data[4] as usize {
+            log::debug!("Truncated Vendor-Identifying option {data:?}");
+            return None;
+        }
+        let enterprise_number =
+            u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let mut sub_data = &data[5..5 + data[4] as usize];
+        data = &data[5 + data[4] as usize..];
+        let mut sub_opts = Vec::new();
+        while let [code, len, remain @ ..] = sub_data {
+            if remain.len() < *len as usize {
+                log::debug!(
+                    "Truncated sub-option {code} of enterprise \
+                    {enterprise_number}"
+                );
+                return None;
+            }
+            sub_opts.push((*code, remain[..*len as usize].to_vec()));
+            sub_data = &remain[*len as usize..];
+        }
+        if !sub_data.is_empty() {
+            log::debug!(
+                "Truncated sub-option of enterprise {enterprise_number}"
+            );
+            return None;
+        }
+        ret.push(DhcpV4VendorSpecificInfo {
+            enterprise_number,
+            sub_opts,
+        });
+    }
+    Some(ret)
+}
+
+fn is_same_subnet(a: &Ipv4Addr, b: &Ipv4Addr, mask: &Ipv4Addr) -> bool {
+    let mask = u32::from(*mask);
+    u32::from(*a) & mask == u32::from(*b) & mask
+}
+
+/// DHCPv4 option decoded by codec registered via
+/// [DhcpV4Config::register_option_codec()].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum DhcpV4Option {
+    Custom(u8, Box<dyn DhcpOptionValue>),
+}
+
+/// RFC 5969 IPv6 Rapid Deployment on IPv4 Infrastructures(6rd)
+/// configuration.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DhcpV4SixRd {
+    /// Number of high-order bits identical across all CE IPv4 addresses
+    /// within the 6rd domain, which are excluded from the delegated prefix.
+    pub ipv4_mask_len: u8,
+    pub prefix: Ipv6Addr,
+    pub prefix_len: u8,
+    /// IPv4 addresses of 6rd Border Relays
+    pub border_relays: Vec<Ipv4Addr>,
+}
+
+/// RFC 3361 SIP Servers option, holding either domain names or IPv4
+/// addresses as indicated by its encoding byte.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum DhcpV4SipServers {
+    Domains(Vec<String>),
+    Addresses(Vec<Ipv4Addr>),
+}
+
+/// Vendor-specific information of single enterprise in RFC 3925
+/// Vendor-Identifying Vendor-Specific Information option.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DhcpV4VendorSpecificInfo {
+    /// IANA Private Enterprise Number
+    pub enterprise_number: u32,
+    /// Sub-options in (code, data) pairs, defined by the enterprise.
+    pub sub_opts: Vec<(u8, Vec<u8>)>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DhcpV4ClasslessRoute {
+    pub destination: Ipv4Addr,
+    pub prefix_length: u8,
+    /// Unspecified(0.0.0.0) router means on-link route
+    pub router: Ipv4Addr,
+}
+
+impl DhcpV4ClasslessRoute {
+    pub fn new(
+        destination: Ipv4Addr,
+        prefix_length: u8,
+        router: Ipv4Addr,
+    ) -> Self {
+        Self {
+            destination,
+            prefix_length,
+            router,
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.prefix_length == 0
+    }
+
+    pub(crate) fn contains(&self, addr: &Ipv4Addr) -> bool {
+        let mask = u32::MAX
+            .checked_shl(32 - u32::from(self.prefix_length.min(32)))
+            .unwrap_or(0);
+        u32::from(*addr) & mask == u32::from(self.destination) & mask
+    }
+}
+
+impl std::fmt::Display for DhcpV4ClasslessRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} via {}",
+            self.destination, self.prefix_length, self.router
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum DhcpV4LeaseWarning {
+    /// Interface MTU(26) is smaller than 576
+    InvalidMtu(u16),
+    /// Classless Static Route(121) contains default route via 0.0.0.0
+    DefaultRouteOnLink,
+    /// The router of Classless Static Route(121) is not on-link
+    RouterNotOnLink(DhcpV4ClasslessRoute),
+    /// Host name(12) or domain name(15) is not valid DNS name, only checked
+    /// with `validate-names` feature.
+    InvalidName(String),
+    /// Server replied T1 > T2 or T2 > lease time, T2 is capped to 87.5% of
+    /// lease time and T1 to 50% of lease time or T2.
+    InvalidTimers { t1: u32, t2: u32, lease_time: u32 },
+}
+
+impl std::fmt::Display for DhcpV4LeaseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidMtu(mtu) => {
+                write!(f, "MTU {mtu} is smaller than {MIN_MTU}")
+            }
+            Self::DefaultRouteOnLink => {
+                write!(f, "default route via 0.0.0.0 is not allowed")
+            }
+            Self::RouterNotOnLink(route) => {
+                write!(f, "router of route {route} is not on-link")
+            }
+            Self::InvalidName(name) => {
+                write!(f, "{name:?} is not valid DNS name")
+            }
+            Self::InvalidTimers { t1, t2, lease_time } => {
+                write!(
+                    f,
+                    "T1({t1}) <= T2({t2}) <= lease time({lease_time}) is not \
+                    met"
+                )
+            }
+        }
+    }
+}
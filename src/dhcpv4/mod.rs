@@ -1,14 +1,54 @@
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "client")]
+mod arp;
+#[cfg(feature = "client")]
 mod client;
 mod config;
+mod conformance;
+#[cfg(feature = "client")]
+mod dhcp4o6;
+#[cfg(feature = "client")]
 mod event;
 mod lease;
 mod msg;
+mod option;
+mod portable;
+mod profile;
+#[cfg(feature = "client")]
+mod snapshot;
+#[cfg(feature = "client")]
+mod socket;
+mod state;
+#[cfg(feature = "client")]
 mod time;
 
+#[cfg(feature = "client")]
 pub use self::client::DhcpV4Client;
-pub use self::config::DhcpV4Config;
+pub use self::config::{
+    DhcpV4Config, DhcpV4ConformancePolicy, DhcpV4LeaseCheckPolicy,
+    DhcpV4LeaseValidation, DhcpV4OptionOrder, DhcpV4Transport,
+};
+#[cfg(feature = "client")]
 pub use self::event::DhcpV4Event;
-pub use self::lease::DhcpV4Lease;
+pub use self::lease::{
+    DhcpV4ClasslessRoute, DhcpV4Lease, DhcpV4LeaseBuilder, DhcpV4LeaseWarning,
+    DhcpV4Option, DhcpV4SipServers, DhcpV4SixRd, DhcpV4VendorSpecificInfo,
+};
 pub use self::msg::{DhcpV4Message, DhcpV4MessageType};
+pub use self::portable::DhcpV4PortableClient;
+pub use self::profile::DhcpV4ClientProfile;
+#[cfg(feature = "client")]
+pub use self::snapshot::DhcpV4Snapshot;
+pub use self::state::{DhcpV4Progress, DhcpV4State};
+
+#[cfg(all(test, feature = "client"))]
+pub(crate) use self::arp::{
+    gen_arp_reply, gen_arp_request, parse_arp_reply, parse_arp_request,
+};
+#[cfg(test)]
+pub(crate) use self::conformance::{
+    apply_conformance_policy, check_client_msg, check_server_msg,
+};
+#[cfg(all(test, feature = "client"))]
+pub(crate) use self::dhcp4o6::{gen_dhcpv4_query, parse_dhcpv4_response};
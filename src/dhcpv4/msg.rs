@@ -1,20 +1,38 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, SocketAddrV4};
 
 use dhcproto::{v4, Decodable, Decoder, Encodable};
 
+use super::conformance::{
+    apply_conformance_policy, check_client_msg, check_server_msg,
+};
+use super::lease::{
+    OPTION_6RD, OPTION_IPV6_ONLY_PREFERRED, OPTION_MS_CLASSLESS_STATIC_ROUTE,
+    OPTION_PCODE, OPTION_SIP_SERVERS, OPTION_TCODE,
+    OPTION_VENDOR_IDENTIFYING_VENDOR_SPECIFIC_INFO,
+};
+use super::option::{
+    concat_long_opts, parse_opts, sort_opts, DHCP_OPTIONS_OFFSET,
+};
 use crate::{
     mac::{
         mac_address_to_eth_mac_bytes, mac_str_to_u8_array,
         BROADCAST_MAC_ADDRESS,
     },
-    DhcpError, DhcpV4Config, DhcpV4Lease, ErrorKind,
+    mud::{validate_mud_url, OPTION_V4_MUD_URL},
+    trace::trace_msg_sent,
+    DhcpError, DhcpV4Config, DhcpV4ConformancePolicy, DhcpV4Lease, ErrorKind,
+    NamePolicy,
 };
 
 const DEFAULT_TTL: u8 = 128;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+const OPTION_CLIENT_ID: u8 = 61;
+// RFC 2131 section 2: client hardware address(16 bytes)
+const CHADDR_RANGE: std::ops::Range<usize> = 28..44;
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub enum DhcpV4MessageType {
     Discovery,
     Offer,
@@ -24,15 +42,10 @@ pub enum DhcpV4MessageType {
     Decline,
     Release,
     Inform,
+    #[default]
     Unknown,
 }
 
-impl Default for DhcpV4MessageType {
-    fn default() -> Self {
-        Self::Unknown
-    }
-}
-
 impl std::fmt::Display for DhcpV4MessageType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -60,6 +73,13 @@ pub struct DhcpV4Message {
     pub config: DhcpV4Config,
     renew_or_rebind: bool,
     pub(crate) xid: u32,
+    pub(crate) chaddr: Vec<u8>,
+    secs: u16,
+    extra_opts: Vec<(u8, Vec<u8>)>,
+    // Raw options of received message for decoding by registered codecs
+    pub(crate) raw_opts: Vec<(u8, Vec<u8>)>,
+    // RFC 2131 violations found in fixed header fields of received message
+    pub(crate) violations: Vec<String>,
 }
 
 impl DhcpV4Message {
@@ -74,23 +94,71 @@ impl DhcpV4Message {
             lease: None,
             renew_or_rebind: false,
             xid,
+            chaddr: Vec::new(),
+            secs: 0,
+            extra_opts: Vec::new(),
+            raw_opts: Vec::new(),
+            violations: Vec::new(),
         }
     }
 
+    /// Transaction ID of this message.
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
     pub fn load_lease(&mut self, lease: DhcpV4Lease) -> &mut Self {
         self.lease = Some(lease);
         self
     }
 
-    pub(crate) fn renew_or_rebind(&mut self, value: bool) -> &mut Self {
+    /// Whether this REQUEST message is for renewing or rebinding the lease,
+    /// which means leased address is placed in `ciaddr` instead of Requested
+    /// IP Address option.
+    pub fn renew_or_rebind(&mut self, value: bool) -> &mut Self {
         self.renew_or_rebind = value;
         self
     }
 
-    pub(crate) fn to_dhcp_pkg(&self) -> Result<Vec<u8>, DhcpError> {
+    /// Client hardware address(16 bytes including padding) of received
+    /// message.
+    pub fn chaddr(&self) -> &[u8] {
+        self.chaddr.as_slice()
+    }
+
+    /// Seconds elapsed since client began address acquisition or renewal
+    /// process.
+    pub fn set_secs(&mut self, secs: u16) -> &mut Self {
+        self.secs = secs;
+        self
+    }
+
+    /// Include specified option in this message, overriding the built-in
+    /// option and the one set by [DhcpV4Config::add_extra_option()] with the
+    /// same code. DHCP Message Type(53), Option Overload(52), Pad(0) and
+    /// End(255) cannot be set.
+    pub fn add_option(&mut self, code: u8, data: &[u8]) -> &mut Self {
+        self.extra_opts.push((code, data.to_vec()));
+        self
+    }
+
+    /// Generate DHCP message as UDP payload.
+    /// Only DISCOVER, REQUEST, DECLINE, RELEASE and INFORM messages are
+    /// supported.
+    /// INFORM message uses `yiaddr` of loaded lease as client address.
+    pub fn to_dhcp_pkg(&self) -> Result<Vec<u8>, DhcpError> {
         let mut dhcp_msg = v4::Message::default();
+        // Codes of built-in options in the order of insertion
+        let mut order: Vec<u8> = Vec::new();
         dhcp_msg.set_flags(v4::Flags::default());
         dhcp_msg.set_xid(self.xid);
+        // RFC 2131: 4.4.1 Table 5: `secs` is 0 in DHCPDECLINE and
+        // DHCPRELEASE
+        if self.msg_type != DhcpV4MessageType::Decline
+            && self.msg_type != DhcpV4MessageType::Release
+        {
+            dhcp_msg.set_secs(self.secs);
+        }
 
         if !self.config.host_name.is_empty() {
             dhcp_msg.set_sname_str(self.config.host_name.clone());
@@ -99,41 +167,61 @@ impl DhcpV4Message {
         if !self.config.src_mac.is_empty() {
             dhcp_msg
                 .set_chaddr(&mac_str_to_u8_array(self.config.src_mac.as_str()));
+        } else {
+            // Interface without hardware address: htype 0 and empty chaddr,
+            // client is identified by client identifier only. Server cannot
+            // unicast reply to unknown hardware address before client has
+            // IP address, hence request broadcast.
+            dhcp_msg.set_htype(v4::HType::Unknown(0));
+            dhcp_msg.set_chaddr(&[]);
+            // RFC 2131: 4.4.1 Table 5: `flags` is 0 in DHCPDECLINE and
+            // DHCPRELEASE
+            if !self.renew_or_rebind
+                && (self.msg_type == DhcpV4MessageType::Discovery
+                    || self.msg_type == DhcpV4MessageType::Request)
+            {
+                dhcp_msg.set_flags(v4::Flags::default().set_broadcast());
+            }
         }
 
         if self.msg_type == DhcpV4MessageType::Discovery {
-            dhcp_msg
-                .opts_mut()
-                .insert(v4::DhcpOption::MessageType(v4::MessageType::Discover));
-            dhcp_msg
-                .opts_mut()
-                .insert(v4::DhcpOption::ParameterRequestList(vec![
-                    v4::OptionCode::Hostname,
-                    v4::OptionCode::SubnetMask,
-                    v4::OptionCode::Router,
-                    v4::OptionCode::DomainNameServer,
-                    v4::OptionCode::DomainName,
-                    v4::OptionCode::InterfaceMtu,
-                    v4::OptionCode::NtpServers,
-                ]));
+            insert_opt(
+                &mut dhcp_msg,
+                &mut order,
+                v4::DhcpOption::MessageType(v4::MessageType::Discover),
+            );
+            // RFC 2131: 3.5 Client may suggest address via option 50
+            if let Some(ip) = self.config.preferred_ip {
+                insert_opt(
+                    &mut dhcp_msg,
+                    &mut order,
+                    v4::DhcpOption::RequestedIpAddress(ip),
+                );
+            }
+            insert_opt(
+                &mut dhcp_msg,
+                &mut order,
+                v4::DhcpOption::ParameterRequestList(self.request_opts()),
+            );
+            self.insert_max_msg_size(&mut dhcp_msg, &mut order);
         } else if self.msg_type == DhcpV4MessageType::Request {
-            dhcp_msg
-                .opts_mut()
-                .insert(v4::DhcpOption::MessageType(v4::MessageType::Request));
+            insert_opt(
+                &mut dhcp_msg,
+                &mut order,
+                v4::DhcpOption::MessageType(v4::MessageType::Request),
+            );
             if let Some(lease) = self.lease.as_ref() {
                 if self.renew_or_rebind {
                     dhcp_msg.set_ciaddr(lease.yiaddr);
                 } else {
-                    if lease.srv_id != Ipv4Addr::new(0, 0, 0, 0) {
-                        dhcp_msg.opts_mut().insert(
-                            v4::DhcpOption::ServerIdentifier(lease.srv_id),
-                        );
-                    } else {
-                        dhcp_msg.opts_mut().insert(
-                            v4::DhcpOption::ServerIdentifier(lease.siaddr),
-                        );
-                    }
-                    dhcp_msg.opts_mut().insert(
+                    insert_opt(
+                        &mut dhcp_msg,
+                        &mut order,
+                        v4::DhcpOption::ServerIdentifier(lease.srv_ip()),
+                    );
+                    insert_opt(
+                        &mut dhcp_msg,
+                        &mut order,
                         v4::DhcpOption::RequestedIpAddress(lease.yiaddr),
                     );
                 }
@@ -147,38 +235,91 @@ impl DhcpV4Message {
                 log::error!("{}", e);
                 return Err(e);
             }
-            dhcp_msg
-                .opts_mut()
-                .insert(v4::DhcpOption::ParameterRequestList(vec![
-                    v4::OptionCode::Hostname,
-                    v4::OptionCode::SubnetMask,
-                    v4::OptionCode::Router,
-                    v4::OptionCode::DomainNameServer,
-                    v4::OptionCode::DomainName,
-                    v4::OptionCode::InterfaceMtu,
-                    v4::OptionCode::NtpServers,
-                ]));
+            insert_opt(
+                &mut dhcp_msg,
+                &mut order,
+                v4::DhcpOption::ParameterRequestList(self.request_opts()),
+            );
+            self.insert_max_msg_size(&mut dhcp_msg, &mut order);
         } else if self.msg_type == DhcpV4MessageType::Release {
             if let Some(lease) = self.lease.as_ref() {
                 dhcp_msg.set_ciaddr(lease.yiaddr);
-                dhcp_msg.opts_mut().insert(v4::DhcpOption::MessageType(
-                    v4::MessageType::Release,
-                ));
-                if lease.srv_id != Ipv4Addr::new(0, 0, 0, 0) {
-                    dhcp_msg
-                        .opts_mut()
-                        .insert(v4::DhcpOption::ServerIdentifier(lease.srv_id));
-                } else {
-                    dhcp_msg
-                        .opts_mut()
-                        .insert(v4::DhcpOption::ServerIdentifier(lease.siaddr));
-                }
+                insert_opt(
+                    &mut dhcp_msg,
+                    &mut order,
+                    v4::DhcpOption::MessageType(v4::MessageType::Release),
+                );
+                insert_opt(
+                    &mut dhcp_msg,
+                    &mut order,
+                    v4::DhcpOption::ServerIdentifier(lease.srv_ip()),
+                );
             } else {
                 return Err(DhcpError::new(
                     ErrorKind::Bug,
                     format!("Got no lease for RELEASE message: {self:?}"),
                 ));
             }
+        } else if self.msg_type == DhcpV4MessageType::Decline {
+            // RFC 2131: 4.4.1 Table 5: Fields and options used by DHCP
+            // clients
+            //      DHCPDECLINE: 'ciaddr' 0, 'requested IP address' MUST,
+            //      'server identifier' MUST
+            if let Some(lease) = self.lease.as_ref() {
+                insert_opt(
+                    &mut dhcp_msg,
+                    &mut order,
+                    v4::DhcpOption::MessageType(v4::MessageType::Decline),
+                );
+                insert_opt(
+                    &mut dhcp_msg,
+                    &mut order,
+                    v4::DhcpOption::RequestedIpAddress(lease.yiaddr),
+                );
+                insert_opt(
+                    &mut dhcp_msg,
+                    &mut order,
+                    v4::DhcpOption::ServerIdentifier(lease.srv_ip()),
+                );
+            } else {
+                let e = DhcpError::new(
+                    ErrorKind::InvalidArgument,
+                    "No DHCP lease found for DHCP decline, \
+                    please run DhcpV4Message::load_lease() first"
+                        .to_string(),
+                );
+                log::error!("{}", e);
+                return Err(e);
+            }
+        } else if self.msg_type == DhcpV4MessageType::Inform {
+            // RFC 2131: 4.4.3 Initialization with an externally assigned
+            // network address
+            //      The client sends a DHCPINFORM message. The client may
+            //      request specific configuration parameters by including
+            //      the 'parameter request list' option.
+            if let Some(lease) = self.lease.as_ref() {
+                dhcp_msg.set_ciaddr(lease.yiaddr);
+            } else {
+                let e = DhcpError::new(
+                    ErrorKind::InvalidArgument,
+                    "No client address found for DHCP inform, \
+                    please run DhcpV4Message::load_lease() first"
+                        .to_string(),
+                );
+                log::error!("{}", e);
+                return Err(e);
+            }
+            insert_opt(
+                &mut dhcp_msg,
+                &mut order,
+                v4::DhcpOption::MessageType(v4::MessageType::Inform),
+            );
+            insert_opt(
+                &mut dhcp_msg,
+                &mut order,
+                v4::DhcpOption::ParameterRequestList(self.request_opts()),
+            );
+            self.insert_max_msg_size(&mut dhcp_msg, &mut order);
         } else {
             let e = DhcpError::new(
                 ErrorKind::InvalidArgument,
@@ -188,45 +329,167 @@ impl DhcpV4Message {
             return Err(e);
         }
 
-        dhcp_msg.opts_mut().insert(v4::DhcpOption::ClientIdentifier(
-            self.config.client_id.clone(),
-        ));
+        // RFC 8520: 10. The MUD URL DHCP Option
+        if let Some(url) = self.config.mud_url.as_ref().filter(|_| {
+            self.msg_type == DhcpV4MessageType::Discovery
+                || self.msg_type == DhcpV4MessageType::Request
+        }) {
+            validate_mud_url(url, u8::MAX.into())?;
+            insert_opt(
+                &mut dhcp_msg,
+                &mut order,
+                v4::DhcpOption::Unknown(v4::UnknownOption::new(
+                    v4::OptionCode::Unknown(OPTION_V4_MUD_URL),
+                    url.as_bytes().to_vec(),
+                )),
+            );
+        }
+        if self.config.send_client_id {
+            insert_opt(
+                &mut dhcp_msg,
+                &mut order,
+                v4::DhcpOption::ClientIdentifier(self.config.client_id.clone()),
+            );
+        }
         if !self.config.host_name.is_empty() {
-            dhcp_msg.opts_mut().insert(v4::DhcpOption::Hostname(
-                self.config.host_name.clone(),
-            ));
+            insert_opt(
+                &mut dhcp_msg,
+                &mut order,
+                v4::DhcpOption::Hostname(self.config.host_name.clone()),
+            );
         }
 
         log::debug!("DHCP message {:?}", dhcp_msg);
 
+        if self.config.conformance_policy != DhcpV4ConformancePolicy::Permissive
+        {
+            let with_ciaddr = match self.msg_type {
+                DhcpV4MessageType::Request => self.renew_or_rebind,
+                DhcpV4MessageType::Release | DhcpV4MessageType::Inform => true,
+                _ => false,
+            };
+            if let Err(e) = apply_conformance_policy(
+                self.config.conformance_policy,
+                ErrorKind::InvalidArgument,
+                &format!("Generated DHCP {}", self.msg_type),
+                &check_client_msg(&dhcp_msg, with_ciaddr),
+            ) {
+                log::error!("{}", e);
+                return Err(e);
+            }
+        }
+
         let mut dhcp_msg_buff = Vec::new();
         let mut e = v4::Encoder::new(&mut dhcp_msg_buff);
         dhcp_msg.encode(&mut e)?;
-        Ok(dhcp_msg_buff)
+        let extra_opts: Vec<(u8, Vec<u8>)> = self
+            .config
+            .extra_opts
+            .iter()
+            .chain(self.extra_opts.iter())
+            .cloned()
+            .collect();
+        let ret = sort_opts(
+            &dhcp_msg_buff,
+            &extra_opts,
+            &order,
+            &self.config.option_order,
+        )?;
+        trace_msg_sent(&self.msg_type, format!("{:#010x}", self.xid));
+        Ok(ret)
     }
 
-    pub(crate) fn from_dhcp_pkg(payload: &[u8]) -> Result<Self, DhcpError> {
+    fn request_opts(&self) -> Vec<v4::OptionCode> {
+        let mut codes: Vec<u8> = match self.config.request_opts.as_ref() {
+            Some(codes) => codes.clone(),
+            None => default_request_opts().into_iter().map(u8::from).collect(),
+        };
+        if self.config.ipv6_only_preferred {
+            codes.push(OPTION_IPV6_ONLY_PREFERRED);
+        }
+        if self.config.request_timezone {
+            codes.push(OPTION_PCODE);
+            codes.push(OPTION_TCODE);
+        }
+        if self.config.request_6rd {
+            codes.push(OPTION_6RD);
+        }
+        if self.config.request_sip_srvs {
+            codes.push(OPTION_SIP_SERVERS);
+        }
+        if self.config.request_captive_portal {
+            codes.push(v4::OptionCode::CaptivePortal.into());
+        }
+        if self.config.request_vendor_specific_infos {
+            codes.push(OPTION_VENDOR_IDENTIFYING_VENDOR_SPECIFIC_INFO);
+        }
+        codes.extend(self.config.option_codecs.iter().map(|(code, _)| *code));
+        let mut opts: Vec<v4::OptionCode> = Vec::new();
+        for code in codes.into_iter().map(v4::OptionCode::from) {
+            if !opts.contains(&code) {
+                opts.push(code);
+            }
+        }
+        opts
+    }
+
+    // RFC 2132: 9.10. Maximum DHCP Message Size
+    fn insert_max_msg_size(
+        &self,
+        dhcp_msg: &mut v4::Message,
+        order: &mut Vec<u8>,
+    ) {
+        if let Some(size) = self.config.max_msg_size {
+            insert_opt(dhcp_msg, order, v4::DhcpOption::MaxMessageSize(size));
+        }
+    }
+
+    /// Parse DHCP message from UDP payload.
+    pub fn from_dhcp_pkg(
+        payload: &[u8],
+        name_policy: NamePolicy,
+    ) -> Result<Self, DhcpError> {
+        let payload = concat_long_opts(payload);
+        let raw_opts = parse_opts(&payload);
+        let (payload, [host_name, domain_name, captive_portal]) = take_raw_opts(
+            &payload,
+            [
+                v4::OptionCode::Hostname,
+                v4::OptionCode::DomainName,
+                v4::OptionCode::CaptivePortal,
+            ],
+        );
+        let payload = payload.as_slice();
         let v4_dhcp_msg = v4::Message::decode(&mut Decoder::new(payload))
             .map_err(|decode_error| {
-                let e = DhcpError::new(
-                    ErrorKind::InvalidDhcpServerReply,
-                    format!(
-                        "Failed to parse DHCP message from payload of pkg \
-                        {payload:?}: {decode_error}"
-                    ),
+                // Logged by caller as malformed packet might be flooded
+                log::debug!(
+                    "Failed to parse DHCP message from payload of pkg \
+                    {payload:?}: {decode_error}"
                 );
-                log::error!("{}", e);
-                e
+                DhcpError::new(
+                    ErrorKind::InvalidDhcpServerReply,
+                    format!("Failed to parse DHCP message: {decode_error}"),
+                )
+                .with_source(decode_error)
             })?;
 
         let msg_type = match v4_dhcp_msg.opts().get(v4::OptionCode::MessageType)
         {
-            Some(v4::DhcpOption::MessageType(v4::MessageType::Offer)) => {
-                DhcpV4MessageType::Offer
-            }
-            Some(v4::DhcpOption::MessageType(v4::MessageType::Ack)) => {
-                DhcpV4MessageType::Ack
-            }
+            Some(v4::DhcpOption::MessageType(t)) => match t {
+                v4::MessageType::Discover => DhcpV4MessageType::Discovery,
+                v4::MessageType::Offer => DhcpV4MessageType::Offer,
+                v4::MessageType::Request => DhcpV4MessageType::Request,
+                v4::MessageType::Ack => DhcpV4MessageType::Ack,
+                v4::MessageType::Nak => DhcpV4MessageType::Nack,
+                v4::MessageType::Decline => DhcpV4MessageType::Decline,
+                v4::MessageType::Release => DhcpV4MessageType::Release,
+                v4::MessageType::Inform => DhcpV4MessageType::Inform,
+                _ => {
+                    log::debug!("Unknown dhcp message type {:?}", t);
+                    DhcpV4MessageType::Unknown
+                }
+            },
             Some(t) => {
                 log::debug!("Unknown dhcp message type {:?}", t);
                 DhcpV4MessageType::Unknown
@@ -236,29 +499,74 @@ impl DhcpV4Message {
                 DhcpV4MessageType::Unknown
             }
         };
+        let mut lease = DhcpV4Lease::try_from(&v4_dhcp_msg)?;
+        lease.host_name = host_name.and_then(|n| name_policy.apply(&n));
+        lease.domain_name = domain_name.and_then(|n| name_policy.apply(&n));
+        lease.captive_portal = captive_portal.and_then(|v| {
+            String::from_utf8(v)
+                .map_err(|e| log::debug!("Invalid captive portal URI: {e}"))
+                .ok()
+        });
         let ret = Self {
-            lease: Some(DhcpV4Lease::try_from(&v4_dhcp_msg)?),
+            lease: Some(lease),
             msg_type,
             xid: v4_dhcp_msg.xid(),
+            // dhcproto panics on `chaddr()` when hlen is larger than 16
+            chaddr: payload.get(CHADDR_RANGE).unwrap_or_default().to_vec(),
+            raw_opts,
+            violations: check_server_msg(&v4_dhcp_msg),
             ..Default::default()
         };
         log::debug!("Got reply DHCP message {:?}", ret);
         Ok(ret)
     }
 
-    pub(crate) fn to_eth_pkg_broadcast(&self) -> Result<Vec<u8>, DhcpError> {
+    // RFC 6842: 3. Client Identifier Option
+    //      When a client receives a DHCP message containing a 'client
+    //      identifier' option, the client MUST compare that client identifier
+    //      to the one it is configured to send.  If the two client
+    //      identifiers do not match, the client MUST silently discard the
+    //      message.
+    pub(crate) fn is_client_id_mismatch(&self, config: &DhcpV4Config) -> bool {
+        if !config.verify_client_id
+            || !config.send_client_id
+            || config.client_id.is_empty()
+        {
+            return false;
+        }
+        match self
+            .raw_opts
+            .iter()
+            .find(|(code, _)| *code == OPTION_CLIENT_ID)
+        {
+            Some((_, client_id)) if client_id != &config.client_id => {
+                log::debug!(
+                    "Client identifier {client_id:?} in DHCP {} does not \
+                    match {:?}",
+                    self.msg_type,
+                    config.client_id
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Generate broadcast ethernet frame holding this DHCP message using
+    /// MAC address of [DhcpV4Config] as source.
+    pub fn to_eth_pkg_broadcast(&self) -> Result<Vec<u8>, DhcpError> {
         let dhcp_msg_buff = self.to_dhcp_pkg()?;
         gen_eth_pkg(
             &mac_address_to_eth_mac_bytes(&self.config.src_mac)?,
             &BROADCAST_MAC_ADDRESS,
             &Ipv4Addr::new(0, 0, 0, 0),
             &Ipv4Addr::new(255, 255, 255, 255),
-            dhcproto::v4::CLIENT_PORT,
-            dhcproto::v4::SERVER_PORT,
+            self.config.dscp,
             &dhcp_msg_buff,
         )
     }
 
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
     pub(crate) fn to_proxy_eth_pkg_unicast(
         &self,
     ) -> Result<Vec<u8>, DhcpError> {
@@ -268,9 +576,8 @@ impl DhcpV4Message {
                 &mac_address_to_eth_mac_bytes(&self.config.src_mac)?,
                 &lease.srv_mac,
                 &lease.yiaddr,
-                &lease.siaddr,
-                dhcproto::v4::CLIENT_PORT,
-                dhcproto::v4::SERVER_PORT,
+                &lease.srv_ip(),
+                self.config.dscp,
                 &dhcp_msg_buff,
             )
         } else {
@@ -281,42 +588,137 @@ impl DhcpV4Message {
         }
     }
 
-    pub(crate) fn from_eth_pkg(data: &[u8]) -> Result<Self, DhcpError> {
+    /// Parse DHCP message from ethernet frame.
+    pub fn from_eth_pkg(
+        data: &[u8],
+        name_policy: NamePolicy,
+    ) -> Result<Self, DhcpError> {
         let pkg = match etherparse::SlicedPacket::from_ethernet(data) {
+            // Logged by caller as malformed packet might be flooded
             Err(error) => {
-                let e = DhcpError::new(
+                return Err(DhcpError::new(
                     ErrorKind::InvalidDhcpServerReply,
                     format!(
                         "Failed to parse ethernet package to Dhcpv4Offer: {error}"
                     ),
-                );
-                log::error!("{}", e);
-                return Err(e);
+                )
+                .with_source(error));
             }
             Ok(v) => v,
         };
-        let mut ret = Self::from_dhcp_pkg(pkg.payload)?;
-        if let Some(eth_header) = pkg.link.map(|l| l.to_header()) {
-            if let Some(lease) = ret.lease.as_mut() {
+        let mut ret = Self::from_dhcp_pkg(pkg.payload, name_policy)?;
+        if let Some(lease) = ret.lease.as_mut() {
+            if let Some(eth_header) = pkg.link.map(|l| l.to_header()) {
                 lease.srv_mac = eth_header.source;
             }
+            if let (
+                Some(etherparse::InternetSlice::Ipv4(ip_header, _)),
+                Some(etherparse::TransportSlice::Udp(udp_header)),
+            ) = (pkg.ip.as_ref(), pkg.transport.as_ref())
+            {
+                lease.src_addr = Some(SocketAddrV4::new(
+                    ip_header.source_addr(),
+                    udp_header.source_port(),
+                ));
+            }
         }
         Ok(ret)
     }
 }
 
+// dhcproto stops parsing all remaining options once it found Host Name(12)
+// or Domain Name(15) option holding invalid UTF-8, or Captive-Portal(114)
+// option holding invalid URL. Hence we take raw value of these options
+// out(concatenated as RFC 3396 requires) and replace them with PAD in the
+// payload before handing it to dhcproto.
+fn take_raw_opts<const N: usize>(
+    payload: &[u8],
+    codes: [v4::OptionCode; N],
+) -> (Vec<u8>, [Option<Vec<u8>>; N]) {
+    let mut payload = payload.to_vec();
+    let mut values: [Option<Vec<u8>>; N] = std::array::from_fn(|_| None);
+    let mut pos = DHCP_OPTIONS_OFFSET;
+    while let Some(&code) = payload.get(pos) {
+        match v4::OptionCode::from(code) {
+            v4::OptionCode::Pad => {
+                pos += 1;
+                continue;
+            }
+            v4::OptionCode::End => break,
+            _ => (),
+        }
+        let Some(&len) = payload.get(pos + 1) else {
+            break;
+        };
+        let end = pos + 2 + len as usize;
+        if end > payload.len() {
+            break;
+        }
+        let Some(value) = codes
+            .iter()
+            .position(|c| u8::from(*c) == code)
+            .and_then(|i| values.get_mut(i))
+        else {
+            pos = end;
+            continue;
+        };
+        value
+            .get_or_insert_with(Vec::new)
+            .extend_from_slice(&payload[pos + 2..end]);
+        payload[pos..end].fill(u8::from(v4::OptionCode::Pad));
+        pos = end;
+    }
+    (payload, values)
+}
+
+// RFC 3442: Classless Static Route option should be placed before Router
+// option in the parameter request list.
+// The dhcproto crate stores options in HashMap, hence record the insertion
+// order for `DhcpV4OptionOrder::Insertion`.
+fn insert_opt(
+    dhcp_msg: &mut v4::Message,
+    order: &mut Vec<u8>,
+    opt: v4::DhcpOption,
+) {
+    order.push(v4::OptionCode::from(&opt).into());
+    dhcp_msg.opts_mut().insert(opt);
+}
+
+fn default_request_opts() -> Vec<v4::OptionCode> {
+    vec![
+        v4::OptionCode::Hostname,
+        v4::OptionCode::SubnetMask,
+        v4::OptionCode::ClasslessStaticRoute,
+        v4::OptionCode::Unknown(OPTION_MS_CLASSLESS_STATIC_ROUTE),
+        v4::OptionCode::Router,
+        v4::OptionCode::DomainNameServer,
+        v4::OptionCode::DomainName,
+        v4::OptionCode::InterfaceMtu,
+        v4::OptionCode::NtpServers,
+        v4::OptionCode::TimeServer,
+        v4::OptionCode::TimeOffset,
+    ]
+}
+
 fn gen_eth_pkg(
     src_mac: &[u8; 6],
     dst_mac: &[u8; 6],
     src_ip: &Ipv4Addr,
     dst_ip: &Ipv4Addr,
-    src_port: u16,
-    dst_port: u16,
+    dscp: u8,
     payload: &[u8],
 ) -> Result<Vec<u8>, DhcpError> {
+    let mut ip_header = etherparse::Ipv4Header::default();
+    ip_header.source = src_ip.octets();
+    ip_header.destination = dst_ip.octets();
+    ip_header.time_to_live = DEFAULT_TTL;
+    ip_header.differentiated_services_code_point = dscp;
     let builder = etherparse::PacketBuilder::ethernet2(*src_mac, *dst_mac)
-        .ipv4(src_ip.octets(), dst_ip.octets(), DEFAULT_TTL)
-        .udp(src_port, dst_port);
+        .ip(etherparse::IpHeader::Version4(
+            ip_header,
+            Default::default(),
+        ))
+        .udp(dhcproto::v4::CLIENT_PORT, dhcproto::v4::SERVER_PORT);
 
     let mut pkg = Vec::<u8>::with_capacity(builder.size(payload.len()));
 
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{DhcpError, DhcpV4OptionOrder, ErrorKind};
+
+// RFC 2131: fixed-length fields(236 bytes) followed by magic cookie(4 bytes)
+pub(crate) const DHCP_OPTIONS_OFFSET: usize = 240;
+
+const OPT_PAD: u8 = 0;
+const OPT_OVERLOAD: u8 = 52;
+const OPT_MSG_TYPE: u8 = 53;
+const OPT_RELAY_AGENT_INFO: u8 = 82;
+const OPT_END: u8 = 255;
+
+// RFC 2131 section 4.1: sname(64 bytes) and file(128 bytes) fields
+const SNAME_RANGE: std::ops::Range<usize> = 44..108;
+const FILE_RANGE: std::ops::Range<usize> = 108..236;
+
+const OVERLOAD_FILE: u8 = 1;
+const OVERLOAD_SNAME: u8 = 2;
+
+const MAX_OPT_LEN: usize = u8::MAX as usize;
+
+// Read all options of DHCP payload in the order of RFC 3396 section 5:
+// options field, then file field and sname field when Option Overload(52)
+// indicates so. Multiple instances of the same option are concatenated into
+// one entry placed at position of its first instance.
+pub(crate) fn parse_opts(payload: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    let mut ret: Vec<(u8, Vec<u8>)> = Vec::new();
+    parse_opts_field(payload.get(DHCP_OPTIONS_OFFSET..), &mut ret);
+    let overload = ret
+        .iter()
+        .find(|(code, _)| *code == OPT_OVERLOAD)
+        .and_then(|(_, data)| data.first().copied())
+        .unwrap_or_default();
+    if overload & OVERLOAD_FILE > 0 {
+        parse_opts_field(payload.get(FILE_RANGE), &mut ret);
+    }
+    if overload & OVERLOAD_SNAME > 0 {
+        parse_opts_field(payload.get(SNAME_RANGE), &mut ret);
+    }
+    ret
+}
+
+fn parse_opts_field(field: Option<&[u8]>, opts: &mut Vec<(u8, Vec<u8>)>) {
+    let Some(field) = field else {
+        return;
+    };
+    let mut pos = 0;
+    while let Some(&code) = field.get(pos) {
+        match code {
+            OPT_PAD => {
+                pos += 1;
+                continue;
+            }
+            OPT_END => break,
+            _ => (),
+        }
+        let Some(&len) = field.get(pos + 1) else {
+            break;
+        };
+        let end = pos + 2 + len as usize;
+        let Some(data) = field.get(pos + 2..end) else {
+            log::debug!("Ignoring truncated DHCP option {code}");
+            break;
+        };
+        if let Some((_, exist)) = opts.iter_mut().find(|(c, _)| *c == code) {
+            exist.extend_from_slice(data);
+        } else {
+            opts.push((code, data.to_vec()));
+        }
+        pos = end;
+    }
+}
+
+// Replace options of DHCP payload with specified ones. Option longer than
+// 255 bytes is split into multiple consecutive instances as RFC 3396
+// required.
+fn replace_opts(payload: &[u8], opts: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    let mut ret = payload[..DHCP_OPTIONS_OFFSET.min(payload.len())].to_vec();
+    for (code, data) in opts {
+        if data.is_empty() {
+            ret.extend_from_slice(&[*code, 0]);
+        }
+        for chunk in data.chunks(MAX_OPT_LEN) {
+            ret.push(*code);
+            ret.push(chunk.len() as u8);
+            ret.extend_from_slice(chunk);
+        }
+    }
+    ret.push(OPT_END);
+    ret
+}
+
+// Concatenate split instances of options in received DHCP payload as
+// RFC 3396 required. The dhcproto crate only concatenates adjacent instances
+// and keeps the last one of non-adjacent instances, so we regroup them before
+// decoding. Options stored in sname and file fields via Option Overload(52)
+// are moved to the options field.
+pub(crate) fn concat_long_opts(payload: &[u8]) -> Vec<u8> {
+    if payload.len() <= DHCP_OPTIONS_OFFSET {
+        return payload.to_vec();
+    }
+    let mut opts = parse_opts(payload);
+    let mut ret = payload.to_vec();
+    if let Some(pos) = opts.iter().position(|(code, _)| *code == OPT_OVERLOAD) {
+        let (_, data) = opts.remove(pos);
+        let overload = data.first().copied().unwrap_or_default();
+        if overload & OVERLOAD_FILE > 0 {
+            ret[FILE_RANGE].fill(0);
+        }
+        if overload & OVERLOAD_SNAME > 0 {
+            ret[SNAME_RANGE].fill(0);
+        }
+    }
+    replace_opts(&ret, &opts)
+}
+
+// The dhcproto crate stores options in HashMap, hence the order of emitted
+// options is random. Reorder them as `DhcpV4OptionOrder` defined, `order`
+// holds codes of built-in options in the order of insertion. DHCP Message
+// Type(53) is always the first and Relay Agent Information(82) the last as
+// RFC 3046 required.
+// The caller supplied options are appended or override the built-in ones with
+// the same option code, long options are split as RFC 3396 required.
+pub(crate) fn sort_opts(
+    payload: &[u8],
+    extra_opts: &[(u8, Vec<u8>)],
+    order: &[u8],
+    option_order: &DhcpV4OptionOrder,
+) -> Result<Vec<u8>, DhcpError> {
+    if payload.len() <= DHCP_OPTIONS_OFFSET {
+        return Ok(payload.to_vec());
+    }
+    let mut opts = parse_opts(payload);
+    for (code, data) in extra_opts {
+        if [OPT_PAD, OPT_OVERLOAD, OPT_MSG_TYPE, OPT_END].contains(code) {
+            let e = DhcpError::new(
+                ErrorKind::InvalidArgument,
+                format!("DHCP option {code} cannot be overridden"),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+        if let Some((_, exist)) = opts.iter_mut().find(|(c, _)| c == code) {
+            *exist = data.clone();
+        } else {
+            opts.push((*code, data.clone()));
+        }
+    }
+    let mut seq: Vec<u8> = match option_order {
+        DhcpV4OptionOrder::Custom(codes) => codes.clone(),
+        _ => Vec::new(),
+    };
+    seq.extend_from_slice(order);
+    seq.extend(extra_opts.iter().map(|(code, _)| *code));
+    opts.sort_by_key(|(code, _)| {
+        let pos = match option_order {
+            DhcpV4OptionOrder::Sorted => usize::from(*code),
+            _ => seq.iter().position(|c| c == code).unwrap_or(seq.len()),
+        };
+        match *code {
+            OPT_MSG_TYPE => (0, pos),
+            OPT_RELAY_AGENT_INFO => (2, pos),
+            _ => (1, pos),
+        }
+    });
+    Ok(replace_opts(payload, &opts))
+}
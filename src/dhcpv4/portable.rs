@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::{Duration, Instant};
+
+use super::conformance::apply_conformance_policy;
+use crate::{
+    consts::DEFAULT_MAX_MSG_SIZE, random::gen_u32, DhcpError, DhcpV4Config,
+    DhcpV4Lease, DhcpV4Message, DhcpV4MessageType, ErrorKind,
+};
+
+const CLIENT_PORT: u16 = 68;
+const SERVER_PORT: u16 = 67;
+
+/// Blocking DHCPv4 client using only standard UDP socket bound to
+/// `INADDR_ANY` with `SO_BROADCAST` enabled, without raw socket, BPF filter,
+/// event loop or interface query, hence also works on non-Linux platforms
+/// and without `client` feature.
+///
+/// As the UDP socket cannot receive reply before interface has IP address,
+/// only flows for address already configured are supported: renewing,
+/// rebinding and releasing lease, and querying configuration of externally
+/// assigned address via DHCPINFORM. Address acquisition, ARP checks and
+/// timers are left to caller.
+#[derive(Debug)]
+pub struct DhcpV4PortableClient {
+    config: DhcpV4Config,
+    socket: UdpSocket,
+    // Destination port of DHCP server
+    pub(crate) srv_port: u16,
+}
+
+impl DhcpV4PortableClient {
+    /// Create client with UDP socket bound to `0.0.0.0:68`, which might
+    /// require elevated privileges. The interface is not queried, the
+    /// specified MAC address(could be empty) is used for `chaddr` of
+    /// messages.
+    pub fn new(
+        config: DhcpV4Config,
+        mac_address: &str,
+    ) -> Result<Self, DhcpError> {
+        let socket = UdpSocket::bind(SocketAddrV4::new(
+            Ipv4Addr::UNSPECIFIED,
+            CLIENT_PORT,
+        ))?;
+        Self::new_with_socket(config, mac_address, socket)
+    }
+
+    /// Create client with UDP socket bound by caller, e.g. to specific
+    /// address or with platform specific socket options.
+    pub fn new_with_socket(
+        mut config: DhcpV4Config,
+        mac_address: &str,
+        socket: UdpSocket,
+    ) -> Result<Self, DhcpError> {
+        socket.set_broadcast(true)?;
+        socket.set_read_timeout(Some(Duration::from_secs(
+            config.socket_timeout.max(1).into(),
+        )))?;
+        log::debug!("Portable DHCPv4 client using UDP socket {:?}", socket);
+        config.src_mac = mac_address.to_string();
+        Ok(Self {
+            config,
+            socket,
+            srv_port: SERVER_PORT,
+        })
+    }
+
+    /// Renew lease by unicasting DHCPREQUEST to the DHCP server granted it.
+    pub fn renew(
+        &mut self,
+        lease: &DhcpV4Lease,
+    ) -> Result<DhcpV4Lease, DhcpError> {
+        self.request(lease, lease.srv_ip())
+    }
+
+    /// Rebind lease by broadcasting DHCPREQUEST to any DHCP server.
+    pub fn rebind(
+        &mut self,
+        lease: &DhcpV4Lease,
+    ) -> Result<DhcpV4Lease, DhcpError> {
+        self.request(lease, Ipv4Addr::BROADCAST)
+    }
+
+    /// Query configuration parameters for externally assigned address via
+    /// DHCPINFORM, unicasting to specified DHCP server or broadcasting. The
+    /// returned lease holds no lease time and timers.
+    pub fn inform(
+        &mut self,
+        ip: Ipv4Addr,
+        srv_ip: Option<Ipv4Addr>,
+    ) -> Result<DhcpV4Lease, DhcpError> {
+        let lease = DhcpV4Lease {
+            yiaddr: ip,
+            ..Default::default()
+        };
+        let mut msg = DhcpV4Message::new(
+            &self.config,
+            DhcpV4MessageType::Inform,
+            gen_u32(),
+        );
+        msg.load_lease(lease);
+        let mut lease =
+            self.exchange(&msg, srv_ip.unwrap_or(Ipv4Addr::BROADCAST))?;
+        // RFC 2131: 4.3.5 DHCPINFORM message
+        //      The server MUST NOT send a lease expiration time to the client
+        //      and SHOULD NOT fill in 'yiaddr'.
+        lease.yiaddr = ip;
+        Ok(lease)
+    }
+
+    /// Release lease by unicasting DHCPRELEASE to the DHCP server granted
+    /// it, no reply is expected.
+    pub fn release(&mut self, lease: &DhcpV4Lease) -> Result<(), DhcpError> {
+        let mut msg = DhcpV4Message::new(
+            &self.config,
+            DhcpV4MessageType::Release,
+            gen_u32(),
+        );
+        msg.load_lease(lease.clone());
+        let dst = SocketAddrV4::new(lease.srv_ip(), self.srv_port);
+        log::debug!("Sending DHCP release to {dst}");
+        self.socket.send_to(&msg.to_dhcp_pkg()?, dst)?;
+        Ok(())
+    }
+
+    fn request(
+        &mut self,
+        lease: &DhcpV4Lease,
+        dst_ip: Ipv4Addr,
+    ) -> Result<DhcpV4Lease, DhcpError> {
+        let mut msg = DhcpV4Message::new(
+            &self.config,
+            DhcpV4MessageType::Request,
+            gen_u32(),
+        );
+        msg.load_lease(lease.clone()).renew_or_rebind(true);
+        self.exchange(&msg, dst_ip)
+    }
+
+    // Send message and wait for DHCPACK, retransmit on every socket timeout
+    // till the overall timeout of config. Both timeouts are deadlines, so
+    // unrelated DHCP traffic cannot postpone them.
+    fn exchange(
+        &self,
+        msg: &DhcpV4Message,
+        dst_ip: Ipv4Addr,
+    ) -> Result<DhcpV4Lease, DhcpError> {
+        let pkg = msg.to_dhcp_pkg()?;
+        let dst = SocketAddrV4::new(dst_ip, self.srv_port);
+        let deadline = (self.config.timeout != 0).then(|| {
+            Instant::now() + Duration::from_secs(self.config.timeout.into())
+        });
+        let socket_timeout =
+            Duration::from_secs(self.config.socket_timeout.max(1).into());
+        let mut buffer = vec![
+            0u8;
+            self.config.max_msg_size.unwrap_or(DEFAULT_MAX_MSG_SIZE)
+                as usize
+        ];
+        loop {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                let e = DhcpError::new(
+                    ErrorKind::Timeout,
+                    format!(
+                        "Timeout on waiting reply of DHCP {} from {dst}",
+                        msg.msg_type
+                    ),
+                );
+                log::error!("{}", e);
+                return Err(e);
+            }
+            log::debug!("Sending DHCP {} to {dst}", msg.msg_type);
+            self.socket.send_to(&pkg, dst)?;
+            let retransmit_at = match deadline {
+                Some(d) => d.min(Instant::now() + socket_timeout),
+                None => Instant::now() + socket_timeout,
+            };
+            loop {
+                let remain =
+                    retransmit_at.saturating_duration_since(Instant::now());
+                if remain.is_zero() {
+                    break;
+                }
+                self.socket.set_read_timeout(Some(remain))?;
+                match self.socket.recv_from(&mut buffer) {
+                    Ok((len, src_addr)) => {
+                        if let Some(lease) =
+                            self.parse_reply(msg, &buffer[..len], src_addr)?
+                        {
+                            return Ok(lease);
+                        }
+                    }
+                    // Unix reports `WouldBlock` on read timeout while
+                    // Windows reports `TimedOut`
+                    Err(e)
+                        if e.kind() == std::io::ErrorKind::WouldBlock
+                            || e.kind() == std::io::ErrorKind::TimedOut =>
+                    {
+                        break;
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+    }
+
+    fn parse_reply(
+        &self,
+        msg: &DhcpV4Message,
+        buffer: &[u8],
+        src_addr: SocketAddr,
+    ) -> Result<Option<DhcpV4Lease>, DhcpError> {
+        let reply =
+            match DhcpV4Message::from_dhcp_pkg(buffer, self.config.name_policy)
+            {
+                Ok(r) => r,
+                Err(e) => {
+                    log::debug!(
+                        "Dropping invalid DHCP message from {src_addr}: {e}"
+                    );
+                    return Ok(None);
+                }
+            };
+        if reply.xid != msg.xid {
+            log::debug!(
+                "Dropping DHCP message due to xid miss-match. \
+                Expecting {}, got {}",
+                msg.xid,
+                reply.xid
+            );
+            return Ok(None);
+        }
+        if reply.is_client_id_mismatch(&self.config) {
+            log::debug!(
+                "Dropping DHCP message from {src_addr} due to client \
+                identifier mismatch"
+            );
+            return Ok(None);
+        }
+        if let Err(e) = apply_conformance_policy(
+            self.config.conformance_policy,
+            ErrorKind::InvalidDhcpServerReply,
+            &format!("DHCP {}", reply.msg_type),
+            &reply.violations,
+        ) {
+            log::debug!("Dropping DHCP message from {src_addr}: {e}");
+            return Ok(None);
+        }
+        match reply.msg_type {
+            DhcpV4MessageType::Ack => (),
+            DhcpV4MessageType::Nack => {
+                let e = DhcpError::new(
+                    ErrorKind::NoLease,
+                    format!(
+                        "DHCP server {src_addr} refused the DHCP {}",
+                        msg.msg_type
+                    ),
+                );
+                log::error!("{}", e);
+                return Err(e);
+            }
+            t => {
+                log::debug!(
+                    "Dropping DHCP message due to type miss-match. \
+                    Expecting ack, got {t}"
+                );
+                return Ok(None);
+            }
+        }
+        let Some(mut lease) = reply.lease else {
+            log::debug!("No lease found in the reply from {src_addr}");
+            return Ok(None);
+        };
+        if let SocketAddr::V4(src_addr) = src_addr {
+            lease.src_addr = Some(src_addr);
+        }
+        lease.decode_custom_opts(&self.config, &reply.raw_opts);
+        if msg.msg_type != DhcpV4MessageType::Inform {
+            if let Err(e) = lease.validate_times(&self.config) {
+                log::warn!("Dropping DHCP ack: {e}");
+                return Ok(None);
+            }
+        }
+        lease.sanitize(&self.config);
+        Ok(Some(lease))
+    }
+}
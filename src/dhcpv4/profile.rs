@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{DhcpV4Config, DhcpV4OptionOrder};
+
+const OPT_VENDOR_CLASS_ID: u8 = 60;
+
+const DHCLIENT_REQUEST_OPTS: [u8; 13] =
+    [1, 28, 2, 3, 15, 6, 119, 12, 44, 47, 26, 121, 42];
+const DHCLIENT_OPTS_ORDER: [u8; 4] = [53, 50, 12, 55];
+
+const WINDOWS_REQUEST_OPTS: [u8; 14] =
+    [1, 3, 6, 15, 31, 33, 43, 44, 46, 47, 119, 121, 249, 252];
+const WINDOWS_OPTS_ORDER: [u8; 7] = [53, 61, 50, 12, 81, 60, 55];
+const WINDOWS_VENDOR_CLASS_ID: &[u8] = b"MSFT 5.0";
+
+const ANDROID_REQUEST_OPTS: [u8; 11] =
+    [1, 3, 6, 15, 26, 28, 51, 58, 59, 43, 114];
+const ANDROID_OPTS_ORDER: [u8; 7] = [53, 61, 50, 57, 60, 12, 55];
+const ANDROID_VENDOR_CLASS_ID: &[u8] = b"android-dhcp-13";
+const ANDROID_MAX_MSG_SIZE: u16 = 1500;
+
+/// Preset of DHCPv4 client behavior mimicking well known DHCP clients, for
+/// lab testing or networks granting access based on DHCP fingerprint:
+/// option order, Parameter Request List(55), Vendor Class Identifier(60)
+/// and host name behavior. Applied by
+/// [DhcpV4Config::set_client_profile()].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum DhcpV4ClientProfile {
+    /// ISC dhclient on Linux: no Client Identifier(61) or Vendor Class
+    /// Identifier(60), configured host name is sent.
+    Dhclient,
+    /// Microsoft Windows 10 and later: Vendor Class Identifier `MSFT 5.0`,
+    /// configured host name is sent.
+    Windows,
+    /// Android 13: Vendor Class Identifier `android-dhcp-13`, Maximum DHCP
+    /// Message Size(57) of 1500, no Host Name(12).
+    Android,
+}
+
+impl DhcpV4ClientProfile {
+    pub(crate) fn apply(&self, config: &mut DhcpV4Config) {
+        config
+            .extra_opts
+            .retain(|(code, _)| *code != OPT_VENDOR_CLASS_ID);
+        match self {
+            Self::Dhclient => {
+                config
+                    .set_request_opts(&DHCLIENT_REQUEST_OPTS)
+                    .set_option_order(DhcpV4OptionOrder::Custom(
+                        DHCLIENT_OPTS_ORDER.to_vec(),
+                    ))
+                    .clear_client_id();
+            }
+            Self::Windows => {
+                config
+                    .set_request_opts(&WINDOWS_REQUEST_OPTS)
+                    .set_option_order(DhcpV4OptionOrder::Custom(
+                        WINDOWS_OPTS_ORDER.to_vec(),
+                    ))
+                    .add_extra_option(
+                        OPT_VENDOR_CLASS_ID,
+                        WINDOWS_VENDOR_CLASS_ID,
+                    );
+            }
+            Self::Android => {
+                config
+                    .set_request_opts(&ANDROID_REQUEST_OPTS)
+                    .set_option_order(DhcpV4OptionOrder::Custom(
+                        ANDROID_OPTS_ORDER.to_vec(),
+                    ))
+                    .set_max_msg_size(ANDROID_MAX_MSG_SIZE)
+                    .set_host_name("")
+                    .add_extra_option(
+                        OPT_VENDOR_CLASS_ID,
+                        ANDROID_VENDOR_CLASS_ID,
+                    );
+            }
+        }
+    }
+}
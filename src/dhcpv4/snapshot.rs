@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use super::client::DhcpV4Phase;
+use crate::{DhcpV4Event, DhcpV4Lease};
+
+/// State of [crate::DhcpV4Client] generated by
+/// [crate::DhcpV4Client::snapshot()], could be used by
+/// [crate::DhcpV4Client::restore()] to resume the client in another process
+/// without losing the lease or restarting the ongoing DHCP exchange.
+/// Serializable with `serde` feature enabled.
+/// The snapshot is only valid before system reboot.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct DhcpV4Snapshot {
+    pub(crate) phase: DhcpV4Phase,
+    pub(crate) lease: Option<DhcpV4Lease>,
+    // CLOCK_BOOTTIME when lease was acquired
+    pub(crate) lease_begin: Option<Duration>,
+    pub(crate) pending_lease: Option<DhcpV4Lease>,
+    pub(crate) xid: u32,
+    pub(crate) retry_count: u32,
+    // Time passed since current DHCP exchange began
+    pub(crate) trans_elapsed: Duration,
+    pub(crate) timers: Vec<(DhcpV4Event, Duration)>,
+    // CLOCK_BOOTTIME when snapshot was taken
+    pub(crate) taken_at: Duration,
+}
+
+impl DhcpV4Snapshot {
+    /// Lease acquired from DHCP server.
+    pub fn lease(&self) -> Option<&DhcpV4Lease> {
+        self.lease.as_ref()
+    }
+
+    /// Transaction ID of ongoing DHCP exchange.
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    /// Pending timer events with time left before expiring when snapshot was
+    /// taken.
+    pub fn timers(&self) -> &[(DhcpV4Event, Duration)] {
+        self.timers.as_slice()
+    }
+}
@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::SocketAddr;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use super::{config::DhcpV4Transport, dhcp4o6::Dhcp4o6Socket};
+#[cfg(feature = "chaos")]
+use crate::DhcpChaosPolicy;
+use crate::{
+    socket::{sealed::Sealed, DhcpRawSocket, DhcpSocket},
+    DhcpError, DhcpMemorySocket, DhcpV4Config, ErrorKind,
+};
+
+// Socket for the DHCP messages sent as ethernet frame: broadcast ones and
+// unicast ones when UDP socket is not usable.
+#[derive(Debug)]
+pub(crate) enum DhcpV4Socket {
+    Raw(Box<DhcpRawSocket>),
+    Dhcp4o6(Dhcp4o6Socket),
+    // Ethernet frame is stripped down to DHCP message
+    Memory(DhcpMemorySocket),
+    #[cfg(feature = "chaos")]
+    Chaos(Box<DhcpV4Socket>, DhcpChaosPolicy),
+}
+
+// Ethernet, IPv4 and UDP headers before DHCP message
+#[cfg(feature = "chaos")]
+const ETH_PKG_PAYLOAD_OFFSET: usize = 42;
+
+impl DhcpV4Socket {
+    pub(crate) fn new(config: &DhcpV4Config) -> Result<Self, DhcpError> {
+        let socket = Self::new_inner(config)?;
+        #[cfg(feature = "chaos")]
+        if let Some(policy) = config.chaos_policy.as_ref() {
+            return Ok(Self::Chaos(Box::new(socket), policy.clone()));
+        }
+        Ok(socket)
+    }
+
+    fn new_inner(config: &DhcpV4Config) -> Result<Self, DhcpError> {
+        if let Some(socket) = config.memory_socket.as_ref() {
+            return Ok(Self::Memory(socket.clone()));
+        }
+        Ok(match config.transport {
+            DhcpV4Transport::Native => {
+                if config.udp_only {
+                    let e = DhcpError::new(
+                        ErrorKind::RawSocketRequired,
+                        format!(
+                            "Refusing to open raw socket on interface {} in \
+                            UDP-only mode",
+                            config.iface_name
+                        ),
+                    );
+                    log::error!("{}", e);
+                    return Err(e);
+                }
+                Self::Raw(Box::new(DhcpRawSocket::new(config)?))
+            }
+            DhcpV4Transport::Dhcp4o6(srv_ip) => {
+                Self::Dhcp4o6(Dhcp4o6Socket::new(config, srv_ip)?)
+            }
+        })
+    }
+}
+
+impl AsRawFd for DhcpV4Socket {
+    fn as_raw_fd(&self) -> RawFd {
+        match self {
+            Self::Raw(s) => s.as_raw_fd(),
+            Self::Dhcp4o6(s) => s.as_raw_fd(),
+            Self::Memory(s) => s.as_raw_fd(),
+            #[cfg(feature = "chaos")]
+            Self::Chaos(s, _) => s.as_raw_fd(),
+        }
+    }
+}
+
+impl Sealed for DhcpV4Socket {}
+
+impl DhcpSocket for DhcpV4Socket {
+    fn is_raw(&self) -> bool {
+        match self {
+            Self::Raw(s) => s.is_raw(),
+            Self::Dhcp4o6(s) => s.is_raw(),
+            Self::Memory(s) => s.is_raw(),
+            #[cfg(feature = "chaos")]
+            Self::Chaos(s, _) => s.is_raw(),
+        }
+    }
+
+    fn send(&self, eth_pkg: &[u8]) -> Result<(), DhcpError> {
+        match self {
+            Self::Raw(s) => s.send(eth_pkg),
+            Self::Dhcp4o6(s) => s.send(eth_pkg),
+            Self::Memory(s) => s.send(eth_pkg_payload(eth_pkg)?),
+            #[cfg(feature = "chaos")]
+            Self::Chaos(s, policy) => {
+                policy.send_with(eth_pkg, ETH_PKG_PAYLOAD_OFFSET, |p| s.send(p))
+            }
+        }
+    }
+
+    fn recv(&self) -> Result<Vec<u8>, DhcpError> {
+        match self {
+            Self::Raw(s) => s.recv(),
+            Self::Dhcp4o6(s) => s.recv(),
+            Self::Memory(s) => s.recv(),
+            #[cfg(feature = "chaos")]
+            Self::Chaos(s, _) => s.recv(),
+        }
+    }
+
+    fn recv_from(&self) -> Result<(Vec<u8>, Option<SocketAddr>), DhcpError> {
+        match self {
+            Self::Raw(s) => s.recv_from(),
+            Self::Dhcp4o6(s) => s.recv_from(),
+            Self::Memory(s) => s.recv_from(),
+            #[cfg(feature = "chaos")]
+            Self::Chaos(s, _) => s.recv_from(),
+        }
+    }
+}
+
+fn eth_pkg_payload(eth_pkg: &[u8]) -> Result<&[u8], DhcpError> {
+    match etherparse::SlicedPacket::from_ethernet(eth_pkg) {
+        Ok(pkg) => Ok(pkg.payload),
+        Err(error) => {
+            let e = DhcpError::new(
+                ErrorKind::Bug,
+                format!("Failed to parse ethernet package: {error}"),
+            );
+            log::error!("{}", e);
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use crate::DhcpV4Lease;
+
+/// Lease state transition of DHCPv4 client, yielded by
+/// [crate::DhcpV4StateStream].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum DhcpV4State {
+    /// Lease acquired or extended.
+    Bound(DhcpV4Lease),
+    /// Requesting lease extension from the DHCP server granted the lease.
+    Renewing,
+    /// Requesting lease extension from any DHCP server.
+    Rebinding,
+    /// DHCP server refused the requested lease, client restarts discovery.
+    /// Contains the lease previously held which should not be used anymore.
+    Nacked(Option<DhcpV4Lease>),
+    /// Lease expired without being extended, client restarts discovery.
+    /// The leased address should be removed from interface.
+    Expired(DhcpV4Lease),
+    /// DHCP server indicated IPv6-only preferred(RFC 8925), client stopped
+    /// and IPv4 should be disabled for the specified period before starting
+    /// a new client.
+    Ipv6OnlyPreferred(Duration),
+}
+
+/// What changed during processing event, returned by
+/// [crate::DhcpV4Client::process_detailed()].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+// Boxing leases would make matching on progress awkward for caller
+#[allow(clippy::large_enum_variant)]
+pub enum DhcpV4Progress {
+    /// Lease state changed from the previous one(None for the first state)
+    /// to the new one.
+    StateChanged(Option<DhcpV4State>, DhcpV4State),
+    /// New lease acquired, the system should be configured with it.
+    LeaseAcquired(DhcpV4Lease),
+    /// Lease currently in use extended, only the lifetimes need to be
+    /// updated. T1 and T2 are counted from now.
+    LeaseRenewed { t1: Duration, t2: Duration },
+}
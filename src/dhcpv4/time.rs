@@ -2,13 +2,33 @@
 
 use std::time::Duration;
 
-use rand::Rng;
+use crate::random::gen_range;
+
+// The renew/rebind timers are shifted by random jitter from -1 to 1 second to
+// avoid synchronized renew storm from a fleet of clients sharing the same
+// T1/T2.
+const RENEW_REBIND_JITTER_MS: i64 = 1000;
 
 // The T1/T2 randomization is done by server side according to RFC 2131:
 //      Times T1 and T2 SHOULD be chosen with some random "fuzz" around a fixed
 //      value, to avoid synchronization of client reacquisition.
-pub(crate) fn gen_renew_rebind_times(t1: u32, t2: u32, lease: u32) -> [u32; 4] {
-    [t1, t1 + (t2 - t1) / 2, t2, t2 + (lease - t2) / 2]
+// Many servers do not, hence we also apply small jitter on client side.
+// The same jitter is used for all timers to preserve their order.
+pub(crate) fn gen_renew_rebind_times(
+    t1: u32,
+    t2: u32,
+    lease: u32,
+) -> [Duration; 4] {
+    let jitter_ms = gen_range(-RENEW_REBIND_JITTER_MS..=RENEW_REBIND_JITTER_MS);
+    let lease_ms = i64::from(lease) * 1000;
+    [t1, t1 + (t2 - t1) / 2, t2, t2 + (lease - t2) / 2].map(|t| {
+        Duration::from_millis(
+            (i64::from(t) * 1000 + jitter_ms)
+                .clamp(0, lease_ms)
+                .try_into()
+                .unwrap_or_default(),
+        )
+    })
 }
 
 // RFC 2131, section 4.1 "Constructing and sending DHCP messages" has
@@ -20,7 +40,7 @@ pub(crate) fn gen_dhcp_request_delay(retry_count: u32) -> u32 {
     if base > 62 {
         base = 62;
     }
-    let ms: u64 = rand::thread_rng().gen_range(0..2000);
+    let ms: u64 = gen_range(0..2000);
     (Duration::from_secs(base) + Duration::from_millis(ms))
         .as_secs()
         .try_into()
@@ -1,27 +1,59 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use std::net::Ipv6Addr;
-use std::os::fd::{AsRawFd, RawFd};
+use std::net::{Ipv6Addr, SocketAddr};
+use std::os::fd::{AsRawFd, BorrowedFd, RawFd};
 use std::time::{Duration, Instant};
 
-use rand::Rng;
+use dhcproto::{v6, Decodable, Decoder};
+use nix::poll::{PollFd, PollFlags, PollTimeout};
 
 use super::{
     msg::{DhcpV6Message, DhcpV6MessageType},
-    time::{
-        gen_rebind_wait_time, gen_renew_wait_time, gen_request_wait_time,
-        gen_solicit_wait_time,
-    },
+    ndp::{gen_neighbor_solicit, parse_neighbor_advert, solicited_node_addr},
+    socket::DhcpV6Socket,
+    state::{DhcpV6Progress, DhcpV6State},
+    time::gen_information_refresh_time,
+    trans::{xid_to_string, DhcpV6Transaction},
 };
 use crate::{
     event::DhcpEventPool,
-    socket::{DhcpSocket, DhcpUdpSocket},
-    DhcpError, DhcpV6Config, DhcpV6Event, DhcpV6IaType, DhcpV6Lease, ErrorKind,
+    log_limit::DhcpLogLimiter,
+    mac::{mac_address_to_eth_mac_bytes, mac_str_to_u8_array},
+    socket::DhcpIcmpV6Socket,
+    time::boot_time,
+    trace::trace_msg_received,
+    DhcpError, DhcpLifetime, DhcpV6Config, DhcpV6Event, DhcpV6IaType,
+    DhcpV6Lease, ErrorKind,
 };
 
 const DHCPV6_REPLAY_AND_SRVS: Ipv6Addr =
     Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 1, 2);
 
+const GATEWAY_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Interval of resending message failed to send within the same
+// retransmission timeout(RT) window
+const SEND_FAILURE_RETRY_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Statistics of DHCPv6 message transmission, please refer to
+/// [DhcpV6Client::retransmit_stats()].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct DhcpV6RetransmitStats {
+    /// Retransmissions of current message exchange, 0 means only the
+    /// initial message was transmitted.
+    pub retransmits: u32,
+    /// Current retransmission timeout(RT).
+    pub retrans_timeout: Duration,
+    /// Time passed since the initial message of current exchange was
+    /// transmitted, sent to server via Elapsed Time option.
+    pub elapsed: Duration,
+    /// Messages sent since client created.
+    pub sent: u64,
+    /// Messages failed to send since client created.
+    pub send_failures: u64,
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 enum DhcpV6Phase {
     Done,
@@ -31,6 +63,7 @@ enum DhcpV6Phase {
     Request,
     Renew,
     Rebind,
+    InformationRequest,
 }
 
 impl std::fmt::Display for DhcpV6Phase {
@@ -46,24 +79,49 @@ impl std::fmt::Display for DhcpV6Phase {
                 Self::Request => "request",
                 Self::Renew => "renew",
                 Self::Rebind => "rebind",
+                Self::InformationRequest => "information_request",
             }
         )
     }
 }
 
+#[derive(Debug)]
+struct DhcpV6GatewayProbe {
+    router: Ipv6Addr,
+    router_mac: [u8; 6],
+    // None means Neighbor Solicitation not sent yet
+    socket: Option<DhcpIcmpV6Socket>,
+}
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct DhcpV6Client {
     config: DhcpV6Config,
     event_pool: DhcpEventPool<DhcpV6Event>,
     lease: Option<DhcpV6Lease>,
+    // CLOCK_BOOTTIME when current lease was acquired
+    lease_begin: Option<Duration>,
+    // Lease advertised by DHCPv6 server and being requested
+    pending_lease: Option<DhcpV6Lease>,
+    // Lease replied by DHCPv6 server but not committed by caller yet
+    uncommitted_lease: Option<DhcpV6Lease>,
+    // All advertisements received since solicit started
+    candidates: Vec<DhcpV6Lease>,
+    gateway_probe: Option<DhcpV6GatewayProbe>,
     phase: DhcpV6Phase,
-    udp_socket: Option<DhcpUdpSocket>,
-    xid: [u8; 3],
-    retrans_timeout: Duration,
-    retrans_count: u32,
-    trans_begin_time: Option<Instant>,
-    trans_dhcp_msg: Option<DhcpV6Message>,
+    udp_socket: Option<DhcpV6Socket>,
+    // On-going message exchange
+    trans: Option<DhcpV6Transaction>,
+    sent_count: u64,
+    send_failure_count: u64,
+    // State transitions not consumed yet, None when nobody subscribed
+    states: Option<Vec<DhcpV6State>>,
+    // Last state and bound lease reported by `process_detailed()`
+    last_state: Option<DhcpV6State>,
+    last_bound: Option<DhcpV6Lease>,
+    // Lease acquisitions restarted in a row since lease bound
+    restart_count: u32,
+    log_limiter: DhcpLogLimiter,
 }
 
 impl AsRawFd for DhcpV6Client {
@@ -75,65 +133,377 @@ impl AsRawFd for DhcpV6Client {
 impl DhcpV6Client {
     fn clean_up(&mut self) {
         self.lease = None;
-        self.retrans_count = 0;
+        self.lease_begin = None;
+        self.pending_lease = None;
+        self.uncommitted_lease = None;
+        self.gateway_probe = None;
+        self.trans = None;
         self.phase = DhcpV6Phase::Done;
         self.event_pool.remove_all_event();
         self.udp_socket = None;
     }
 
+    /// Create the DHCP client. No packet is sent by this function, the first
+    /// one is sent when processing the event returned by
+    /// [DhcpV6Client::poll()].
+    /// Requires CAP_NET_RAW and CAP_NET_BIND_SERVICE, use
+    /// [crate::check_privileges()] to check beforehand.
+    /// The specified lease is renewed, unless its valid lifetime is 0
+    /// indicating expired, then its address or prefix is requested as hint
+    /// in SOLICIT.
     pub fn init(
         mut config: DhcpV6Config,
         lease: Option<DhcpV6Lease>,
     ) -> Result<Self, DhcpError> {
         config.init()?;
+        Self::init_with_resolved_config(config, lease)
+    }
+
+    // Create client with config already resolved by `DhcpV6Config::init()`
+    pub(crate) fn init_with_resolved_config(
+        config: DhcpV6Config,
+        lease: Option<DhcpV6Lease>,
+    ) -> Result<Self, DhcpError> {
         let mut event_pool = DhcpEventPool::new()?;
-        event_pool.add_timer(
-            Duration::from_secs(config.timeout.into()),
-            DhcpV6Event::Timeout,
-        )?;
+        if config.timeout > 0 {
+            event_pool.add_timer(
+                Duration::from_secs(config.timeout.into()),
+                DhcpV6Event::Timeout,
+            )?;
+        }
 
-        // In RFC 8415, the `transaction-id` is a 3-octet field
-        let mut xid: [u8; 3] = [0; 3];
-        xid.copy_from_slice(
-            &rand::thread_rng().gen::<u32>().to_le_bytes()[..3],
-        );
         let mut ret = Self {
             config,
             event_pool,
             lease,
+            lease_begin: None,
+            pending_lease: None,
+            uncommitted_lease: None,
+            candidates: Vec::new(),
+            gateway_probe: None,
             phase: DhcpV6Phase::Done,
-            xid,
             udp_socket: None,
-            retrans_timeout: Duration::new(0, 0),
-            retrans_count: 0,
-            trans_begin_time: None,
-            trans_dhcp_msg: None,
+            trans: None,
+            sent_count: 0,
+            send_failure_count: 0,
+            states: None,
+            last_state: None,
+            last_bound: None,
+            restart_count: 0,
+            log_limiter: DhcpLogLimiter::default(),
         };
-        if ret.lease.is_some() {
+        // Stateless DHCPv6 holds no address to renew
+        if ret.config.information_only {
+            ret.lease = None;
+            ret.process_information_request()?;
+        } else if ret.lease.as_ref().map(|l| l.valid_life == 0) == Some(true) {
+            // Address of expired lease is only used as hint in SOLICIT
+            let hint = ret.lease.take();
+            ret.process_solicit(hint)?;
+        } else if ret.lease.is_some() {
             ret.process_renew()?;
+            if let Err(e) = ret.init_gateway_probe() {
+                log::warn!("Skipping gateway probe due to error: {e}");
+            }
         } else {
-            ret.process_solicit()?;
+            ret.process_solicit(None)?;
         }
 
         Ok(ret)
     }
 
-    fn clean_trans_counters(&mut self) {
-        self.trans_dhcp_msg = None;
-        self.retrans_count = 0;
-        self.retrans_timeout = Duration::new(0, 0);
-        self.trans_begin_time = None;
+    fn init_gateway_probe(&mut self) -> Result<(), DhcpError> {
+        if let Some((router, router_mac)) = self.config.fast_resume.as_ref() {
+            self.gateway_probe = Some(DhcpV6GatewayProbe {
+                router: *router,
+                router_mac: mac_address_to_eth_mac_bytes(router_mac)?,
+                socket: None,
+            });
+            self.event_pool
+                .add_timer(Duration::ZERO, DhcpV6Event::GatewayProbeTimeout)?;
+        }
+        Ok(())
+    }
+
+    fn stop_gateway_probe(&mut self) -> Result<(), DhcpError> {
+        self.event_pool.del_socket(DhcpV6Event::IcmpPackageIn)?;
+        self.event_pool
+            .del_timer(DhcpV6Event::GatewayProbeTimeout)?;
+        self.gateway_probe = None;
+        Ok(())
+    }
+
+    // First timeout is for sending Neighbor Solicitation, second one is the
+    // timeout of waiting Neighbor Advertisement.
+    fn process_gateway_probe_timeout(&mut self) -> Result<(), DhcpError> {
+        self.event_pool
+            .del_timer(DhcpV6Event::GatewayProbeTimeout)?;
+        let probe = match self.gateway_probe.as_mut() {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        if probe.socket.is_some() {
+            log::info!(
+                "Router {} does not reply Neighbor Solicitation, \
+                waiting DHCPv6 server",
+                probe.router
+            );
+            return self.stop_gateway_probe();
+        }
+        let socket = DhcpIcmpV6Socket::new(
+            self.config.iface_name.as_str(),
+            self.config.iface_index,
+            self.config.socket_timeout,
+        )?;
+        // Multicast Neighbor Solicitation to make sure the Neighbor
+        // Advertisement contains Target Link-Layer Address option.
+        socket.send_to(
+            &solicited_node_addr(&probe.router),
+            &gen_neighbor_solicit(
+                &probe.router,
+                &mac_str_to_u8_array(self.config.src_mac.as_str()),
+            ),
+        )?;
+        self.event_pool
+            .add_socket(socket.as_raw_fd(), DhcpV6Event::IcmpPackageIn)?;
+        probe.socket = Some(socket);
+        self.event_pool
+            .add_timer(GATEWAY_PROBE_TIMEOUT, DhcpV6Event::GatewayProbeTimeout)
+    }
+
+    fn process_gateway_probe_recv(
+        &mut self,
+    ) -> Result<Option<DhcpV6Lease>, DhcpError> {
+        let probe = match self.gateway_probe.as_ref() {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+        let data = match probe.socket.as_ref().map(|s| s.recv()) {
+            Some(Ok(d)) => d,
+            _ => return Ok(None),
+        };
+        let (router, mac) = match parse_neighbor_advert(&data) {
+            Some((router, Some(mac))) if router == probe.router => {
+                (router, mac)
+            }
+            _ => return Ok(None),
+        };
+        let is_expected = mac == probe.router_mac;
+        self.stop_gateway_probe()?;
+        if is_expected {
+            log::info!(
+                "Router {router} replied with expected MAC, lease is \
+                considered bound while renewing with DHCPv6 server"
+            );
+            Ok(self.lease.clone())
+        } else {
+            log::info!(
+                "Router {router} replied with unexpected MAC {mac:02x?}, \
+                waiting DHCPv6 server"
+            );
+            Ok(None)
+        }
+    }
+
+    /// Statistics of DHCPv6 message transmission.
+    pub fn retransmit_stats(&self) -> DhcpV6RetransmitStats {
+        DhcpV6RetransmitStats {
+            retransmits: self
+                .trans
+                .as_ref()
+                .map(|t| t.retrans_count.saturating_sub(1))
+                .unwrap_or_default(),
+            retrans_timeout: self
+                .trans
+                .as_ref()
+                .map(|t| t.retrans_timeout)
+                .unwrap_or_default(),
+            elapsed: self
+                .trans
+                .as_ref()
+                .map(|t| t.elapsed())
+                .unwrap_or_default(),
+            sent: self.sent_count,
+            send_failures: self.send_failure_count,
+        }
+    }
+
+    // Start new message exchange, the initial message is sent once
+    // `DhcpV6Event::TransmitWait` expired.
+    fn start_transaction(
+        &mut self,
+        trans: DhcpV6Transaction,
+    ) -> Result<(), DhcpError> {
+        self.event_pool.del_timer(DhcpV6Event::TransmitWait)?;
+        self.event_pool
+            .add_timer(trans.retrans_timeout, DhcpV6Event::TransmitWait)?;
+        self.trans = Some(trans);
+        Ok(())
+    }
+
+    // Transaction ID expected in server reply
+    fn trans_xid(&self) -> Result<[u8; 3], DhcpError> {
+        match self.trans.as_ref() {
+            Some(t) => Ok(t.xid()),
+            None => {
+                let e = DhcpError::new(
+                    ErrorKind::Bug,
+                    format!("Got reply without on-going transaction {self:?}"),
+                );
+                log::error!("{}", e);
+                Err(e)
+            }
+        }
     }
 
     pub fn poll(&self, wait_time: u32) -> Result<Vec<DhcpV6Event>, DhcpError> {
         self.event_pool.poll(wait_time)
     }
 
+    /// Pending timers with time left before firing, sorted by time left.
+    /// Useful for external supervisors to align their own tasks with DHCP
+    /// timing, e.g. [DhcpV6Event::Renew] for the upcoming renewal.
+    pub fn timers(&self) -> Result<Vec<(DhcpV6Event, Duration)>, DhcpError> {
+        self.event_pool.timers()
+    }
+
+    /// Time left before specified timer fires, `None` if not scheduled.
+    pub fn remaining(
+        &self,
+        event: DhcpV6Event,
+    ) -> Result<Option<Duration>, DhcpError> {
+        Ok(self
+            .event_pool
+            .timers()?
+            .into_iter()
+            .find_map(|(e, t)| (e == event).then_some(t)))
+    }
+
+    /// Time of next scheduled (re)transmission of current message exchange
+    /// along with current retransmission timeout(RT), `None` if no
+    /// transmission scheduled, e.g. lease bound or waiting reply of the
+    /// final transmission.
+    pub fn next_retransmit_at(
+        &self,
+    ) -> Result<Option<(Instant, Duration)>, DhcpError> {
+        let rt = match self.trans.as_ref() {
+            Some(t) => t.retrans_timeout,
+            None => return Ok(None),
+        };
+        Ok(self
+            .remaining(DhcpV6Event::TransmitWait)?
+            .map(|remain| (Instant::now() + remain, rt)))
+    }
+
+    /// All the leases advertised by DHCPv6 servers during last solicit,
+    /// including the ones not selected or not approved. Useful for detecting
+    /// rogue or misconfigured DHCPv6 servers.
+    pub fn last_candidates(&self) -> &[DhcpV6Lease] {
+        self.candidates.as_slice()
+    }
+
+    /// The lease advertised by DHCPv6 server which client is requesting.
+    pub fn pending_lease(&self) -> Option<&DhcpV6Lease> {
+        self.pending_lease.as_ref()
+    }
+
+    /// Effective config with interface information resolved.
+    pub fn config(&self) -> &DhcpV6Config {
+        &self.config
+    }
+
+    /// Update config of running client, only fields listed below are
+    /// allowed to be different from [DhcpV6Client::config()], otherwise
+    /// [ErrorKind::InvalidArgument] is returned:
+    ///  * timeout, state timeout and renew timeout
+    ///  * restart policy
+    ///  * request options
+    ///  * host name
+    ///
+    /// Changes take effect at the next message exchange, timers already
+    /// started are not changed.
+    pub fn update_config(
+        &mut self,
+        config: &DhcpV6Config,
+    ) -> Result<(), DhcpError> {
+        self.config.update(config)
+    }
+
+    // Start recording state transitions for `take_states()`
+    pub(crate) fn track_states(&mut self) {
+        self.states.get_or_insert_with(Vec::new);
+    }
+
+    pub(crate) fn take_states(&mut self) -> Vec<DhcpV6State> {
+        self.states.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    fn push_state(&mut self, state: DhcpV6State) {
+        if let Some(states) = self.states.as_mut() {
+            states.push(state);
+        }
+    }
+
+    /// Same as [DhcpV6Client::process()] but report what changed, so
+    /// caller does not need to compare leases to know when to reconfigure
+    /// the system. State transitions happened without returning lease
+    /// (e.g. entering renewing) are also included.
+    pub fn process_detailed(
+        &mut self,
+        event: DhcpV6Event,
+    ) -> Result<Vec<DhcpV6Progress>, DhcpError> {
+        self.track_states();
+        self.process(event)?;
+        let mut ret = Vec::new();
+        for state in self.take_states() {
+            let old_state = self.last_state.replace(state.clone());
+            match &state {
+                DhcpV6State::Bound(lease) => {
+                    let progress = match self.last_bound.replace(lease.clone())
+                    {
+                        Some(old)
+                            if old.ia_type == lease.ia_type
+                                && old.addr == lease.addr
+                                && old.prefix_len == lease.prefix_len =>
+                        {
+                            let mut progress = Vec::new();
+                            // Stateless DHCPv6 has no lifetime to extend
+                            if !self.config.information_only {
+                                progress.push(DhcpV6Progress::LeaseRenewed {
+                                    t1: Duration::from_secs(lease.t1.into()),
+                                    t2: Duration::from_secs(lease.t2.into()),
+                                });
+                            }
+                            if !old.same_options(lease) {
+                                progress.push(DhcpV6Progress::OptionsChanged(
+                                    lease.clone(),
+                                ));
+                            }
+                            progress
+                        }
+                        _ => vec![DhcpV6Progress::LeaseAcquired(lease.clone())],
+                    };
+                    ret.push(DhcpV6Progress::StateChanged(old_state, state));
+                    ret.extend(progress);
+                }
+                DhcpV6State::Nacked(_) | DhcpV6State::Expired(_) => {
+                    self.last_bound = None;
+                    ret.push(DhcpV6Progress::StateChanged(old_state, state));
+                }
+                _ => {
+                    ret.push(DhcpV6Progress::StateChanged(old_state, state));
+                }
+            }
+        }
+        Ok(ret)
+    }
+
     pub fn process(
         &mut self,
         event: DhcpV6Event,
     ) -> Result<Option<DhcpV6Lease>, DhcpError> {
         log::debug!("Processing event {:?}", event);
+        let _span = self.trans.as_ref().map(|t| t.span.enter());
         match event {
             DhcpV6Event::TransmitWait => {
                 self.process_transmit()?;
@@ -146,85 +516,338 @@ impl DhcpV6Client {
                 }
                 DhcpV6Phase::Request
                 | DhcpV6Phase::Renew
-                | DhcpV6Phase::Rebind => self.process_reply(),
-                _ => Err(DhcpError::new(
-                    ErrorKind::Bug,
-                    format!(
-                        "Cannot process unsupported phase {} in \
-                        UdpPackageIn",
-                        self.phase
-                    ),
-                )),
+                | DhcpV6Phase::Rebind
+                | DhcpV6Phase::InformationRequest => self.process_reply(),
+                // Stray packet, e.g. late reply of previous transaction
+                DhcpV6Phase::Done => {
+                    log::debug!(
+                        "Removing UDP socket from event pool as no DHCPv6 \
+                        exchange in progress"
+                    );
+                    self.event_pool.del_socket(DhcpV6Event::UdpPackageIn)?;
+                    self.udp_socket = None;
+                    Ok(None)
+                }
+                DhcpV6Phase::PreSolicit | DhcpV6Phase::PreRequest => {
+                    if let Some(socket) = self.udp_socket.as_ref() {
+                        if let Ok((_, src)) = socket.recv_from() {
+                            log::debug!(
+                                "Ignoring DHCPv6 packet from {src:?} in \
+                                phase {}",
+                                self.phase
+                            );
+                        }
+                    }
+                    Ok(None)
+                }
             },
             DhcpV6Event::Renew => {
                 self.process_renew()?;
                 Ok(None)
             }
             DhcpV6Event::LeaseExpired => {
-                self.process_solicit()?;
+                self.process_lease_expired()?;
                 Ok(None)
             }
+            DhcpV6Event::Timeout
+            | DhcpV6Event::StateTimeout
+            | DhcpV6Event::RenewTimeout => self.process_timeout(event),
             DhcpV6Event::Rebind => {
                 self.process_rebind()?;
                 Ok(None)
             }
-            _ => Err(DhcpError::new(
-                ErrorKind::Bug,
-                format!("Cannot process unsupported event {}", event),
-            )),
+            DhcpV6Event::IcmpPackageIn => self.process_gateway_probe_recv(),
+            DhcpV6Event::GatewayProbeTimeout => {
+                self.process_gateway_probe_timeout()?;
+                Ok(None)
+            }
+            DhcpV6Event::InformationRefresh => {
+                self.process_information_request()?;
+                Ok(None)
+            }
         }
     }
 
-    /// The RFC 8415:
-    ///     Implementations SHOULD retransmit one or more times but MAY choose
-    ///     to terminate the retransmission procedure early.
-    /// So here we decided not to wait reply from DHCPv6 server.
+    /// Release the lease and block till DHCPv6 server replied. The RELEASE
+    /// is retransmitted as RFC 8415 18.2.7 required, limited by
+    /// [DhcpV6Config::set_release_timeout()]. The RFC 8415 said
+    ///     If the client does not receive a response from the server by the
+    ///     end of the retransmission process, the client abandons the
+    ///     Release.
+    /// Hence no error is returned when server never replied.
     /// To request new release, you need to create new instance of
     /// [DhcpV6Client].
     pub fn release(&mut self, lease: &DhcpV6Lease) -> Result<(), DhcpError> {
+        let result = self.send_release(DhcpV6MessageType::RELEASE, lease, true);
+        self.clean_up();
+        result
+    }
+
+    /// Send RELEASE once without waiting server reply, intended for
+    /// shutdown path which cannot afford blocking. The RFC 8415 said
+    ///     Implementations SHOULD retransmit one or more times but MAY choose
+    ///     to terminate the retransmission procedure early.
+    pub fn release_force(
+        &mut self,
+        lease: &DhcpV6Lease,
+    ) -> Result<(), DhcpError> {
+        let result =
+            self.send_release(DhcpV6MessageType::RELEASE, lease, false);
+        self.clean_up();
+        result
+    }
+
+    // Send RELEASE or DECLINE, both are retransmitted till server replied
+    fn send_release(
+        &mut self,
+        msg_type: DhcpV6MessageType,
+        lease: &DhcpV6Lease,
+        wait_reply: bool,
+    ) -> Result<(), DhcpError> {
         if self.udp_socket.is_none() {
-            let socket = DhcpUdpSocket::new_v6(
-                self.config.iface_index,
-                &self.config.src_ip,
-                self.config.socket_timeout,
-            )?;
-            self.udp_socket = Some(socket);
+            self.udp_socket = Some(DhcpV6Socket::new(&self.config)?);
         }
         let socket = self.udp_socket.as_ref().unwrap();
 
-        let mut dhcp_msg = DhcpV6Message::new(
-            &self.config,
-            DhcpV6MessageType::RELEASE,
-            self.xid,
-        );
-        dhcp_msg.load_lease(lease.clone())?;
+        let max_duration =
+            Duration::from_secs(self.config.release_timeout.into());
+        let mut trans =
+            DhcpV6Transaction::new(&self.config, msg_type, max_duration)?;
+        // RFC 8415 has no delay before initial RELEASE or DECLINE
+        trans.retrans_timeout = Duration::ZERO;
+        trans.msg.load_lease(lease.clone())?;
         let dst = if lease.srv_ip.is_unspecified() {
             &DHCPV6_REPLAY_AND_SRVS
         } else {
             &lease.srv_ip
         };
-        socket.send_to_v6(dst, &dhcp_msg.to_dhcp_pkg()?)?;
+        let deadline =
+            (!max_duration.is_zero()).then(|| Instant::now() + max_duration);
+
+        loop {
+            if let Err(e) = trans.start_retransmit() {
+                log::warn!("Abandoning DHCPv6 {msg_type}: {e}");
+                return Ok(());
+            }
+            socket.send_to_v6(dst, &trans.gen_dhcp_pkg()?)?;
+            self.sent_count += 1;
+            if !wait_reply {
+                return Ok(());
+            }
+            let mut wait_time = trans.remain();
+            if let Some(deadline) = deadline {
+                wait_time = wait_time
+                    .min(deadline.saturating_duration_since(Instant::now()));
+            }
+            if wait_release_reply(socket, &self.config, trans.xid(), wait_time)?
+            {
+                log::info!("DHCPv6 server replied to {msg_type}");
+                return Ok(());
+            }
+            if deadline.map(|d| d <= Instant::now()) == Some(true) {
+                log::warn!(
+                    "Abandoning DHCPv6 {msg_type}: no reply within {} seconds",
+                    self.config.release_timeout
+                );
+                return Ok(());
+            }
+        }
+    }
+
+    /// Notify the client that system has just resumed from suspend.
+    /// The lease lifetime will be revalidated against the time elapsed since
+    /// lease acquired(suspended time included). If the lease has expired, the
+    /// client starts solicit again; if T2 has passed, the client jumps
+    /// straight to rebind.
+    pub fn notify_resumed(&mut self) -> Result<(), DhcpError> {
+        // Options of stateless DHCPv6 never expire
+        if self.config.information_only {
+            return Ok(());
+        }
+        let (lease, lease_begin) = match (self.lease.as_ref(), self.lease_begin)
+        {
+            (Some(l), Some(b)) => (l, b),
+            _ => return Ok(()),
+        };
+        let elapsed = boot_time()?.saturating_sub(lease_begin);
+        if lease
+            .valid_lifetime()
+            .as_duration()
+            .is_some_and(|valid| elapsed >= valid)
+        {
+            log::info!("DHCPv6 lease expired during suspend, starting solicit");
+            self.process_lease_expired()?;
+        } else if lease.ia_type != DhcpV6IaType::TemporaryAddresses
+            && lease
+                .t2_lifetime()
+                .as_duration()
+                .is_some_and(|t2| elapsed >= t2)
+            && self.phase != DhcpV6Phase::Rebind
+        {
+            log::info!(
+                "DHCPv6 lease passed T2 during suspend, starting rebind"
+            );
+            self.stop_transaction()?;
+            self.event_pool.del_timer(DhcpV6Event::Renew)?;
+            self.process_rebind()?;
+        }
+        Ok(())
+    }
+
+    // Abort on-going renew or rebind transaction
+    fn stop_transaction(&mut self) -> Result<(), DhcpError> {
+        self.event_pool.del_timer(DhcpV6Event::TransmitWait)?;
+        self.event_pool.del_socket(DhcpV6Event::UdpPackageIn)?;
+        self.udp_socket = None;
+        self.trans = None;
+        Ok(())
+    }
+
+    fn process_lease_expired(&mut self) -> Result<(), DhcpError> {
+        let hint = self.lease.clone();
+        if let Some(lease) = hint.clone() {
+            self.push_state(DhcpV6State::Expired(lease));
+        }
+        if !self.restart_allowed() {
+            self.clean_up();
+            let e = DhcpError::new(
+                ErrorKind::LeaseExpired,
+                "DHCPv6 lease expired, not restarting solicit as per \
+                restart policy"
+                    .to_string(),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+        self.restart_solicit(hint)
+    }
+
+    fn restart_allowed(&mut self) -> bool {
+        if self.config.restart_policy.allows(self.restart_count) {
+            self.restart_count += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Drop current lease and start new lease acquisition, the address of
+    // `hint` lease is requested in SOLICIT.
+    fn restart_solicit(
+        &mut self,
+        hint: Option<DhcpV6Lease>,
+    ) -> Result<(), DhcpError> {
+        self.stop_transaction()?;
+        self.event_pool.del_timer(DhcpV6Event::Renew)?;
+        self.event_pool.del_timer(DhcpV6Event::Rebind)?;
+        self.event_pool.del_timer(DhcpV6Event::LeaseExpired)?;
+        self.event_pool.del_timer(DhcpV6Event::RenewTimeout)?;
+        if self.config.timeout > 0 {
+            self.event_pool.add_timer(
+                Duration::from_secs(self.config.timeout.into()),
+                DhcpV6Event::Timeout,
+            )?;
+        }
+        self.process_solicit(hint)
+    }
 
+    // Reply with failure status code means the requested or leased IA is
+    // not available anymore, start over from solicit.
+    fn process_nack(
+        &mut self,
+        e: DhcpError,
+    ) -> Result<Option<DhcpV6Lease>, DhcpError> {
+        self.push_state(DhcpV6State::Nacked(self.lease.clone()));
+        if !self.restart_allowed() {
+            self.clean_up();
+            log::error!("{}", e);
+            return Err(e);
+        }
+        log::warn!("{e}, restarting solicit");
+        if self.phase == DhcpV6Phase::Request {
+            self.stop_transaction()?;
+            self.pending_lease = None;
+            self.process_solicit(None)?;
+        } else {
+            self.restart_solicit(None)?;
+        }
+        Ok(None)
+    }
+
+    fn process_timeout(
+        &mut self,
+        event: DhcpV6Event,
+    ) -> Result<Option<DhcpV6Lease>, DhcpError> {
+        let phase = self.phase;
         self.clean_up();
+        let e = match event {
+            DhcpV6Event::StateTimeout => DhcpError::new(
+                ErrorKind::StateTimeout,
+                format!(
+                    "Timeout on {phase} state after {} seconds",
+                    self.config.state_timeout
+                ),
+            ),
+            DhcpV6Event::RenewTimeout => DhcpError::new(
+                ErrorKind::RenewTimeout,
+                format!(
+                    "Failed to renew lease within {} seconds",
+                    self.config.renew_timeout
+                ),
+            ),
+            _ => DhcpError::new(
+                ErrorKind::Timeout,
+                format!(
+                    "Failed to acquire lease within {} seconds",
+                    self.config.timeout
+                ),
+            ),
+        };
+        log::error!("{}", e);
+        Err(e)
+    }
+
+    // Restart per-state timeout on entering new lease acquisition state
+    fn reset_state_timer(&mut self) -> Result<(), DhcpError> {
+        self.event_pool.del_timer(DhcpV6Event::StateTimeout)?;
+        if self.config.state_timeout > 0 {
+            self.event_pool.add_timer(
+                Duration::from_secs(self.config.state_timeout.into()),
+                DhcpV6Event::StateTimeout,
+            )?;
+        }
         Ok(())
     }
 
-    fn process_solicit(&mut self) -> Result<(), DhcpError> {
+    // RFC 8415: 18.2.1. Creation and Transmission of Solicit Messages
+    //      The client MAY include addresses in IA Address options encapsulated
+    //      within IA_NA and IA_TA options as hints to the server about the
+    //      addresses for which the client has a preference.
+    fn process_solicit(
+        &mut self,
+        hint: Option<DhcpV6Lease>,
+    ) -> Result<(), DhcpError> {
+        self.reset_state_timer()?;
         self.phase = DhcpV6Phase::PreSolicit;
+        self.candidates.clear();
         self.lease = None;
-        self.retrans_timeout =
-            gen_solicit_wait_time(Instant::now(), 0, Duration::new(0, 0))?;
-        self.trans_dhcp_msg = Some(DhcpV6Message::new(
+        self.lease_begin = None;
+        let mut trans = DhcpV6Transaction::new(
             &self.config,
             DhcpV6MessageType::SOLICIT,
-            self.xid,
-        ));
-        self.event_pool
-            .add_timer(self.retrans_timeout, DhcpV6Event::TransmitWait)
+            Duration::ZERO,
+        )?;
+        if let Some(hint) = hint {
+            log::info!("Requesting previous {} in SOLICIT", hint.addr);
+            if let Err(e) = trans.msg.load_lease(hint) {
+                log::warn!("Not using invalid lease as SOLICIT hint: {e}");
+            }
+        }
+        self.start_transaction(trans)
     }
 
     fn process_advertise(&mut self) -> Result<(), DhcpError> {
-        self.event_pool.del_timer(DhcpV6Event::Timeout)?;
+        let xid = self.trans_xid()?;
         let socket = match self.udp_socket.as_ref() {
             Some(s) => s,
             None => {
@@ -236,36 +859,53 @@ impl DhcpV6Client {
         };
         let lease = match recv_dhcp_msg(
             socket,
+            &self.config,
             DhcpV6MessageType::ADVERTISE,
-            self.xid,
-        )? {
-            Some(l) => l,
-            None => return Ok(()),
+            xid,
+            Some(&mut self.candidates),
+            None,
+        ) {
+            Ok(Some(l)) => l,
+            Ok(None) => return Ok(()),
+            Err(e) if e.kind() == ErrorKind::InvalidDhcpServerReply => {
+                self.log_limiter.log(
+                    log::Level::Info,
+                    "Ignoring invalid DHCPv6 package",
+                    &e,
+                );
+                return Ok(());
+            }
+            Err(e) => return Err(e),
         };
-
-        let mut dhcp_msg = DhcpV6Message::new(
+        if let Some(approver) = self.config.lease_approver.as_ref() {
+            if !approver.approve(&lease) {
+                log::info!(
+                    "DHCPv6 advertise from server {} is not approved, \
+                    waiting for other servers",
+                    lease.srv_ip
+                );
+                return Ok(());
+            }
+        }
+        let mut trans = DhcpV6Transaction::new(
             &self.config,
             DhcpV6MessageType::REQUEST,
-            self.xid,
-        );
-        if let Err(e) = dhcp_msg.load_lease(lease.clone()) {
+            Duration::ZERO,
+        )?;
+        if let Err(e) = trans.msg.load_lease(lease.clone()) {
             log::warn!("Invalid DHCPv6 lease: {e}, will retry later");
             return Ok(());
         }
-        self.event_pool.del_timer(DhcpV6Event::TransmitWait)?;
-        self.clean_trans_counters();
-        self.retrans_timeout =
-            gen_request_wait_time(Instant::now(), 0, Duration::new(0, 0))?;
-        self.trans_dhcp_msg = Some(dhcp_msg);
-        self.event_pool
-            .add_timer(self.retrans_timeout, DhcpV6Event::TransmitWait)?;
+        self.start_transaction(trans)?;
+        self.pending_lease = Some(lease);
         self.phase = DhcpV6Phase::PreRequest;
-        Ok(())
+        self.reset_state_timer()
     }
 
     // TODO: Handle sever reply with valid_life with 0(indicate requested
     //       IA is invalid)
     fn process_reply(&mut self) -> Result<Option<DhcpV6Lease>, DhcpError> {
+        let xid = self.trans_xid()?;
         let socket = match self.udp_socket.as_ref() {
             Some(s) => s,
             None => {
@@ -275,27 +915,211 @@ impl DhcpV6Client {
                 ));
             }
         };
-        let lease =
-            match recv_dhcp_msg(socket, DhcpV6MessageType::REPLY, self.xid)? {
-                Some(l) => l,
-                None => return Ok(None),
-            };
+        let is_new_lease = self.phase == DhcpV6Phase::Request;
+        let is_information = self.phase == DhcpV6Phase::InformationRequest;
+        let candidates = if is_new_lease {
+            Some(&mut self.candidates)
+        } else {
+            None
+        };
+        // Renew is addressed to the DHCPv6 server granted current lease
+        let srv_duid = match self.phase {
+            DhcpV6Phase::Renew => {
+                self.lease.as_ref().map(|l| l.srv_duid.as_slice())
+            }
+            _ => None,
+        };
+        let lease = match recv_dhcp_msg(
+            socket,
+            &self.config,
+            DhcpV6MessageType::REPLY,
+            xid,
+            candidates,
+            srv_duid,
+        ) {
+            Ok(Some(l)) => l,
+            Ok(None) => return Ok(None),
+            // No binding to lose, keep retransmitting for other servers
+            Err(e) if e.kind() == ErrorKind::NoLease && is_information => {
+                self.log_limiter.log(
+                    log::Level::Warn,
+                    "Ignoring DHCPv6 reply to INFORMATION-REQUEST",
+                    &e,
+                );
+                return Ok(None);
+            }
+            Err(e) if e.kind() == ErrorKind::NoLease => {
+                return self.process_nack(e);
+            }
+            Err(e) if e.kind() == ErrorKind::InvalidDhcpServerReply => {
+                self.log_limiter.log(
+                    log::Level::Info,
+                    "Ignoring invalid DHCPv6 package",
+                    &e,
+                );
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
 
         self.phase = DhcpV6Phase::Done;
         self.event_pool.del_socket(DhcpV6Event::UdpPackageIn)?;
         self.udp_socket = None;
         self.event_pool.del_timer(DhcpV6Event::TransmitWait)?;
+        self.event_pool.del_timer(DhcpV6Event::Timeout)?;
+        self.event_pool.del_timer(DhcpV6Event::StateTimeout)?;
+        self.event_pool.del_timer(DhcpV6Event::RenewTimeout)?;
+        self.stop_gateway_probe()?;
+        self.pending_lease = None;
+        self.trans = None;
+        if is_information {
+            self.bind_information(&lease)?;
+            return Ok(Some(lease));
+        }
+        if is_new_lease && self.config.manual_commit {
+            log::info!(
+                "DHCPv6 lease {} replied, waiting caller to commit",
+                lease.addr
+            );
+            self.uncommitted_lease = Some(lease.clone());
+            return Ok(Some(lease));
+        }
+        self.bind_lease(&lease)?;
+        Ok(Some(lease))
+    }
+
+    fn bind_lease(&mut self, lease: &DhcpV6Lease) -> Result<(), DhcpError> {
         self.lease = Some(lease.clone());
-        self.clean_trans_counters();
+        self.lease_begin = Some(boot_time()?);
         self.schedule_renew_rebind_restart()?;
+        self.restart_count = 0;
+        self.push_state(DhcpV6State::Bound(lease.clone()));
+        Ok(())
+    }
 
-        Ok(Some(lease))
+    // Hold options of stateless DHCPv6 till refresh
+    fn bind_information(
+        &mut self,
+        lease: &DhcpV6Lease,
+    ) -> Result<(), DhcpError> {
+        self.lease = Some(lease.clone());
+        self.lease_begin = Some(boot_time()?);
+        self.restart_count = 0;
+        if let Some(refresh) =
+            gen_information_refresh_time(lease.information_refresh_time)
+        {
+            log::info!(
+                "DHCPv6 options will be refreshed in {} seconds",
+                refresh.as_secs()
+            );
+            self.event_pool
+                .add_timer(refresh, DhcpV6Event::InformationRefresh)?;
+        }
+        self.push_state(DhcpV6State::Bound(lease.clone()));
+        Ok(())
+    }
+
+    /// Lease returned by [DhcpV6Client::process()] but not committed yet
+    /// when [DhcpV6Config::set_manual_commit()] enabled.
+    pub fn uncommitted_lease(&self) -> Option<&DhcpV6Lease> {
+        self.uncommitted_lease.as_ref()
+    }
+
+    /// Confirm the lease has been applied to the system, the client then
+    /// binds the lease and schedules renewal.
+    /// Only needed when [DhcpV6Config::set_manual_commit()] enabled.
+    pub fn commit_lease(
+        &mut self,
+        lease: &DhcpV6Lease,
+    ) -> Result<(), DhcpError> {
+        let lease = self.take_uncommitted_lease(lease)?;
+        log::info!("DHCPv6 lease {} committed", lease.addr);
+        self.bind_lease(&lease)
+    }
+
+    /// Report the lease failed to be applied to the system(e.g. duplicate
+    /// address detected), the client sends DECLINE and starts solicit
+    /// again, unless disallowed by [DhcpV6Config::set_restart_policy()].
+    /// As DECLINE is only defined for addresses, delegated prefix is
+    /// released instead. Like [DhcpV6Client::release()], this blocks till
+    /// server replied or retransmission limited by
+    /// [DhcpV6Config::set_release_timeout()] finished.
+    /// Only needed when [DhcpV6Config::set_manual_commit()] enabled.
+    pub fn decline_lease(
+        &mut self,
+        lease: &DhcpV6Lease,
+    ) -> Result<(), DhcpError> {
+        let lease = self.take_uncommitted_lease(lease)?;
+        let msg_type = if lease.ia_type == DhcpV6IaType::PrefixDelegation {
+            DhcpV6MessageType::RELEASE
+        } else {
+            DhcpV6MessageType::DECLINE
+        };
+        // Failing to send only wastes the address on server, still move on
+        // to request new lease.
+        if let Err(e) = self.send_release(msg_type, &lease, true) {
+            log::warn!(
+                "Failed to send DHCPv6 {msg_type} of {}: {e}",
+                lease.addr
+            );
+        }
+        if !self.restart_allowed() {
+            self.clean_up();
+            let e = DhcpError::new(
+                ErrorKind::NoLease,
+                format!(
+                    "DHCPv6 lease {} declined, not restarting solicit as \
+                    per restart policy",
+                    lease.addr
+                ),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+        self.restart_solicit(None)
+    }
+
+    fn take_uncommitted_lease(
+        &mut self,
+        lease: &DhcpV6Lease,
+    ) -> Result<DhcpV6Lease, DhcpError> {
+        match self.uncommitted_lease.take() {
+            Some(l) if l.addr == lease.addr && l.iaid == lease.iaid => Ok(l),
+            l => {
+                self.uncommitted_lease = l;
+                let e = DhcpError::new(
+                    ErrorKind::InvalidArgument,
+                    format!(
+                        "DHCPv6 lease {} is not waiting for commit",
+                        lease.addr
+                    ),
+                );
+                log::error!("{}", e);
+                Err(e)
+            }
+        }
     }
 
     // TODO: rate control
     fn process_transmit(&mut self) -> Result<(), DhcpError> {
         self.event_pool.del_timer(DhcpV6Event::TransmitWait)?;
-        self.schedule_next_retransmit()?;
+        let trans = match self.trans.as_mut() {
+            Some(t) => t,
+            None => {
+                return Err(DhcpError::new(
+                    ErrorKind::Bug,
+                    format!(
+                        "Got NULL transaction for process_transmit {:?}",
+                        self
+                    ),
+                ));
+            }
+        };
+        // Resending does not start new RT window
+        if !trans.resending {
+            trans.start_retransmit()?;
+        }
+        trans.resending = false;
 
         // The RFC 8415 said
         //      A client is not expected to listen for a response during the
@@ -306,131 +1130,100 @@ impl DhcpV6Client {
         //      with the server.
         // Hence it is OK to create UDP socket when actual transmitting happens.
         if self.udp_socket.is_none() {
-            let socket = DhcpUdpSocket::new_v6(
-                self.config.iface_index,
-                &self.config.src_ip,
-                self.config.socket_timeout,
-            )?;
+            let socket = match DhcpV6Socket::new(&self.config) {
+                Ok(s) => s,
+                Err(e) => {
+                    // Still retry on next retransmission
+                    self.event_pool
+                        .add_timer(trans.remain(), DhcpV6Event::TransmitWait)?;
+                    return Err(e);
+                }
+            };
             self.event_pool
                 .add_socket(socket.as_raw_fd(), DhcpV6Event::UdpPackageIn)?;
             self.udp_socket = Some(socket);
         }
         let socket = self.udp_socket.as_ref().unwrap();
-        let dhcp_msg = match self.trans_dhcp_msg.as_mut() {
-            Some(p) => p,
-            None => {
-                return Err(DhcpError::new(
-                    ErrorKind::Bug,
-                    format!(
-                        "Got NULL DHCP package for process_transmit {:?}",
-                        self
-                    ),
-                ));
-            }
-        };
-        if self.retrans_count > 1 {
-            // We are safe to use unwrap as `schedule_next_retransmit()`
-            // already confirmed so.
-            dhcp_msg.add_elapsed_time(self.trans_begin_time.unwrap());
-        }
         // TODO Support unicast to server
-        socket.send_to_v6(&DHCPV6_REPLAY_AND_SRVS, &dhcp_msg.to_dhcp_pkg()?)?;
-        match self.phase {
-            DhcpV6Phase::PreSolicit => self.phase = DhcpV6Phase::Solicit,
-            DhcpV6Phase::PreRequest => self.phase = DhcpV6Phase::Request,
-            _ => (),
-        }
-        Ok(())
-    }
-
-    fn schedule_next_retransmit(&mut self) -> Result<(), DhcpError> {
-        self.retrans_count += 1;
-        if self.trans_begin_time.is_none() {
-            self.trans_begin_time = Some(Instant::now());
-        }
-        self.retrans_timeout = match self.phase {
-            DhcpV6Phase::PreSolicit | DhcpV6Phase::Solicit => {
-                gen_solicit_wait_time(
-                    self.trans_begin_time.unwrap(),
-                    self.retrans_count,
-                    self.retrans_timeout,
-                )?
-            }
-            DhcpV6Phase::PreRequest | DhcpV6Phase::Request => {
-                gen_request_wait_time(
-                    self.trans_begin_time.unwrap(),
-                    self.retrans_count,
-                    self.retrans_timeout,
-                )?
-            }
-            DhcpV6Phase::Renew => {
-                if let Some(lease) = self.lease.as_ref() {
-                    gen_rebind_wait_time(
-                        self.trans_begin_time.unwrap(),
-                        self.retrans_count,
-                        self.retrans_timeout,
-                        Duration::from_secs(lease.t2.into()),
-                    )?
-                } else {
-                    return Err(DhcpError::new(
-                        ErrorKind::Bug,
-                        format!(
-                            "Got NULL lease for DhcpV6Phase::Rebind in \
-                            schedule_next_retransmit(): {:?}",
-                            self
-                        ),
-                    ));
+        let result =
+            socket.send_to_v6(&DHCPV6_REPLAY_AND_SRVS, &trans.gen_dhcp_pkg()?);
+        let remain = trans.remain();
+        match result {
+            Ok(()) => {
+                self.sent_count += 1;
+                match self.phase {
+                    DhcpV6Phase::PreSolicit => {
+                        self.phase = DhcpV6Phase::Solicit
+                    }
+                    DhcpV6Phase::PreRequest => {
+                        self.phase = DhcpV6Phase::Request
+                    }
+                    _ => (),
                 }
+                self.event_pool.add_timer(remain, DhcpV6Event::TransmitWait)
             }
-            DhcpV6Phase::Rebind => {
-                if let Some(lease) = self.lease.as_ref() {
-                    gen_rebind_wait_time(
-                        self.trans_begin_time.unwrap(),
-                        self.retrans_count,
-                        self.retrans_timeout,
-                        Duration::from_secs(lease.valid_life.into()),
-                    )?
+            Err(e) => {
+                self.send_failure_count += 1;
+                // Instead of waiting the whole RT, resend within the
+                // remaining time of current RT window if possible.
+                if remain > SEND_FAILURE_RETRY_INTERVAL {
+                    log::warn!(
+                        "Failed to send DHCPv6 {} message: {e}, resending in \
+                        {} milliseconds",
+                        trans.msg_type(),
+                        SEND_FAILURE_RETRY_INTERVAL.as_millis()
+                    );
+                    trans.resending = true;
+                    self.event_pool.add_timer(
+                        SEND_FAILURE_RETRY_INTERVAL,
+                        DhcpV6Event::TransmitWait,
+                    )
                 } else {
-                    return Err(DhcpError::new(
-                        ErrorKind::Bug,
-                        format!(
-                            "Got NULL lease for DhcpV6Phase::Rebind in \
-                            schedule_next_retransmit(): {:?}",
-                            self
-                        ),
-                    ));
+                    log::warn!(
+                        "Failed to send DHCPv6 {} message: {e}, will retry on \
+                        next retransmission",
+                        trans.msg_type()
+                    );
+                    self.event_pool.add_timer(remain, DhcpV6Event::TransmitWait)
                 }
             }
-            _ => {
-                return Err(DhcpError::new(
-                    ErrorKind::Bug,
-                    format!(
-                        "Got invalid phase {:?} for \
-                        `schedule_next_retransmit()`: {:?}",
-                        self.phase, self
-                    ),
-                ));
-            }
-        };
-        self.event_pool
-            .add_timer(self.retrans_timeout, DhcpV6Event::TransmitWait)
+        }
     }
 
     fn schedule_renew_rebind_restart(&mut self) -> Result<(), DhcpError> {
         if let Some(lease) = self.lease.as_ref() {
-            self.event_pool.add_timer(
-                Duration::from_secs(lease.valid_life.into()),
-                DhcpV6Event::LeaseExpired,
-            )?;
+            // RFC 8415: 7.7. Infinite lifetime and T1/T2 never expire
+            match lease.valid_lifetime().as_duration() {
+                Some(valid) => {
+                    self.event_pool
+                        .add_timer(valid, DhcpV6Event::LeaseExpired)?;
+                }
+                None => log::info!("DHCPv6 lease {} is infinite", lease.addr),
+            }
+            let t1 = if lease.is_infinite() {
+                lease.t1
+            } else {
+                self.config.renew_policy.renew_time(
+                    lease.t1,
+                    lease.t2,
+                    lease.valid_life,
+                )
+            };
             if lease.ia_type != DhcpV6IaType::TemporaryAddresses {
-                self.event_pool.add_timer(
-                    Duration::from_secs(lease.t1.into()),
-                    DhcpV6Event::Renew,
-                )?;
-                self.event_pool.add_timer(
-                    Duration::from_secs(lease.t2.into()),
-                    DhcpV6Event::Rebind,
-                )?;
+                if let Some(t1) = DhcpLifetime::from(t1).as_duration() {
+                    self.event_pool.add_timer(t1, DhcpV6Event::Renew)?;
+                    if self.config.renew_timeout > 0 {
+                        self.event_pool.add_timer(
+                            t1 + Duration::from_secs(
+                                self.config.renew_timeout.into(),
+                            ),
+                            DhcpV6Event::RenewTimeout,
+                        )?;
+                    }
+                }
+                if let Some(t2) = lease.t2_lifetime().as_duration() {
+                    self.event_pool.add_timer(t2, DhcpV6Event::Rebind)?;
+                }
             }
             Ok(())
         } else {
@@ -447,22 +1240,15 @@ impl DhcpV6Client {
     fn process_renew(&mut self) -> Result<(), DhcpError> {
         self.event_pool.del_timer(DhcpV6Event::Renew)?;
         self.phase = DhcpV6Phase::Renew;
+        self.push_state(DhcpV6State::Renewing);
         if let Some(lease) = self.lease.as_ref() {
-            self.retrans_timeout = gen_renew_wait_time(
-                Instant::now(),
-                0,
-                Duration::new(0, 0),
-                Duration::from_secs(lease.t2.into()),
-            )?;
-            let mut dhcp_msg = DhcpV6Message::new(
+            let mut trans = DhcpV6Transaction::new(
                 &self.config,
                 DhcpV6MessageType::RENEW,
-                self.xid,
-            );
-            dhcp_msg.load_lease(lease.clone())?;
-            self.trans_dhcp_msg = Some(dhcp_msg);
-            self.event_pool
-                .add_timer(self.retrans_timeout, DhcpV6Event::TransmitWait)
+                Duration::from_secs(lease.t2.into()),
+            )?;
+            trans.msg.load_lease(lease.clone())?;
+            self.start_transaction(trans)
         } else {
             Err(DhcpError::new(
                 ErrorKind::Bug,
@@ -471,41 +1257,88 @@ impl DhcpV6Client {
         }
     }
 
+    // RFC 8415: 18.2.6. Creation and Transmission of Information-request
+    // Messages
+    // Options held are still in use till server replied.
+    fn process_information_request(&mut self) -> Result<(), DhcpError> {
+        self.event_pool.del_timer(DhcpV6Event::InformationRefresh)?;
+        if self.lease.is_none() {
+            self.reset_state_timer()?;
+        }
+        self.phase = DhcpV6Phase::InformationRequest;
+        let trans = DhcpV6Transaction::new(
+            &self.config,
+            DhcpV6MessageType::INFORMATION_REQUEST,
+            Duration::ZERO,
+        )?;
+        self.start_transaction(trans)
+    }
+
     fn process_rebind(&mut self) -> Result<(), DhcpError> {
         self.event_pool.del_timer(DhcpV6Event::Rebind)?;
         self.phase = DhcpV6Phase::Rebind;
+        self.push_state(DhcpV6State::Rebinding);
         if let Some(lease) = self.lease.as_ref() {
-            self.retrans_timeout = gen_rebind_wait_time(
-                Instant::now(),
-                0,
-                Duration::new(0, 0),
-                Duration::from_secs(lease.valid_life.into()),
-            )?;
-            let mut dhcp_msg = DhcpV6Message::new(
+            let mut trans = DhcpV6Transaction::new(
                 &self.config,
                 DhcpV6MessageType::REBIND,
-                self.xid,
-            );
-            dhcp_msg.load_lease(lease.clone())?;
-            self.trans_dhcp_msg = Some(dhcp_msg);
-            self.event_pool
-                .add_timer(self.retrans_timeout, DhcpV6Event::TransmitWait)
+                Duration::from_secs(lease.valid_life.into()),
+            )?;
+            trans.msg.load_lease(lease.clone())?;
+            self.start_transaction(trans)
         } else {
             Err(DhcpError::new(
                 ErrorKind::Bug,
-                format!("Got NULL lease for `process_renew()`: {:?}", self),
+                format!("Got NULL lease for `process_rebind()`: {:?}", self),
             ))
         }
     }
 }
 
 fn recv_dhcp_msg(
-    socket: &DhcpUdpSocket,
+    socket: &DhcpV6Socket,
+    config: &DhcpV6Config,
     expected: DhcpV6MessageType,
     xid: [u8; 3],
+    candidates: Option<&mut Vec<DhcpV6Lease>>,
+    srv_duid: Option<&[u8]>,
 ) -> Result<Option<DhcpV6Lease>, DhcpError> {
-    let buffer: Vec<u8> = socket.recv()?;
-    let reply_dhcp_msg = DhcpV6Message::from_dhcp_pkg(&buffer)?;
+    let (buffer, src_addr) = socket.recv_from()?;
+    let src_addr = match src_addr {
+        Some(SocketAddr::V6(a)) => a,
+        _ => {
+            log::debug!(
+                "Dropping DHCP message from unknown source {src_addr:?}"
+            );
+            return Ok(None);
+        }
+    };
+    // Reply from both DHCPv6 server and relay agent is sent from server port
+    if src_addr.port() != dhcproto::v6::SERVER_PORT {
+        log::debug!(
+            "Dropping DHCP message from {src_addr} which is not server port {}",
+            dhcproto::v6::SERVER_PORT
+        );
+        return Ok(None);
+    }
+    let mut reply_dhcp_msg = match DhcpV6Message::from_dhcp_pkg(&buffer) {
+        Ok(m) => m,
+        // Failure status code of reply to other client is none of our
+        // business. The transaction-id follows the 1 octet msg-type.
+        Err(e)
+            if e.kind() == ErrorKind::NoLease
+                && buffer.get(1..4) != Some(&xid[..]) =>
+        {
+            log::debug!("Dropping DHCP message due to xid miss-match: {e}");
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+    trace_msg_received(
+        reply_dhcp_msg.msg_type,
+        xid_to_string(reply_dhcp_msg.xid),
+        Some(src_addr.ip()),
+    );
     if reply_dhcp_msg.xid != xid {
         log::debug!(
             "Dropping DHCP message due to xid miss-match. \
@@ -515,6 +1348,51 @@ fn recv_dhcp_msg(
         );
         return Ok(None);
     }
+    if let Some(lease) = reply_dhcp_msg.lease.as_ref() {
+        if let Err(reason) =
+            validate_srv_duids(config, &lease.cli_duid, &lease.srv_duid)
+        {
+            log::debug!(
+                "Dropping DHCPv6 {} from {src_addr}: {reason}",
+                reply_dhcp_msg.msg_type
+            );
+            return Ok(None);
+        }
+    }
+    if let Some(lease) = reply_dhcp_msg.lease.as_mut() {
+        // Reply carrying options only is harmless but not a lease
+        if !config.information_only
+            && (lease.ia_type != config.ia_type || lease.addr.is_unspecified())
+        {
+            log::warn!(
+                "Ignoring DHCPv6 {} from {src_addr} as it contains no {} \
+                address",
+                reply_dhcp_msg.msg_type,
+                config.ia_type
+            );
+            return Ok(None);
+        }
+        lease.src_addr = Some(src_addr);
+        lease.decode_custom_opts(config);
+        lease.limit_unknown_opts(config);
+        if let Some(srv_duid) = srv_duid.filter(|d| *d != lease.srv_duid) {
+            log::debug!(
+                "Dropping DHCP message from {src_addr} due to server DUID \
+                miss-match. Expecting {srv_duid:?}, got {:?}",
+                lease.srv_duid
+            );
+            return Ok(None);
+        }
+    }
+    if let (Some(candidates), Some(lease)) =
+        (candidates, reply_dhcp_msg.lease.as_ref())
+    {
+        if reply_dhcp_msg.msg_type == DhcpV6MessageType::ADVERTISE {
+            // Replace retransmitted advertise from the same server
+            candidates.retain(|l| l.srv_duid != lease.srv_duid);
+            candidates.push(lease.clone());
+        }
+    }
     if reply_dhcp_msg.msg_type != expected {
         log::debug!(
             "Dropping DHCP message due to type miss-match.
@@ -534,3 +1412,113 @@ fn recv_dhcp_msg(
         Ok(None)
     }
 }
+
+// RFC 8415: 16.3. Advertise Message and 16.10. Reply Message
+//      Clients MUST discard any received Advertise/Reply message that meets
+//      any of the following conditions:
+//      -  the message does not include a Server Identifier option
+//      -  the message does not include a Client Identifier option and the
+//         original message from the client contained a Client Identifier
+//         option
+//      -  the message includes a Client Identifier option, and the contents
+//         of the Client Identifier option do not match the client's DUID
+// The client always sends Client Identifier option.
+fn validate_srv_duids(
+    config: &DhcpV6Config,
+    cli_duid: &[u8],
+    srv_duid: &[u8],
+) -> Result<(), String> {
+    if srv_duid.is_empty() {
+        Err("no Server Identifier option".to_string())
+    } else if cli_duid.is_empty() {
+        Err("no Client Identifier option".to_string())
+    } else if cli_duid != config.duid.to_vec().as_slice() {
+        Err(format!(
+            "Client Identifier {cli_duid:?} does not match DUID {:?}",
+            config.duid.to_vec()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+// Wait DHCPv6 Reply of specified transaction ID till timeout, return false
+// on timeout. RFC 8415 18.2.10.2: the client considers the Release event
+// completed, regardless of the Status Code option(s) returned by the server.
+fn wait_release_reply(
+    socket: &DhcpV6Socket,
+    config: &DhcpV6Config,
+    xid: [u8; 3],
+    timeout: Duration,
+) -> Result<bool, DhcpError> {
+    let deadline = Instant::now() + timeout;
+    // Safe as the socket outlives this function
+    let fd = unsafe { BorrowedFd::borrow_raw(socket.as_raw_fd()) };
+    loop {
+        let remain = deadline.saturating_duration_since(Instant::now());
+        if remain.is_zero() {
+            return Ok(false);
+        }
+        let poll_timeout = PollTimeout::try_from(remain.as_millis().max(1))
+            .unwrap_or(PollTimeout::MAX);
+        let mut poll_fds = [PollFd::new(fd, PollFlags::POLLIN)];
+        match nix::poll::poll(&mut poll_fds, poll_timeout) {
+            Ok(0) => return Ok(false),
+            Ok(_) => (),
+            Err(nix::errno::Errno::EINTR) => continue,
+            Err(e) => {
+                let e = DhcpError::new(
+                    ErrorKind::Bug,
+                    format!("Failed to poll DHCPv6 socket: {e}"),
+                );
+                log::error!("{}", e);
+                return Err(e);
+            }
+        }
+        let (buffer, src_addr) = match socket.recv_from() {
+            Ok(r) => r,
+            Err(e) if e.kind() == ErrorKind::Timeout => continue,
+            Err(e) => return Err(e),
+        };
+        if src_addr.map(|a| a.port()) != Some(v6::SERVER_PORT) {
+            log::debug!(
+                "Dropping DHCP message from {src_addr:?} which is not \
+                server port {}",
+                v6::SERVER_PORT
+            );
+            continue;
+        }
+        match v6::Message::decode(&mut Decoder::new(&buffer)) {
+            Ok(msg)
+                if msg.msg_type() == v6::MessageType::Reply
+                    && msg.xid() == xid =>
+            {
+                let cli_duid = match msg.opts().get(v6::OptionCode::ClientId) {
+                    Some(v6::DhcpOption::ClientId(v)) => v.as_slice(),
+                    _ => &[],
+                };
+                let srv_duid = match msg.opts().get(v6::OptionCode::ServerId) {
+                    Some(v6::DhcpOption::ServerId(v)) => v.as_slice(),
+                    _ => &[],
+                };
+                match validate_srv_duids(config, cli_duid, srv_duid) {
+                    Ok(()) => return Ok(true),
+                    Err(reason) => log::debug!(
+                        "Dropping DHCPv6 reply to RELEASE: {reason}"
+                    ),
+                }
+            }
+            Ok(msg) => {
+                log::debug!(
+                    "Dropping DHCPv6 {:?} message of xid {:?} while waiting \
+                    reply to RELEASE",
+                    msg.msg_type(),
+                    msg.xid()
+                );
+            }
+            Err(e) => {
+                log::debug!("Dropping invalid DHCPv6 message: {e}");
+            }
+        }
+    }
+}
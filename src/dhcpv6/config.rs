@@ -1,24 +1,46 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::hash::Hasher;
 use std::net::Ipv6Addr;
+#[cfg(feature = "client")]
+use std::os::unix::io::RawFd;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
+use dhcproto::v6;
 use rand::RngCore;
+use siphasher::sip::SipHasher24;
 
+use super::msg::gen_fqdn_opt_data;
 use crate::{
+    approver::DhcpLeaseApprover,
+    codec::DhcpCodec,
+    consts::{DEFAULT_MAX_MSG_SIZE, DEFAULT_SOCKET_TIMEOUT, MIN_MAX_MSG_SIZE},
+    identity::DhcpIdentity,
     mac::mac_str_to_u8_array,
+    mud::validate_mud_url,
+    DhcpError, DhcpIdentityProvider, DhcpOptionCodec, DhcpOptionValue,
+    DhcpV6Lease, ErrorKind, RenewPolicy, RestartPolicy,
+};
+#[cfg(feature = "client")]
+use crate::{
+    mac::is_eth_mac_address,
     nispor::{get_ipv6_addr_of_iface, get_nispor_iface},
-    socket::DEFAULT_SOCKET_TIMEOUT,
-    DhcpError,
+    DhcpMemorySocket,
 };
 
 // https://www.iana.org/assignments/arp-parameters/arp-parameters.xhtml
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
 const ARP_HW_TYPE_ETHERNET: u16 = 1;
 
 const OPTION_IA_NA: u16 = 3;
 const OPTION_IA_TA: u16 = 4;
 const OPTION_IA_PD: u16 = 5;
 
+// Unknown options retained in DhcpV6Lease::dhcp_opts
+const DEFAULT_MAX_UNKNOWN_OPTS: usize = 32;
+const DEFAULT_MAX_UNKNOWN_OPTS_BYTES: usize = 4096;
+
 // RFC 8415 11.2.  DUID Based on Link-Layer Address Plus Time (DUID-LLT)
 // Indicate the base time is midnight (UTC), January 1, 2000
 // This is calculated value by chrono:
@@ -31,20 +53,32 @@ const DHCPV6_DUID_TYPE_EN: u16 = 2;
 const DHCPV6_DUID_TYPE_LL: u16 = 3;
 const DHCPV6_DUID_TYPE_UUID: u16 = 4;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+// SipHash key used by systemd-networkd and NetworkManager to derive IAID,
+// `HASH_KEY` in systemd `src/libsystemd-network/dhcp-identifier.c`
+const IAID_HASH_KEY: [u8; 16] = [
+    0x80, 0x11, 0x8f, 0xc2, 0xc2, 0xe4, 0x4b, 0xfa, 0x97, 0x3c, 0xdc, 0xd8,
+    0x4e, 0x15, 0xc4, 0x75,
+];
+
+#[cfg(feature = "machine-id")]
+const MACHINE_ID_PATH: &str = "/etc/machine-id";
+// Application ID used to derive DUID-UUID from machine-id, so that the
+// machine-id itself is never exposed on the network.
+#[cfg(feature = "machine-id")]
+const MOZIM_APP_ID: [u8; 16] = [
+    0x5e, 0x3c, 0x9a, 0x0b, 0x6d, 0x2f, 0x4e, 0x81, 0xa7, 0x13, 0xc4, 0x58,
+    0xf2, 0x9d, 0x61, 0x0e,
+];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 #[non_exhaustive]
 pub enum DhcpV6IaType {
+    #[default]
     NonTemporaryAddresses,
     TemporaryAddresses,
     PrefixDelegation,
 }
 
-impl Default for DhcpV6IaType {
-    fn default() -> Self {
-        Self::NonTemporaryAddresses
-    }
-}
-
 impl std::fmt::Display for DhcpV6IaType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -76,9 +110,39 @@ pub struct DhcpV6Config {
     pub(crate) iface_index: u32,
     pub(crate) duid: Dhcpv6Duid,
     pub(crate) timeout: u32,
+    pub(crate) state_timeout: u32,
+    pub(crate) release_timeout: u32,
+    pub(crate) renew_timeout: u32,
+    pub(crate) restart_policy: RestartPolicy,
+    pub(crate) manual_commit: bool,
+    pub(crate) renew_policy: RenewPolicy,
     pub(crate) ia_type: DhcpV6IaType,
+    pub(crate) information_only: bool,
     pub(crate) src_ip: Ipv6Addr,
     pub(crate) socket_timeout: u32,
+    pub(crate) lease_approver: Option<DhcpLeaseApprover<DhcpV6Lease>>,
+    pub(crate) src_mac: String,
+    pub(crate) fast_resume: Option<(Ipv6Addr, String)>,
+    pub(crate) iaid: Option<u32>,
+    pub(crate) anonymity_profile: bool,
+    pub(crate) request_opts: Vec<u16>,
+    pub(crate) host_name: String,
+    pub(crate) mud_url: Option<String>,
+    pub(crate) dscp: u8,
+    #[cfg(feature = "client")]
+    pub(crate) udp_socket_fd: Option<RawFd>,
+    pub(crate) max_msg_size: u16,
+    #[cfg(feature = "client")]
+    pub(crate) memory_socket: Option<DhcpMemorySocket>,
+    pub(crate) identity_provider: Option<DhcpIdentity>,
+    pub(crate) option_codecs: Vec<(u16, DhcpCodec)>,
+    pub(crate) extra_opts: Vec<(u16, Vec<u8>)>,
+    pub(crate) max_unknown_opts: usize,
+    pub(crate) max_unknown_opts_bytes: usize,
+    #[cfg(feature = "chaos")]
+    pub(crate) chaos_policy: Option<crate::DhcpChaosPolicy>,
+    #[cfg(feature = "pcap")]
+    pub(crate) pcap_file: Option<String>,
 }
 
 impl Default for DhcpV6Config {
@@ -88,9 +152,39 @@ impl Default for DhcpV6Config {
             iface_index: 0,
             duid: Dhcpv6Duid::Other(Vec::new()),
             timeout: 0,
+            state_timeout: 0,
+            release_timeout: 0,
+            renew_timeout: 0,
+            restart_policy: RestartPolicy::default(),
+            manual_commit: false,
+            renew_policy: RenewPolicy::default(),
             ia_type: DhcpV6IaType::default(),
+            information_only: false,
             src_ip: Ipv6Addr::UNSPECIFIED,
             socket_timeout: DEFAULT_SOCKET_TIMEOUT,
+            lease_approver: None,
+            src_mac: String::new(),
+            fast_resume: None,
+            iaid: None,
+            anonymity_profile: false,
+            request_opts: default_request_opts(),
+            host_name: String::new(),
+            mud_url: None,
+            dscp: 0,
+            #[cfg(feature = "client")]
+            udp_socket_fd: None,
+            max_msg_size: DEFAULT_MAX_MSG_SIZE,
+            #[cfg(feature = "client")]
+            memory_socket: None,
+            identity_provider: None,
+            option_codecs: Vec::new(),
+            extra_opts: Vec::new(),
+            max_unknown_opts: DEFAULT_MAX_UNKNOWN_OPTS,
+            max_unknown_opts_bytes: DEFAULT_MAX_UNKNOWN_OPTS_BYTES,
+            #[cfg(feature = "chaos")]
+            chaos_policy: None,
+            #[cfg(feature = "pcap")]
+            pcap_file: None,
         }
     }
 }
@@ -104,35 +198,490 @@ impl DhcpV6Config {
         }
     }
 
-    /// Set timeout in seconds
+    /// Set overall timeout in seconds of lease acquisition, counting from
+    /// client initialized or lease expired till lease acquired. Renewing and
+    /// rebinding are not included. On expiry, [ErrorKind::Timeout] is
+    /// returned by [crate::DhcpV6Client::process()]. 0 means no timeout.
+    /// Default is 0.
     pub fn set_timeout(&mut self, timeout: u32) -> &mut Self {
         self.timeout = timeout;
         self
     }
 
+    /// Set timeout in seconds of each lease acquisition state(solicit or
+    /// request), restarted on entering new state. On expiry,
+    /// [ErrorKind::StateTimeout] is returned by
+    /// [crate::DhcpV6Client::process()]. 0 means no timeout. Default is 0.
+    pub fn set_state_timeout(&mut self, timeout: u32) -> &mut Self {
+        self.state_timeout = timeout;
+        self
+    }
+
+    /// Set timeout in seconds of waiting server reply to RELEASE sent by
+    /// [crate::DhcpV6Client::release()] or DECLINE sent by
+    /// [crate::DhcpV6Client::decline_lease()], retransmissions included.
+    /// 0 means only limited by the RFC 8415 maximum of 4 retransmissions,
+    /// which could take about 30 seconds. Default is 0.
+    pub fn set_release_timeout(&mut self, timeout: u32) -> &mut Self {
+        self.release_timeout = timeout;
+        self
+    }
+
+    /// Set timeout in seconds of lease renewal, counting from T1. If lease
+    /// is not extended via renewing or rebinding within this time,
+    /// [ErrorKind::RenewTimeout] is returned by
+    /// [crate::DhcpV6Client::process()]. 0 means no timeout, client starts
+    /// solicit once lease expired. Default is 0.
+    pub fn set_renew_timeout(&mut self, timeout: u32) -> &mut Self {
+        self.renew_timeout = timeout;
+        self
+    }
+
+    /// Set policy on whether client starts solicit again once lease
+    /// expired or refused by server. Default is [RestartPolicy::Forever].
+    pub fn set_restart_policy(&mut self, policy: RestartPolicy) -> &mut Self {
+        self.restart_policy = policy;
+        self
+    }
+
+    /// When enabled, newly acquired lease returned by
+    /// [crate::DhcpV6Client::process()] is not bound till caller applied it
+    /// to the system and invoked [crate::DhcpV6Client::commit_lease()]: no
+    /// renew timer is scheduled in the meantime. If applying failed,
+    /// [crate::DhcpV6Client::decline_lease()] sends DECLINE(or RELEASE for
+    /// delegated prefix) and starts solicit again. Lease extended by
+    /// renewing or rebinding is bound immediately. Default is false.
+    pub fn set_manual_commit(&mut self, enabled: bool) -> &mut Self {
+        self.manual_commit = enabled;
+        self
+    }
+
+    /// Set when to start lease renewal instead of T1 from DHCP server.
+    /// Default is [RenewPolicy::ServerT1].
+    pub fn set_renew_policy(&mut self, policy: RenewPolicy) -> &mut Self {
+        self.renew_policy = policy;
+        self
+    }
+
     /// Set arbitrary DUID
     pub fn set_duid(&mut self, duid: Dhcpv6Duid) -> &mut Self {
         self.duid = duid;
         self
     }
 
+    /// Set callback to approve the lease advertised by DHCPv6 server before
+    /// requesting it. When callback returns false, the advertisement is
+    /// ignored and client keeps waiting for other DHCPv6 servers.
+    pub fn set_lease_approver(
+        &mut self,
+        approver: Arc<dyn Fn(&DhcpV6Lease) -> bool + Send + Sync>,
+    ) -> &mut Self {
+        self.lease_approver = Some(DhcpLeaseApprover(approver));
+        self
+    }
+
+    /// When initializing client with previous lease, probe the specified
+    /// router via IPv6 Neighbor Solicitation. If router replied with
+    /// specified MAC address, the lease is returned as bound immediately
+    /// while the client keeps renewing the lease in the background.
+    pub fn set_fast_resume(
+        &mut self,
+        router: Ipv6Addr,
+        router_mac: &str,
+    ) -> &mut Self {
+        self.fast_resume = Some((router, router_mac.to_string()));
+        self
+    }
+
+    /// Set the options to request in Option Request Option(ORO) of
+    /// Solicit, Request, Renew and Rebind messages. Default is DNS
+    /// Recursive Name Server(23), Domain Search List(24), SNTP Servers(31)
+    /// and NTP Server(56).
+    pub fn set_request_opts(&mut self, opts: &[u16]) -> &mut Self {
+        self.request_opts = opts.to_vec();
+        self
+    }
+
+    /// Add or remove RFC 3319 SIP Servers Domain Name List(21) and SIP
+    /// Servers IPv6 Address List(22) options in the options set by
+    /// [DhcpV6Config::set_request_opts()]. Default is not requested.
+    pub fn set_request_sip_srvs(&mut self, enabled: bool) -> &mut Self {
+        let codes: [u16; 2] = [
+            v6::OptionCode::SipServerD.into(),
+            v6::OptionCode::SipServerA.into(),
+        ];
+        self.request_opts.retain(|c| !codes.contains(c));
+        if enabled {
+            self.request_opts.extend_from_slice(&codes);
+        }
+        self
+    }
+
+    /// Add or remove RFC 8910 Captive-Portal(103) option in the options set
+    /// by [DhcpV6Config::set_request_opts()]. Default is not requested.
+    pub fn set_request_captive_portal(&mut self, enabled: bool) -> &mut Self {
+        let code: u16 = v6::OptionCode::DhcpCaptivePortal.into();
+        self.request_opts.retain(|c| *c != code);
+        if enabled {
+            self.request_opts.push(code);
+        }
+        self
+    }
+
+    /// Use stateless DHCPv6(RFC 8415 18.2.6): only configuration options
+    /// like DNS and NTP servers are requested via Information-request, the
+    /// IA type is ignored and the returned lease holds no address or prefix.
+    /// The options are refreshed as per Information Refresh Time option of
+    /// server reply(24 hours if absent, no less than 10 minutes), the lease
+    /// is returned by [crate::DhcpV6Client::process()] again on every
+    /// refresh. Intended for link whose Router Advertisement sets only the
+    /// Other configuration(O) flag. Default is false.
+    pub fn set_information_only(&mut self, enabled: bool) -> &mut Self {
+        self.information_only = enabled;
+        self
+    }
+
+    /// Set Identity Association Identifier(IAID), default is generated by
+    /// [iaid_from_iface()].
+    pub fn set_iaid(&mut self, iaid: u32) -> &mut Self {
+        self.iaid = Some(iaid);
+        self
+    }
+
+    /// Query DUID and IAID from specified provider when client initialized,
+    /// unless defined by [DhcpV6Config::set_duid()] or
+    /// [DhcpV6Config::set_iaid()].
+    pub fn set_identity_provider(
+        &mut self,
+        provider: Arc<dyn DhcpIdentityProvider>,
+    ) -> &mut Self {
+        self.identity_provider = Some(DhcpIdentity(provider));
+        self
+    }
+
+    /// Decode option of specified code in DHCPv6 server reply using the
+    /// codec, stored as [crate::DhcpV6Option::Custom] in
+    /// [DhcpV6Lease::custom_opts]. The code is also requested in Option
+    /// Request Option(ORO). Registering the same code again replaces the
+    /// previous codec.
+    pub fn register_option_codec(
+        &mut self,
+        code: u16,
+        codec: Arc<dyn DhcpOptionCodec>,
+    ) -> &mut Self {
+        self.option_codecs.retain(|(c, _)| *c != code);
+        self.option_codecs.push((code, DhcpCodec(codec)));
+        self
+    }
+
+    /// Include option encoded by [DhcpOptionValue::encode()] in all messages
+    /// except RELEASE and DECLINE, overriding the built-in option with the
+    /// same code. Adding the same option code again replaces its data.
+    pub fn add_custom_option(
+        &mut self,
+        code: u16,
+        value: Box<dyn DhcpOptionValue>,
+    ) -> &mut Self {
+        self.extra_opts.retain(|(c, _)| *c != code);
+        self.extra_opts.push((code, value.encode()));
+        self
+    }
+
+    /// Limit the count and total data size of options not decoded by mozim
+    /// retained in [DhcpV6Lease::dhcp_opts], the excess ones are dropped
+    /// with [DhcpV6Lease::unknown_opts_truncated] set. Options of registered
+    /// codecs are decoded before truncation.
+    /// Default is 32 options and 4096 bytes.
+    pub fn set_unknown_opts_limit(
+        &mut self,
+        max_count: usize,
+        max_bytes: usize,
+    ) -> &mut Self {
+        self.max_unknown_opts = max_count;
+        self.max_unknown_opts_bytes = max_bytes;
+        self
+    }
+
+    /// Enable RFC 7844 anonymity profile: random DUID and IAID are generated
+    /// for each client session, and options identifying the host like FQDN
+    /// or vendor class are never sent.
+    pub fn enable_anonymity_profile(&mut self) -> &mut Self {
+        self.anonymity_profile = true;
+        self
+    }
+
+    /// Set host name sent in RFC 4704 Client FQDN option of Solicit,
+    /// Request, Renew and Rebind messages, requesting DHCPv6 server to
+    /// update both AAAA and PTR DNS records. Name without dot is sent as
+    /// partial name for server to append its domain. Ignored when
+    /// anonymity profile enabled. Default is empty, no FQDN option sent.
+    pub fn set_host_name(&mut self, host_name: &str) -> &mut Self {
+        self.host_name = host_name.to_string();
+        self
+    }
+
+    /// Send RFC 8520 Manufacturer Usage Description URL(112) option in
+    /// Solicit, Request, Renew, Rebind and Information-request messages.
+    /// The URL should use https scheme. Ignored when anonymity profile
+    /// enabled. Default is not sending.
+    pub fn set_mud_url(&mut self, url: &str) -> &mut Self {
+        self.mud_url = Some(url.to_string());
+        self
+    }
+
+    /// Set DSCP(0-63) of outgoing DHCPv6 packets. Default is 0.
+    pub fn set_dscp(&mut self, dscp: u8) -> &mut Self {
+        self.dscp = dscp & 0x3f;
+        self
+    }
+
+    #[cfg(feature = "client")]
+    /// Use pre-opened UDP socket bound to DHCPv6 client port 546 instead of
+    /// creating one, so client could run without `CAP_NET_BIND_SERVICE` when
+    /// socket is passed from privileged helper or systemd socket activation.
+    /// The fd is duplicated on use, caller should keep it open till client
+    /// is dropped.
+    pub fn set_udp_socket_fd(&mut self, fd: RawFd) -> &mut Self {
+        self.udp_socket_fd = Some(fd);
+        self
+    }
+
+    /// Set the maximum size of IP package could be received, e.g. 9000 for
+    /// jumbo frame network or DHCPv6 reply holding many options. Default is
+    /// 1500.
+    pub fn set_max_msg_size(&mut self, size: u16) -> &mut Self {
+        self.max_msg_size = size.max(MIN_MAX_MSG_SIZE);
+        self
+    }
+
+    /// Store every DHCP packet sent or received by client into specified
+    /// pcapng file, appending new section if file already exists. Packets
+    /// sent or received via UDP socket are stored with generated IP and UDP
+    /// headers.
+    #[cfg(feature = "pcap")]
+    pub fn set_pcap_file(&mut self, path: &str) -> &mut Self {
+        self.pcap_file = Some(path.to_string());
+        self
+    }
+
+    #[cfg(feature = "client")]
+    /// Exchange DHCPv6 messages via in-memory socket instead of network
+    /// interface, the interface is not queried and specified MAC address is
+    /// used. Replies should be sent by
+    /// [DhcpMemorySocket::send_from()] with DHCPv6 server port 547.
+    /// Intended for testing client against scripted DHCPv6 server replies.
+    pub fn set_memory_socket(
+        &mut self,
+        socket: DhcpMemorySocket,
+        mac_address: &str,
+    ) -> &mut Self {
+        self.memory_socket = Some(socket);
+        self.src_mac = mac_address.to_string();
+        self
+    }
+
+    /// Inject faults into DHCPv6 messages sent by client, only intended for
+    /// testing.
+    #[cfg(feature = "chaos")]
+    pub fn set_chaos_policy(
+        &mut self,
+        policy: crate::DhcpChaosPolicy,
+    ) -> &mut Self {
+        self.chaos_policy = Some(policy);
+        self
+    }
+
+    pub fn iface_name(&self) -> &str {
+        self.iface_name.as_str()
+    }
+
+    /// Interface index, resolved when client initialized.
+    pub fn iface_index(&self) -> u32 {
+        self.iface_index
+    }
+
+    /// MAC address of interface, resolved when client initialized. Empty if
+    /// interface has no ethernet MAC address.
+    pub fn mac_address(&self) -> &str {
+        self.src_mac.as_str()
+    }
+
+    /// IPv6 link-local address of interface used to send DHCPv6 messages,
+    /// resolved when client initialized.
+    pub fn src_ip(&self) -> Ipv6Addr {
+        self.src_ip
+    }
+
+    /// DUID in use, generated when client initialized if not defined by
+    /// [DhcpV6Config::set_duid()].
+    pub fn duid(&self) -> &Dhcpv6Duid {
+        &self.duid
+    }
+
+    pub fn iaid(&self) -> Option<u32> {
+        self.iaid
+    }
+
+    pub fn request_opts(&self) -> &[u16] {
+        self.request_opts.as_slice()
+    }
+
+    pub fn host_name(&self) -> &str {
+        self.host_name.as_str()
+    }
+
+    // Apply the fields allowed to be changed on running client: timeouts,
+    // restart policy, request options and host name.
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub(crate) fn update(&mut self, new: &Self) -> Result<(), DhcpError> {
+        let mut config = self.clone();
+        config.timeout = new.timeout;
+        config.state_timeout = new.state_timeout;
+        config.renew_timeout = new.renew_timeout;
+        config.restart_policy = new.restart_policy;
+        config.request_opts.clone_from(&new.request_opts);
+        config.host_name.clone_from(&new.host_name);
+        if &config != new {
+            let e = DhcpError::new(
+                ErrorKind::InvalidArgument,
+                "Only timeouts, restart policy, request options and host \
+                name are allowed to be changed on running DHCPv6 client"
+                    .to_string(),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+        if !config.host_name.is_empty() {
+            gen_fqdn_opt_data(&config.host_name)?;
+        }
+        *self = config;
+        Ok(())
+    }
+
+    #[cfg(feature = "client")]
     // Check whether interface exists and resolve iface_index and MAC
     pub(crate) fn init(&mut self) -> Result<(), DhcpError> {
+        if self.memory_socket.is_some() {
+            return self.init_identity();
+        }
         let np_iface = get_nispor_iface(self.iface_name.as_str(), true)?;
+        self.init_with_iface(&np_iface)
+    }
+
+    #[cfg(feature = "client")]
+    // Resolve iface_index, IPv6 link-local address and MAC from interface
+    // information(with IP included) retrieved by caller
+    pub(crate) fn init_with_iface(
+        &mut self,
+        np_iface: &nispor::Iface,
+    ) -> Result<(), DhcpError> {
+        if self.memory_socket.is_some() {
+            return self.init_identity();
+        }
         self.iface_index = np_iface.index;
-        self.src_ip = get_ipv6_addr_of_iface(&np_iface)?;
-        self.duid = if np_iface.mac_address.is_empty() {
-            Dhcpv6Duid::default()
+        self.src_ip = get_ipv6_addr_of_iface(np_iface)?;
+        // Interface like PPP or wireguard has no MAC address
+        self.src_mac = if is_eth_mac_address(&np_iface.mac_address) {
+            np_iface.mac_address.clone()
         } else {
-            Dhcpv6Duid::LL(Dhcpv6DuidLl::new(
-                ARP_HW_TYPE_ETHERNET,
-                &mac_str_to_u8_array(np_iface.mac_address.as_str()),
-            ))
+            String::new()
         };
+        self.init_identity()
+    }
+
+    // Validate host name and MUD URL, then generate DUID and IAID from
+    // resolved MAC
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    fn init_identity(&mut self) -> Result<(), DhcpError> {
+        if !self.host_name.is_empty() {
+            gen_fqdn_opt_data(&self.host_name)?;
+        }
+        if let Some(url) = self.mud_url.as_ref() {
+            validate_mud_url(url, u16::MAX.into())?;
+        }
+        let provider = self
+            .identity_provider
+            .as_ref()
+            .filter(|_| !self.anonymity_profile)
+            .map(|p| p.0.clone());
+        if let Some(provider) = provider {
+            if self.duid.to_vec().is_empty() {
+                if let Some(duid) =
+                    provider.duid(&self.iface_name, &self.src_mac)?
+                {
+                    self.duid = duid;
+                }
+            }
+            if self.iaid.is_none() {
+                self.iaid = provider.iaid(&self.iface_name, &self.src_mac)?;
+            }
+        }
+        // Only generate DUID when not defined by `set_duid()`
+        if self.duid.to_vec().is_empty() {
+            self.duid = if self.src_mac.is_empty() {
+                // RFC 8415: 11.5. DUID Based on Universally Unique Identifier
+                // (DUID-UUID), derived from machine-id if `machine-id`
+                // feature enabled.
+                log::info!(
+                    "Interface {} has no ethernet MAC address, using DUID-UUID",
+                    self.iface_name
+                );
+                Dhcpv6Duid::UUID(Dhcpv6DuidUuid::generate())
+            } else {
+                Dhcpv6Duid::LL(Dhcpv6DuidLl::new(
+                    ARP_HW_TYPE_ETHERNET,
+                    &mac_str_to_u8_array(self.src_mac.as_str()),
+                ))
+            };
+        }
+        if self.iaid.is_none() {
+            self.iaid = Some(iaid_from_iface(&self.iface_name, &self.src_mac));
+        }
+        if self.anonymity_profile {
+            // RFC 7844: 4.3. Client Identifier DHCPv6 Option
+            //      clients SHOULD use DUID-LL
+            let mut mac = [0u8; 6];
+            rand::thread_rng().fill_bytes(&mut mac);
+            // Locally administered unicast address
+            mac[0] = (mac[0] & 0xfe) | 0x02;
+            self.duid =
+                Dhcpv6Duid::LL(Dhcpv6DuidLl::new(ARP_HW_TYPE_ETHERNET, &mac));
+            // RFC 7844: 4.5. Identity Association Options
+            self.iaid = Some(rand::thread_rng().next_u32());
+        }
         Ok(())
     }
 }
 
+fn default_request_opts() -> Vec<u16> {
+    vec![
+        v6::OptionCode::DomainNameServers.into(),
+        v6::OptionCode::DomainSearchList.into(),
+        v6::OptionCode::SntpServers.into(),
+        v6::OptionCode::NtpServer.into(),
+    ]
+}
+
+/// Generate IAID which is stable across reboots and unique among interfaces
+/// of the same host, following systemd-networkd and NetworkManager: SipHash-2-4
+/// of interface name(or of MAC address bytes if interface name is empty),
+/// with upper and lower 32 bits XORed.
+///
+/// The IAID is sent in network byte order. systemd-networkd by default
+/// keeps its historical native byte order, so on little-endian hosts its
+/// IAID is this value with bytes swapped. systemd-networkd also hashes the
+/// udev predictable name which might differ from the kernel interface name.
+pub fn iaid_from_iface(iface_name: &str, mac: &str) -> u32 {
+    let mut hasher = SipHasher24::new_with_key(&IAID_HASH_KEY);
+    if iface_name.is_empty() {
+        hasher.write(&mac_str_to_u8_array(mac));
+    } else {
+        hasher.write(iface_name.as_bytes());
+    }
+    let hash = hasher.finish();
+    (hash as u32) ^ ((hash >> 32) as u32)
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 #[non_exhaustive]
 pub enum Dhcpv6Duid {
@@ -161,6 +710,134 @@ impl Dhcpv6Duid {
             Self::Other(v) => v.clone(),
         }
     }
+
+    /// Parse DUID in wire format, unknown or malformed DUID is stored as
+    /// [Dhcpv6Duid::Other].
+    pub fn from_slice(data: &[u8]) -> Self {
+        let duid_type = match data.get(..2) {
+            Some(t) => u16::from_be_bytes([t[0], t[1]]),
+            None => return Self::Other(data.to_vec()),
+        };
+        let body = &data[2..];
+        match duid_type {
+            DHCPV6_DUID_TYPE_LLT if body.len() >= 6 => {
+                Self::LLT(Dhcpv6DuidLlt {
+                    hardware_type: u16::from_be_bytes([body[0], body[1]]),
+                    time: u32::from_be_bytes([
+                        body[2], body[3], body[4], body[5],
+                    ]),
+                    link_layer_address: body[6..].to_vec(),
+                })
+            }
+            DHCPV6_DUID_TYPE_EN if body.len() >= 4 => {
+                Self::EN(Dhcpv6DuidEn::new(
+                    u32::from_be_bytes([body[0], body[1], body[2], body[3]]),
+                    &body[4..],
+                ))
+            }
+            DHCPV6_DUID_TYPE_LL if body.len() >= 2 => {
+                Self::LL(Dhcpv6DuidLl::new(
+                    u16::from_be_bytes([body[0], body[1]]),
+                    &body[2..],
+                ))
+            }
+            DHCPV6_DUID_TYPE_UUID if body.len() == 16 => {
+                let mut uuid = [0u8; 16];
+                uuid.copy_from_slice(body);
+                Self::UUID(Dhcpv6DuidUuid::new(u128::from_be_bytes(uuid)))
+            }
+            _ => Self::Other(data.to_vec()),
+        }
+    }
+
+    /// RFC 8415 requires DUID to be stable across reboots. Load DUID from
+    /// specified file, or generate a DUID-UUID and store it into that file
+    /// if not exist. With `machine-id` feature enabled, the generated
+    /// DUID-UUID is derived from `/etc/machine-id`.
+    /// The file holds DUID in hex string separated by colon, for example:
+    /// `00:04:5e:3c:9a:0b:6d:2f:4e:81:a7:13:c4:58:f2:9d:61:0e`.
+    pub fn load_or_generate(path: &str) -> Result<Self, DhcpError> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                let duid = parse_duid_str(content.trim()).ok_or_else(|| {
+                    let e = DhcpError::new(
+                        ErrorKind::InvalidArgument,
+                        format!("Invalid DUID {:?} in file {path}", content),
+                    );
+                    log::error!("{}", e);
+                    e
+                })?;
+                Ok(Self::from_slice(&duid))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let duid = Self::UUID(Dhcpv6DuidUuid::generate());
+                store_duid(path, &duid)?;
+                Ok(duid)
+            }
+            Err(e) => {
+                let e = DhcpError::new(
+                    ErrorKind::InvalidArgument,
+                    format!("Failed to read DUID file {path}: {e}"),
+                );
+                log::error!("{}", e);
+                Err(e)
+            }
+        }
+    }
+}
+
+fn parse_duid_str(duid: &str) -> Option<Vec<u8>> {
+    let ret = duid
+        .split(':')
+        .map(|b| u8::from_str_radix(b, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    if ret.is_empty() {
+        None
+    } else {
+        Some(ret)
+    }
+}
+
+// Write to temporary file, flush it to disk and rename, so the DUID file is
+// never partially written even on crash or power loss.
+fn store_duid(path: &str, duid: &Dhcpv6Duid) -> Result<(), DhcpError> {
+    let content = duid
+        .to_vec()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<String>>()
+        .join(":");
+    let tmp_path = format!("{path}.tmp");
+    write_and_sync(&tmp_path, format!("{content}\n").as_bytes())
+        .and_then(|_| std::fs::rename(&tmp_path, path))
+        .and_then(|_| sync_parent_dir(path))
+        .map_err(|e| {
+            std::fs::remove_file(&tmp_path).ok();
+            let e = DhcpError::new(
+                ErrorKind::InvalidArgument,
+                format!("Failed to store DUID to file {path}: {e}"),
+            );
+            log::error!("{}", e);
+            e
+        })
+}
+
+fn write_and_sync(path: &str, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut fd = std::fs::File::create(path)?;
+    fd.write_all(data)?;
+    fd.sync_all()
+}
+
+// Persist the rename itself
+fn sync_parent_dir(path: &str) -> std::io::Result<()> {
+    match std::path::Path::new(path).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => {
+            std::fs::File::open(dir)?.sync_all()
+        }
+        _ => std::fs::File::open(".")?.sync_all(),
+    }
 }
 
 // Type 1
@@ -261,6 +938,65 @@ impl Dhcpv6DuidUuid {
         Self { uuid }
     }
 
+    // Derived from machine-id when `machine-id` feature enabled, otherwise
+    // random.
+    fn generate() -> Self {
+        #[cfg(feature = "machine-id")]
+        match Self::from_machine_id() {
+            Ok(v) => return v,
+            Err(e) => {
+                log::warn!("{e}, using random DUID-UUID instead");
+            }
+        }
+        let mut uuid = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut uuid);
+        Self::new(uuid_v4(uuid))
+    }
+
+    /// Derive UUID from `/etc/machine-id` using HMAC-SHA256 keyed by the
+    /// machine-id, so the machine-id itself is not exposed on network.
+    #[cfg(feature = "machine-id")]
+    pub fn from_machine_id() -> Result<Self, DhcpError> {
+        let content =
+            std::fs::read_to_string(MACHINE_ID_PATH).map_err(|e| {
+                DhcpError::new(
+                    ErrorKind::InvalidArgument,
+                    format!("Failed to read {MACHINE_ID_PATH}: {e}"),
+                )
+            })?;
+        Self::from_machine_id_str(content.trim())
+    }
+
+    #[cfg(feature = "machine-id")]
+    pub(crate) fn from_machine_id_str(
+        machine_id: &str,
+    ) -> Result<Self, DhcpError> {
+        use hmac::Mac;
+
+        let key = u128::from_str_radix(machine_id, 16)
+            .ok()
+            .filter(|_| machine_id.len() == 32)
+            .ok_or_else(|| {
+                DhcpError::new(
+                    ErrorKind::InvalidArgument,
+                    format!("Invalid machine-id {machine_id:?}"),
+                )
+            })?;
+        let mut mac =
+            hmac::Hmac::<sha2::Sha256>::new_from_slice(&key.to_be_bytes())
+                .map_err(|e| {
+                    DhcpError::new(
+                        ErrorKind::Bug,
+                        format!("Failed to create HMAC-SHA256: {e}"),
+                    )
+                })?;
+        mac.update(&MOZIM_APP_ID);
+        let hash = mac.finalize().into_bytes();
+        let mut uuid = [0u8; 16];
+        uuid.copy_from_slice(&hash[..16]);
+        Ok(Self::new(uuid_v4(uuid)))
+    }
+
     pub fn to_vec(&self) -> Vec<u8> {
         let mut ret: Vec<u8> = Vec::new();
         ret.extend_from_slice(&DHCPV6_DUID_TYPE_UUID.to_be_bytes());
@@ -268,3 +1004,10 @@ impl Dhcpv6DuidUuid {
         ret
     }
 }
+
+// RFC 9562: Set the version and variant bits of UUIDv4
+fn uuid_v4(mut uuid: [u8; 16]) -> u128 {
+    uuid[6] = (uuid[6] & 0x0f) | 0x40;
+    uuid[8] = (uuid[8] & 0x3f) | 0x80;
+    u128::from_be_bytes(uuid)
+}
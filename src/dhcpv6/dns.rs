@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv6Addr;
+
+use crate::{name::is_valid_dns_name, DhcpV6Lease};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum DhcpV6DnsSource {
+    Dhcp,
+    /// RDNSS or DNSSL option of IPv6 Router Advertisement(RFC 8106)
+    RouterAdvertisement,
+}
+
+impl std::fmt::Display for DhcpV6DnsSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Dhcp => "dhcp",
+                Self::RouterAdvertisement => "ra",
+            }
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub struct DhcpV6DnsServer {
+    pub addr: Ipv6Addr,
+    /// Lifetime in seconds, `u32::MAX` means infinity.
+    pub lifetime: u32,
+    pub source: DhcpV6DnsSource,
+}
+
+impl DhcpV6DnsServer {
+    /// DNS server learned from RDNSS option of Router Advertisement.
+    pub fn new_ra(addr: Ipv6Addr, lifetime: u32) -> Self {
+        Self {
+            addr,
+            lifetime,
+            source: DhcpV6DnsSource::RouterAdvertisement,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub struct DhcpV6DnsDomain {
+    pub domain: String,
+    /// Lifetime in seconds, `u32::MAX` means infinity.
+    pub lifetime: u32,
+    pub source: DhcpV6DnsSource,
+}
+
+impl DhcpV6DnsDomain {
+    /// Search domain learned from DNSSL option of Router Advertisement.
+    pub fn new_ra(domain: &str, lifetime: u32) -> Self {
+        Self {
+            domain: domain.to_string(),
+            lifetime,
+            source: DhcpV6DnsSource::RouterAdvertisement,
+        }
+    }
+}
+
+/// Resolved DNS configuration merged from DHCPv6 lease and IPv6 Router
+/// Advertisement.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[non_exhaustive]
+pub struct DhcpV6DnsConfig {
+    pub servers: Vec<DhcpV6DnsServer>,
+    pub domains: Vec<DhcpV6DnsDomain>,
+}
+
+impl DhcpV6DnsConfig {
+    /// Merge DNS servers and search domains of DHCPv6 lease with the ones
+    /// learned from Router Advertisement:
+    ///  * DHCPv6 entries are placed before RA ones, the order within each
+    ///    source is preserved.
+    ///  * DHCPv6 entries are valid for the `valid_life` of the lease.
+    ///  * Entries with zero lifetime are removed as required by RFC 8106.
+    ///  * Duplicate entries are merged with the longest lifetime kept.
+    ///  * Unspecified, loopback or multicast DNS servers and invalid domain
+    ///    names are ignored.
+    pub fn merge(
+        lease: Option<&DhcpV6Lease>,
+        ra_servers: &[DhcpV6DnsServer],
+        ra_domains: &[DhcpV6DnsDomain],
+    ) -> Self {
+        let mut ret = Self::default();
+        if let Some(lease) = lease {
+            for addr in lease.dns_srvs.as_deref().unwrap_or_default() {
+                ret.add_server(DhcpV6DnsServer {
+                    addr: *addr,
+                    lifetime: lease.valid_life,
+                    source: DhcpV6DnsSource::Dhcp,
+                });
+            }
+            for domain in lease.domain_list.as_deref().unwrap_or_default() {
+                ret.add_domain(DhcpV6DnsDomain {
+                    domain: domain.to_string(),
+                    lifetime: lease.valid_life,
+                    source: DhcpV6DnsSource::Dhcp,
+                });
+            }
+        }
+        for srv in ra_servers {
+            ret.add_server(srv.clone());
+        }
+        for domain in ra_domains {
+            ret.add_domain(domain.clone());
+        }
+        ret
+    }
+
+    fn add_server(&mut self, srv: DhcpV6DnsServer) {
+        if srv.lifetime == 0 {
+            return;
+        }
+        if srv.addr.is_unspecified()
+            || srv.addr.is_loopback()
+            || srv.addr.is_multicast()
+        {
+            log::warn!(
+                "Ignoring invalid DNS server {} from {}",
+                srv.addr,
+                srv.source
+            );
+            return;
+        }
+        if let Some(exist) =
+            self.servers.iter_mut().find(|s| s.addr == srv.addr)
+        {
+            exist.lifetime = exist.lifetime.max(srv.lifetime);
+        } else {
+            self.servers.push(srv);
+        }
+    }
+
+    fn add_domain(&mut self, mut domain: DhcpV6DnsDomain) {
+        if domain.lifetime == 0 {
+            return;
+        }
+        domain.domain = domain.domain.trim_end_matches('.').to_lowercase();
+        if !is_valid_dns_name(domain.domain.as_str()) {
+            log::warn!(
+                "Ignoring invalid DNS search domain {:?} from {}",
+                domain.domain,
+                domain.source
+            );
+            return;
+        }
+        if let Some(exist) =
+            self.domains.iter_mut().find(|d| d.domain == domain.domain)
+        {
+            exist.lifetime = exist.lifetime.max(domain.lifetime);
+        } else {
+            self.domains.push(domain);
+        }
+    }
+}
@@ -13,6 +13,11 @@ pub enum DhcpV6Event {
     Renew,
     Rebind,
     LeaseExpired,
+    IcmpPackageIn,
+    GatewayProbeTimeout,
+    StateTimeout,
+    RenewTimeout,
+    InformationRefresh,
 }
 
 impl From<DhcpV6Event> for u64 {
@@ -31,6 +36,15 @@ impl TryFrom<u64> for DhcpV6Event {
             x if x == Self::Renew as u64 => Ok(Self::Renew),
             x if x == Self::Rebind as u64 => Ok(Self::Rebind),
             x if x == Self::LeaseExpired as u64 => Ok(Self::LeaseExpired),
+            x if x == Self::IcmpPackageIn as u64 => Ok(Self::IcmpPackageIn),
+            x if x == Self::GatewayProbeTimeout as u64 => {
+                Ok(Self::GatewayProbeTimeout)
+            }
+            x if x == Self::StateTimeout as u64 => Ok(Self::StateTimeout),
+            x if x == Self::RenewTimeout as u64 => Ok(Self::RenewTimeout),
+            x if x == Self::InformationRefresh as u64 => {
+                Ok(Self::InformationRefresh)
+            }
             _ => {
                 let e = DhcpError::new(
                     ErrorKind::Bug,
@@ -55,6 +69,11 @@ impl std::fmt::Display for DhcpV6Event {
                 Self::Renew => "Renew",
                 Self::Rebind => "Rebind",
                 Self::LeaseExpired => "LeaseExpired",
+                Self::IcmpPackageIn => "IcmpPackageIn",
+                Self::GatewayProbeTimeout => "GatewayProbeTimeout",
+                Self::StateTimeout => "StateTimeout",
+                Self::RenewTimeout => "RenewTimeout",
+                Self::InformationRefresh => "InformationRefresh",
             }
         )
     }
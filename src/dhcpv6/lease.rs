@@ -1,13 +1,21 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use std::net::Ipv6Addr;
+use std::net::{Ipv6Addr, SocketAddrV6};
 
 use dhcproto::{
     v6,
     v6::{DhcpOption, DhcpOptions},
+    Encodable,
 };
 
-use crate::{DhcpError, DhcpV6IaType, ErrorKind};
+use crate::{
+    mud::parse_mud_url, name::decode_dns_names, DhcpError, DhcpLifetime,
+    DhcpOptionValue, DhcpV6Config, DhcpV6IaType, ErrorKind,
+};
+
+// RFC 8910: 2. Captive Portal API URI indicating no captive portal
+const CAPTIVE_PORTAL_UNRESTRICTED: &str =
+    "urn:ietf:params:capport:unrestricted";
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 #[non_exhaustive]
@@ -29,6 +37,39 @@ pub struct DhcpV6Lease {
     pub srv_duid: Vec<u8>,
     pub dhcp_opts: Vec<dhcproto::v6::DhcpOption>,
     pub srv_ip: Ipv6Addr,
+    /// RFC 3646 DNS Recursive Name Server option
+    pub dns_srvs: Option<Vec<Ipv6Addr>>,
+    /// RFC 3646 Domain Search List option
+    pub domain_list: Option<Vec<String>>,
+    /// RFC 4075 Simple Network Time Protocol (SNTP) Servers option
+    pub sntp_srvs: Option<Vec<Ipv6Addr>>,
+    /// RFC 5908 NTP Server option
+    pub ntp_srvs: Option<Vec<DhcpV6OptionNtpServer>>,
+    /// RFC 3319 SIP Servers Domain Name List option
+    pub sip_srv_domains: Option<Vec<String>>,
+    /// RFC 3319 SIP Servers IPv6 Address List option
+    pub sip_srvs: Option<Vec<Ipv6Addr>>,
+    /// RFC 8520 Manufacturer Usage Description URL option
+    pub mud_url: Option<String>,
+    /// RFC 8910 Captive-Portal option
+    pub captive_portal: Option<String>,
+    /// Options decoded by codecs registered via
+    /// [crate::DhcpV6Config::register_option_codec()].
+    pub custom_opts: Vec<DhcpV6Option>,
+    /// Source IP address and UDP port of the DHCPv6 server or relay agent
+    /// reply carrying this lease.
+    pub src_addr: Option<SocketAddrV6>,
+    /// RFC 6603 Prefix Exclude option of delegated prefix: the excluded
+    /// prefix and its length, which should not be assigned to downstream
+    /// links.
+    pub excluded_prefix: Option<(Ipv6Addr, u8)>,
+    /// Whether unknown options in [DhcpV6Lease::dhcp_opts] have been
+    /// truncated as per [crate::DhcpV6Config::set_unknown_opts_limit()].
+    pub unknown_opts_truncated: bool,
+    /// RFC 8415 Information Refresh Time option in seconds, only honored in
+    /// reply to Information-request of
+    /// [crate::DhcpV6Config::set_information_only()].
+    pub information_refresh_time: Option<u32>,
 }
 
 impl Default for DhcpV6Lease {
@@ -47,6 +88,19 @@ impl Default for DhcpV6Lease {
             srv_duid: Vec::new(),
             dhcp_opts: Vec::new(),
             srv_ip: Ipv6Addr::UNSPECIFIED,
+            dns_srvs: None,
+            domain_list: None,
+            sntp_srvs: None,
+            ntp_srvs: None,
+            sip_srv_domains: None,
+            sip_srvs: None,
+            mud_url: None,
+            captive_portal: None,
+            custom_opts: Vec::new(),
+            src_addr: None,
+            excluded_prefix: None,
+            unknown_opts_truncated: false,
+            information_refresh_time: None,
         }
     }
 }
@@ -84,6 +138,69 @@ impl std::convert::TryFrom<&v6::Message> for DhcpV6Lease {
                 DhcpOption::ServerUnicast(srv_ip) => {
                     ret.srv_ip = *srv_ip;
                 }
+                DhcpOption::DomainNameServers(v) => {
+                    ret.dns_srvs = Some(v.clone());
+                }
+                DhcpOption::DomainSearchList(v) => {
+                    ret.domain_list = Some(
+                        v.iter()
+                            .map(|n| n.to_string().trim_end_matches('.').into())
+                            .collect(),
+                    );
+                }
+                DhcpOption::InformationRefreshTime(v) => {
+                    ret.information_refresh_time = Some(*v);
+                }
+                // Not decoded by dhcproto
+                DhcpOption::Unknown(v)
+                    if v.code() == v6::OptionCode::InformationRefreshTime =>
+                {
+                    ret.information_refresh_time = v
+                        .data()
+                        .try_into()
+                        .map(u32::from_be_bytes)
+                        .map_err(|_| {
+                            log::debug!(
+                                "Invalid Information Refresh Time option {:?}",
+                                v.data()
+                            )
+                        })
+                        .ok();
+                }
+                DhcpOption::NtpServer(v) => {
+                    ret.ntp_srvs = Some(
+                        v.iter().map(DhcpV6OptionNtpServer::from).collect(),
+                    );
+                }
+                DhcpOption::Unknown(v)
+                    if v.code() == v6::OptionCode::SntpServers =>
+                {
+                    ret.sntp_srvs = Some(parse_ipv6_addrs(v.data()));
+                }
+                DhcpOption::Unknown(v)
+                    if v.code() == v6::OptionCode::SipServerD =>
+                {
+                    ret.sip_srv_domains = parse_domains(v.data());
+                }
+                DhcpOption::Unknown(v)
+                    if v.code() == v6::OptionCode::SipServerA =>
+                {
+                    ret.sip_srvs = Some(parse_ipv6_addrs(v.data()));
+                }
+                DhcpOption::Unknown(v)
+                    if v.code() == v6::OptionCode::MudUrlV6 =>
+                {
+                    ret.mud_url = parse_mud_url(v.data());
+                }
+                DhcpOption::Unknown(v)
+                    if v.code() == v6::OptionCode::DhcpCaptivePortal =>
+                {
+                    ret.captive_portal = String::from_utf8(v.data().to_vec())
+                        .map_err(|e| {
+                            log::debug!("Invalid captive portal URI: {e}")
+                        })
+                        .ok();
+                }
                 DhcpOption::StatusCode(v) => {
                     if v.status != v6::Status::Success {
                         return Err(DhcpError::new(
@@ -103,18 +220,297 @@ impl std::convert::TryFrom<&v6::Message> for DhcpV6Lease {
                 }
             }
         }
+        #[cfg(feature = "validate-names")]
+        if let Some(domains) = ret.domain_list.as_mut() {
+            domains.retain(|d| {
+                let valid = crate::name::is_valid_dns_name(d);
+                if !valid {
+                    log::warn!("Ignoring invalid DNS search domain {d:?}");
+                }
+                valid
+            });
+        }
         ret.dhcp_opts = v6_dhcp_msg.opts().iter().cloned().collect();
         // TODO: Validate T1 < T2 < lease_time.
         Ok(ret)
     }
 }
 
+impl DhcpV6Lease {
+    // Decode options in DHCPv6 server reply with registered codecs
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub(crate) fn decode_custom_opts(&mut self, config: &DhcpV6Config) {
+        if config.option_codecs.is_empty() {
+            return;
+        }
+        let raw_opts: Vec<(u16, Vec<u8>)> =
+            self.dhcp_opts.iter().filter_map(encode_opt).collect();
+        self.custom_opts = config
+            .option_codecs
+            .iter()
+            .filter_map(|(code, codec)| {
+                raw_opts
+                    .iter()
+                    .find(|(c, _)| c == code)
+                    .and_then(|(_, data)| codec.decode(*code, data))
+                    .map(|v| DhcpV6Option::Custom(*code, v))
+            })
+            .collect();
+    }
+
+    // Drop unknown options exceeding the count or size limit of config, so
+    // server padding reply with large options cannot grow memory unbounded.
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub(crate) fn limit_unknown_opts(&mut self, config: &DhcpV6Config) {
+        let mut count = 0usize;
+        let mut bytes = 0usize;
+        let mut truncated = false;
+        self.dhcp_opts.retain(|opt| {
+            let DhcpOption::Unknown(v) = opt else {
+                return true;
+            };
+            if count < config.max_unknown_opts
+                && bytes + v.data().len() <= config.max_unknown_opts_bytes
+            {
+                count += 1;
+                bytes += v.data().len();
+                true
+            } else {
+                truncated = true;
+                false
+            }
+        });
+        if truncated {
+            log::debug!(
+                "Truncated unknown DHCPv6 options to {count} options of \
+                {bytes} bytes"
+            );
+            self.unknown_opts_truncated = true;
+        }
+    }
+
+    /// Whether valid lifetime is infinite(0xffffffff), the lease never
+    /// expires. Renew and rebind are still scheduled unless T1 and T2 are
+    /// infinite too.
+    pub fn is_infinite(&self) -> bool {
+        self.valid_lifetime().is_infinite()
+    }
+
+    /// [DhcpV6Lease::valid_life] with infinity represented explicitly.
+    pub fn valid_lifetime(&self) -> DhcpLifetime {
+        self.valid_life.into()
+    }
+
+    /// [DhcpV6Lease::preferred_life] with infinity represented explicitly.
+    pub fn preferred_lifetime(&self) -> DhcpLifetime {
+        self.preferred_life.into()
+    }
+
+    /// [DhcpV6Lease::t1] with infinity represented explicitly.
+    pub fn t1_lifetime(&self) -> DhcpLifetime {
+        self.t1.into()
+    }
+
+    /// [DhcpV6Lease::t2] with infinity represented explicitly.
+    pub fn t2_lifetime(&self) -> DhcpLifetime {
+        self.t2.into()
+    }
+
+    // Whether configuration options(not address, prefix or lifetimes) are
+    // identical, including the excluded prefix and options unknown to
+    // dhcproto in `dhcp_opts`. Other options in `dhcp_opts` are either
+    // decoded into above fields or carrying identity and lifetimes.
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub(crate) fn same_options(&self, other: &Self) -> bool {
+        self.dns_srvs == other.dns_srvs
+            && self.domain_list == other.domain_list
+            && self.sntp_srvs == other.sntp_srvs
+            && self.ntp_srvs == other.ntp_srvs
+            && self.sip_srv_domains == other.sip_srv_domains
+            && self.sip_srvs == other.sip_srvs
+            && self.mud_url == other.mud_url
+            && self.captive_portal == other.captive_portal
+            && self.custom_opts == other.custom_opts
+            && self.excluded_prefix == other.excluded_prefix
+            && self.unknown_opts().eq(other.unknown_opts())
+    }
+
+    fn unknown_opts(&self) -> impl Iterator<Item = &DhcpOption> {
+        self.dhcp_opts
+            .iter()
+            .filter(|opt| matches!(opt, DhcpOption::Unknown(_)))
+    }
+
+    /// URI of captive portal API, `None` if not provided by DHCPv6 server
+    /// or server indicated no captive portal.
+    pub fn captive_portal_url(&self) -> Option<&str> {
+        self.captive_portal
+            .as_deref()
+            .filter(|u| *u != CAPTIVE_PORTAL_UNRESTRICTED)
+    }
+}
+
+/// Build [DhcpV6Lease] without DHCPv6 server involved, e.g. for unit test
+/// of projects using this crate.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DhcpV6LeaseBuilder {
+    lease: DhcpV6Lease,
+}
+
+impl DhcpV6LeaseBuilder {
+    /// When not set, preferred lifetime equals to `valid_life`, T1 is 50%
+    /// and T2 is 80% of preferred lifetime as suggested by RFC 8415. Prefix
+    /// length default to 128.
+    pub fn new(
+        ia_type: DhcpV6IaType,
+        addr: Ipv6Addr,
+        srv_duid: &[u8],
+        valid_life: u32,
+    ) -> Self {
+        Self {
+            lease: DhcpV6Lease {
+                ia_type,
+                addr,
+                srv_duid: srv_duid.to_vec(),
+                valid_life,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn set_t1(&mut self, t1: u32) -> &mut Self {
+        self.lease.t1 = t1;
+        self
+    }
+
+    pub fn set_t2(&mut self, t2: u32) -> &mut Self {
+        self.lease.t2 = t2;
+        self
+    }
+
+    pub fn set_preferred_life(&mut self, preferred_life: u32) -> &mut Self {
+        self.lease.preferred_life = preferred_life;
+        self
+    }
+
+    pub fn set_prefix_len(&mut self, prefix_len: u8) -> &mut Self {
+        self.lease.prefix_len = prefix_len;
+        self
+    }
+
+    pub fn set_excluded_prefix(
+        &mut self,
+        prefix: Ipv6Addr,
+        prefix_len: u8,
+    ) -> &mut Self {
+        self.lease.excluded_prefix = Some((prefix, prefix_len));
+        self
+    }
+
+    pub fn set_xid(&mut self, xid: [u8; 3]) -> &mut Self {
+        self.lease.xid = xid;
+        self
+    }
+
+    pub fn set_iaid(&mut self, iaid: u32) -> &mut Self {
+        self.lease.iaid = iaid;
+        self
+    }
+
+    pub fn set_cli_duid(&mut self, duid: &[u8]) -> &mut Self {
+        self.lease.cli_duid = duid.to_vec();
+        self
+    }
+
+    pub fn set_dns_srvs(&mut self, srvs: &[Ipv6Addr]) -> &mut Self {
+        self.lease.dns_srvs = Some(srvs.to_vec());
+        self
+    }
+
+    pub fn set_domain_list(&mut self, domains: &[&str]) -> &mut Self {
+        self.lease.domain_list =
+            Some(domains.iter().map(|d| d.to_string()).collect());
+        self
+    }
+
+    pub fn set_ntp_srvs(
+        &mut self,
+        srvs: &[DhcpV6OptionNtpServer],
+    ) -> &mut Self {
+        self.lease.ntp_srvs = Some(srvs.to_vec());
+        self
+    }
+
+    pub fn set_sip_srv_domains(&mut self, domains: &[&str]) -> &mut Self {
+        self.lease.sip_srv_domains =
+            Some(domains.iter().map(|d| d.to_string()).collect());
+        self
+    }
+
+    pub fn set_sip_srvs(&mut self, srvs: &[Ipv6Addr]) -> &mut Self {
+        self.lease.sip_srvs = Some(srvs.to_vec());
+        self
+    }
+
+    /// Return [ErrorKind::InvalidArgument] if address is unspecified,
+    /// server DUID is empty, prefix length is bigger than 128, or not
+    /// meeting T1 <= T2 and preferred lifetime <= valid lifetime.
+    pub fn build(&self) -> Result<DhcpV6Lease, DhcpError> {
+        let mut lease = self.lease.clone();
+        if lease.preferred_life == 0 {
+            lease.preferred_life = lease.valid_life;
+        }
+        if lease.t1 == 0 {
+            lease.t1 = lease.preferred_life / 2;
+        }
+        if lease.t2 == 0 {
+            lease.t2 = u32::try_from(u64::from(lease.preferred_life) * 4 / 5)
+                .unwrap_or(0);
+        }
+        let e = if lease.addr.is_unspecified() {
+            Some("Lease address should not be ::".to_string())
+        } else if lease.srv_duid.is_empty() {
+            Some("Lease server DUID should not be empty".to_string())
+        } else if lease.prefix_len > 128 {
+            Some(format!(
+                "Lease prefix length {} should not be bigger than 128",
+                lease.prefix_len
+            ))
+        } else if lease.t1 > lease.t2 {
+            Some(format!(
+                "Lease should have T1({}) <= T2({})",
+                lease.t1, lease.t2
+            ))
+        } else if lease.preferred_life > lease.valid_life {
+            Some(format!(
+                "Lease should have preferred lifetime({}) <= valid \
+                lifetime({})",
+                lease.preferred_life, lease.valid_life
+            ))
+        } else {
+            None
+        };
+        if let Some(msg) = e {
+            let e = DhcpError::new(ErrorKind::InvalidArgument, msg);
+            log::error!("{}", e);
+            return Err(e);
+        }
+        Ok(lease)
+    }
+}
+
 fn parse_dhcp_opt_iaadr(opts: &DhcpOptions, lease: &mut DhcpV6Lease) {
     if let Some(DhcpOption::IAPrefix(a)) = opts.get(v6::OptionCode::IAPrefix) {
         lease.addr = a.prefix_ip;
         lease.prefix_len = a.prefix_len;
         lease.preferred_life = a.preferred_lifetime;
         lease.valid_life = a.valid_lifetime;
+        if let Some(DhcpOption::Unknown(v)) =
+            a.opts.get(v6::OptionCode::PdExclude)
+        {
+            lease.excluded_prefix =
+                parse_pd_exclude(a.prefix_ip, a.prefix_len, v.data());
+        }
     }
     if let Some(DhcpOption::IAAddr(a)) = opts.get(v6::OptionCode::IAAddr) {
         lease.addr = a.addr;
@@ -123,3 +519,114 @@ fn parse_dhcp_opt_iaadr(opts: &DhcpOptions, lease: &mut DhcpV6Lease) {
         lease.prefix_len = 128
     }
 }
+
+// RFC 6603: 4.2. Prefix Exclude Option
+// Length of excluded prefix followed by the bits of excluded prefix after
+// the delegated prefix(IPv6 subnet ID), padded with zero to octet boundary.
+fn parse_pd_exclude(
+    prefix: Ipv6Addr,
+    prefix_len: u8,
+    data: &[u8],
+) -> Option<(Ipv6Addr, u8)> {
+    let (&excluded_len, subnet_id) = data.split_first()?;
+    if excluded_len <= prefix_len || excluded_len > 128 {
+        log::warn!(
+            "Ignoring invalid Prefix Exclude option: excluded prefix length \
+            {excluded_len} should be within {prefix_len}+1 to 128"
+        );
+        return None;
+    }
+    let bit_count = u32::from(excluded_len - prefix_len);
+    if subnet_id.len() != (bit_count as usize).div_ceil(8) {
+        log::warn!(
+            "Ignoring invalid Prefix Exclude option: expecting {bit_count} \
+            bits IPv6 subnet ID, got {} octets",
+            subnet_id.len()
+        );
+        return None;
+    }
+    let subnet_id = subnet_id
+        .iter()
+        .fold(0u128, |id, b| (id << 8) | u128::from(*b))
+        >> (subnet_id.len() as u32 * 8 - bit_count);
+    let prefix_mask = u128::MAX
+        .checked_shl(128 - u32::from(prefix_len))
+        .unwrap_or(0);
+    let excluded = (u128::from(prefix) & prefix_mask)
+        | (subnet_id << (128 - u32::from(excluded_len)));
+    Some((Ipv6Addr::from(excluded), excluded_len))
+}
+
+/// DHCPv6 option decoded by codec registered via
+/// [crate::DhcpV6Config::register_option_codec()].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum DhcpV6Option {
+    Custom(u16, Box<dyn DhcpOptionValue>),
+}
+
+/// Suboptions of RFC 5908 NTP Server option
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum DhcpV6OptionNtpServer {
+    ServerAddress(Ipv6Addr),
+    MulticastAddress(Ipv6Addr),
+    Fqdn(String),
+}
+
+impl From<&v6::NtpSuboption> for DhcpV6OptionNtpServer {
+    fn from(v: &v6::NtpSuboption) -> Self {
+        match v {
+            v6::NtpSuboption::ServerAddress(a) => Self::ServerAddress(*a),
+            v6::NtpSuboption::MulticastAddress(a) => Self::MulticastAddress(*a),
+            v6::NtpSuboption::FQDN(n) => {
+                Self::Fqdn(n.to_string().trim_end_matches('.').to_string())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for DhcpV6OptionNtpServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ServerAddress(a) | Self::MulticastAddress(a) => {
+                write!(f, "{a}")
+            }
+            Self::Fqdn(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+// RFC 3319: Domain names encoded as RFC 1035 section 3.1 without
+// compression, while some servers still compress them.
+fn parse_domains(data: &[u8]) -> Option<Vec<String>> {
+    let ret = decode_dns_names(data);
+    if ret.is_none() {
+        log::debug!("Invalid domain name list {data:?}");
+    }
+    ret
+}
+
+// Option code and data(without length), re-encoded as dhcproto might have
+// parsed the option already.
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
+fn encode_opt(opt: &DhcpOption) -> Option<(u16, Vec<u8>)> {
+    let mut buffer = Vec::new();
+    if let Err(e) = opt.encode(&mut v6::Encoder::new(&mut buffer)) {
+        log::debug!("Failed to encode DHCPv6 option {opt:?}: {e}");
+        return None;
+    }
+    match buffer.as_slice() {
+        [c0, c1, _, _, data @ ..] => {
+            Some((u16::from_be_bytes([*c0, *c1]), data.to_vec()))
+        }
+        _ => None,
+    }
+}
+
+fn parse_ipv6_addrs(data: &[u8]) -> Vec<Ipv6Addr> {
+    data.chunks_exact(16)
+        .filter_map(|c| <[u8; 16]>::try_from(c).ok())
+        .map(Ipv6Addr::from)
+        .collect()
+}
@@ -1,17 +1,48 @@
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "client")]
 mod client;
 mod config;
+mod dns;
+#[cfg(feature = "client")]
 mod event;
 mod lease;
 mod msg;
+#[cfg(feature = "client")]
+mod ndp;
+#[cfg(feature = "ra")]
+mod ra;
+#[cfg(feature = "client")]
+mod socket;
+mod state;
+#[cfg(feature = "client")]
 mod time;
+#[cfg(feature = "client")]
+mod trans;
 
-pub use self::client::DhcpV6Client;
+#[cfg(feature = "client")]
+pub use self::client::{DhcpV6Client, DhcpV6RetransmitStats};
 pub use self::config::{
-    DhcpV6Config, DhcpV6IaType, Dhcpv6Duid, Dhcpv6DuidEn, Dhcpv6DuidLl,
-    Dhcpv6DuidLlt, Dhcpv6DuidUuid,
+    iaid_from_iface, DhcpV6Config, DhcpV6IaType, Dhcpv6Duid, Dhcpv6DuidEn,
+    Dhcpv6DuidLl, Dhcpv6DuidLlt, Dhcpv6DuidUuid,
 };
+pub use self::dns::{
+    DhcpV6DnsConfig, DhcpV6DnsDomain, DhcpV6DnsServer, DhcpV6DnsSource,
+};
+#[cfg(feature = "client")]
 pub use self::event::DhcpV6Event;
-pub use self::lease::DhcpV6Lease;
-pub use self::msg::DhcpV6Message;
+pub use self::lease::{
+    DhcpV6Lease, DhcpV6LeaseBuilder, DhcpV6Option, DhcpV6OptionNtpServer,
+};
+pub use self::msg::{DhcpV6Message, DhcpV6MessageType};
+#[cfg(all(test, feature = "ra"))]
+pub(crate) use self::ra::{gen_router_solicit, parse_router_advert};
+#[cfg(feature = "ra")]
+pub use self::ra::{DhcpV6RaListener, DhcpV6RaMode, DhcpV6RouterAdvert};
+pub use self::state::{DhcpV6Progress, DhcpV6State};
+#[cfg(all(test, feature = "client"))]
+pub(crate) use self::time::{
+    gen_information_refresh_time, gen_request_wait_time, gen_solicit_wait_time,
+};
+#[cfg(all(test, feature = "client"))]
+pub(crate) use self::trans::DhcpV6Transaction;
@@ -1,7 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use std::net::Ipv6Addr;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use dhcproto::{
     v6,
@@ -9,28 +8,36 @@ use dhcproto::{
     Decodable, Decoder, Encodable,
 };
 
-use crate::{DhcpError, DhcpV6Config, DhcpV6IaType, DhcpV6Lease, ErrorKind};
+use crate::{
+    mud::validate_mud_url, DhcpError, DhcpV6Config, DhcpV6IaType, DhcpV6Lease,
+    ErrorKind,
+};
 
-const DEFAULT_IAID: u32 = 0;
+// RFC 4704: 4.1. The Flags Field
+//      S: 1 if the server SHOULD perform the AAAA RR (FQDN-to-address)
+//         DNS updates.
+const FQDN_FLAG_S: u8 = 0x01;
+const FQDN_MAX_LABEL_LEN: usize = 63;
+const FQDN_MAX_LEN: usize = 255;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub(crate) struct DhcpV6MessageType(v6::MessageType);
+pub struct DhcpV6MessageType(v6::MessageType);
 
 impl DhcpV6MessageType {
-    pub(crate) const SOLICIT: Self =
-        DhcpV6MessageType(v6::MessageType::Solicit);
+    pub const SOLICIT: Self = DhcpV6MessageType(v6::MessageType::Solicit);
 
-    pub(crate) const ADVERTISE: Self =
-        DhcpV6MessageType(v6::MessageType::Advertise);
+    pub const ADVERTISE: Self = DhcpV6MessageType(v6::MessageType::Advertise);
 
-    pub(crate) const REQUEST: Self =
-        DhcpV6MessageType(v6::MessageType::Request);
+    pub const REQUEST: Self = DhcpV6MessageType(v6::MessageType::Request);
 
-    pub(crate) const REPLY: Self = DhcpV6MessageType(v6::MessageType::Reply);
-    pub(crate) const RENEW: Self = DhcpV6MessageType(v6::MessageType::Renew);
-    pub(crate) const REBIND: Self = DhcpV6MessageType(v6::MessageType::Rebind);
-    pub(crate) const RELEASE: Self =
-        DhcpV6MessageType(v6::MessageType::Release);
+    pub const CONFIRM: Self = DhcpV6MessageType(v6::MessageType::Confirm);
+    pub const REPLY: Self = DhcpV6MessageType(v6::MessageType::Reply);
+    pub const RENEW: Self = DhcpV6MessageType(v6::MessageType::Renew);
+    pub const REBIND: Self = DhcpV6MessageType(v6::MessageType::Rebind);
+    pub const RELEASE: Self = DhcpV6MessageType(v6::MessageType::Release);
+    pub const DECLINE: Self = DhcpV6MessageType(v6::MessageType::Decline);
+    pub const INFORMATION_REQUEST: Self =
+        DhcpV6MessageType(v6::MessageType::InformationRequest);
 }
 
 impl Default for DhcpV6MessageType {
@@ -54,6 +61,7 @@ impl std::fmt::Display for DhcpV6MessageType {
                 v6::MessageType::Rebind => "rebind",
                 v6::MessageType::Release => "release",
                 v6::MessageType::Reply => "reply",
+                v6::MessageType::InformationRequest => "information-request",
                 _ => {
                     log::warn!("Got unknown message type {:?}", self.0);
                     "unknown"
@@ -86,7 +94,11 @@ pub struct DhcpV6Message {
 }
 
 impl DhcpV6Message {
-    pub(crate) fn new(
+    /// Create DHCPv6 message of specified type and transaction ID. Only
+    /// SOLICIT, REQUEST, RENEW, REBIND, RELEASE, DECLINE and
+    /// INFORMATION-REQUEST messages can be generated by
+    /// [DhcpV6Message::to_dhcp_pkg()].
+    pub fn new(
         config: &DhcpV6Config,
         msg_type: DhcpV6MessageType,
         xid: [u8; 3],
@@ -100,16 +112,30 @@ impl DhcpV6Message {
         }
     }
 
-    pub(crate) fn load_lease(
-        &mut self,
-        lease: DhcpV6Lease,
-    ) -> Result<(), DhcpError> {
-        validate_lease(&self.config, &lease)?;
+    /// Transaction ID of this message.
+    pub fn xid(&self) -> [u8; 3] {
+        self.xid
+    }
+
+    pub fn msg_type(&self) -> DhcpV6MessageType {
+        self.msg_type
+    }
+
+    /// Lease included in this message.
+    pub fn lease(&self) -> Option<&DhcpV6Lease> {
+        self.lease.as_ref()
+    }
+
+    /// Load lease required by REQUEST, RENEW, REBIND, RELEASE and DECLINE
+    /// message.
+    pub fn load_lease(&mut self, lease: DhcpV6Lease) -> Result<(), DhcpError> {
+        validate_lease(&self.config, self.msg_type, &lease)?;
         self.lease = Some(lease);
         Ok(())
     }
 
-    pub(crate) fn to_dhcp_pkg(&self) -> Result<Vec<u8>, DhcpError> {
+    /// Generate DHCPv6 message as UDP payload.
+    pub fn to_dhcp_pkg(&self) -> Result<Vec<u8>, DhcpError> {
         let mut dhcp_msg =
             v6::Message::new_with_id(self.msg_type.into(), self.xid);
 
@@ -117,14 +143,26 @@ impl DhcpV6Message {
             .opts_mut()
             .insert(DhcpOption::ClientId(self.config.duid.to_vec()));
 
-        match self.config.ia_type {
-            DhcpV6IaType::NonTemporaryAddresses => {
+        // RFC 8415: 16.12. Information-request Message
+        //      Servers MUST discard any received Information-request message
+        //      that meets any of the following conditions:
+        //      -  the message includes an IA option.
+        let ia_type = if self.msg_type == DhcpV6MessageType::INFORMATION_REQUEST
+        {
+            None
+        } else {
+            Some(self.config.ia_type)
+        };
+        match ia_type {
+            None => (),
+            Some(DhcpV6IaType::NonTemporaryAddresses) => {
                 dhcp_msg.opts_mut().insert(DhcpOption::IANA(v6::IANA {
                     id: self
                         .lease
                         .as_ref()
                         .map(|l| l.iaid)
-                        .unwrap_or(DEFAULT_IAID),
+                        .or(self.config.iaid)
+                        .unwrap_or_default(),
                     // Required by RFC 8415 section 21.4
                     t1: 0,
                     // Required by RFC 8415 section 21.4
@@ -136,13 +174,14 @@ impl DhcpV6Message {
                         .unwrap_or_default(),
                 }))
             }
-            DhcpV6IaType::TemporaryAddresses => {
+            Some(DhcpV6IaType::TemporaryAddresses) => {
                 dhcp_msg.opts_mut().insert(DhcpOption::IATA(v6::IATA {
                     id: self
                         .lease
                         .as_ref()
                         .map(|l| l.iaid)
-                        .unwrap_or(DEFAULT_IAID),
+                        .or(self.config.iaid)
+                        .unwrap_or_default(),
                     opts: self
                         .lease
                         .as_ref()
@@ -150,13 +189,14 @@ impl DhcpV6Message {
                         .unwrap_or_default(),
                 }))
             }
-            DhcpV6IaType::PrefixDelegation => {
+            Some(DhcpV6IaType::PrefixDelegation) => {
                 dhcp_msg.opts_mut().insert(DhcpOption::IAPD(v6::IAPD {
                     id: self
                         .lease
                         .as_ref()
                         .map(|l| l.iaid)
-                        .unwrap_or(DEFAULT_IAID),
+                        .or(self.config.iaid)
+                        .unwrap_or_default(),
                     // Required by RFC 8415 section 21.21
                     t1: 0,
                     // Required by RFC 8415 section 21.21
@@ -171,10 +211,13 @@ impl DhcpV6Message {
         }
 
         match self.msg_type {
-            DhcpV6MessageType::SOLICIT | DhcpV6MessageType::REBIND => (),
+            DhcpV6MessageType::SOLICIT
+            | DhcpV6MessageType::REBIND
+            | DhcpV6MessageType::INFORMATION_REQUEST => (),
             DhcpV6MessageType::REQUEST
             | DhcpV6MessageType::RENEW
-            | DhcpV6MessageType::RELEASE => {
+            | DhcpV6MessageType::RELEASE
+            | DhcpV6MessageType::DECLINE => {
                 if let Some(lease) = self.lease.as_ref() {
                     dhcp_msg
                         .opts_mut()
@@ -189,10 +232,91 @@ impl DhcpV6Message {
                 }
             }
             _ => {
-                log::error!(
-                    "BUG: Invalid DhcpV6MessageType {:?}",
-                    self.msg_type
+                let e = DhcpError::new(
+                    ErrorKind::InvalidArgument,
+                    format!(
+                        "Unsupported DHCPv6 message type {}",
+                        self.msg_type
+                    ),
                 );
+                log::error!("{}", e);
+                return Err(e);
+            }
+        }
+
+        // RFC 8415: 18.2. Client Behavior
+        //      Clients MUST include an Option Request option in Solicit,
+        //      Request, Renew, Rebind and Information-request messages
+        if !self.is_release_or_decline() {
+            let mut opts: Vec<v6::OptionCode> = self
+                .config
+                .request_opts
+                .iter()
+                .map(|c| v6::OptionCode::from(*c))
+                .collect();
+            // RFC 6603: 4.2. Prefix Exclude Option
+            //      A requesting router MUST include the OPTION_PD_EXCLUDE
+            //      option code in the OPTION_ORO option to indicate support
+            if ia_type == Some(DhcpV6IaType::PrefixDelegation)
+                && !opts.contains(&v6::OptionCode::PdExclude)
+            {
+                opts.push(v6::OptionCode::PdExclude);
+            }
+            // RFC 8415: 21.23. Information Refresh Time Option
+            //      A client MUST include OPTION_INFORMATION_REFRESH_TIME in
+            //      the Option Request option in Information-request
+            if ia_type.is_none()
+                && !opts.contains(&v6::OptionCode::InformationRefreshTime)
+            {
+                opts.push(v6::OptionCode::InformationRefreshTime);
+            }
+            for (code, _) in self.config.option_codecs.iter() {
+                let code = v6::OptionCode::from(*code);
+                if !opts.contains(&code) {
+                    opts.push(code);
+                }
+            }
+            if !opts.is_empty() {
+                dhcp_msg
+                    .opts_mut()
+                    .insert(DhcpOption::ORO(v6::ORO { opts }));
+            }
+        }
+
+        // RFC 7844: 4.4. Client FQDN Option
+        //      clients SHOULD NOT include the Client FQDN option
+        if !self.is_release_or_decline()
+            && !self.config.host_name.is_empty()
+            && !self.config.anonymity_profile
+        {
+            dhcp_msg.opts_mut().insert(DhcpOption::Unknown(
+                v6::UnknownOption::new(
+                    v6::OptionCode::ClientFqdn,
+                    gen_fqdn_opt_data(&self.config.host_name)?,
+                ),
+            ));
+        }
+
+        // RFC 8520: 10. The MUD URL DHCP Option
+        if !self.is_release_or_decline() && !self.config.anonymity_profile {
+            if let Some(url) = self.config.mud_url.as_ref() {
+                validate_mud_url(url, u16::MAX.into())?;
+                dhcp_msg.opts_mut().insert(DhcpOption::Unknown(
+                    v6::UnknownOption::new(
+                        v6::OptionCode::MudUrlV6,
+                        url.as_bytes().to_vec(),
+                    ),
+                ));
+            }
+        }
+
+        if !self.is_release_or_decline() {
+            for (code, data) in self.config.extra_opts.iter() {
+                let code = v6::OptionCode::from(*code);
+                dhcp_msg.opts_mut().remove_all(code);
+                dhcp_msg.opts_mut().insert(DhcpOption::Unknown(
+                    v6::UnknownOption::new(code, data.clone()),
+                ));
             }
         }
 
@@ -210,18 +334,20 @@ impl DhcpV6Message {
         Ok(dhcp_msg_buff)
     }
 
-    pub(crate) fn from_dhcp_pkg(payload: &[u8]) -> Result<Self, DhcpError> {
+    /// Parse DHCPv6 message from UDP payload.
+    pub fn from_dhcp_pkg(payload: &[u8]) -> Result<Self, DhcpError> {
         let v6_dhcp_msg = v6::Message::decode(&mut Decoder::new(payload))
             .map_err(|decode_error| {
-                let e = DhcpError::new(
-                    ErrorKind::InvalidDhcpServerReply,
-                    format!(
-                        "Failed to parse DHCPv6 message from payload of pkg \
-                        {payload:?}: {decode_error}"
-                    ),
+                // Logged by caller as malformed packet might be flooded
+                log::debug!(
+                    "Failed to parse DHCPv6 message from payload of pkg \
+                    {payload:?}: {decode_error}"
                 );
-                log::error!("{}", e);
-                e
+                DhcpError::new(
+                    ErrorKind::InvalidDhcpServerReply,
+                    format!("Failed to parse DHCPv6 message: {decode_error}"),
+                )
+                .with_source(decode_error)
             })?;
 
         let ret = Self {
@@ -234,17 +360,73 @@ impl DhcpV6Message {
         Ok(ret)
     }
 
-    pub(crate) fn add_elapsed_time(&mut self, trans_begin_time: Instant) {
+    // Message only identifying the lease, no configuration requested
+    fn is_release_or_decline(&self) -> bool {
+        self.msg_type == DhcpV6MessageType::RELEASE
+            || self.msg_type == DhcpV6MessageType::DECLINE
+    }
+
+    /// Include Elapsed Time option with time passed since specified
+    /// beginning of the exchange.
+    pub fn add_elapsed_time(&mut self, trans_begin_time: Instant) {
+        // RFC 8415: 21.9. Elapsed Time Option
+        //      expressed in hundredths of a second (10^-2 seconds). A
+        //      value of 0xffff is used to represent any elapsed-time values
+        //      greater than the largest time value that can be represented
         self.elapsed_time =
-            match u16::try_from(trans_begin_time.elapsed().as_secs() / 100) {
-                Ok(i) => i,
-                Err(_) => u16::MAX,
-            };
+            u16::try_from(trans_begin_time.elapsed().as_millis() / 10)
+                .unwrap_or(u16::MAX);
+    }
+
+    /// Time sent via Elapsed Time option, zero means option not included.
+    pub fn elapsed_time(&self) -> Duration {
+        Duration::from_millis(u64::from(self.elapsed_time) * 10)
     }
 }
 
+// RFC 4704: 4. The DHCPv6 Client FQDN Option
+// Flags followed by domain name in DNS wire format, partial name(no dot
+// included) is not terminated by zero length label.
+pub(crate) fn gen_fqdn_opt_data(host_name: &str) -> Result<Vec<u8>, DhcpError> {
+    let fully_qualified = host_name.contains('.');
+    let mut ret = vec![FQDN_FLAG_S];
+    for label in host_name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > FQDN_MAX_LABEL_LEN {
+            let e = DhcpError::new(
+                ErrorKind::InvalidArgument,
+                format!(
+                    "Invalid host name {host_name}: label should be 1 to \
+                    {FQDN_MAX_LABEL_LEN} bytes"
+                ),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+        ret.push(label.len() as u8);
+        ret.extend_from_slice(label.as_bytes());
+    }
+    if fully_qualified {
+        ret.push(0);
+    }
+    if ret.len() - 1 > FQDN_MAX_LEN {
+        let e = DhcpError::new(
+            ErrorKind::InvalidArgument,
+            format!(
+                "Invalid host name {host_name}: longer than {FQDN_MAX_LEN} \
+                bytes in wire format"
+            ),
+        );
+        log::error!("{}", e);
+        return Err(e);
+    }
+    Ok(ret)
+}
+
+// Only violations making the message unusable are fatal, others are logged
+// and the message is still generated.
 fn validate_lease(
     config: &DhcpV6Config,
+    msg_type: DhcpV6MessageType,
     lease: &DhcpV6Lease,
 ) -> Result<(), DhcpError> {
     if lease.ia_type != config.ia_type {
@@ -258,27 +440,43 @@ fn validate_lease(
         ));
     }
     if lease.srv_duid.is_empty() {
-        return Err(DhcpError::new(
-            ErrorKind::InvalidArgument,
-            "DHCPv6 lease contains empty server DUID, please run \
-            DhcpV6Message::load_lease() with correct lease"
-                .to_string(),
-        ));
+        // RFC 8415: 16. Client Message Validation, Server Identifier option
+        // is mandatory in Request, Renew, Release and Decline
+        if [
+            DhcpV6MessageType::REQUEST,
+            DhcpV6MessageType::RENEW,
+            DhcpV6MessageType::RELEASE,
+            DhcpV6MessageType::DECLINE,
+        ]
+        .contains(&msg_type)
+        {
+            return Err(DhcpError::new(
+                ErrorKind::InvalidArgument,
+                format!(
+                    "DHCPv6 lease contains empty server DUID which is \
+                    required by {msg_type}, please run \
+                    DhcpV6Message::load_lease() with correct lease"
+                ),
+            ));
+        }
+        log::warn!("DHCPv6 lease for {msg_type} contains empty server DUID");
     }
-    if lease.addr == Ipv6Addr::UNSPECIFIED {
-        return Err(DhcpError::new(
-            ErrorKind::InvalidArgument,
-            "DHCPv6 lease contains invalid all zero lease \
-            IPv6 address, please run DhcpV6Message::load_lease()
-            with correct lease"
-                .to_string(),
-        ));
+    if lease.addr.is_unspecified() {
+        log::warn!(
+            "DHCPv6 lease for {msg_type} contains all zero {} address, \
+            sending {} without it",
+            lease.ia_type,
+            lease.ia_type
+        );
     }
     Ok(())
 }
 
 fn gen_iaadr_dhcp_opt(lease: &DhcpV6Lease) -> DhcpOptions {
     let mut ret = DhcpOptions::new();
+    if lease.addr.is_unspecified() {
+        return ret;
+    }
     match lease.ia_type {
         DhcpV6IaType::TemporaryAddresses
         | DhcpV6IaType::NonTemporaryAddresses => {
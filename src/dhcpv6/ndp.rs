@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv6Addr;
+
+// RFC 4861 Neighbor Discovery for IP version 6
+const ICMPV6_NEIGHBOR_SOLICITATION: u8 = 135;
+const ICMPV6_NEIGHBOR_ADVERTISEMENT: u8 = 136;
+const ND_OPT_SOURCE_LINK_ADDR: u8 = 1;
+const ND_OPT_TARGET_LINK_ADDR: u8 = 2;
+const ND_HEADER_LEN: usize = 24;
+
+// RFC 4291: 2.7.1. Pre-Defined Multicast Addresses
+pub(crate) fn solicited_node_addr(target: &Ipv6Addr) -> Ipv6Addr {
+    let o = target.octets();
+    Ipv6Addr::new(
+        0xff02,
+        0,
+        0,
+        0,
+        0,
+        1,
+        0xff00 | u16::from(o[13]),
+        u16::from_be_bytes([o[14], o[15]]),
+    )
+}
+
+// ICMPv6 Neighbor Solicitation, checksum is filled by kernel.
+pub(crate) fn gen_neighbor_solicit(
+    target: &Ipv6Addr,
+    src_mac: &[u8],
+) -> Vec<u8> {
+    let mut ret = vec![ICMPV6_NEIGHBOR_SOLICITATION, 0, 0, 0, 0, 0, 0, 0];
+    ret.extend_from_slice(&target.octets());
+    if src_mac.len() == libc::ETH_ALEN as usize {
+        ret.push(ND_OPT_SOURCE_LINK_ADDR);
+        // Length in units of 8 octets
+        ret.push(1);
+        ret.extend_from_slice(src_mac);
+    }
+    ret
+}
+
+// Return target address and Target Link-Layer Address option of ICMPv6
+// Neighbor Advertisement, None if not Neighbor Advertisement.
+pub(crate) fn parse_neighbor_advert(
+    data: &[u8],
+) -> Option<(Ipv6Addr, Option<[u8; 6]>)> {
+    if data.len() < ND_HEADER_LEN || data[0] != ICMPV6_NEIGHBOR_ADVERTISEMENT {
+        return None;
+    }
+    let mut target = [0u8; 16];
+    target.copy_from_slice(&data[8..24]);
+    let mut mac = None;
+    let mut opts = &data[ND_HEADER_LEN..];
+    while opts.len() >= 2 {
+        let len = usize::from(opts[1]) * 8;
+        if len == 0 || len > opts.len() {
+            break;
+        }
+        if opts[0] == ND_OPT_TARGET_LINK_ADDR && len >= 8 {
+            let mut m = [0u8; 6];
+            m.copy_from_slice(&opts[2..8]);
+            mac = Some(m);
+        }
+        opts = &opts[len..];
+    }
+    Some((Ipv6Addr::from(target), mac))
+}
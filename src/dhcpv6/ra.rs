@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv6Addr;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use crate::{
+    mac::mac_str_to_u8_array, nispor::get_nispor_iface,
+    socket::DhcpIcmpV6Socket, DhcpError,
+};
+
+// RFC 4861 Neighbor Discovery for IP version 6
+const ICMPV6_ROUTER_SOLICITATION: u8 = 133;
+const ICMPV6_ROUTER_ADVERTISEMENT: u8 = 134;
+const ND_OPT_SOURCE_LINK_ADDR: u8 = 1;
+const RA_HEADER_LEN: usize = 16;
+const ND_HOP_LIMIT: u8 = 255;
+const RA_FLAG_MANAGED: u8 = 0x80;
+const RA_FLAG_OTHER_CONFIG: u8 = 0x40;
+const ALL_ROUTERS_ADDR: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 2);
+
+/// How DHCPv6 should be used on the link according to the Router
+/// Advertisement flags.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum DhcpV6RaMode {
+    /// M flag set: addresses are available via stateful DHCPv6.
+    Stateful,
+    /// Only O flag set: other configuration like DNS is available via
+    /// stateless DHCPv6 Information-request.
+    Stateless,
+    /// Neither M nor O flag set: DHCPv6 is not available on the link.
+    Disabled,
+}
+
+impl std::fmt::Display for DhcpV6RaMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Stateful => "stateful",
+                Self::Stateless => "stateless",
+                Self::Disabled => "disabled",
+            }
+        )
+    }
+}
+
+/// RFC 4861 Router Advertisement received on the interface.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub struct DhcpV6RouterAdvert {
+    /// Link-local address of the router
+    pub router: Ipv6Addr,
+    /// Managed address configuration(M) flag
+    pub managed: bool,
+    /// Other configuration(O) flag
+    pub other_config: bool,
+    /// Router lifetime in seconds, 0 means not a default router
+    pub router_lifetime: u16,
+}
+
+impl DhcpV6RouterAdvert {
+    /// DHCPv6 mode indicated by M and O flags. RFC 4861 states the O flag
+    /// is redundant when M flag is set.
+    pub fn dhcpv6_mode(&self) -> DhcpV6RaMode {
+        if self.managed {
+            DhcpV6RaMode::Stateful
+        } else if self.other_config {
+            DhcpV6RaMode::Stateless
+        } else {
+            DhcpV6RaMode::Disabled
+        }
+    }
+}
+
+/// Listener of Router Advertisement on specified interface, allowing
+/// integrator to decide between stateful DHCPv6, stateless DHCPv6 or
+/// nothing before starting [crate::DhcpV6Client].
+/// Requires CAP_NET_RAW.
+#[derive(Debug)]
+pub struct DhcpV6RaListener {
+    socket: DhcpIcmpV6Socket,
+    src_mac: Vec<u8>,
+}
+
+impl AsRawFd for DhcpV6RaListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl DhcpV6RaListener {
+    /// Create ICMPv6 socket bound to the interface. The `socket_timeout` in
+    /// seconds limits how long [DhcpV6RaListener::recv()] blocks.
+    pub fn new(
+        iface_name: &str,
+        socket_timeout: u32,
+    ) -> Result<Self, DhcpError> {
+        let np_iface = get_nispor_iface(iface_name, false)?;
+        let socket =
+            DhcpIcmpV6Socket::new(iface_name, np_iface.index, socket_timeout)?;
+        Ok(Self {
+            socket,
+            src_mac: mac_str_to_u8_array(np_iface.mac_address.as_str()),
+        })
+    }
+
+    /// Send Router Solicitation to all-routers multicast address requesting
+    /// routers to advertise immediately instead of waiting for their
+    /// periodic advertisement.
+    pub fn solicit(&self) -> Result<(), DhcpError> {
+        self.socket
+            .send_to(&ALL_ROUTERS_ADDR, &gen_router_solicit(&self.src_mac))
+    }
+
+    /// Receive ICMPv6 message, return None if it is not valid Router
+    /// Advertisement.
+    pub fn recv(&self) -> Result<Option<DhcpV6RouterAdvert>, DhcpError> {
+        let (data, src_ip, hop_limit) = self.socket.recv_from()?;
+        let ra = parse_router_advert(&data, src_ip, hop_limit);
+        if let Some(ra) = ra.as_ref() {
+            log::debug!(
+                "Got Router Advertisement from {} with M flag {} O flag {}",
+                ra.router,
+                ra.managed,
+                ra.other_config
+            );
+        }
+        Ok(ra)
+    }
+}
+
+// ICMPv6 Router Solicitation, checksum is filled by kernel.
+pub(crate) fn gen_router_solicit(src_mac: &[u8]) -> Vec<u8> {
+    let mut ret = vec![ICMPV6_ROUTER_SOLICITATION, 0, 0, 0, 0, 0, 0, 0];
+    if src_mac.len() == libc::ETH_ALEN as usize {
+        ret.push(ND_OPT_SOURCE_LINK_ADDR);
+        // Length in units of 8 octets
+        ret.push(1);
+        ret.extend_from_slice(src_mac);
+    }
+    ret
+}
+
+// RFC 4861: Router Advertisement must be sent from link-local address with
+// IP hop limit 255, which guarantees it was not forwarded by a router.
+pub(crate) fn parse_router_advert(
+    data: &[u8],
+    src_ip: Ipv6Addr,
+    hop_limit: Option<u8>,
+) -> Option<DhcpV6RouterAdvert> {
+    if data.len() < RA_HEADER_LEN
+        || data[0] != ICMPV6_ROUTER_ADVERTISEMENT
+        || data[1] != 0
+    {
+        return None;
+    }
+    if hop_limit != Some(ND_HOP_LIMIT) {
+        log::debug!(
+            "Ignoring Router Advertisement from {src_ip} with hop limit \
+            {hop_limit:?}"
+        );
+        return None;
+    }
+    if src_ip.segments()[0] & 0xffc0 != 0xfe80 {
+        log::debug!(
+            "Ignoring Router Advertisement from non link-local address \
+            {src_ip}"
+        );
+        return None;
+    }
+    Some(DhcpV6RouterAdvert {
+        router: src_ip,
+        managed: data[5] & RA_FLAG_MANAGED > 0,
+        other_config: data[5] & RA_FLAG_OTHER_CONFIG > 0,
+        router_lifetime: u16::from_be_bytes([data[6], data[7]]),
+    })
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::{Ipv6Addr, SocketAddr};
+use std::os::unix::io::{AsRawFd, RawFd};
+
+#[cfg(feature = "chaos")]
+use crate::DhcpChaosPolicy;
+use crate::{
+    socket::{DhcpSocket, DhcpUdpSocket},
+    DhcpError, DhcpMemorySocket, DhcpV6Config,
+};
+
+#[derive(Debug)]
+pub(crate) enum DhcpV6Socket {
+    Udp(DhcpUdpSocket),
+    Memory(DhcpMemorySocket),
+    #[cfg(feature = "chaos")]
+    Chaos(Box<DhcpV6Socket>, DhcpChaosPolicy),
+}
+
+impl DhcpV6Socket {
+    pub(crate) fn new(config: &DhcpV6Config) -> Result<Self, DhcpError> {
+        let socket = Self::new_inner(config)?;
+        #[cfg(feature = "chaos")]
+        if let Some(policy) = config.chaos_policy.as_ref() {
+            return Ok(Self::Chaos(Box::new(socket), policy.clone()));
+        }
+        Ok(socket)
+    }
+
+    fn new_inner(config: &DhcpV6Config) -> Result<Self, DhcpError> {
+        if let Some(socket) = config.memory_socket.as_ref() {
+            return Ok(Self::Memory(socket.clone()));
+        }
+        let socket = DhcpUdpSocket::new_v6(config)?;
+        #[cfg(feature = "pcap")]
+        let socket = socket.with_pcap(config.pcap_file.as_deref())?;
+        Ok(Self::Udp(socket))
+    }
+
+    pub(crate) fn send_to_v6(
+        &self,
+        dst_ip: &Ipv6Addr,
+        buff: &[u8],
+    ) -> Result<(), DhcpError> {
+        match self {
+            Self::Udp(s) => s.send_to_v6(dst_ip, buff),
+            Self::Memory(s) => s.send(buff),
+            #[cfg(feature = "chaos")]
+            Self::Chaos(s, policy) => {
+                policy.send_with(buff, 0, |p| s.send_to_v6(dst_ip, p))
+            }
+        }
+    }
+
+    pub(crate) fn recv_from(
+        &self,
+    ) -> Result<(Vec<u8>, Option<SocketAddr>), DhcpError> {
+        match self {
+            Self::Udp(s) => s.recv_from(),
+            Self::Memory(s) => s.recv_from(),
+            #[cfg(feature = "chaos")]
+            Self::Chaos(s, _) => s.recv_from(),
+        }
+    }
+}
+
+impl AsRawFd for DhcpV6Socket {
+    fn as_raw_fd(&self) -> RawFd {
+        match self {
+            Self::Udp(s) => s.as_raw_fd(),
+            Self::Memory(s) => s.as_raw_fd(),
+            #[cfg(feature = "chaos")]
+            Self::Chaos(s, _) => s.as_raw_fd(),
+        }
+    }
+}
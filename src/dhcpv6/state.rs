@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use crate::DhcpV6Lease;
+
+/// Lease state transition of DHCPv6 client, yielded by
+/// [crate::DhcpV6StateStream].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum DhcpV6State {
+    /// Lease acquired or extended.
+    Bound(DhcpV6Lease),
+    /// Requesting lease extension from the DHCPv6 server granted the lease.
+    Renewing,
+    /// Requesting lease extension from any DHCPv6 server.
+    Rebinding,
+    /// DHCPv6 server replied with failure status code, client restarts
+    /// solicitation. Contains the lease previously held which should not be
+    /// used anymore.
+    Nacked(Option<DhcpV6Lease>),
+    /// Lease expired without being extended, client restarts solicitation.
+    /// The leased address or prefix should be removed.
+    Expired(DhcpV6Lease),
+}
+
+/// What changed during processing event, returned by
+/// [crate::DhcpV6Client::process_detailed()].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+// Boxing leases would make matching on progress awkward for caller
+#[allow(clippy::large_enum_variant)]
+pub enum DhcpV6Progress {
+    /// Lease state changed from the previous one(None for the first state)
+    /// to the new one.
+    StateChanged(Option<DhcpV6State>, DhcpV6State),
+    /// New lease acquired, the system should be configured with it.
+    LeaseAcquired(DhcpV6Lease),
+    /// Lease currently in use extended, only the lifetimes need to be
+    /// updated. T1 and T2 are counted from now.
+    LeaseRenewed { t1: Duration, t2: Duration },
+    /// Configuration options(e.g. DNS or NTP servers) of lease in use
+    /// changed on renewal, rebinding or refresh of stateless DHCPv6, while
+    /// the address or prefix stays the same. The system should be
+    /// reconfigured with the options of this lease.
+    OptionsChanged(DhcpV6Lease),
+}
@@ -1,10 +1,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::ops::RangeInclusive;
 use std::time::{Duration, Instant};
 
-use rand::Rng;
-
-use crate::{DhcpError, ErrorKind};
+use crate::{random::gen_range, DhcpError, DhcpLifetime, ErrorKind};
 
 // RFC 8415 section 7.6 Transmission and Retransmission Parameters
 const SOL_TIMEOUT: Duration = Duration::from_secs(1);
@@ -16,6 +15,17 @@ const REN_TIMEOUT: Duration = Duration::from_secs(10);
 const REN_MAX_RT: Duration = Duration::from_secs(600);
 const REB_TIMEOUT: Duration = Duration::from_secs(10);
 const REB_MAX_RT: Duration = Duration::from_secs(600);
+const REL_TIMEOUT: Duration = Duration::from_secs(1);
+const REL_MAX_RC: u32 = 4;
+const DEC_TIMEOUT: Duration = Duration::from_secs(1);
+const DEC_MAX_RC: u32 = 4;
+const INF_TIMEOUT: Duration = Duration::from_secs(1);
+const INF_MAX_RT: Duration = Duration::from_secs(3600);
+// RFC 8415 section 7.6 Configuration Variables, in seconds
+const IRT_DEFAULT: u32 = 86400;
+const IRT_MINIMUM: u32 = 600;
+// RAND is between -0.1 and 0.1, stored in permille.
+const RAND_MAX: i128 = 100;
 
 // RFC 8415 section 15.  Reliability of Client-Initiated Message Exchanges
 //  RT      Retransmission timeout
@@ -24,6 +34,7 @@ const REB_MAX_RT: Duration = Duration::from_secs(600);
 //  MRT     Maximum retransmission time
 //  MRD     Maximum retransmission duration
 //  RAND    Randomization factor
+#[allow(clippy::too_many_arguments)]
 fn gen_retransmit_time(
     trans_begin_time: Instant,
     retransmit_count: u32,
@@ -32,6 +43,7 @@ fn gen_retransmit_time(
     mrt: Duration,
     mrc: u32,
     mrd: Duration,
+    positive_first_rand: bool,
 ) -> Option<Duration> {
     if mrc != 0 && mrc < retransmit_count {
         return None;
@@ -40,31 +52,50 @@ fn gen_retransmit_time(
         return None;
     }
 
+    // RFC 8415 section 15:
+    //      RT for the first message transmission is based on IRT:
+    //          RT = IRT + RAND*IRT
+    //      RT for each subsequent message transmission is based on the
+    //      previous value of RT:
+    //          RT = 2*RTprev + RAND*RTprev
+    //      If MRT is specified and RT > MRT:
+    //          RT = MRT + RAND*MRT
+    // RFC 8415 section 18.2.1:
+    //      the first RT MUST be selected to be strictly greater than IRT by
+    //      choosing RAND to be strictly greater than 0.
     let rt = if rt == Duration::new(0, 0) {
-        Duration::from_millis(
-            (irt.as_millis() * rand::thread_rng().gen_range(900..1100) / 1000)
-                .try_into()
-                .unwrap_or(u64::MAX),
-        )
+        if positive_first_rand {
+            apply_rand(irt, 0, 1..=RAND_MAX)
+        } else {
+            apply_rand(irt, 0, -RAND_MAX..=RAND_MAX)
+        }
     } else {
-        Duration::from_millis(
-            (rt.as_millis() * rand::thread_rng().gen_range(1900..2100) / 1000)
-                .try_into()
-                .unwrap_or(u64::MAX),
-        )
+        apply_rand(rt, 1, -RAND_MAX..=RAND_MAX)
     };
 
     if mrt != Duration::new(0, 0) && rt > mrt {
-        Some(Duration::from_millis(
-            (mrt.as_millis() * rand::thread_rng().gen_range(900..1100) / 1000)
-                .try_into()
-                .unwrap_or(u64::MAX),
-        ))
+        Some(apply_rand(mrt, 0, -RAND_MAX..=RAND_MAX))
     } else {
         Some(rt)
     }
 }
 
+// Return `base * 2^factor + RAND * base` where RAND is uniformly distributed
+// random number in specified range of permille.
+fn apply_rand(
+    base: Duration,
+    factor: u32,
+    rand_range: RangeInclusive<i128>,
+) -> Duration {
+    let base_ms = i128::try_from(base.as_millis()).unwrap_or(i128::MAX);
+    let rand_ms = base_ms * gen_range(rand_range) / 1000;
+    Duration::from_millis(
+        ((base_ms << factor) + rand_ms)
+            .try_into()
+            .unwrap_or_default(),
+    )
+}
+
 pub(crate) fn gen_solicit_wait_time(
     trans_begin_time: Instant,
     retransmit_count: u32,
@@ -78,6 +109,7 @@ pub(crate) fn gen_solicit_wait_time(
         SOL_MAX_RT,
         0,
         Duration::new(0, 0),
+        true,
     ) {
         Some(rt) => Ok(rt),
         None => Err(DhcpError::new(
@@ -100,6 +132,7 @@ pub(crate) fn gen_request_wait_time(
         REQ_MAX_RT,
         REQ_MAX_RC,
         Duration::new(0, 0),
+        false,
     ) {
         Some(rt) => Ok(rt),
         None => Err(DhcpError::new(
@@ -123,6 +156,7 @@ pub(crate) fn gen_renew_wait_time(
         REN_MAX_RT,
         0,
         t2,
+        false,
     ) {
         Some(rt) => Ok(rt),
         None => Err(DhcpError::new(
@@ -146,6 +180,7 @@ pub(crate) fn gen_rebind_wait_time(
         REB_MAX_RT,
         0,
         valid_life,
+        false,
     ) {
         Some(rt) => Ok(rt),
         None => Err(DhcpError::new(
@@ -154,3 +189,112 @@ pub(crate) fn gen_rebind_wait_time(
         )),
     }
 }
+
+// RFC 8415 section 18.2.7: Creation and Transmission of Release Messages
+//      The client transmits the message according to Section 15, using the
+//      following parameters:
+//          IRT   REL_TIMEOUT
+//          MRT   0
+//          MRC   REL_MAX_RC
+//          MRD   0
+// The `max_duration` is cap on the whole exchange defined by user, 0 means
+// unlimited.
+pub(crate) fn gen_release_wait_time(
+    trans_begin_time: Instant,
+    retransmit_count: u32,
+    previous_wait_time: Duration,
+    max_duration: Duration,
+) -> Result<Duration, DhcpError> {
+    match gen_retransmit_time(
+        trans_begin_time,
+        retransmit_count,
+        previous_wait_time,
+        REL_TIMEOUT,
+        Duration::new(0, 0),
+        REL_MAX_RC,
+        max_duration,
+        false,
+    ) {
+        Some(rt) => Ok(rt),
+        None => Err(DhcpError::new(
+            ErrorKind::Timeout,
+            "Timeout on waiting DHCPv6 reply on RELEASE message".to_string(),
+        )),
+    }
+}
+
+// RFC 8415 section 18.2.8: Creation and Transmission of Decline Messages
+//      The client transmits the message according to Section 15, using the
+//      following parameters:
+//          IRT   DEC_TIMEOUT
+//          MRT   0
+//          MRC   DEC_MAX_RC
+//          MRD   0
+// The `max_duration` is cap on the whole exchange defined by user, 0 means
+// unlimited.
+pub(crate) fn gen_decline_wait_time(
+    trans_begin_time: Instant,
+    retransmit_count: u32,
+    previous_wait_time: Duration,
+    max_duration: Duration,
+) -> Result<Duration, DhcpError> {
+    match gen_retransmit_time(
+        trans_begin_time,
+        retransmit_count,
+        previous_wait_time,
+        DEC_TIMEOUT,
+        Duration::new(0, 0),
+        DEC_MAX_RC,
+        max_duration,
+        false,
+    ) {
+        Some(rt) => Ok(rt),
+        None => Err(DhcpError::new(
+            ErrorKind::Timeout,
+            "Timeout on waiting DHCPv6 reply on DECLINE message".to_string(),
+        )),
+    }
+}
+
+pub(crate) fn gen_information_request_wait_time(
+    trans_begin_time: Instant,
+    retransmit_count: u32,
+    previous_wait_time: Duration,
+) -> Result<Duration, DhcpError> {
+    match gen_retransmit_time(
+        trans_begin_time,
+        retransmit_count,
+        previous_wait_time,
+        INF_TIMEOUT,
+        INF_MAX_RT,
+        0,
+        Duration::new(0, 0),
+        false,
+    ) {
+        Some(rt) => Ok(rt),
+        None => Err(DhcpError::new(
+            ErrorKind::Timeout,
+            "Timeout on waiting DHCPv6 reply on INFORMATION-REQUEST message"
+                .to_string(),
+        )),
+    }
+}
+
+// RFC 8415 section 18.2.6: Creation and Transmission of Information-request
+// Messages
+//      If the Reply to an Information-request message does not contain this
+//      option, the client MUST behave as if the option with value
+//      IRT_DEFAULT was provided.
+//      A client MUST use the refresh time IRT_MINIMUM if it receives the
+//      option with a value less than IRT_MINIMUM.
+// Return None for infinite refresh time, the options never need refreshing.
+pub(crate) fn gen_information_refresh_time(
+    refresh_time: Option<u32>,
+) -> Option<Duration> {
+    match DhcpLifetime::from(refresh_time.unwrap_or(IRT_DEFAULT)) {
+        DhcpLifetime::Infinite => None,
+        DhcpLifetime::Seconds(t) => {
+            Some(Duration::from_secs(t.max(IRT_MINIMUM).into()))
+        }
+    }
+}
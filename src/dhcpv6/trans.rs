@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::{Duration, Instant};
+
+use super::time::{
+    gen_decline_wait_time, gen_information_request_wait_time,
+    gen_rebind_wait_time, gen_release_wait_time, gen_renew_wait_time,
+    gen_request_wait_time, gen_solicit_wait_time,
+};
+use crate::{
+    random::gen_u32,
+    trace::{trace_msg_sent, DhcpTraceSpan},
+    DhcpError, DhcpV6Config, DhcpV6Message, DhcpV6MessageType, ErrorKind,
+};
+
+// RFC 8415: The client MUST generate a new transaction ID for each new
+// message exchange.
+pub(crate) fn gen_xid() -> [u8; 3] {
+    // In RFC 8415, the `transaction-id` is a 3-octet field
+    let mut xid: [u8; 3] = [0; 3];
+    xid.copy_from_slice(&gen_u32().to_le_bytes()[..3]);
+    xid
+}
+
+pub(crate) fn xid_to_string(xid: [u8; 3]) -> String {
+    format!("{:02x}{:02x}{:02x}", xid[0], xid[1], xid[2])
+}
+
+// Client initiated message exchange: the message to be (re)transmitted and
+// its retransmission state.
+#[derive(Debug, Clone)]
+pub(crate) struct DhcpV6Transaction {
+    pub(crate) msg: DhcpV6Message,
+    // Maximum retransmission duration(MRD) of RENEW and REBIND, or cap of
+    // the whole RELEASE or DECLINE exchange
+    max_duration: Duration,
+    // Retransmission timeout(RT) of current window
+    pub(crate) retrans_timeout: Duration,
+    // Transmissions made including the initial one
+    pub(crate) retrans_count: u32,
+    // When current RT window ends
+    retrans_deadline: Option<Instant>,
+    // Message failed to send in current RT window, resending it
+    pub(crate) resending: bool,
+    // When the initial message was transmitted
+    begin_time: Option<Instant>,
+    pub(crate) span: DhcpTraceSpan,
+}
+
+impl DhcpV6Transaction {
+    // Start new exchange with new transaction ID. The `retrans_timeout` holds
+    // the delay before the initial transmission.
+    pub(crate) fn new(
+        config: &DhcpV6Config,
+        msg_type: DhcpV6MessageType,
+        max_duration: Duration,
+    ) -> Result<Self, DhcpError> {
+        let xid = gen_xid();
+        let mut ret = Self {
+            msg: DhcpV6Message::new(config, msg_type, xid),
+            max_duration,
+            retrans_timeout: Duration::ZERO,
+            retrans_count: 0,
+            retrans_deadline: None,
+            resending: false,
+            begin_time: None,
+            span: DhcpTraceSpan::new(
+                "dhcpv6",
+                msg_type,
+                &config.iface_name,
+                xid_to_string(xid),
+            ),
+        };
+        ret.retrans_timeout = ret.gen_wait_time(Instant::now())?;
+        Ok(ret)
+    }
+
+    pub(crate) fn xid(&self) -> [u8; 3] {
+        self.msg.xid
+    }
+
+    pub(crate) fn msg_type(&self) -> DhcpV6MessageType {
+        self.msg.msg_type
+    }
+
+    // Begin new RT window for upcoming transmission
+    pub(crate) fn start_retransmit(&mut self) -> Result<(), DhcpError> {
+        self.retrans_count += 1;
+        let begin_time = *self.begin_time.get_or_insert_with(Instant::now);
+        self.retrans_timeout = self.gen_wait_time(begin_time)?;
+        self.retrans_deadline = Some(Instant::now() + self.retrans_timeout);
+        Ok(())
+    }
+
+    // Generate UDP payload for current transmission, only retransmission
+    // includes the time passed since the initial one.
+    pub(crate) fn gen_dhcp_pkg(&mut self) -> Result<Vec<u8>, DhcpError> {
+        if let Some(begin_time) = self.begin_time {
+            if self.retrans_count > 1 {
+                self.msg.add_elapsed_time(begin_time);
+            }
+        }
+        let ret = self.msg.to_dhcp_pkg()?;
+        trace_msg_sent(self.msg_type(), xid_to_string(self.xid()));
+        Ok(ret)
+    }
+
+    // Time passed since the initial transmission
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.begin_time.map(|t| t.elapsed()).unwrap_or_default()
+    }
+
+    // Time left in current RT window
+    pub(crate) fn remain(&self) -> Duration {
+        self.retrans_deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+            .unwrap_or_default()
+    }
+
+    fn gen_wait_time(
+        &self,
+        begin_time: Instant,
+    ) -> Result<Duration, DhcpError> {
+        let count = self.retrans_count;
+        let rt = self.retrans_timeout;
+        match self.msg_type() {
+            DhcpV6MessageType::SOLICIT => {
+                gen_solicit_wait_time(begin_time, count, rt)
+            }
+            DhcpV6MessageType::REQUEST => {
+                gen_request_wait_time(begin_time, count, rt)
+            }
+            DhcpV6MessageType::RENEW => {
+                gen_renew_wait_time(begin_time, count, rt, self.max_duration)
+            }
+            DhcpV6MessageType::REBIND => {
+                gen_rebind_wait_time(begin_time, count, rt, self.max_duration)
+            }
+            DhcpV6MessageType::RELEASE => {
+                gen_release_wait_time(begin_time, count, rt, self.max_duration)
+            }
+            DhcpV6MessageType::DECLINE => {
+                gen_decline_wait_time(begin_time, count, rt, self.max_duration)
+            }
+            DhcpV6MessageType::INFORMATION_REQUEST => {
+                gen_information_request_wait_time(begin_time, count, rt)
+            }
+            t => {
+                let e = DhcpError::new(
+                    ErrorKind::Bug,
+                    format!("No retransmission defined for {t} message"),
+                );
+                log::error!("{}", e);
+                Err(e)
+            }
+        }
+    }
+}
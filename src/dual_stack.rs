@@ -0,0 +1,384 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::pin::Pin;
+
+use futures::{
+    task::{Context, Poll},
+    Stream,
+};
+
+use crate::{
+    nispor::get_nispor_iface, DhcpError, DhcpV4Client, DhcpV4ClientAsync,
+    DhcpV4Config, DhcpV4Lease, DhcpV4State, DhcpV4StateStream, DhcpV6Client,
+    DhcpV6ClientAsync, DhcpV6Config, DhcpV6Lease, DhcpV6State,
+    DhcpV6StateStream, ErrorKind,
+};
+
+#[derive(Debug, Clone, Copy)]
+enum IpFamily {
+    V4,
+    V6,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum DhcpLeaseEvent {
+    V4(DhcpV4Lease),
+    V6(DhcpV6Lease),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum DhcpStateEvent {
+    V4(DhcpV4State),
+    V6(DhcpV6State),
+}
+
+/// Async client running DHCPv4 and DHCPv6 on the same interface
+/// concurrently, yielding leases of both as [DhcpLeaseEvent].
+#[derive(Debug)]
+pub struct DhcpDualStackClient {
+    v4_client: DhcpV4ClientAsync,
+    v6_client: DhcpV6ClientAsync,
+    v4_lease: Option<DhcpV4Lease>,
+    v6_lease: Option<DhcpV6Lease>,
+    // Alternate the client polled first, so busy one cannot starve the other
+    v6_first: bool,
+    // Stream of that family has ended, the other one is still polled
+    v4_done: bool,
+    v6_done: bool,
+}
+
+impl DhcpDualStackClient {
+    /// Create DHCPv4 and DHCPv6 clients for the same interface, the
+    /// interface is only queried once for both.
+    pub fn init(
+        mut v4_config: DhcpV4Config,
+        mut v6_config: DhcpV6Config,
+        v4_lease: Option<DhcpV4Lease>,
+        v6_lease: Option<DhcpV6Lease>,
+    ) -> Result<Self, DhcpError> {
+        if v4_config.iface_name != v6_config.iface_name {
+            let e = DhcpError::new(
+                ErrorKind::InvalidArgument,
+                format!(
+                    "DHCPv4 config is using interface {} while DHCPv6 config \
+                    is using interface {}",
+                    v4_config.iface_name, v6_config.iface_name
+                ),
+            );
+            log::error!("{}", e);
+            return Err(e);
+        }
+        // In-memory sockets need no interface query
+        if v4_config.memory_socket.is_some()
+            && v6_config.memory_socket.is_some()
+        {
+            v4_config.init()?;
+            v6_config.init()?;
+        } else {
+            let np_iface =
+                get_nispor_iface(v6_config.iface_name.as_str(), true)?;
+            v4_config.init_with_iface(&np_iface)?;
+            v6_config.init_with_iface(&np_iface)?;
+        }
+        Ok(Self {
+            v4_client: DhcpV4ClientAsync::from_client(
+                DhcpV4Client::init_with_resolved_config(
+                    v4_config,
+                    v4_lease.clone(),
+                )?,
+            )?,
+            v6_client: DhcpV6ClientAsync::from_client(
+                DhcpV6Client::init_with_resolved_config(
+                    v6_config,
+                    v6_lease.clone(),
+                )?,
+            )?,
+            v4_lease,
+            v6_lease,
+            v6_first: false,
+            v4_done: false,
+            v6_done: false,
+        })
+    }
+
+    /// Latest DHCPv4 lease acquired.
+    pub fn v4_lease(&self) -> Option<&DhcpV4Lease> {
+        self.v4_lease.as_ref()
+    }
+
+    /// Latest DHCPv6 lease acquired.
+    pub fn v6_lease(&self) -> Option<&DhcpV6Lease> {
+        self.v6_lease.as_ref()
+    }
+
+    /// Release both DHCPv4 and DHCPv6 leases. Failure of releasing one does
+    /// not prevent the other from being released, the first error is
+    /// returned.
+    pub fn release(&mut self) -> Result<(), DhcpError> {
+        let v4_result = match self.v4_lease.take() {
+            Some(lease) => self.v4_client.release(&lease),
+            None => Ok(()),
+        };
+        let v6_result = match self.v6_lease.take() {
+            Some(lease) => self.v6_client.release(&lease),
+            None => Ok(()),
+        };
+        v4_result.and(v6_result)
+    }
+
+    /// Stop and join the background threads of both clients, please refer
+    /// to [DhcpV4ClientAsync::close()].
+    pub fn close(&mut self) {
+        self.v4_client.close();
+        self.v6_client.close();
+    }
+
+    /// Stop the DHCPv4 client only, the stream keeps yielding DHCPv6 leases
+    /// till [DhcpDualStackClient::close_v6()] or
+    /// [DhcpDualStackClient::close()] is invoked.
+    pub fn close_v4(&mut self) {
+        self.v4_client.close();
+    }
+
+    /// Stop the DHCPv6 client only, the stream keeps yielding DHCPv4 leases
+    /// till [DhcpDualStackClient::close_v4()] or
+    /// [DhcpDualStackClient::close()] is invoked.
+    pub fn close_v6(&mut self) {
+        self.v6_client.close();
+    }
+
+    /// Convert into stream of DHCPv4 and DHCPv6 lease state transitions.
+    /// Please refer to [DhcpV4ClientAsync::into_state_stream()] and
+    /// [DhcpV6ClientAsync::into_state_stream()].
+    pub fn into_state_stream(self) -> DhcpDualStackStateStream {
+        DhcpDualStackStateStream {
+            v4_stream: self.v4_client.into_state_stream(),
+            v6_stream: self.v6_client.into_state_stream(),
+            v4_lease: self.v4_lease,
+            v6_lease: self.v6_lease,
+            v6_first: self.v6_first,
+            v4_done: self.v4_done,
+            v6_done: self.v6_done,
+        }
+    }
+
+    fn poll_v4(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<DhcpLeaseEvent, DhcpError>>> {
+        match Pin::new(&mut self.v4_client).poll_next(cx) {
+            Poll::Ready(Some(Ok(lease))) => {
+                self.v4_lease = Some(lease.clone());
+                Poll::Ready(Some(Ok(DhcpLeaseEvent::V4(lease))))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                log::error!("DHCPv4 client error: {e}");
+                Poll::Ready(Some(Err(e)))
+            }
+            Poll::Ready(None) => {
+                self.v4_done = true;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn poll_v6(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<DhcpLeaseEvent, DhcpError>>> {
+        match Pin::new(&mut self.v6_client).poll_next(cx) {
+            Poll::Ready(Some(Ok(lease))) => {
+                self.v6_lease = Some(lease.clone());
+                Poll::Ready(Some(Ok(DhcpLeaseEvent::V6(lease))))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                log::error!("DHCPv6 client error: {e}");
+                Poll::Ready(Some(Err(e)))
+            }
+            Poll::Ready(None) => {
+                self.v6_done = true;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Stream for DhcpDualStackClient {
+    type Item = Result<DhcpLeaseEvent, DhcpError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.v6_first = !self.v6_first;
+        let order = if self.v6_first {
+            [IpFamily::V6, IpFamily::V4]
+        } else {
+            [IpFamily::V4, IpFamily::V6]
+        };
+        for family in order {
+            let ret = match family {
+                IpFamily::V4 if !self.v4_done => self.poll_v4(cx),
+                IpFamily::V6 if !self.v6_done => self.poll_v6(cx),
+                _ => continue,
+            };
+            // Ended stream of one family should not end the other
+            if let Poll::Ready(Some(item)) = ret {
+                return Poll::Ready(Some(item));
+            }
+        }
+        if self.v4_done && self.v6_done {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Stream of [DhcpStateEvent] created by
+/// [DhcpDualStackClient::into_state_stream()].
+#[derive(Debug)]
+pub struct DhcpDualStackStateStream {
+    v4_stream: DhcpV4StateStream,
+    v6_stream: DhcpV6StateStream,
+    v4_lease: Option<DhcpV4Lease>,
+    v6_lease: Option<DhcpV6Lease>,
+    v6_first: bool,
+    v4_done: bool,
+    v6_done: bool,
+}
+
+impl DhcpDualStackStateStream {
+    /// Latest DHCPv4 lease bound, None if lost.
+    pub fn v4_lease(&self) -> Option<&DhcpV4Lease> {
+        self.v4_lease.as_ref()
+    }
+
+    /// Latest DHCPv6 lease bound, None if lost.
+    pub fn v6_lease(&self) -> Option<&DhcpV6Lease> {
+        self.v6_lease.as_ref()
+    }
+
+    /// Please refer to [DhcpDualStackClient::release()].
+    pub fn release(&mut self) -> Result<(), DhcpError> {
+        let v4_result = match self.v4_lease.take() {
+            Some(lease) => self.v4_stream.release(&lease),
+            None => Ok(()),
+        };
+        let v6_result = match self.v6_lease.take() {
+            Some(lease) => self.v6_stream.release(&lease),
+            None => Ok(()),
+        };
+        v4_result.and(v6_result)
+    }
+
+    /// Please refer to [DhcpDualStackClient::close()].
+    pub fn close(&mut self) {
+        self.v4_stream.close();
+        self.v6_stream.close();
+    }
+
+    /// Please refer to [DhcpDualStackClient::close_v4()].
+    pub fn close_v4(&mut self) {
+        self.v4_stream.close();
+    }
+
+    /// Please refer to [DhcpDualStackClient::close_v6()].
+    pub fn close_v6(&mut self) {
+        self.v6_stream.close();
+    }
+
+    fn poll_v4(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<DhcpStateEvent, DhcpError>>> {
+        match Pin::new(&mut self.v4_stream).poll_next(cx) {
+            Poll::Ready(Some(Ok(state))) => {
+                match &state {
+                    DhcpV4State::Bound(lease) => {
+                        self.v4_lease = Some(lease.clone());
+                    }
+                    DhcpV4State::Nacked(_) | DhcpV4State::Expired(_) => {
+                        self.v4_lease = None;
+                    }
+                    _ => (),
+                }
+                Poll::Ready(Some(Ok(DhcpStateEvent::V4(state))))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                log::error!("DHCPv4 client error: {e}");
+                Poll::Ready(Some(Err(e)))
+            }
+            Poll::Ready(None) => {
+                self.v4_done = true;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn poll_v6(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<DhcpStateEvent, DhcpError>>> {
+        match Pin::new(&mut self.v6_stream).poll_next(cx) {
+            Poll::Ready(Some(Ok(state))) => {
+                match &state {
+                    DhcpV6State::Bound(lease) => {
+                        self.v6_lease = Some(lease.clone());
+                    }
+                    DhcpV6State::Nacked(_) | DhcpV6State::Expired(_) => {
+                        self.v6_lease = None;
+                    }
+                    _ => (),
+                }
+                Poll::Ready(Some(Ok(DhcpStateEvent::V6(state))))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                log::error!("DHCPv6 client error: {e}");
+                Poll::Ready(Some(Err(e)))
+            }
+            Poll::Ready(None) => {
+                self.v6_done = true;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Stream for DhcpDualStackStateStream {
+    type Item = Result<DhcpStateEvent, DhcpError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.v6_first = !self.v6_first;
+        let order = if self.v6_first {
+            [IpFamily::V6, IpFamily::V4]
+        } else {
+            [IpFamily::V4, IpFamily::V6]
+        };
+        for family in order {
+            let ret = match family {
+                IpFamily::V4 if !self.v4_done => self.poll_v4(cx),
+                IpFamily::V6 if !self.v6_done => self.poll_v6(cx),
+                _ => continue,
+            };
+            // Ended stream of one family should not end the other
+            if let Poll::Ready(Some(item)) = ret {
+                return Poll::Ready(Some(item));
+            }
+        }
+        if self.v4_done && self.v6_done {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -1,24 +1,69 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::sync::Arc;
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
 pub enum ErrorKind {
+    /// Overall timeout of lease acquisition, or socket timeout
     Timeout,
     InvalidArgument,
     InvalidDhcpServerReply,
     NoLease,
     Bug,
     LeaseExpired,
+    /// Missing capability or permission, please refer to
+    /// [crate::check_privileges()].
+    PermissionDenied,
+    /// Address or port is used by other process
+    AddressInUse,
+    /// Network interface is down or removed, could be transient during
+    /// link flap.
+    InterfaceDown,
+    /// Per-state timeout of lease acquisition
+    StateTimeout,
+    /// Lease renewal and rebinding failed within renew timeout
+    RenewTimeout,
+    /// DHCP server indicated the network is IPv6-only preferred(RFC 8925),
+    /// IPv4 should be disabled for the period in
+    /// [crate::DhcpV4State::Ipv6OnlyPreferred].
+    Ipv6OnlyPreferred,
+    /// Operation requires `AF_PACKET` raw socket(e.g. broadcasting for new
+    /// lease) which is disallowed by
+    /// [crate::DhcpV4Config::set_udp_only()].
+    RawSocketRequired,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// Error of this crate.
+///
+/// Besides the [ErrorKind] and human readable message, the underlying
+/// error(e.g. [std::io::Error] of failed system call) is preserved and
+/// accessible via [std::error::Error::source()], [DhcpError::io_error_kind()]
+/// and [DhcpError::raw_os_error()], so callers could distinguish `EPERM`
+/// from `ENETDOWN` without matching on message.
+#[derive(Debug, Clone)]
 pub struct DhcpError {
     kind: ErrorKind,
     msg: String,
+    source: Option<Arc<dyn std::error::Error + Send + Sync + 'static>>,
 }
 
 impl DhcpError {
     pub fn new(kind: ErrorKind, msg: String) -> Self {
-        Self { kind, msg }
+        Self {
+            kind,
+            msg,
+            source: None,
+        }
+    }
+
+    /// Attach the underlying error causing this error.
+    pub fn with_source<E>(mut self, source: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        self.source = Some(Arc::new(source));
+        self
     }
 
     pub fn kind(&self) -> ErrorKind {
@@ -28,9 +73,47 @@ impl DhcpError {
     pub fn msg(&self) -> &str {
         self.msg.as_str()
     }
+
+    /// The [std::io::ErrorKind] of first [std::io::Error] found in the
+    /// source chain.
+    pub fn io_error_kind(&self) -> Option<std::io::ErrorKind> {
+        self.io_error().map(|e| e.kind())
+    }
+
+    /// The OS error number(e.g. `libc::ENETDOWN`) of first
+    /// [std::io::Error] found in the source chain.
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.io_error().and_then(|e| e.raw_os_error())
+    }
+
+    fn io_error(&self) -> Option<&std::io::Error> {
+        let mut cur = std::error::Error::source(self);
+        while let Some(e) = cur {
+            if let Some(io_err) = e.downcast_ref::<std::io::Error>() {
+                return Some(io_err);
+            }
+            cur = e.source();
+        }
+        None
+    }
+}
+
+// The source is not compared as most errors do not implement `PartialEq`.
+impl PartialEq for DhcpError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.msg == other.msg
+    }
 }
 
-impl std::error::Error for DhcpError {}
+impl Eq for DhcpError {}
+
+impl std::error::Error for DhcpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
 
 impl std::fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -46,30 +129,38 @@ impl std::fmt::Display for DhcpError {
 
 impl From<std::io::Error> for DhcpError {
     fn from(e: std::io::Error) -> Self {
-        Self::new(ErrorKind::Bug, format!("IO error: {e}"))
+        let kind = if e.kind() == std::io::ErrorKind::PermissionDenied {
+            ErrorKind::PermissionDenied
+        } else {
+            ErrorKind::Bug
+        };
+        Self::new(kind, format!("IO error: {e}")).with_source(e)
     }
 }
 
 impl From<std::ffi::NulError> for DhcpError {
     fn from(e: std::ffi::NulError) -> Self {
-        Self::new(ErrorKind::Bug, format!("CString error: {e}"))
+        Self::new(ErrorKind::Bug, format!("CString error: {e}")).with_source(e)
     }
 }
 
 impl From<dhcproto::v4::EncodeError> for DhcpError {
     fn from(e: dhcproto::v4::EncodeError) -> Self {
         Self::new(ErrorKind::Bug, format!("DHCP protocol error: {e}"))
+            .with_source(e)
     }
 }
 
 impl From<etherparse::WriteError> for DhcpError {
     fn from(e: etherparse::WriteError) -> Self {
         Self::new(ErrorKind::Bug, format!("etherparse protocol error: {e}"))
+            .with_source(e)
     }
 }
 
 impl From<std::net::AddrParseError> for DhcpError {
     fn from(e: std::net::AddrParseError) -> Self {
         Self::new(ErrorKind::Bug, format!("IPv4 address parse error: {e}"))
+            .with_source(e)
     }
 }
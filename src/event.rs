@@ -35,7 +35,7 @@ impl AsRawFd for DhcpEpoll {
 impl DhcpEpoll {
     pub(crate) fn new() -> Result<Self, DhcpError> {
         Ok(Self {
-            fd: Epoll::new(EpollCreateFlags::empty()).map_err(|e| {
+            fd: Epoll::new(EpollCreateFlags::EPOLL_CLOEXEC).map_err(|e| {
                 let e = DhcpError::new(
                     ErrorKind::Bug,
                     format!("Failed to create Epoll: {e}"),
@@ -199,6 +199,16 @@ impl<T: DhcpEvent> DhcpEventPool<T> {
         Ok(())
     }
 
+    /// Pending timers with time left before expiring.
+    pub(crate) fn timers(&self) -> Result<Vec<(T, Duration)>, DhcpError> {
+        let mut ret = Vec::new();
+        for (event, timer_fd) in self.timer_fds.iter() {
+            ret.push((*event, timer_fd.remaining()?));
+        }
+        ret.sort_by_key(|(_, t)| *t);
+        Ok(ret)
+    }
+
     pub(crate) fn poll(&self, wait_time: u32) -> Result<Vec<T>, DhcpError> {
         match isize::try_from(wait_time) {
             Ok(i) => self.epoll.poll(i),
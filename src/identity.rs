@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use crate::{DhcpError, Dhcpv6Duid};
+
+/// Source of client identity, allowing integrator to store or derive
+/// DHCPv4 client identifier, DHCPv6 DUID and IAID from its own backend
+/// (e.g. state file, TPM or machine-id) instead of the default generation
+/// of this crate. Set via [crate::DhcpV4Config::set_identity_provider()] or
+/// [crate::DhcpV6Config::set_identity_provider()].
+///
+/// Each function is invoked once when client initialized with the
+/// interface name and resolved MAC address(empty string if interface has
+/// none). Returning `Ok(None)` falls back to default behavior. Identity
+/// explicitly defined in config always takes precedence and RFC 7844
+/// anonymity profile ignores the provider.
+pub trait DhcpIdentityProvider: std::fmt::Debug + Send + Sync {
+    /// DHCPv4 Client Identifier(61) option data including the type byte.
+    fn client_id(
+        &self,
+        _iface_name: &str,
+        _mac_address: &str,
+    ) -> Result<Option<Vec<u8>>, DhcpError> {
+        Ok(None)
+    }
+
+    /// DHCPv6 DUID.
+    fn duid(
+        &self,
+        _iface_name: &str,
+        _mac_address: &str,
+    ) -> Result<Option<Dhcpv6Duid>, DhcpError> {
+        Ok(None)
+    }
+
+    /// DHCPv6 Identity Association Identifier(IAID).
+    fn iaid(
+        &self,
+        _iface_name: &str,
+        _mac_address: &str,
+    ) -> Result<Option<u32>, DhcpError> {
+        Ok(None)
+    }
+}
+
+// Wrapper of shared identity provider, allowing config to derive
+// `PartialEq`.
+#[derive(Debug, Clone)]
+pub(crate) struct DhcpIdentity(pub(crate) Arc<dyn DhcpIdentityProvider>);
+
+impl PartialEq for DhcpIdentity {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for DhcpIdentity {}
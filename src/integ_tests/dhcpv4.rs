@@ -38,6 +38,24 @@ fn test_dhcpv4_manual_client_id() {
     })
 }
 
+// Client should retransmit till lease acquired regardless of packet loss
+#[cfg(feature = "chaos")]
+#[test]
+fn test_dhcpv4_lossy_link() {
+    with_dhcp_env(|| {
+        let mut policy = crate::DhcpChaosPolicy::new(0);
+        policy.set_drop(50).set_duplicate(20).set_reorder(20);
+        let mut config = DhcpV4Config::new(TEST_NIC_CLI);
+        config
+            .set_client_id(0, FOO1_CLIENT_ID.as_bytes())
+            .set_chaos_policy(policy);
+        let mut cli = DhcpV4Client::init(config, None).unwrap();
+
+        let lease = get_lease(&mut cli);
+        assert_eq!(lease.map(|l| l.yiaddr), Some(FOO1_STATIC_IP));
+    })
+}
+
 fn get_lease(cli: &mut DhcpV4Client) -> Option<DhcpV4Lease> {
     while let Ok(events) = cli.poll(POLL_WAIT_TIME) {
         for event in events {
@@ -24,6 +24,25 @@ fn test_dhcpv6_use_default_client_id() {
     })
 }
 
+// Client should retransmit till lease acquired regardless of packet loss
+#[cfg(feature = "chaos")]
+#[test]
+fn test_dhcpv6_lossy_link() {
+    with_dhcp_env(|| {
+        let mut policy = crate::DhcpChaosPolicy::new(0);
+        policy.set_drop(50).set_duplicate(20).set_reorder(20);
+        let mut config = DhcpV6Config::new(
+            TEST_NIC_CLI,
+            DhcpV6IaType::NonTemporaryAddresses,
+        );
+        config.set_timeout(60).set_chaos_policy(policy);
+        let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+        let lease = get_lease(&mut cli);
+        assert_eq!(lease.map(|l| l.addr), Some(FOO1_STATIC_IPV6));
+    })
+}
+
 fn get_lease(cli: &mut DhcpV6Client) -> Option<DhcpV6Lease> {
     while let Ok(events) = cli.poll(POLL_WAIT_TIME) {
         for event in events {
@@ -1,28 +1,101 @@
 // SPDX-License-Identifier: Apache-2.0
 
+mod approver;
+#[cfg(feature = "client")]
 mod bpf;
+mod capabilities;
+#[cfg(feature = "client")]
+mod channel;
+#[cfg(feature = "chaos")]
+mod chaos;
+#[cfg(feature = "client")]
 mod client_async;
+mod codec;
+mod consts;
 mod dhcpv4;
 mod dhcpv6;
+#[cfg(feature = "client")]
+mod dual_stack;
 mod error;
+#[cfg(feature = "client")]
 mod event;
+mod identity;
+mod lifetime;
+#[cfg(feature = "client")]
+mod log_limit;
 mod mac;
+#[cfg(feature = "client")]
+mod memory;
+mod mud;
+mod name;
+#[cfg(feature = "client")]
 mod nispor;
+#[cfg(feature = "pcap")]
+mod pcap;
+#[cfg(feature = "client")]
+mod privilege;
+#[cfg(feature = "client")]
 mod proiscuous;
+mod random;
+mod renew;
+mod restart;
+#[cfg(feature = "client")]
 mod socket;
+#[cfg(feature = "client")]
 mod time;
+mod trace;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "client"))]
 mod integ_tests;
+#[cfg(test)]
+mod unit_tests;
 
-pub use crate::client_async::{DhcpV4ClientAsync, DhcpV6ClientAsync};
+pub use crate::capabilities::{capabilities, DhcpCapabilities};
+#[cfg(feature = "client")]
+pub use crate::channel::{spawn_lease_channel, DhcpLeaseChannel};
+#[cfg(feature = "chaos")]
+pub use crate::chaos::DhcpChaosPolicy;
+#[cfg(feature = "client")]
+pub use crate::client_async::{
+    DhcpV4ClientAsync, DhcpV4StateStream, DhcpV6ClientAsync, DhcpV6StateStream,
+};
+pub use crate::codec::{DhcpOptionCodec, DhcpOptionValue};
 pub use crate::dhcpv4::{
-    DhcpV4Client, DhcpV4Config, DhcpV4Event, DhcpV4Lease, DhcpV4Message,
-    DhcpV4MessageType,
+    DhcpV4ClasslessRoute, DhcpV4ClientProfile, DhcpV4Config,
+    DhcpV4ConformancePolicy, DhcpV4Lease, DhcpV4LeaseBuilder,
+    DhcpV4LeaseCheckPolicy, DhcpV4LeaseValidation, DhcpV4LeaseWarning,
+    DhcpV4Message, DhcpV4MessageType, DhcpV4Option, DhcpV4OptionOrder,
+    DhcpV4PortableClient, DhcpV4Progress, DhcpV4SipServers, DhcpV4SixRd,
+    DhcpV4State, DhcpV4Transport, DhcpV4VendorSpecificInfo,
 };
+#[cfg(feature = "client")]
+pub use crate::dhcpv4::{DhcpV4Client, DhcpV4Event, DhcpV4Snapshot};
 pub use crate::dhcpv6::{
-    DhcpV6Client, DhcpV6Config, DhcpV6Event, DhcpV6IaType, DhcpV6Lease,
-    DhcpV6Message, Dhcpv6Duid, Dhcpv6DuidEn, Dhcpv6DuidLl, Dhcpv6DuidLlt,
-    Dhcpv6DuidUuid,
+    iaid_from_iface, DhcpV6Config, DhcpV6DnsConfig, DhcpV6DnsDomain,
+    DhcpV6DnsServer, DhcpV6DnsSource, DhcpV6IaType, DhcpV6Lease,
+    DhcpV6LeaseBuilder, DhcpV6Message, DhcpV6MessageType, DhcpV6Option,
+    DhcpV6OptionNtpServer, DhcpV6Progress, DhcpV6State, Dhcpv6Duid,
+    Dhcpv6DuidEn, Dhcpv6DuidLl, Dhcpv6DuidLlt, Dhcpv6DuidUuid,
+};
+#[cfg(feature = "client")]
+pub use crate::dhcpv6::{DhcpV6Client, DhcpV6Event, DhcpV6RetransmitStats};
+#[cfg(feature = "ra")]
+pub use crate::dhcpv6::{DhcpV6RaListener, DhcpV6RaMode, DhcpV6RouterAdvert};
+#[cfg(feature = "client")]
+pub use crate::dual_stack::{
+    DhcpDualStackClient, DhcpDualStackStateStream, DhcpLeaseEvent,
+    DhcpStateEvent,
 };
 pub use crate::error::{DhcpError, ErrorKind};
+pub use crate::identity::DhcpIdentityProvider;
+pub use crate::lifetime::DhcpLifetime;
+#[cfg(feature = "client")]
+pub use crate::memory::DhcpMemorySocket;
+pub use crate::name::NamePolicy;
+#[cfg(feature = "client")]
+pub use crate::privilege::check_privileges;
+pub use crate::random::set_random_seed;
+pub use crate::renew::RenewPolicy;
+pub use crate::restart::RestartPolicy;
+#[cfg(feature = "client")]
+pub use crate::socket::DhcpSocket;
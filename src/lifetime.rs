@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+// RFC 2131: 3.3 Interpretation and representation of time values
+// RFC 8415: 7.7. Representation of Time Values and "Infinity" as a Time
+// Value
+const INFINITE: u32 = u32::MAX;
+
+/// Lease time, lifetime or renew/rebind time of a lease, with the wire
+/// value 0xffffffff represented as [DhcpLifetime::Infinite] instead of a
+/// number of seconds.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum DhcpLifetime {
+    /// Number of seconds.
+    Seconds(u32),
+    /// Never expires, or never happens for renew and rebind time.
+    Infinite,
+}
+
+impl From<u32> for DhcpLifetime {
+    fn from(v: u32) -> Self {
+        if v == INFINITE {
+            Self::Infinite
+        } else {
+            Self::Seconds(v)
+        }
+    }
+}
+
+impl From<DhcpLifetime> for u32 {
+    fn from(v: DhcpLifetime) -> Self {
+        match v {
+            DhcpLifetime::Seconds(s) => s,
+            DhcpLifetime::Infinite => INFINITE,
+        }
+    }
+}
+
+impl DhcpLifetime {
+    pub fn is_infinite(&self) -> bool {
+        *self == Self::Infinite
+    }
+
+    /// `None` if infinite.
+    pub fn as_duration(&self) -> Option<Duration> {
+        match self {
+            Self::Seconds(s) => Some(Duration::from_secs((*s).into())),
+            Self::Infinite => None,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::{Duration, Instant};
+
+use crate::DhcpError;
+
+// Identical log lines are emitted at most once per interval
+const LOG_LIMIT_INTERVAL: Duration = Duration::from_secs(30);
+// Signatures tracked per client, the least recently logged is forgotten
+const LOG_LIMIT_MAX_ENTRIES: usize = 32;
+
+// Per client deduplication of log lines triggered by received packets, so a
+// server or peer flooding malformed messages does not flood the journal.
+#[derive(Debug, Default)]
+pub(crate) struct DhcpLogLimiter {
+    // Signature, last time logged and lines suppressed since then
+    entries: Vec<(String, Instant, u32)>,
+}
+
+impl DhcpLogLimiter {
+    // Log the error unless an error of identical signature has been logged
+    // within LOG_LIMIT_INTERVAL, suppressed count is included once allowed
+    // again.
+    pub(crate) fn log(
+        &mut self,
+        level: log::Level,
+        prefix: &str,
+        e: &DhcpError,
+    ) {
+        if let Some(suppressed) =
+            self.check(&error_signature(e), Instant::now())
+        {
+            if suppressed > 0 {
+                log::log!(
+                    level,
+                    "{prefix}: {e} ({suppressed} identical errors suppressed)"
+                );
+            } else {
+                log::log!(level, "{prefix}: {e}");
+            }
+        }
+    }
+
+    // Return None when suppressed, otherwise the count of suppressed lines
+    // since last logged.
+    pub(crate) fn check(
+        &mut self,
+        signature: &str,
+        now: Instant,
+    ) -> Option<u32> {
+        if let Some((_, last, suppressed)) =
+            self.entries.iter_mut().find(|(s, _, _)| s == signature)
+        {
+            if now.saturating_duration_since(*last) < LOG_LIMIT_INTERVAL {
+                *suppressed = suppressed.saturating_add(1);
+                None
+            } else {
+                *last = now;
+                Some(std::mem::take(suppressed))
+            }
+        } else {
+            if self.entries.len() >= LOG_LIMIT_MAX_ENTRIES {
+                if let Some(pos) = self
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, (_, last, _))| *last)
+                    .map(|(pos, _)| pos)
+                {
+                    self.entries.swap_remove(pos);
+                }
+            }
+            self.entries.push((signature.to_string(), now, 0));
+            Some(0)
+        }
+    }
+}
+
+// Error message might hold packet content, use the underlying error when
+// available.
+fn error_signature(e: &DhcpError) -> String {
+    match std::error::Error::source(e) {
+        Some(source) => format!("{:?}: {source}", e.kind()),
+        None => format!("{:?}: {}", e.kind(), e.msg()),
+    }
+}
@@ -4,7 +4,9 @@ use log::error;
 
 use crate::{DhcpError, ErrorKind};
 
-pub(crate) const BROADCAST_MAC_ADDRESS: [u8; 6] = [u8::MAX; 6];
+// Octets in one ethernet address, `libc::ETH_ALEN`
+pub(crate) const ETH_ALEN: usize = 6;
+pub(crate) const BROADCAST_MAC_ADDRESS: [u8; ETH_ALEN] = [u8::MAX; ETH_ALEN];
 
 pub(crate) fn mac_str_to_u8_array(mac: &str) -> Vec<u8> {
     let mut mac_bytes = Vec::new();
@@ -25,17 +27,20 @@ pub(crate) fn mac_str_to_u8_array(mac: &str) -> Vec<u8> {
 
 pub(crate) fn mac_address_to_eth_mac_bytes(
     mac_address: &str,
-) -> Result<[u8; libc::ETH_ALEN as usize], DhcpError> {
-    let mut ret = [0u8; libc::ETH_ALEN as usize];
+) -> Result<[u8; ETH_ALEN], DhcpError> {
+    let mut ret = [0u8; ETH_ALEN];
+    // Interface without MAC address is using all zero MAC
+    if mac_address.is_empty() {
+        return Ok(ret);
+    }
     let mac_bytes = mac_str_to_u8_array(mac_address);
 
-    if mac_bytes.len() > libc::ETH_ALEN as usize {
+    if mac_bytes.len() != ETH_ALEN {
         Err(DhcpError::new(
-            ErrorKind::Bug,
+            ErrorKind::InvalidArgument,
             format!(
-                "MAC address {} exceeded the max length {}",
-                mac_address,
-                libc::ETH_ALEN
+                "MAC address {} is not {} bytes long",
+                mac_address, ETH_ALEN
             ),
         ))
     } else {
@@ -43,3 +48,13 @@ pub(crate) fn mac_address_to_eth_mac_bytes(
         Ok(ret)
     }
 }
+
+// Interface like PPP, wireguard or IP tunnel has no MAC address or has
+// non-ethernet hardware address which cannot be used for DHCP.
+#[cfg(feature = "client")]
+pub(crate) fn is_eth_mac_address(mac_address: &str) -> bool {
+    !mac_address.is_empty() && {
+        let mac_bytes = mac_str_to_u8_array(mac_address);
+        mac_bytes.len() == ETH_ALEN && mac_bytes.iter().any(|b| *b != 0)
+    }
+}
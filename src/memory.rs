@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::{
+    IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6,
+};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixDatagram;
+use std::sync::Arc;
+
+use crate::{
+    socket::{sealed::Sealed, DhcpSocket},
+    DhcpError, ErrorKind,
+};
+
+const MAX_MSG_SIZE: usize = 65535;
+
+// Source address encoding prefixed to each datagram
+const ADDR_NONE: u8 = 0;
+const ADDR_V4: u8 = 4;
+const ADDR_V6: u8 = 6;
+
+/// In-memory datagram channel for running the client without network
+/// interface, e.g. unit testing the whole state machine against scripted
+/// DHCP server replies.
+///
+/// Created in pair by [DhcpMemorySocket::new_pair()]: one end is passed to
+/// [crate::DhcpV4Config::set_memory_socket()] or
+/// [crate::DhcpV6Config::set_memory_socket()], the other end acts as the
+/// DHCP server. Only DHCP messages without any IP or UDP header are
+/// exchanged. Both ends are non-blocking, [ErrorKind::Timeout] is returned
+/// when nothing to receive.
+#[derive(Debug, Clone)]
+pub struct DhcpMemorySocket {
+    socket: Arc<UnixDatagram>,
+}
+
+impl PartialEq for DhcpMemorySocket {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.socket, &other.socket)
+    }
+}
+
+impl Eq for DhcpMemorySocket {}
+
+impl AsRawFd for DhcpMemorySocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl DhcpMemorySocket {
+    /// Create both ends of the channel.
+    pub fn new_pair() -> Result<(Self, Self), DhcpError> {
+        let (a, b) = UnixDatagram::pair()?;
+        a.set_nonblocking(true)?;
+        b.set_nonblocking(true)?;
+        Ok((
+            Self {
+                socket: Arc::new(a),
+            },
+            Self {
+                socket: Arc::new(b),
+            },
+        ))
+    }
+
+    /// Send message claiming to be from specified address, e.g. DHCPv6
+    /// client only accepts replies from server port 547.
+    pub fn send_from(
+        &self,
+        src: SocketAddr,
+        data: &[u8],
+    ) -> Result<(), DhcpError> {
+        self.send_with_addr(Some(src), data)
+    }
+
+    fn send_with_addr(
+        &self,
+        src: Option<SocketAddr>,
+        data: &[u8],
+    ) -> Result<(), DhcpError> {
+        let mut buffer = match src.map(|s| (s.ip(), s.port())) {
+            Some((IpAddr::V4(ip), port)) => {
+                let mut b = vec![ADDR_V4];
+                b.extend_from_slice(&ip.octets());
+                b.extend_from_slice(&port.to_be_bytes());
+                b
+            }
+            Some((IpAddr::V6(ip), port)) => {
+                let mut b = vec![ADDR_V6];
+                b.extend_from_slice(&ip.octets());
+                b.extend_from_slice(&port.to_be_bytes());
+                b
+            }
+            None => vec![ADDR_NONE],
+        };
+        buffer.extend_from_slice(data);
+        self.socket.send(&buffer)?;
+        Ok(())
+    }
+}
+
+impl Sealed for DhcpMemorySocket {}
+
+impl DhcpSocket for DhcpMemorySocket {
+    fn is_raw(&self) -> bool {
+        false
+    }
+
+    fn send(&self, pkg: &[u8]) -> Result<(), DhcpError> {
+        self.send_with_addr(None, pkg)
+    }
+
+    fn recv(&self) -> Result<Vec<u8>, DhcpError> {
+        Ok(self.recv_from()?.0)
+    }
+
+    fn recv_from(&self) -> Result<(Vec<u8>, Option<SocketAddr>), DhcpError> {
+        let mut buffer = vec![0u8; MAX_MSG_SIZE];
+        let received = match self.socket.recv(&mut buffer) {
+            Ok(r) => r,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                return Err(DhcpError::new(
+                    ErrorKind::Timeout,
+                    "No message in memory socket".to_string(),
+                ));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        buffer.truncate(received);
+        let (src, hdr_len) = match buffer.first() {
+            Some(&ADDR_V4) if buffer.len() >= 7 => {
+                let mut ip = [0u8; 4];
+                ip.copy_from_slice(&buffer[1..5]);
+                let port = u16::from_be_bytes([buffer[5], buffer[6]]);
+                (
+                    Some(SocketAddr::V4(SocketAddrV4::new(
+                        Ipv4Addr::from(ip),
+                        port,
+                    ))),
+                    7,
+                )
+            }
+            Some(&ADDR_V6) if buffer.len() >= 19 => {
+                let mut ip = [0u8; 16];
+                ip.copy_from_slice(&buffer[1..17]);
+                let port = u16::from_be_bytes([buffer[17], buffer[18]]);
+                (
+                    Some(SocketAddr::V6(SocketAddrV6::new(
+                        Ipv6Addr::from(ip),
+                        port,
+                        0,
+                        0,
+                    ))),
+                    19,
+                )
+            }
+            Some(&ADDR_NONE) => (None, 1),
+            _ => {
+                let e = DhcpError::new(
+                    ErrorKind::Bug,
+                    format!("Invalid memory socket message {buffer:?}"),
+                );
+                log::error!("{}", e);
+                return Err(e);
+            }
+        };
+        Ok((buffer.split_off(hdr_len), src))
+    }
+}
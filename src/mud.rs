@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{DhcpError, ErrorKind};
+
+// RFC 8520: 10. The MUD URL DHCP Option
+pub(crate) const OPTION_V4_MUD_URL: u8 = 161;
+
+// RFC 8520: 10. The MUD URL DHCP Option
+//      MUD URLs MUST use the "https" scheme
+pub(crate) fn validate_mud_url(
+    url: &str,
+    max_len: usize,
+) -> Result<(), DhcpError> {
+    let msg = if !url.starts_with("https://") || url.len() <= "https://".len() {
+        Some(format!("Invalid MUD URL {url}: should use https scheme"))
+    } else if url.len() > max_len {
+        Some(format!(
+            "Invalid MUD URL {url}: longer than {max_len} characters"
+        ))
+    } else if !url.is_ascii() {
+        Some(format!("Invalid MUD URL {url}: should be ASCII only"))
+    } else {
+        None
+    };
+    if let Some(msg) = msg {
+        let e = DhcpError::new(ErrorKind::InvalidArgument, msg);
+        log::error!("{}", e);
+        Err(e)
+    } else {
+        Ok(())
+    }
+}
+
+// Server reply is trusted less, anything not valid UTF-8 is ignored.
+pub(crate) fn parse_mud_url(data: &[u8]) -> Option<String> {
+    match std::str::from_utf8(data) {
+        Ok(url) => Some(url.to_string()),
+        Err(e) => {
+            log::debug!("Invalid MUD URL {data:?}: {e}");
+            None
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// RFC 1035: 2.3.4. Size limits
+const MAX_DOMAIN_NAME_LEN: usize = 253;
+const MAX_DOMAIN_LABEL_LEN: usize = 63;
+// RFC 1035: 3.1. Name space definitions, including length octets
+const MAX_DOMAIN_NAME_WIRE_LEN: usize = 255;
+// RFC 1035: 4.1.4. Message compression, the first two bits of pointer are
+// ones.
+const DNS_POINTER_MASK: u8 = 0b1100_0000;
+
+// Check whether the host name or domain name is safe to be used in DNS:
+//  * Total length no longer than 253 and each label no longer than 63.
+//  * No empty label(leading or consecutive dots), trailing dot is allowed.
+//  * Labels only contain letters, digits, hyphen and underscore, and do not
+//    start or end with hyphen.
+pub(crate) fn is_valid_dns_name(name: &str) -> bool {
+    let name = name.strip_suffix('.').unwrap_or(name);
+    !name.is_empty()
+        && name.len() <= MAX_DOMAIN_NAME_LEN
+        && name.split('.').all(is_valid_dns_label)
+}
+
+fn is_valid_dns_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= MAX_DOMAIN_LABEL_LEN
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+}
+
+// Decode domain names in DNS wire format(RFC 1035 section 3.1) with
+// compression pointers relative to the start of `data`, as RFC 3397 defined
+// for DHCPv4 Domain Search option. Each pointer should refer to data before
+// the previous one, so malformed pointers cannot loop. Return `None` if any
+// name is malformed instead of truncating the list.
+pub(crate) fn decode_dns_names(data: &[u8]) -> Option<Vec<String>> {
+    let mut ret = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let (name, next) = decode_dns_name(data, pos)?;
+        ret.push(name);
+        pos = next;
+    }
+    Some(ret)
+}
+
+// Decode single name starting at `start`, return it along with the position
+// after it.
+fn decode_dns_name(data: &[u8], start: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut wire_len = 1;
+    let mut pos = start;
+    // Pointer should be prior to this
+    let mut limit = start;
+    // Position after the name, decided by the first pointer if any
+    let mut end = None;
+    loop {
+        let len = *data.get(pos)?;
+        if len & DNS_POINTER_MASK == DNS_POINTER_MASK {
+            let ptr = usize::from(u16::from_be_bytes([
+                len & !DNS_POINTER_MASK,
+                *data.get(pos + 1)?,
+            ]));
+            if ptr >= limit {
+                log::debug!(
+                    "DNS name pointer {ptr} at {pos} is not prior to {limit}"
+                );
+                return None;
+            }
+            end.get_or_insert(pos + 2);
+            limit = ptr;
+            pos = ptr;
+        } else if len & DNS_POINTER_MASK != 0 {
+            log::debug!("Unsupported DNS label type {len:#x} at {pos}");
+            return None;
+        } else if len == 0 {
+            return Some((labels.join("."), end.unwrap_or(pos + 1)));
+        } else {
+            let label = data.get(pos + 1..pos + 1 + usize::from(len))?;
+            wire_len += label.len() + 1;
+            if wire_len > MAX_DOMAIN_NAME_WIRE_LEN {
+                log::debug!("DNS name at {start} exceeds 255 octets");
+                return None;
+            }
+            labels.push(String::from_utf8_lossy(label).to_string());
+            pos += 1 + label.len();
+        }
+    }
+}
+
+/// Policy on host name and domain name options in DHCP server reply which
+/// are not valid UTF-8 or not safe to be used as DNS name.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum NamePolicy {
+    /// Drop the option.
+    Strict,
+    /// Escape unsafe bytes in the form of `\DDD` (RFC 1035 master file
+    /// format), for example `a b` becomes `a\032b`.
+    Lossy,
+    /// Pass the option through, invalid UTF-8 sequences are replaced with
+    /// `U+FFFD`.
+    #[default]
+    Raw,
+}
+
+impl NamePolicy {
+    pub(crate) fn apply(&self, raw: &[u8]) -> Option<String> {
+        match self {
+            Self::Strict => match std::str::from_utf8(raw) {
+                Ok(name) if is_valid_dns_name(name) => Some(name.to_string()),
+                _ => {
+                    log::warn!(
+                        "Dropping invalid DNS name {}",
+                        String::from_utf8_lossy(raw)
+                    );
+                    None
+                }
+            },
+            Self::Lossy => Some(
+                raw.iter()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric()
+                            || [b'-', b'_', b'.'].contains(c)
+                        {
+                            char::from(*c).to_string()
+                        } else {
+                            format!("\\{c:03}")
+                        }
+                    })
+                    .collect(),
+            ),
+            Self::Raw => Some(String::from_utf8_lossy(raw).to_string()),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// Minimum pcapng writer (draft-ietf-opsawg-pcapng) storing every DHCP packet
+// sent or received by the client for troubleshooting.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{DhcpError, ErrorKind};
+
+pub(crate) const BLOCK_TYPE_SHB: u32 = 0x0A0D_0D0A;
+pub(crate) const BLOCK_TYPE_IDB: u32 = 0x0000_0001;
+pub(crate) const BLOCK_TYPE_EPB: u32 = 0x0000_0006;
+pub(crate) const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+// https://www.tcpdump.org/linktypes.html
+pub(crate) const LINKTYPE_ETHERNET: u16 = 1;
+pub(crate) const LINKTYPE_IPV4: u16 = 228;
+pub(crate) const LINKTYPE_IPV6: u16 = 229;
+
+// Interface ID is the index of Interface Description Block in section
+pub(crate) const IFACE_ID_ETHERNET: u32 = 0;
+pub(crate) const IFACE_ID_IPV4: u32 = 1;
+const IFACE_ID_IPV6: u32 = 2;
+
+const OPT_END_OF_OPT: u16 = 0;
+const OPT_EPB_FLAGS: u16 = 2;
+pub(crate) const EPB_FLAG_INBOUND: u32 = 0b01;
+pub(crate) const EPB_FLAG_OUTBOUND: u32 = 0b10;
+
+const SNAP_LEN: u32 = 0xFFFF;
+const DEFAULT_TTL: u8 = 64;
+
+// Clients sharing the same pcap file share the same writer, so the file holds
+// a single section with consistent interface IDs.
+static WRITERS: Mutex<Vec<(String, Weak<DhcpPcapWriter>)>> =
+    Mutex::new(Vec::new());
+
+pub(crate) struct DhcpPcapWriter {
+    path: String,
+    file: Mutex<File>,
+}
+
+impl std::fmt::Debug for DhcpPcapWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DhcpPcapWriter {{ path: {:?} }}", self.path)
+    }
+}
+
+impl PartialEq for DhcpPcapWriter {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl DhcpPcapWriter {
+    pub(crate) fn open(
+        path: Option<&str>,
+    ) -> Result<Option<Arc<Self>>, DhcpError> {
+        let Some(path) = path else {
+            return Ok(None);
+        };
+        let mut writers = WRITERS.lock().map_err(|e| {
+            DhcpError::new(
+                ErrorKind::Bug,
+                format!("Failed to lock pcap writer list: {e}"),
+            )
+        })?;
+        writers.retain(|(_, w)| w.strong_count() > 0);
+        if let Some(writer) = writers
+            .iter()
+            .find(|(p, _)| p == path)
+            .and_then(|(_, w)| w.upgrade())
+        {
+            return Ok(Some(writer));
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                let e = DhcpError::new(
+                    ErrorKind::InvalidArgument,
+                    format!("Failed to open pcap file {path}: {e}"),
+                );
+                log::error!("{}", e);
+                e
+            })?;
+        // Appending new section to existing file is valid pcapng
+        let mut buff = gen_shb();
+        for link_type in [LINKTYPE_ETHERNET, LINKTYPE_IPV4, LINKTYPE_IPV6] {
+            buff.extend_from_slice(&gen_idb(link_type));
+        }
+        file.write_all(&buff)?;
+        log::debug!("Writing DHCP packets to pcap file {path}");
+
+        let writer = Arc::new(Self {
+            path: path.to_string(),
+            file: Mutex::new(file),
+        });
+        writers.push((path.to_string(), Arc::downgrade(&writer)));
+        Ok(Some(writer))
+    }
+
+    /// Store ethernet frame
+    pub(crate) fn write_eth(&self, data: &[u8], is_inbound: bool) {
+        self.write_epb(IFACE_ID_ETHERNET, data, is_inbound);
+    }
+
+    /// Store UDP payload with IP and UDP header generated from specified
+    /// socket addresses.
+    pub(crate) fn write_udp(
+        &self,
+        src: SocketAddr,
+        dst: SocketAddr,
+        payload: &[u8],
+        is_inbound: bool,
+    ) {
+        let (iface_id, builder) = match (src.ip(), dst.ip()) {
+            (IpAddr::V4(src_ip), IpAddr::V4(dst_ip)) => (
+                IFACE_ID_IPV4,
+                etherparse::PacketBuilder::ipv4(
+                    src_ip.octets(),
+                    dst_ip.octets(),
+                    DEFAULT_TTL,
+                ),
+            ),
+            (IpAddr::V6(src_ip), IpAddr::V6(dst_ip)) => (
+                IFACE_ID_IPV6,
+                etherparse::PacketBuilder::ipv6(
+                    src_ip.octets(),
+                    dst_ip.octets(),
+                    DEFAULT_TTL,
+                ),
+            ),
+            _ => {
+                log::debug!(
+                    "Not storing packet of mixed IP family {src} {dst} to \
+                    pcap file"
+                );
+                return;
+            }
+        };
+        let builder = builder.udp(src.port(), dst.port());
+        let mut pkg = Vec::<u8>::with_capacity(builder.size(payload.len()));
+        if let Err(e) = builder.write(&mut pkg, payload) {
+            log::warn!("Failed to generate UDP packet for pcap file: {e}");
+            return;
+        }
+        self.write_epb(iface_id, &pkg, is_inbound);
+    }
+
+    // Failure of writing pcap file should not impact DHCP, hence only warn
+    fn write_epb(&self, iface_id: u32, data: &[u8], is_inbound: bool) {
+        let buff = gen_epb(iface_id, data, is_inbound);
+        match self.file.lock() {
+            Ok(mut file) => {
+                if let Err(e) = file.write_all(&buff) {
+                    log::warn!(
+                        "Failed to write pcap file {}: {}",
+                        self.path,
+                        e
+                    );
+                }
+            }
+            Err(e) => {
+                log::warn!("Failed to lock pcap file {}: {}", self.path, e);
+            }
+        }
+    }
+}
+
+// Block Type, Block Total Length, Body, Block Total Length
+fn gen_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let pad_len = (4 - body.len() % 4) % 4;
+    let total_len = (12 + body.len() + pad_len) as u32;
+    let mut ret = Vec::with_capacity(total_len as usize);
+    ret.extend_from_slice(&block_type.to_ne_bytes());
+    ret.extend_from_slice(&total_len.to_ne_bytes());
+    ret.extend_from_slice(body);
+    ret.resize(ret.len() + pad_len, 0);
+    ret.extend_from_slice(&total_len.to_ne_bytes());
+    ret
+}
+
+fn gen_shb() -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&BYTE_ORDER_MAGIC.to_ne_bytes());
+    // Version 1.0
+    body.extend_from_slice(&1u16.to_ne_bytes());
+    body.extend_from_slice(&0u16.to_ne_bytes());
+    // Section length unknown
+    body.extend_from_slice(&(-1i64).to_ne_bytes());
+    gen_block(BLOCK_TYPE_SHB, &body)
+}
+
+fn gen_idb(link_type: u16) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&link_type.to_ne_bytes());
+    // Reserved
+    body.extend_from_slice(&0u16.to_ne_bytes());
+    body.extend_from_slice(&SNAP_LEN.to_ne_bytes());
+    gen_block(BLOCK_TYPE_IDB, &body)
+}
+
+fn gen_epb(iface_id: u32, data: &[u8], is_inbound: bool) -> Vec<u8> {
+    // Default timestamp resolution is microsecond
+    let ts: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_micros().try_into().unwrap_or(u64::MAX))
+        .unwrap_or_default();
+    let cap_len = data.len().min(SNAP_LEN as usize);
+    let mut body = Vec::new();
+    body.extend_from_slice(&iface_id.to_ne_bytes());
+    body.extend_from_slice(&((ts >> 32) as u32).to_ne_bytes());
+    body.extend_from_slice(&(ts as u32).to_ne_bytes());
+    body.extend_from_slice(&(cap_len as u32).to_ne_bytes());
+    body.extend_from_slice(&(data.len() as u32).to_ne_bytes());
+    body.extend_from_slice(&data[..cap_len]);
+    body.resize(body.len() + (4 - cap_len % 4) % 4, 0);
+    body.extend_from_slice(&OPT_EPB_FLAGS.to_ne_bytes());
+    body.extend_from_slice(&4u16.to_ne_bytes());
+    body.extend_from_slice(
+        &if is_inbound {
+            EPB_FLAG_INBOUND
+        } else {
+            EPB_FLAG_OUTBOUND
+        }
+        .to_ne_bytes(),
+    );
+    body.extend_from_slice(&OPT_END_OF_OPT.to_ne_bytes());
+    body.extend_from_slice(&0u16.to_ne_bytes());
+    gen_block(BLOCK_TYPE_EPB, &body)
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{DhcpError, ErrorKind};
+
+const PROC_STATUS_PATH: &str = "/proc/self/status";
+
+// linux/capability.h
+const CAP_NET_BIND_SERVICE: u32 = 10;
+const CAP_NET_RAW: u32 = 13;
+
+/// Check whether current process holds the capabilities required by DHCP
+/// clients:
+///  * `CAP_NET_RAW` for raw sockets used by DHCPv4 and IPv6 Neighbor
+///    Discovery, and for binding socket to interface.
+///  * `CAP_NET_BIND_SERVICE` for binding DHCPv6 client UDP port 546.
+///
+/// Return [ErrorKind::PermissionDenied] listing all the missing capabilities.
+pub fn check_privileges() -> Result<(), DhcpError> {
+    let content = std::fs::read_to_string(PROC_STATUS_PATH).map_err(|e| {
+        let e = DhcpError::new(
+            ErrorKind::Bug,
+            format!("Failed to read {PROC_STATUS_PATH}: {e}"),
+        );
+        log::error!("{}", e);
+        e
+    })?;
+    let cap_eff = content
+        .lines()
+        .find_map(|l| l.strip_prefix("CapEff:"))
+        .and_then(|v| u64::from_str_radix(v.trim(), 16).ok())
+        .ok_or_else(|| {
+            let e = DhcpError::new(
+                ErrorKind::Bug,
+                format!("Failed to find CapEff in {PROC_STATUS_PATH}"),
+            );
+            log::error!("{}", e);
+            e
+        })?;
+
+    let missing: Vec<&str> = [
+        (CAP_NET_RAW, "CAP_NET_RAW"),
+        (CAP_NET_BIND_SERVICE, "CAP_NET_BIND_SERVICE"),
+    ]
+    .iter()
+    .filter(|(cap, _)| cap_eff & (1u64 << cap) == 0)
+    .map(|(_, name)| *name)
+    .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(DhcpError::new(
+            ErrorKind::PermissionDenied,
+            format!("Missing capabilities: {}", missing.join(", ")),
+        ))
+    }
+}
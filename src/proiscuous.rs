@@ -27,7 +27,8 @@ pub(crate) fn enable_promiscuous_mode(
                 format!(
                     "Failed to set socket to promiscuous mode with error: {rc}"
                 ),
-            ));
+            )
+            .with_source(std::io::Error::last_os_error()));
         }
     }
     Ok(())
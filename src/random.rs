@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::cell::RefCell;
+
+use rand::{
+    distributions::uniform::{SampleRange, SampleUniform},
+    rngs::StdRng,
+    Rng, SeedableRng,
+};
+
+thread_local! {
+    static SEEDED_RNG: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+/// Make the random values used by DHCP clients created in current thread
+/// (transaction ID and retransmission jitter) deterministic.
+/// Only intended for testing, never use it in production as it makes all
+/// clients of the same seed behave synchronized.
+pub fn set_random_seed(seed: u64) {
+    SEEDED_RNG.with(|rng| {
+        rng.replace(Some(StdRng::seed_from_u64(seed)));
+    });
+}
+
+pub(crate) fn gen_range<T, R>(range: R) -> T
+where
+    T: SampleUniform,
+    R: SampleRange<T>,
+{
+    SEEDED_RNG.with(|rng| match rng.borrow_mut().as_mut() {
+        Some(rng) => rng.gen_range(range),
+        None => rand::thread_rng().gen_range(range),
+    })
+}
+
+pub(crate) fn gen_u32() -> u32 {
+    gen_range(0..=u32::MAX)
+}
@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Policy on when client starts renewing the lease, overriding T1 from DHCP
+/// server, e.g. smearing renewals of a fleet or refreshing options more
+/// frequently. The renew time is never later than T2. Not applied to
+/// infinite lease.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum RenewPolicy {
+    /// Renew at T1 provided by DHCP server.
+    #[default]
+    ServerT1,
+    /// Renew at specified percentage(1 to 100) of lease time, value out of
+    /// range is clamped.
+    Percent(u8),
+    /// Renew specified seconds before lease expiry, renew immediately if
+    /// bigger than lease time.
+    BeforeExpiry(u32),
+}
+
+impl RenewPolicy {
+    // Renew time in seconds for lease with specified T1, T2 and lease time
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub(crate) fn renew_time(&self, t1: u32, t2: u32, lease_time: u32) -> u32 {
+        let t = match self {
+            Self::ServerT1 => return t1,
+            Self::Percent(p) => u32::try_from(
+                u64::from(lease_time) * u64::from((*p).clamp(1, 100)) / 100,
+            )
+            .unwrap_or(u32::MAX),
+            Self::BeforeExpiry(s) => lease_time.saturating_sub(*s),
+        };
+        t.min(t2)
+    }
+}
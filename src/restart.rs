@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/// Policy on whether client starts lease acquisition again once lease expired
+/// or refused by server(DHCPNAK in DHCPv4, failure status code in DHCPv6).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum RestartPolicy {
+    /// Always start lease acquisition again.
+    #[default]
+    Forever,
+    /// Stop the client, [crate::ErrorKind::LeaseExpired] or
+    /// [crate::ErrorKind::NoLease] is returned.
+    Stop,
+    /// Start lease acquisition again for at most specified times in a row,
+    /// counter is reset once lease bound. Stop afterwards like
+    /// [RestartPolicy::Stop].
+    Limit(u32),
+}
+
+impl RestartPolicy {
+    // Whether another restart is allowed after `count` restarts in a row
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub(crate) fn allows(&self, count: u32) -> bool {
+        match self {
+            Self::Forever => true,
+            Self::Stop => false,
+            Self::Limit(max) => count < *max,
+        }
+    }
+}
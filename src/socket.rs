@@ -1,26 +1,52 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::ffi::CString;
-use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV6, UdpSocket};
-use std::os::unix::io::AsRawFd;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6, UdpSocket};
 use std::os::unix::io::RawFd;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+#[cfg(feature = "pcap")]
+use std::sync::Arc;
 
 use nix::errno::Errno;
 
+#[cfg(feature = "pcap")]
+use crate::pcap::DhcpPcapWriter;
 use crate::{
     bpf::apply_dhcp_bpf,
+    consts::DEFAULT_MAX_MSG_SIZE,
     mac::{mac_address_to_eth_mac_bytes, BROADCAST_MAC_ADDRESS},
     proiscuous::enable_promiscuous_mode,
-    DhcpError, DhcpV4Config, ErrorKind,
+    DhcpError, DhcpV4Config, DhcpV6Config, ErrorKind,
 };
 
-pub(crate) const DEFAULT_SOCKET_TIMEOUT: u32 = 5;
-
 const PACKET_HOST: u8 = 0; // a packet addressed to the local host
+const ETH_HEADER_LEN: usize = 14;
+const ETHER_TYPE_IPV4: [u8; 2] = [0x08, 0x00];
+const IPV4_HEADER_LEN: usize = 20;
+const IPV6_HEADER_LEN: usize = 40;
+const UDP_HEADER_LEN: usize = 8;
+// Sending could be interrupted by signal or blocked by full socket buffer
+const MAX_SEND_ATTEMPTS: u32 = 3;
+const SEND_RETRY_INTERVAL: std::time::Duration =
+    std::time::Duration::from_millis(10);
+
+pub(crate) mod sealed {
+    pub trait Sealed {}
+}
 
-pub(crate) trait DhcpSocket {
+/// Transport of DHCP messages used by client. Sealed, only implemented by
+/// this crate, e.g. [crate::DhcpMemorySocket].
+pub trait DhcpSocket: sealed::Sealed {
     fn recv(&self) -> Result<Vec<u8>, DhcpError>;
+    /// Received message with its source address. Source address is only
+    /// provided by UDP socket, raw socket should parse it from the IP and
+    /// UDP header of the received package.
+    fn recv_from(&self) -> Result<(Vec<u8>, Option<SocketAddr>), DhcpError> {
+        Ok((self.recv()?, None))
+    }
     fn send(&self, eth_pkg: &[u8]) -> Result<(), DhcpError>;
+    /// Whether messages sent and received are ethernet frames holding IP
+    /// and UDP headers.
     fn is_raw(&self) -> bool;
 }
 
@@ -28,6 +54,11 @@ pub(crate) trait DhcpSocket {
 pub(crate) struct DhcpRawSocket {
     config: DhcpV4Config,
     raw_fd: libc::c_int,
+    // False for interface without MAC address, the socket is sending and
+    // receiving IP packages instead of ethernet frames.
+    with_eth_header: bool,
+    #[cfg(feature = "pcap")]
+    pcap: Option<Arc<DhcpPcapWriter>>,
 }
 
 impl std::os::unix::io::AsRawFd for DhcpRawSocket {
@@ -49,10 +80,61 @@ impl Drop for DhcpRawSocket {
 impl DhcpRawSocket {
     pub(crate) fn new(config: &DhcpV4Config) -> Result<Self, DhcpError> {
         let iface_index = config.iface_index as libc::c_int;
-        let eth_protocol = libc::ETH_P_ALL;
-        let raw_fd = create_raw_socket(eth_protocol)?;
+        let with_eth_header = config.is_proxy || !config.src_mac.is_empty();
+        // Interface without MAC address(e.g. PPP, wireguard) has no
+        // link-layer header, use cooked packet socket for IP packages only.
+        let (sock_type, eth_protocol) = if with_eth_header {
+            (libc::SOCK_RAW, libc::ETH_P_ALL)
+        } else {
+            (libc::SOCK_DGRAM, libc::ETH_P_IP)
+        };
+        // Socket pre-opened by caller is expected to be bound to interface
+        let raw_fd = if let Some(fd) = config.raw_socket_fd {
+            let raw_fd = dup_fd(fd)?;
+            apply_dhcp_bpf(raw_fd, with_eth_header)?;
+            raw_fd
+        } else {
+            let raw_fd = create_raw_socket(sock_type, eth_protocol)?;
+            apply_dhcp_bpf(raw_fd, with_eth_header)?;
+            bind_raw_socket(
+                raw_fd,
+                eth_protocol,
+                iface_index,
+                &config.src_mac,
+            )?;
+            raw_fd
+        };
 
-        apply_dhcp_bpf(raw_fd)?;
+        if config.is_proxy {
+            enable_promiscuous_mode(raw_fd, iface_index)?;
+        }
+
+        if let Some(priority) = config.socket_priority {
+            set_socket_opt_int(
+                raw_fd,
+                libc::SOL_SOCKET,
+                libc::SO_PRIORITY,
+                priority,
+                "SO_PRIORITY",
+            )?;
+        }
+
+        set_socket_timeout(raw_fd, config.socket_timeout)?;
+        log::debug!("Raw socket created {}", raw_fd);
+        Ok(DhcpRawSocket {
+            raw_fd,
+            with_eth_header,
+            config: config.clone(),
+            #[cfg(feature = "pcap")]
+            pcap: DhcpPcapWriter::open(config.pcap_file.as_deref())?,
+        })
+    }
+
+    // Raw socket only receiving ARP packages
+    pub(crate) fn new_arp(config: &DhcpV4Config) -> Result<Self, DhcpError> {
+        let iface_index = config.iface_index as libc::c_int;
+        let eth_protocol = libc::ETH_P_ARP;
+        let raw_fd = create_raw_socket(libc::SOCK_RAW, eth_protocol)?;
 
         bind_raw_socket(raw_fd, eth_protocol, iface_index, &config.src_mac)?;
 
@@ -61,14 +143,19 @@ impl DhcpRawSocket {
         }
 
         set_socket_timeout(raw_fd, config.socket_timeout)?;
-        log::debug!("Raw socket created {}", raw_fd);
+        log::debug!("ARP raw socket created {}", raw_fd);
         Ok(DhcpRawSocket {
             raw_fd,
+            with_eth_header: true,
             config: config.clone(),
+            #[cfg(feature = "pcap")]
+            pcap: None,
         })
     }
 }
 
+impl sealed::Sealed for DhcpRawSocket {}
+
 impl DhcpSocket for DhcpRawSocket {
     fn is_raw(&self) -> bool {
         true
@@ -84,9 +171,15 @@ impl DhcpSocket for DhcpRawSocket {
         }
 
         let mut dst_addr: libc::sockaddr_ll = unsafe { std::mem::zeroed() };
-        dst_addr.sll_halen = libc::ETH_ALEN as u8;
-        dst_addr.sll_addr[..libc::ETH_ALEN as usize]
-            .clone_from_slice(&BROADCAST_MAC_ADDRESS);
+        let data = if self.with_eth_header {
+            dst_addr.sll_halen = libc::ETH_ALEN as u8;
+            dst_addr.sll_addr[..libc::ETH_ALEN as usize]
+                .clone_from_slice(&BROADCAST_MAC_ADDRESS);
+            eth_pkg
+        } else {
+            dst_addr.sll_protocol = (libc::ETH_P_IP as libc::c_ushort).to_be();
+            eth_pkg.get(ETH_HEADER_LEN..).unwrap_or_default()
+        };
         dst_addr.sll_ifindex = self.config.iface_index as i32;
         let addr_buffer_size: libc::socklen_t =
             std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
@@ -96,38 +189,88 @@ impl DhcpSocket for DhcpRawSocket {
             )
         };
 
-        unsafe {
-            log::debug!("Sending raw ethernet package: {:?}", eth_pkg);
-            let sent_bytes = libc::sendto(
-                self.raw_fd,
-                eth_pkg.as_ptr() as *mut libc::c_void,
-                eth_pkg.len(),
-                0, // flags
-                addr_ptr,
-                addr_buffer_size,
-            );
+        log::debug!("Sending raw ethernet package: {:?}", eth_pkg);
+        let mut attempt = 1;
+        loop {
+            let sent_bytes = unsafe {
+                libc::sendto(
+                    self.raw_fd,
+                    data.as_ptr() as *mut libc::c_void,
+                    data.len(),
+                    0, // flags
+                    addr_ptr,
+                    addr_buffer_size,
+                )
+            };
             log::debug!("Raw socket sent: {} bytes", sent_bytes);
-            if sent_bytes <= 0 {
-                let e = DhcpError::new(
-                    ErrorKind::Bug,
-                    format!(
-                        "Failed to send data to socket {}: {}, data: {:?}",
+            if sent_bytes >= 0 && sent_bytes as usize == data.len() {
+                break;
+            }
+            // Packet socket never sends partial frame, but in case it
+            // happens, the whole frame is resent.
+            let errno = if sent_bytes < 0 {
+                Errno::last()
+            } else {
+                Errno::EAGAIN
+            };
+            match errno {
+                Errno::EINTR | Errno::EAGAIN if attempt < MAX_SEND_ATTEMPTS => {
+                    log::debug!(
+                        "Retrying sending to raw socket {} on {}, sent {} \
+                        of {} bytes, attempt {}",
                         self.raw_fd,
-                        Errno::last(),
-                        eth_pkg,
-                    ),
-                );
-                log::error!("{}", e);
-                return Err(e);
+                        errno,
+                        sent_bytes,
+                        data.len(),
+                        attempt
+                    );
+                    std::thread::sleep(SEND_RETRY_INTERVAL * attempt);
+                    attempt += 1;
+                }
+                // Interface is down or removed, caller might retry later
+                Errno::ENETDOWN | Errno::ENXIO | Errno::ENODEV => {
+                    let e = DhcpError::new(
+                        ErrorKind::InterfaceDown,
+                        format!(
+                            "Failed to send data to socket {}: interface {} \
+                            is down or removed: {}",
+                            self.raw_fd, self.config.iface_name, errno
+                        ),
+                    )
+                    .with_source(std::io::Error::from(errno));
+                    log::error!("{}", e);
+                    return Err(e);
+                }
+                _ => {
+                    let e = DhcpError::new(
+                        ErrorKind::Bug,
+                        format!(
+                            "Failed to send data to socket {}: {}, sent {} \
+                            bytes after {} attempts, data: {:?}",
+                            self.raw_fd, errno, sent_bytes, attempt, eth_pkg,
+                        ),
+                    )
+                    .with_source(std::io::Error::from(errno));
+                    log::error!("{}", e);
+                    return Err(e);
+                }
             }
         }
+        #[cfg(feature = "pcap")]
+        if let Some(pcap) = self.pcap.as_ref() {
+            pcap.write_eth(eth_pkg, false);
+        }
         Ok(())
     }
 
     fn recv(&self) -> Result<Vec<u8>, DhcpError> {
         let mut src_addr: libc::sockaddr_ll = unsafe { std::mem::zeroed() };
-        // TODO: Add support of `Maximum DHCP Message Size` option
-        let mut buffer = [0u8; 1500];
+        let mut buffer = vec![
+            0u8;
+            usize::from(
+                self.config.max_msg_size.unwrap_or(DEFAULT_MAX_MSG_SIZE)
+            ) + ETH_HEADER_LEN
+        ];
         let mut addr_buffer_size: libc::socklen_t =
             std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
         let addr_ptr = unsafe {
@@ -161,29 +304,57 @@ impl DhcpSocket for DhcpRawSocket {
                             self.raw_fd, errno
                         ),
                     )
-                };
+                }
+                .with_source(std::io::Error::from(errno));
                 log::error!("{}", e);
                 return Err(e);
             }
             log::debug!("Raw socket received {:?}", &buffer[..rc as usize]);
-            Ok(buffer[..rc as usize].to_vec())
+            let mut data = Vec::new();
+            if !self.with_eth_header {
+                // Prepend dummy ethernet header, so the IP package could be
+                // parsed in the same way as ethernet frame.
+                data.resize(ETH_HEADER_LEN - ETHER_TYPE_IPV4.len(), 0);
+                data.extend_from_slice(&ETHER_TYPE_IPV4);
+            }
+            data.extend_from_slice(&buffer[..rc as usize]);
+            #[cfg(feature = "pcap")]
+            if let Some(pcap) = self.pcap.as_ref() {
+                pcap.write_eth(&data, true);
+            }
+            Ok(data)
         }
     }
 }
 
 fn create_raw_socket(
+    sock_type: libc::c_int,
     eth_protocol: libc::c_int,
 ) -> Result<libc::c_int, DhcpError> {
     unsafe {
         match libc::socket(
             libc::AF_PACKET,
-            libc::SOCK_RAW,
+            sock_type | libc::SOCK_CLOEXEC,
             eth_protocol.to_be() as libc::c_int,
         ) {
-            -1 => Err(DhcpError::new(
-                ErrorKind::Bug,
-                "libc::socket() failed with -1".to_string(),
-            )),
+            -1 => {
+                let errno = Errno::last();
+                let e = if errno == Errno::EPERM {
+                    DhcpError::new(
+                        ErrorKind::PermissionDenied,
+                        "CAP_NET_RAW is required for creating raw socket"
+                            .to_string(),
+                    )
+                } else {
+                    DhcpError::new(
+                        ErrorKind::Bug,
+                        format!("libc::socket() failed: {errno}"),
+                    )
+                }
+                .with_source(std::io::Error::from(errno));
+                log::error!("{}", e);
+                Err(e)
+            }
             fd => Ok(fd),
         }
     }
@@ -206,7 +377,11 @@ fn bind_raw_socket(
         sll_ifindex: iface_index,
         sll_hatype: libc::ARPHRD_ETHER as libc::c_ushort,
         sll_pkttype: PACKET_HOST as libc::c_uchar,
-        sll_halen: libc::ETH_ALEN as libc::c_uchar,
+        sll_halen: if mac_address.is_empty() {
+            0
+        } else {
+            libc::ETH_ALEN as libc::c_uchar
+        },
         sll_addr,
     };
     unsafe {
@@ -221,11 +396,13 @@ fn bind_raw_socket(
         ) {
             0 => Ok(()),
             rc => {
+                let errno = Errno::last();
                 libc::close(fd);
                 Err(DhcpError::new(
                     ErrorKind::Bug,
-                    format!("Failed to bind socket: {rc}"),
-                ))
+                    format!("Failed to bind socket: {rc}, {errno}"),
+                )
+                .with_source(std::io::Error::from(errno)))
             }
         }
     }
@@ -234,6 +411,10 @@ fn bind_raw_socket(
 #[derive(Debug)]
 pub(crate) struct DhcpUdpSocket {
     socket: UdpSocket,
+    // UDP payload size could be received
+    recv_size: usize,
+    #[cfg(feature = "pcap")]
+    pcap: Option<Arc<DhcpPcapWriter>>,
 }
 
 impl std::os::unix::io::AsRawFd for DhcpUdpSocket {
@@ -244,18 +425,22 @@ impl std::os::unix::io::AsRawFd for DhcpUdpSocket {
 
 impl DhcpUdpSocket {
     pub(crate) fn new(
-        iface_name: &str,
+        config: &DhcpV4Config,
         src_ip: &Ipv4Addr,
         dst_ip: &Ipv4Addr,
-        socket_timeout: u32,
     ) -> Result<Self, DhcpError> {
-        let socket = UdpSocket::bind(format!(
-            "{}:{}",
-            src_ip,
-            0 // Use random source port
-        ))?;
+        let socket_timeout = config.socket_timeout;
+        let socket = if let Some(fd) = config.udp_socket_fd {
+            // Socket pre-opened by caller is expected to be bound already
+            unsafe { UdpSocket::from_raw_fd(dup_fd(fd)?) }
+        } else {
+            new_udp_socket(config, src_ip)?
+        };
+        let fd = socket.as_raw_fd();
         log::debug!("UDP socket bind to {:?}", socket);
-        bind_socket_to_iface(socket.as_raw_fd(), iface_name)?;
+        if config.dscp != 0 {
+            set_socket_dscp(fd, config.dscp, false)?;
+        }
         socket.set_read_timeout(Some(std::time::Duration::from_secs(
             socket_timeout.into(),
         )))?;
@@ -264,21 +449,65 @@ impl DhcpUdpSocket {
         )))?;
         socket.connect(format!("{}:{}", dst_ip, dhcproto::v4::SERVER_PORT))?;
 
-        Ok(Self { socket })
+        Ok(Self {
+            socket,
+            recv_size: udp_payload_size(
+                config.max_msg_size.unwrap_or(DEFAULT_MAX_MSG_SIZE),
+                IPV4_HEADER_LEN,
+            ),
+            #[cfg(feature = "pcap")]
+            pcap: None,
+        })
     }
 
-    pub(crate) fn new_v6(
-        iface_index: u32,
-        src_ip: &Ipv6Addr,
-        socket_timeout: u32,
+    pub(crate) fn new_v6(config: &DhcpV6Config) -> Result<Self, DhcpError> {
+        let socket = if let Some(fd) = config.udp_socket_fd {
+            // Socket pre-opened by caller is expected to be bound already
+            unsafe { UdpSocket::from_raw_fd(dup_fd(fd)?) }
+        } else {
+            UdpSocket::bind(SocketAddrV6::new(
+                config.src_ip,
+                dhcproto::v6::CLIENT_PORT,
+                0,
+                config.iface_index,
+            ))?
+        };
+        Self::from_v6_socket(
+            socket,
+            config.dscp,
+            config.socket_timeout,
+            config.max_msg_size,
+        )
+    }
+
+    // UDP socket bound to DHCPv6 client port for RFC 7341 DHCP 4o6
+    pub(crate) fn new_dhcp4o6(
+        config: &DhcpV4Config,
     ) -> Result<Self, DhcpError> {
         let socket = UdpSocket::bind(SocketAddrV6::new(
-            *src_ip,
+            config.src_ip6,
             dhcproto::v6::CLIENT_PORT,
             0,
-            iface_index,
+            config.iface_index,
         ))?;
+        Self::from_v6_socket(
+            socket,
+            config.dscp,
+            config.socket_timeout,
+            config.max_msg_size.unwrap_or(DEFAULT_MAX_MSG_SIZE),
+        )
+    }
+
+    fn from_v6_socket(
+        socket: UdpSocket,
+        dscp: u8,
+        socket_timeout: u32,
+        max_msg_size: u16,
+    ) -> Result<Self, DhcpError> {
         log::debug!("UDP socket bind to {:?}", socket);
+        if dscp != 0 {
+            set_socket_dscp(socket.as_raw_fd(), dscp, true)?;
+        }
         socket.set_read_timeout(Some(std::time::Duration::from_secs(
             socket_timeout.into(),
         )))?;
@@ -286,7 +515,22 @@ impl DhcpUdpSocket {
             socket_timeout.into(),
         )))?;
 
-        Ok(Self { socket })
+        Ok(Self {
+            socket,
+            recv_size: udp_payload_size(max_msg_size, IPV6_HEADER_LEN),
+            #[cfg(feature = "pcap")]
+            pcap: None,
+        })
+    }
+
+    // Store packets of this socket into specified pcap file
+    #[cfg(feature = "pcap")]
+    pub(crate) fn with_pcap(
+        mut self,
+        pcap_file: Option<&str>,
+    ) -> Result<Self, DhcpError> {
+        self.pcap = DhcpPcapWriter::open(pcap_file)?;
+        Ok(self)
     }
 
     pub(crate) fn send_to_v6(
@@ -294,14 +538,18 @@ impl DhcpUdpSocket {
         dst_ip: &Ipv6Addr,
         buff: &[u8],
     ) -> Result<(), DhcpError> {
-        self.socket.send_to(
-            buff,
-            SocketAddrV6::new(*dst_ip, dhcproto::v6::SERVER_PORT, 0, 0),
-        )?;
+        let dst = SocketAddrV6::new(*dst_ip, dhcproto::v6::SERVER_PORT, 0, 0);
+        self.socket.send_to(buff, dst)?;
+        #[cfg(feature = "pcap")]
+        if let Some(pcap) = self.pcap.as_ref() {
+            pcap.write_udp(self.socket.local_addr()?, dst.into(), buff, false);
+        }
         Ok(())
     }
 }
 
+impl sealed::Sealed for DhcpUdpSocket {}
+
 impl DhcpSocket for DhcpUdpSocket {
     fn is_raw(&self) -> bool {
         false
@@ -309,14 +557,217 @@ impl DhcpSocket for DhcpUdpSocket {
 
     fn send(&self, pkg: &[u8]) -> Result<(), DhcpError> {
         self.socket.send(pkg)?;
+        #[cfg(feature = "pcap")]
+        if let Some(pcap) = self.pcap.as_ref() {
+            pcap.write_udp(
+                self.socket.local_addr()?,
+                self.socket.peer_addr()?,
+                pkg,
+                false,
+            );
+        }
         Ok(())
     }
 
     fn recv(&self) -> Result<Vec<u8>, DhcpError> {
-        // TODO: Add support of `Maximum DHCP Message Size` option
+        Ok(self.recv_from()?.0)
+    }
+
+    fn recv_from(&self) -> Result<(Vec<u8>, Option<SocketAddr>), DhcpError> {
+        let mut buffer = vec![0u8; self.recv_size];
+        let (received, src) = self.socket.recv_from(&mut buffer)?;
+        #[cfg(feature = "pcap")]
+        if let Some(pcap) = self.pcap.as_ref() {
+            pcap.write_udp(
+                src,
+                self.socket.local_addr()?,
+                &buffer[..received],
+                true,
+            );
+        }
+        buffer.truncate(received);
+        Ok((buffer, Some(src)))
+    }
+}
+
+// Raw ICMPv6 socket for Neighbor Discovery
+#[derive(Debug)]
+pub(crate) struct DhcpIcmpV6Socket {
+    fd: libc::c_int,
+    iface_index: u32,
+}
+
+impl std::os::unix::io::AsRawFd for DhcpIcmpV6Socket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd as RawFd
+    }
+}
+
+impl Drop for DhcpIcmpV6Socket {
+    fn drop(&mut self) {
+        if self.fd >= 0 {
+            unsafe {
+                libc::close(self.fd);
+            }
+        }
+    }
+}
+
+impl DhcpIcmpV6Socket {
+    pub(crate) fn new(
+        iface_name: &str,
+        iface_index: u32,
+        socket_timeout: u32,
+    ) -> Result<Self, DhcpError> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_INET6,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                libc::IPPROTO_ICMPV6,
+            )
+        };
+        if fd < 0 {
+            let errno = Errno::last();
+            let e = if errno == Errno::EPERM {
+                DhcpError::new(
+                    ErrorKind::PermissionDenied,
+                    "CAP_NET_RAW is required for creating ICMPv6 socket"
+                        .to_string(),
+                )
+            } else {
+                DhcpError::new(
+                    ErrorKind::Bug,
+                    format!("Failed to create ICMPv6 socket: {errno}"),
+                )
+            }
+            .with_source(std::io::Error::from(errno));
+            log::error!("{}", e);
+            return Err(e);
+        }
+        // Socket will be closed by Drop on failure
+        let socket = Self { fd, iface_index };
+        bind_socket_to_iface(fd, iface_name)?;
+        // RFC 4861: Neighbor Discovery messages are sent with hop limit 255
+        for opt in [libc::IPV6_UNICAST_HOPS, libc::IPV6_MULTICAST_HOPS] {
+            set_socket_opt_int(fd, libc::IPPROTO_IPV6, opt, 255, "hop limit")?;
+        }
+        // Hop limit of received packets is required to validate Neighbor
+        // Discovery messages
+        set_socket_opt_int(
+            fd,
+            libc::IPPROTO_IPV6,
+            libc::IPV6_RECVHOPLIMIT,
+            1,
+            "IPV6_RECVHOPLIMIT",
+        )?;
+        set_socket_timeout(fd, socket_timeout)?;
+        log::debug!("ICMPv6 socket created {}", fd);
+        Ok(socket)
+    }
+
+    pub(crate) fn send_to(
+        &self,
+        dst_ip: &Ipv6Addr,
+        buff: &[u8],
+    ) -> Result<(), DhcpError> {
+        let dst_addr = libc::sockaddr_in6 {
+            sin6_family: libc::AF_INET6 as libc::sa_family_t,
+            sin6_port: 0,
+            sin6_flowinfo: 0,
+            sin6_addr: libc::in6_addr {
+                s6_addr: dst_ip.octets(),
+            },
+            sin6_scope_id: self.iface_index,
+        };
+        let sent_bytes = unsafe {
+            libc::sendto(
+                self.fd,
+                buff.as_ptr() as *const libc::c_void,
+                buff.len(),
+                0, // flags
+                (&dst_addr as *const libc::sockaddr_in6)
+                    as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t,
+            )
+        };
+        if sent_bytes <= 0 {
+            let errno = Errno::last();
+            let e = DhcpError::new(
+                ErrorKind::Bug,
+                format!(
+                    "Failed to send data to ICMPv6 socket {}: {}",
+                    self.fd, errno,
+                ),
+            )
+            .with_source(std::io::Error::from(errno));
+            log::error!("{}", e);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn recv(&self) -> Result<Vec<u8>, DhcpError> {
+        self.recv_from().map(|(data, _, _)| data)
+    }
+
+    // Return ICMPv6 message, its source address and IPv6 hop limit
+    pub(crate) fn recv_from(
+        &self,
+    ) -> Result<(Vec<u8>, Ipv6Addr, Option<u8>), DhcpError> {
         let mut buffer = [0u8; 1500];
-        let received = self.socket.recv(&mut buffer)?;
-        Ok(buffer[..received].to_vec())
+        let mut src_addr: libc::sockaddr_in6 = unsafe { std::mem::zeroed() };
+        // u64 for the alignment required by cmsghdr
+        let mut cmsg_buffer = [0u64; 8];
+        let mut iov = libc::iovec {
+            iov_base: buffer.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buffer.len(),
+        };
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_name =
+            (&mut src_addr as *mut libc::sockaddr_in6) as *mut libc::c_void;
+        msg.msg_namelen =
+            std::mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t;
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = cmsg_buffer.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = std::mem::size_of_val(&cmsg_buffer) as _;
+
+        let rc = unsafe { libc::recvmsg(self.fd, &mut msg, 0) };
+        if rc <= 0 {
+            let errno = Errno::last();
+            let e = DhcpError::new(
+                ErrorKind::Bug,
+                format!(
+                    "Failed to recv from ICMPv6 socket {}: {}",
+                    self.fd, errno
+                ),
+            )
+            .with_source(std::io::Error::from(errno));
+            log::error!("{}", e);
+            return Err(e);
+        }
+
+        let mut hop_limit = None;
+        // Safe as the msghdr is filled by kernel
+        unsafe {
+            let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+            while !cmsg.is_null() {
+                if (*cmsg).cmsg_level == libc::IPPROTO_IPV6
+                    && (*cmsg).cmsg_type == libc::IPV6_HOPLIMIT
+                {
+                    let value = std::ptr::read_unaligned(
+                        libc::CMSG_DATA(cmsg) as *const libc::c_int
+                    );
+                    hop_limit = u8::try_from(value).ok();
+                }
+                cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+            }
+        }
+        Ok((
+            buffer[..rc as usize].to_vec(),
+            Ipv6Addr::from(src_addr.sin6_addr.s6_addr),
+            hop_limit,
+        ))
     }
 }
 
@@ -349,7 +800,8 @@ fn set_socket_timeout(fd: libc::c_int, timeout: u32) -> Result<(), DhcpError> {
                     "Failed to set the send timeout SO_SNDTIMEO to \
                     socket {fd}: {rc}"
                 ),
-            ));
+            )
+            .with_source(std::io::Error::last_os_error()));
         }
         let rc = libc::setsockopt(
             fd,
@@ -365,7 +817,8 @@ fn set_socket_timeout(fd: libc::c_int, timeout: u32) -> Result<(), DhcpError> {
                     "Failed to set the recv timeout SO_RCVTIMEO to \
                     socket {fd}: {rc}"
                 ),
-            );
+            )
+            .with_source(std::io::Error::last_os_error());
             log::error!("{}", e);
             return Err(e);
         }
@@ -373,6 +826,159 @@ fn set_socket_timeout(fd: libc::c_int, timeout: u32) -> Result<(), DhcpError> {
     Ok(())
 }
 
+fn set_socket_opt_int(
+    fd: RawFd,
+    level: libc::c_int,
+    opt: libc::c_int,
+    value: libc::c_int,
+    opt_name: &str,
+) -> Result<(), DhcpError> {
+    let rc = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            opt,
+            (&value as *const libc::c_int) as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if rc < 0 {
+        let errno = Errno::last();
+        let e = DhcpError::new(
+            ErrorKind::Bug,
+            format!("Failed to set {opt_name} {value} to socket {fd}: {errno}"),
+        )
+        .with_source(std::io::Error::from(errno));
+        log::error!("{}", e);
+        return Err(e);
+    }
+    Ok(())
+}
+
+// DSCP is the upper 6 bits of IPv4 TOS and IPv6 Traffic Class
+fn set_socket_dscp(
+    fd: RawFd,
+    dscp: u8,
+    is_ipv6: bool,
+) -> Result<(), DhcpError> {
+    let value = libc::c_int::from(dscp) << 2;
+    if is_ipv6 {
+        set_socket_opt_int(
+            fd,
+            libc::IPPROTO_IPV6,
+            libc::IPV6_TCLASS,
+            value,
+            "IPV6_TCLASS",
+        )
+    } else {
+        set_socket_opt_int(fd, libc::IPPROTO_IP, libc::IP_TOS, value, "IP_TOS")
+    }
+}
+
+fn udp_payload_size(max_msg_size: u16, ip_header_len: usize) -> usize {
+    usize::from(max_msg_size).saturating_sub(ip_header_len + UDP_HEADER_LEN)
+}
+
+fn new_udp_socket(
+    config: &DhcpV4Config,
+    src_ip: &Ipv4Addr,
+) -> Result<UdpSocket, DhcpError> {
+    let fd = unsafe {
+        libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0)
+    };
+    if fd < 0 {
+        let errno = Errno::last();
+        let e = DhcpError::new(
+            ErrorKind::Bug,
+            format!("Failed to create UDP socket: {errno}"),
+        )
+        .with_source(std::io::Error::from(errno));
+        log::error!("{}", e);
+        return Err(e);
+    }
+    // The UdpSocket takes the ownership of fd and close it on drop
+    let socket = unsafe { UdpSocket::from_raw_fd(fd) };
+    bind_socket_to_iface(fd, config.iface_name.as_str())?;
+    if config.udp_reuse_addr {
+        for (opt, opt_name) in [
+            (libc::SO_REUSEADDR, "SO_REUSEADDR"),
+            (libc::SO_REUSEPORT, "SO_REUSEPORT"),
+        ] {
+            set_socket_opt_int(fd, libc::SOL_SOCKET, opt, 1, opt_name)?;
+        }
+    }
+    bind_udp_socket(fd, src_ip, dhcproto::v4::CLIENT_PORT)?;
+    Ok(socket)
+}
+
+fn dup_fd(fd: RawFd) -> Result<RawFd, DhcpError> {
+    let new_fd = unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) };
+    if new_fd < 0 {
+        let errno = Errno::last();
+        let e = DhcpError::new(
+            ErrorKind::InvalidArgument,
+            format!("Failed to duplicate socket fd {fd}: {errno}"),
+        )
+        .with_source(std::io::Error::from(errno));
+        log::error!("{}", e);
+        return Err(e);
+    }
+    Ok(new_fd)
+}
+
+fn bind_udp_socket(
+    fd: RawFd,
+    src_ip: &Ipv4Addr,
+    port: u16,
+) -> Result<(), DhcpError> {
+    let addr = libc::sockaddr_in {
+        sin_family: libc::AF_INET as libc::sa_family_t,
+        sin_port: port.to_be(),
+        sin_addr: libc::in_addr {
+            s_addr: u32::from_ne_bytes(src_ip.octets()),
+        },
+        sin_zero: [0; 8],
+    };
+    let rc = unsafe {
+        libc::bind(
+            fd,
+            (&addr as *const libc::sockaddr_in) as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        )
+    };
+    if rc != 0 {
+        let errno = Errno::last();
+        let e = match errno {
+            Errno::EADDRINUSE => DhcpError::new(
+                ErrorKind::AddressInUse,
+                format!(
+                    "UDP port {src_ip}:{port} is used by another DHCP client, \
+                    please stop it or enable \
+                    DhcpV4Config::set_udp_reuse_addr() if it is expected \
+                    to share the port"
+                ),
+            ),
+            Errno::EACCES | Errno::EPERM => DhcpError::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "CAP_NET_BIND_SERVICE is required for binding UDP port \
+                    {src_ip}:{port}"
+                ),
+            ),
+            _ => DhcpError::new(
+                ErrorKind::Bug,
+                format!(
+                    "Failed to bind UDP socket to {src_ip}:{port}: {errno}"
+                ),
+            ),
+        }
+        .with_source(std::io::Error::from(errno));
+        log::error!("{}", e);
+        return Err(e);
+    }
+    Ok(())
+}
+
 fn bind_socket_to_iface(fd: RawFd, iface_name: &str) -> Result<(), DhcpError> {
     let iface_name_cstr = CString::new(iface_name)?;
 
@@ -385,14 +991,15 @@ fn bind_socket_to_iface(fd: RawFd, iface_name: &str) -> Result<(), DhcpError> {
             std::mem::size_of::<CString>() as libc::socklen_t,
         );
         if rc != 0 {
+            let errno = Errno::last();
             let e = DhcpError::new(
                 ErrorKind::Bug,
                 format!(
-                    "Failed to bind socket to interface {} with error: {}",
-                    iface_name,
-                    Errno::last(),
+                    "Failed to bind socket to interface {iface_name} with \
+                    error: {errno}"
                 ),
-            );
+            )
+            .with_source(std::io::Error::from(errno));
             log::error!("{}", e);
             return Err(e);
         }
@@ -8,6 +8,7 @@ use nix::sys::time::TimeSpec;
 use nix::sys::timerfd::{
     ClockId::CLOCK_BOOTTIME, Expiration, TimerFd, TimerFlags, TimerSetTimeFlags,
 };
+use nix::time::{clock_gettime, ClockId};
 
 use crate::{DhcpError, ErrorKind};
 
@@ -24,8 +25,8 @@ impl AsRawFd for DhcpTimerFd {
 
 impl DhcpTimerFd {
     pub(crate) fn new(time: Duration) -> Result<Self, DhcpError> {
-        let fd =
-            TimerFd::new(CLOCK_BOOTTIME, TimerFlags::empty()).map_err(|e| {
+        let fd = TimerFd::new(CLOCK_BOOTTIME, TimerFlags::TFD_CLOEXEC)
+            .map_err(|e| {
                 let e = DhcpError::new(
                     ErrorKind::Bug,
                     format!("Failed to create timerfd {e}"),
@@ -33,6 +34,9 @@ impl DhcpTimerFd {
                 log::error!("{}", e);
                 e
             })?;
+        // Zero expiration disarms the timer, use the minimum value instead
+        // so that event is triggered immediately.
+        let time = time.max(Duration::from_nanos(1));
         fd.set(
             Expiration::OneShot(TimeSpec::from_duration(time)),
             TimerSetTimeFlags::empty(),
@@ -52,4 +56,35 @@ impl DhcpTimerFd {
         );
         Ok(Self { fd })
     }
+
+    /// Time left before timer expires, zero if already expired.
+    pub(crate) fn remaining(&self) -> Result<Duration, DhcpError> {
+        match self.fd.get() {
+            Ok(Some(Expiration::OneShot(t))) => Ok(Duration::from(t)),
+            Ok(_) => Ok(Duration::ZERO),
+            Err(e) => {
+                let e = DhcpError::new(
+                    ErrorKind::Bug,
+                    format!("Failed to get timerfd {e}"),
+                );
+                log::error!("{}", e);
+                Err(e)
+            }
+        }
+    }
+}
+
+// Unlike `std::time::Instant`(CLOCK_MONOTONIC), CLOCK_BOOTTIME includes the
+// time elapsed during system suspend.
+pub(crate) fn boot_time() -> Result<Duration, DhcpError> {
+    clock_gettime(ClockId::CLOCK_BOOTTIME)
+        .map(Duration::from)
+        .map_err(|e| {
+            let e = DhcpError::new(
+                ErrorKind::Bug,
+                format!("Failed to get CLOCK_BOOTTIME {e}"),
+            );
+            log::error!("{}", e);
+            e
+        })
 }
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// Structured spans and events of the `tracing` feature, everything here is
+// no-op without it. Each client initiated message exchange gets its own root
+// span holding the interface, transaction ID and, once replied, the server,
+// while every DHCP message sent or received is an event within that span.
+
+use std::fmt::Display;
+
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub(crate) struct DhcpTraceSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+// Keeps the span entered till dropped
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
+#[must_use]
+pub(crate) struct DhcpTraceGuard {
+    #[cfg(feature = "tracing")]
+    _entered: tracing::span::EnteredSpan,
+}
+
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
+impl DhcpTraceSpan {
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn new(
+        proto: &'static str,
+        kind: impl Display,
+        iface: &str,
+        xid: impl Display,
+    ) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                parent: None,
+                "dhcp_transaction",
+                proto,
+                kind = %kind,
+                iface,
+                xid = %xid,
+                server = tracing::field::Empty,
+            ),
+        }
+    }
+
+    pub(crate) fn enter(&self) -> DhcpTraceGuard {
+        DhcpTraceGuard {
+            #[cfg(feature = "tracing")]
+            _entered: self.span.clone().entered(),
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn trace_msg_sent(msg_type: impl Display, xid: impl Display) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(msg_type = %msg_type, xid = %xid, "DHCP message sent");
+}
+
+// Also record the server to the current transaction span
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn trace_msg_received(
+    msg_type: impl Display,
+    xid: impl Display,
+    server: Option<impl Display>,
+) {
+    #[cfg(feature = "tracing")]
+    if let Some(server) = server {
+        tracing::Span::current()
+            .record("server", tracing::field::display(&server));
+        tracing::debug!(
+            msg_type = %msg_type,
+            xid = %xid,
+            server = %server,
+            "DHCP message received"
+        );
+    } else {
+        tracing::debug!(
+            msg_type = %msg_type,
+            xid = %xid,
+            "DHCP message received"
+        );
+    }
+}
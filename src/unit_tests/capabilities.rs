@@ -0,0 +1,16 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{capabilities, DhcpV4MessageType, DhcpV6MessageType};
+
+#[test]
+fn test_capabilities() {
+    let caps = capabilities();
+    assert!(caps.v4_option_codes.contains(&121));
+    assert!(caps.v4_option_codes.windows(2).all(|w| w[0] < w[1]));
+    assert!(caps.v6_option_codes.contains(&23));
+    assert!(caps.v6_option_codes.windows(2).all(|w| w[0] < w[1]));
+    assert!(caps.v4_message_types.contains(&DhcpV4MessageType::Inform));
+    assert!(caps.v6_message_types.contains(&DhcpV6MessageType::DECLINE));
+    assert_eq!(caps.features.contains(&"client"), cfg!(feature = "client"));
+    assert_eq!(caps.features.contains(&"ra"), cfg!(feature = "ra"));
+}
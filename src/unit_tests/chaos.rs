@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use crate::DhcpChaosPolicy;
+
+const PKG: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+fn send_all(policy: &DhcpChaosPolicy, pkgs: &[&[u8]]) -> Vec<Vec<u8>> {
+    let sent = RefCell::new(Vec::new());
+    for pkg in pkgs {
+        policy
+            .send_with(pkg, 2, |p| {
+                sent.borrow_mut().push(p.to_vec());
+                Ok(())
+            })
+            .unwrap();
+    }
+    sent.into_inner()
+}
+
+#[test]
+fn test_chaos_no_fault() {
+    let policy = DhcpChaosPolicy::new(1);
+    assert_eq!(send_all(&policy, &[&PKG]), vec![PKG.to_vec()]);
+}
+
+#[test]
+fn test_chaos_drop_and_duplicate() {
+    let mut policy = DhcpChaosPolicy::new(1);
+    policy.set_drop(100);
+    assert!(send_all(&policy, &[&PKG, &PKG]).is_empty());
+
+    let mut policy = DhcpChaosPolicy::new(1);
+    policy.set_duplicate(100);
+    assert_eq!(send_all(&policy, &[&PKG]), vec![PKG.to_vec(), PKG.to_vec()]);
+}
+
+#[test]
+fn test_chaos_reorder() {
+    let mut policy = DhcpChaosPolicy::new(1);
+    policy.set_reorder(100);
+    assert_eq!(
+        send_all(&policy, &[&[1], &[2], &[3]]),
+        vec![vec![2], vec![1]]
+    );
+}
+
+#[test]
+fn test_chaos_corrupt_payload_only() {
+    let mut policy = DhcpChaosPolicy::new(1);
+    policy.set_corrupt(100);
+    for sent in send_all(&policy, &[&PKG[..]; 10]) {
+        assert_eq!(&sent[..2], &PKG[..2]);
+        assert_ne!(sent, PKG.to_vec());
+    }
+}
+
+#[test]
+fn test_chaos_delay() {
+    let mut policy = DhcpChaosPolicy::new(1);
+    policy.set_delay(100, Duration::from_millis(50));
+    let now = Instant::now();
+    assert_eq!(send_all(&policy, &[&PKG]), vec![PKG.to_vec()]);
+    assert!(now.elapsed() >= Duration::from_millis(50));
+}
+
+#[test]
+fn test_chaos_reproducible_by_seed() {
+    let pkgs: Vec<&[u8]> = vec![&PKG; 20];
+    let mut policy_a = DhcpChaosPolicy::new(42);
+    policy_a.set_drop(50).set_corrupt(50);
+    let mut policy_b = DhcpChaosPolicy::new(42);
+    policy_b.set_drop(50).set_corrupt(50);
+    assert_eq!(send_all(&policy_a, &pkgs), send_all(&policy_b, &pkgs));
+}
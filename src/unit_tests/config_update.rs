@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{DhcpV4Config, DhcpV6Config, DhcpV6IaType, ErrorKind};
+
+#[test]
+fn test_dhcpv4_config_update() {
+    let mut config = DhcpV4Config::new("eth1");
+    let mut new_config = config.clone();
+    new_config.set_timeout(30).set_host_name("host-a");
+    config.update(&new_config).unwrap();
+    assert_eq!(config, new_config);
+
+    new_config.set_dscp(10);
+    assert_eq!(
+        config.update(&new_config).unwrap_err().kind(),
+        ErrorKind::InvalidArgument
+    );
+    assert_eq!(config.host_name(), "host-a");
+}
+
+#[test]
+fn test_dhcpv6_config_update() {
+    let mut config =
+        DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    let mut new_config = config.clone();
+    new_config.set_request_opts(&[23]).set_renew_timeout(60);
+    config.update(&new_config).unwrap();
+    assert_eq!(config.request_opts(), &[23]);
+
+    new_config.set_host_name("host-a..example.org");
+    assert!(config.update(&new_config).is_err());
+
+    let mut new_config = config.clone();
+    new_config.set_iaid(1);
+    assert!(config.update(&new_config).is_err());
+}
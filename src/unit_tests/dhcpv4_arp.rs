@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv4Addr;
+
+use crate::dhcpv4::{
+    gen_arp_reply, gen_arp_request, parse_arp_reply, parse_arp_request,
+};
+
+const PROXY_MAC: [u8; 6] = [2, 0, 0, 0, 0, 1];
+const PEER_MAC: [u8; 6] = [2, 0, 0, 0, 0, 2];
+const LEASE_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 99);
+const PEER_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+
+#[test]
+fn test_dhcpv4_arp_reply_to_request() {
+    let request = gen_arp_request(&PEER_MAC, &PEER_IP, &LEASE_IP);
+    assert_eq!(parse_arp_reply(&request), None);
+    assert_eq!(
+        parse_arp_request(&request),
+        Some((PEER_MAC, PEER_IP, LEASE_IP))
+    );
+
+    let reply = gen_arp_reply(&PROXY_MAC, &LEASE_IP, &PEER_MAC, &PEER_IP);
+    // Unicast to requester
+    assert_eq!(&reply[..6], &PEER_MAC);
+    assert_eq!(parse_arp_request(&reply), None);
+    assert_eq!(parse_arp_reply(&reply), Some((LEASE_IP, PROXY_MAC)));
+}
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv4Addr;
+
+use dhcproto::{v4, Decodable, Decoder};
+
+use crate::{
+    dhcpv4::{apply_conformance_policy, check_client_msg, check_server_msg},
+    DhcpV4Config, DhcpV4ConformancePolicy, DhcpV4Lease, DhcpV4Message,
+    DhcpV4MessageType, ErrorKind, NamePolicy,
+};
+
+const CLIENT_MAC: &str = "02:00:00:00:00:01";
+const LEASE_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 99);
+const SRV_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+
+fn gen_lease() -> DhcpV4Lease {
+    DhcpV4Lease {
+        yiaddr: LEASE_IP,
+        srv_id: SRV_IP,
+        lease_time: 3600,
+        ..Default::default()
+    }
+}
+
+fn gen_client_msg(
+    mac: &str,
+    msg_type: &DhcpV4MessageType,
+    renew_or_rebind: bool,
+) -> v4::Message {
+    let mut config = DhcpV4Config::new("eth1");
+    config
+        .set_conformance_policy(DhcpV4ConformancePolicy::Strict)
+        .src_mac = mac.to_string();
+    let mut msg = DhcpV4Message::new(&config, msg_type.clone(), 1);
+    msg.set_secs(10);
+    if *msg_type != DhcpV4MessageType::Discovery {
+        msg.load_lease(gen_lease()).renew_or_rebind(renew_or_rebind);
+    }
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    v4::Message::decode(&mut Decoder::new(&pkg)).unwrap()
+}
+
+fn gen_server_pkg(msg_type: v4::MessageType, secs: u16) -> Vec<u8> {
+    let mut pkg = vec![0u8; 236];
+    // BOOTREPLY
+    pkg[0] = 2;
+    pkg[1] = 1;
+    pkg[2] = 6;
+    pkg[8..10].copy_from_slice(&secs.to_be_bytes());
+    if msg_type != v4::MessageType::Nak {
+        pkg[16..20].copy_from_slice(&LEASE_IP.octets());
+    }
+    pkg.extend_from_slice(&[99, 130, 83, 99]);
+    pkg.extend_from_slice(&[53, 1, u8::from(msg_type)]);
+    pkg.extend_from_slice(&[54, 4]);
+    pkg.extend_from_slice(&SRV_IP.octets());
+    pkg.push(255);
+    pkg
+}
+
+// RFC 2131: 4.4.1 Table 5: Fields and options used by DHCP clients
+#[test]
+fn test_dhcpv4_conformance_client_matrix() {
+    // (msg_type, renew_or_rebind, ciaddr, secs, broadcast without MAC)
+    let matrix = [
+        (DhcpV4MessageType::Discovery, false, None, 10, true),
+        (DhcpV4MessageType::Request, false, None, 10, true),
+        (DhcpV4MessageType::Request, true, Some(LEASE_IP), 10, false),
+        (DhcpV4MessageType::Decline, false, None, 0, false),
+        (DhcpV4MessageType::Release, false, Some(LEASE_IP), 0, false),
+        (DhcpV4MessageType::Inform, false, Some(LEASE_IP), 10, false),
+    ];
+    for (msg_type, renew_or_rebind, ciaddr, secs, broadcast) in matrix {
+        for mac in [CLIENT_MAC, ""] {
+            let msg = gen_client_msg(mac, &msg_type, renew_or_rebind);
+            let desc = format!("{msg_type} renew {renew_or_rebind} MAC {mac}");
+            assert_eq!(msg.opcode(), v4::Opcode::BootRequest, "{desc}");
+            assert_eq!(
+                msg.ciaddr(),
+                ciaddr.unwrap_or(Ipv4Addr::UNSPECIFIED),
+                "{desc}"
+            );
+            assert!(msg.yiaddr().is_unspecified(), "{desc}");
+            assert!(msg.siaddr().is_unspecified(), "{desc}");
+            assert!(msg.giaddr().is_unspecified(), "{desc}");
+            assert_eq!(msg.secs(), secs, "{desc}");
+            assert_eq!(
+                u16::from(msg.flags()),
+                if broadcast && mac.is_empty() {
+                    0x8000
+                } else {
+                    0
+                },
+                "{desc}"
+            );
+            assert!(
+                check_client_msg(&msg, ciaddr.is_some()).is_empty(),
+                "{desc}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_dhcpv4_conformance_client_violations() {
+    let mut msg = v4::Message::default();
+    msg.set_opcode(v4::Opcode::BootReply)
+        .set_ciaddr(LEASE_IP)
+        .set_giaddr(SRV_IP)
+        .set_secs(3)
+        .set_flags(v4::Flags::new(0x8001));
+    msg.opts_mut()
+        .insert(v4::DhcpOption::MessageType(v4::MessageType::Decline));
+    assert_eq!(check_client_msg(&msg, false).len(), 6);
+    assert_eq!(check_client_msg(&msg, true).len(), 5);
+}
+
+#[test]
+fn test_dhcpv4_conformance_emit_policy() {
+    // Release without client address
+    let lease = DhcpV4Lease {
+        yiaddr: Ipv4Addr::UNSPECIFIED,
+        ..gen_lease()
+    };
+    let mut config = DhcpV4Config::new("eth1");
+    let mut msg = DhcpV4Message::new(&config, DhcpV4MessageType::Release, 1);
+    msg.load_lease(lease.clone());
+    assert!(msg.to_dhcp_pkg().is_ok());
+
+    config.set_conformance_policy(DhcpV4ConformancePolicy::Warn);
+    let mut msg = DhcpV4Message::new(&config, DhcpV4MessageType::Release, 1);
+    msg.load_lease(lease.clone());
+    assert!(msg.to_dhcp_pkg().is_ok());
+
+    config.set_conformance_policy(DhcpV4ConformancePolicy::Strict);
+    let mut msg = DhcpV4Message::new(&config, DhcpV4MessageType::Release, 1);
+    msg.load_lease(lease);
+    let e = msg.to_dhcp_pkg().unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidArgument);
+}
+
+// RFC 2131: 4.3.1 Table 3: Fields and options used by DHCP servers
+#[test]
+fn test_dhcpv4_conformance_server_reply() {
+    for msg_type in [
+        v4::MessageType::Offer,
+        v4::MessageType::Ack,
+        v4::MessageType::Nak,
+    ] {
+        let pkg = gen_server_pkg(msg_type, 0);
+        let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+        assert!(msg.violations.is_empty(), "{msg_type:?}");
+    }
+
+    let pkg = gen_server_pkg(v4::MessageType::Offer, 5);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(msg.violations, vec!["secs 5 is not 0".to_string()]);
+    for (policy, is_ok) in [
+        (DhcpV4ConformancePolicy::Permissive, true),
+        (DhcpV4ConformancePolicy::Warn, true),
+        (DhcpV4ConformancePolicy::Strict, false),
+    ] {
+        let ret = apply_conformance_policy(
+            policy,
+            ErrorKind::InvalidDhcpServerReply,
+            "DHCP offer",
+            &msg.violations,
+        );
+        assert_eq!(ret.is_ok(), is_ok, "{policy:?}");
+    }
+
+    let mut msg = v4::Message::default();
+    msg.set_opcode(v4::Opcode::BootReply).set_yiaddr(LEASE_IP);
+    msg.opts_mut()
+        .insert(v4::DhcpOption::MessageType(v4::MessageType::Nak));
+    assert_eq!(
+        check_server_msg(&msg),
+        vec![format!("yiaddr {LEASE_IP} is not 0")]
+    );
+}
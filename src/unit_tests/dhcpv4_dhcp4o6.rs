@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    dhcpv4::{gen_dhcpv4_query, parse_dhcpv4_response},
+    ErrorKind,
+};
+
+const DHCPV4_PKG: [u8; 4] = [1, 2, 3, 4];
+
+#[test]
+fn test_dhcp4o6_query_flags() {
+    assert_eq!(
+        gen_dhcpv4_query(&DHCPV4_PKG, false),
+        vec![20, 0, 0, 0, 0, 87, 0, 4, 1, 2, 3, 4]
+    );
+    assert_eq!(
+        gen_dhcpv4_query(&DHCPV4_PKG, true),
+        vec![20, 0x80, 0, 0, 0, 87, 0, 4, 1, 2, 3, 4]
+    );
+}
+
+#[test]
+fn test_dhcp4o6_parse_response() {
+    // DHCPv4 Message option after unknown option
+    let data = [21, 0, 0, 0, 0, 99, 0, 1, 0xff, 0, 87, 0, 4, 1, 2, 3, 4];
+    assert_eq!(parse_dhcpv4_response(&data).unwrap(), DHCPV4_PKG.to_vec());
+
+    // DHCPv4-query is not a response
+    let data = gen_dhcpv4_query(&DHCPV4_PKG, false);
+    assert_eq!(
+        parse_dhcpv4_response(&data).unwrap_err().kind(),
+        ErrorKind::InvalidDhcpServerReply
+    );
+
+    // Truncated option
+    let data = [21, 0, 0, 0, 0, 87, 0, 5, 1, 2, 3, 4];
+    assert!(parse_dhcpv4_response(&data).is_err());
+}
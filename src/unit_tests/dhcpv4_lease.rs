@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv4Addr;
+
+use crate::{
+    DhcpV4ClasslessRoute, DhcpV4Config, DhcpV4Lease, DhcpV4LeaseCheckPolicy,
+    DhcpV4LeaseWarning,
+};
+
+fn gen_invalid_lease() -> DhcpV4Lease {
+    DhcpV4Lease {
+        yiaddr: Ipv4Addr::new(192, 0, 2, 99),
+        subnet_mask: Ipv4Addr::new(255, 255, 255, 0),
+        mtu: Some(500),
+        classless_routes: Some(vec![
+            // Valid on-link route
+            DhcpV4ClasslessRoute::new(
+                Ipv4Addr::new(198, 51, 100, 0),
+                24,
+                Ipv4Addr::UNSPECIFIED,
+            ),
+            // Router reachable via above on-link route
+            DhcpV4ClasslessRoute::new(
+                Ipv4Addr::new(203, 0, 113, 0),
+                24,
+                Ipv4Addr::new(198, 51, 100, 1),
+            ),
+            DhcpV4ClasslessRoute::new(
+                Ipv4Addr::UNSPECIFIED,
+                0,
+                Ipv4Addr::UNSPECIFIED,
+            ),
+            DhcpV4ClasslessRoute::new(
+                Ipv4Addr::new(10, 0, 0, 0),
+                8,
+                Ipv4Addr::new(172, 16, 0, 1),
+            ),
+        ]),
+        ..Default::default()
+    }
+}
+
+fn sanitize(
+    policy: DhcpV4LeaseCheckPolicy,
+    allow_default_route_on_link: bool,
+) -> DhcpV4Lease {
+    let mut config = DhcpV4Config::new("eth1");
+    config
+        .set_lease_check_policy(policy)
+        .set_allow_default_route_on_link(allow_default_route_on_link);
+    let mut lease = gen_invalid_lease();
+    lease.sanitize(&config);
+    lease
+}
+
+fn expected_warnings() -> Vec<DhcpV4LeaseWarning> {
+    vec![
+        DhcpV4LeaseWarning::InvalidMtu(500),
+        DhcpV4LeaseWarning::DefaultRouteOnLink,
+        DhcpV4LeaseWarning::RouterNotOnLink(DhcpV4ClasslessRoute::new(
+            Ipv4Addr::new(10, 0, 0, 0),
+            8,
+            Ipv4Addr::new(172, 16, 0, 1),
+        )),
+    ]
+}
+
+#[test]
+fn test_dhcpv4_lease_sanitize_strip() {
+    let lease = sanitize(DhcpV4LeaseCheckPolicy::Strip, false);
+    assert_eq!(lease.warnings, expected_warnings());
+    assert_eq!(lease.mtu, None);
+    assert_eq!(
+        lease.classless_routes,
+        Some(gen_invalid_lease().classless_routes.unwrap()[..2].to_vec())
+    );
+}
+
+#[test]
+fn test_dhcpv4_lease_sanitize_warn() {
+    let lease = sanitize(DhcpV4LeaseCheckPolicy::Warn, false);
+    assert_eq!(lease.warnings, expected_warnings());
+    assert_eq!(lease.mtu, Some(500));
+    assert_eq!(lease.classless_routes, gen_invalid_lease().classless_routes);
+}
+
+#[test]
+fn test_dhcpv4_lease_sanitize_ignore() {
+    let lease = sanitize(DhcpV4LeaseCheckPolicy::Ignore, false);
+    assert!(lease.warnings.is_empty());
+    assert_eq!(lease, gen_invalid_lease());
+}
+
+#[test]
+fn test_dhcpv4_lease_sanitize_allow_default_route_on_link() {
+    let lease = sanitize(DhcpV4LeaseCheckPolicy::Strip, true);
+    assert!(!lease
+        .warnings
+        .contains(&DhcpV4LeaseWarning::DefaultRouteOnLink));
+    assert_eq!(
+        lease.classless_routes,
+        Some(gen_invalid_lease().classless_routes.unwrap()[..3].to_vec())
+    );
+}
+
+#[test]
+fn test_dhcpv4_lease_sanitize_default_warn() {
+    let mut lease = gen_invalid_lease();
+    lease.sanitize(&DhcpV4Config::new("eth1"));
+    assert_eq!(lease.warnings, expected_warnings());
+    assert_eq!(lease.mtu, Some(500));
+    assert_eq!(lease.classless_routes, gen_invalid_lease().classless_routes);
+}
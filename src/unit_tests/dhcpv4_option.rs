@@ -0,0 +1,699 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv4Addr;
+
+use crate::{
+    DhcpV4ClasslessRoute, DhcpV4ClientProfile, DhcpV4Config, DhcpV4Lease,
+    DhcpV4Message, DhcpV4MessageType, DhcpV4OptionOrder, DhcpV4SipServers,
+    DhcpV4SixRd, DhcpV4VendorSpecificInfo, NamePolicy,
+};
+
+fn gen_offer_pkg(opts: &[u8]) -> Vec<u8> {
+    let mut pkg = vec![0u8; 236];
+    // BOOTREPLY
+    pkg[0] = 2;
+    pkg[1] = 1;
+    pkg[2] = 6;
+    // yiaddr
+    pkg[16..20].copy_from_slice(&[192, 0, 2, 99]);
+    pkg.extend_from_slice(&[99, 130, 83, 99]);
+    // DHCP Message Type: Offer
+    pkg.extend_from_slice(&[53, 1, 2]);
+    pkg.extend_from_slice(opts);
+    pkg.push(255);
+    pkg
+}
+
+#[test]
+fn test_dhcpv4_concat_non_adjacent_opts() {
+    let pkg = gen_offer_pkg(&[
+        6, 4, 192, 0, 2, 1, // DNS
+        54, 4, 192, 0, 2, 254, // Server ID
+        6, 8, 192, 0, 2, 2, 192, 0, 2, 3, // DNS
+    ]);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(msg.msg_type, DhcpV4MessageType::Offer);
+    let lease = msg.lease.unwrap();
+    assert_eq!(lease.srv_id, Ipv4Addr::new(192, 0, 2, 254));
+    assert_eq!(
+        lease.dns_srvs,
+        Some(vec![
+            Ipv4Addr::new(192, 0, 2, 1),
+            Ipv4Addr::new(192, 0, 2, 2),
+            Ipv4Addr::new(192, 0, 2, 3),
+        ])
+    );
+}
+
+#[test]
+fn test_dhcpv4_opts_in_overloaded_file() {
+    let mut pkg = gen_offer_pkg(&[
+        52, 1, 1, // Option Overload: file
+        3, 4, 192, 0, 2, 1, // Router
+    ]);
+    pkg[108..118].copy_from_slice(&[3, 4, 192, 0, 2, 2, 51, 2, 0, 0]);
+    pkg[118] = 255;
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    let lease = msg.lease.unwrap();
+    assert_eq!(
+        lease.gateways,
+        Some(vec![
+            Ipv4Addr::new(192, 0, 2, 1),
+            Ipv4Addr::new(192, 0, 2, 2)
+        ])
+    );
+}
+
+#[test]
+fn test_dhcpv4_emit_opts_order() {
+    let mut config = DhcpV4Config::new("eth1");
+    config.set_host_name("host-a").use_host_name_as_client_id();
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    let mut codes = Vec::new();
+    let mut pos = 240;
+    while pkg[pos] != 255 {
+        codes.push(pkg[pos]);
+        pos += 2 + pkg[pos + 1] as usize;
+    }
+    assert_eq!(codes, vec![53, 12, 55, 61]);
+    assert_eq!(pkg, msg.to_dhcp_pkg().unwrap());
+}
+
+#[test]
+fn test_dhcpv4_emit_long_extra_opt() {
+    let mut config = DhcpV4Config::new("eth1");
+    let data: Vec<u8> = (0..600).map(|i| (i % 256) as u8).collect();
+    config.add_extra_option(43, &data);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    let mut lens = Vec::new();
+    let mut emitted = Vec::new();
+    let mut pos = 240;
+    while pkg[pos] != 255 {
+        let len = pkg[pos + 1] as usize;
+        if pkg[pos] == 43 {
+            lens.push(len);
+            emitted.extend_from_slice(&pkg[pos + 2..pos + 2 + len]);
+        }
+        pos += 2 + len;
+    }
+    assert_eq!(lens, vec![255, 255, 90]);
+    assert_eq!(emitted, data);
+}
+
+#[test]
+fn test_dhcpv4_extra_opt_msg_type_rejected() {
+    let mut config = DhcpV4Config::new("eth1");
+    config.add_extra_option(53, &[3]);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    assert!(msg.to_dhcp_pkg().is_err());
+}
+
+#[test]
+fn test_dhcpv4_public_builder_round_trip() {
+    let config = DhcpV4Config::new("eth1");
+    let mut msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 9);
+    msg.add_option(60, b"mozim-test");
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    assert_eq!(&pkg[236..240], &[99, 130, 83, 99]);
+    let parsed = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(parsed.msg_type, DhcpV4MessageType::Discovery);
+    assert_eq!(parsed.xid(), 9);
+}
+
+#[test]
+fn test_dhcpv4_emit_max_msg_size() {
+    let mut config = DhcpV4Config::new("eth1");
+    config.set_max_msg_size(9000);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    let pos = pkg.windows(2).position(|w| w == [57, 2]).unwrap();
+    assert_eq!(&pkg[pos + 2..pos + 4], &9000u16.to_be_bytes());
+}
+
+#[test]
+fn test_dhcpv4_emit_preferred_ip() {
+    let mut config = DhcpV4Config::new("eth1");
+    config.set_preferred_ip(Ipv4Addr::new(192, 0, 2, 9));
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    let pos = pkg.windows(2).position(|w| w == [50, 4]).unwrap();
+    assert_eq!(&pkg[pos + 2..pos + 6], &[192, 0, 2, 9]);
+}
+
+fn get_opt_codes(pkg: &[u8]) -> Vec<u8> {
+    let mut codes = Vec::new();
+    let mut pos = 240;
+    while pkg[pos] != 255 {
+        codes.push(pkg[pos]);
+        pos += 2 + pkg[pos + 1] as usize;
+    }
+    codes
+}
+
+fn get_opt(pkg: &[u8], code: u8) -> Option<Vec<u8>> {
+    let mut pos = 240;
+    while pkg[pos] != 255 {
+        let len = pkg[pos + 1] as usize;
+        if pkg[pos] == code {
+            return Some(pkg[pos + 2..pos + 2 + len].to_vec());
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+#[test]
+fn test_dhcpv4_emit_opts_custom_order() {
+    let mut config = DhcpV4Config::new("eth1");
+    config
+        .set_host_name("host-a")
+        .use_host_name_as_client_id()
+        .add_extra_option(60, b"vendor")
+        .add_extra_option(43, &[1]);
+
+    config.set_option_order(DhcpV4OptionOrder::Insertion);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    assert_eq!(get_opt_codes(&pkg), vec![53, 55, 61, 12, 60, 43]);
+
+    config.set_option_order(DhcpV4OptionOrder::Custom(vec![61, 60, 53]));
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    assert_eq!(get_opt_codes(&pkg), vec![53, 61, 60, 55, 12, 43]);
+}
+
+#[test]
+fn test_dhcpv4_emit_request_opts_order() {
+    let mut config = DhcpV4Config::new("eth1");
+    config
+        .set_request_opts(&[1, 3, 6, 15, 100])
+        .set_request_timezone(true);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    assert_eq!(get_opt(&pkg, 55), Some(vec![1, 3, 6, 15, 100, 101]));
+}
+
+#[test]
+fn test_dhcpv4_client_profile() {
+    let mut config = DhcpV4Config::new("eth1");
+    config
+        .set_host_name("host-a")
+        .set_client_id(1, &[2, 0, 0, 0, 0, 1])
+        .set_client_profile(DhcpV4ClientProfile::Windows);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    assert_eq!(get_opt_codes(&pkg)[..4], [53, 61, 12, 60]);
+    assert_eq!(get_opt(&pkg, 60), Some(b"MSFT 5.0".to_vec()));
+    assert_eq!(
+        get_opt(&pkg, 55),
+        Some(vec![
+            1, 3, 6, 15, 31, 33, 43, 44, 46, 47, 119, 121, 249, 252
+        ])
+    );
+
+    // Switching profile should not leave vendor class of previous one
+    config.set_client_profile(DhcpV4ClientProfile::Dhclient);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let codes = get_opt_codes(&msg.to_dhcp_pkg().unwrap());
+    assert_eq!(codes[..3], [53, 12, 55]);
+    assert!(!codes.contains(&60));
+    assert!(!codes.contains(&61));
+
+    config.set_client_profile(DhcpV4ClientProfile::Android);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    assert_eq!(get_opt_codes(&pkg)[..4], [53, 57, 60, 55]);
+    assert_eq!(get_opt(&pkg, 60), Some(b"android-dhcp-13".to_vec()));
+}
+
+#[test]
+fn test_dhcpv4_clear_client_id() {
+    let mut config = DhcpV4Config::new("eth1");
+    config.set_client_id(0, b"host-a").clear_client_id();
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    assert!(!get_opt_codes(&msg.to_dhcp_pkg().unwrap()).contains(&61));
+
+    config.set_client_id(0, b"host-a");
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    assert!(get_opt_codes(&msg.to_dhcp_pkg().unwrap()).contains(&61));
+}
+
+#[test]
+fn test_dhcpv4_sip_srvs() {
+    let pkg = gen_offer_pkg(&[
+        120, 9, 1, 192, 0, 2, 5, 192, 0, 2, 6, // SIP Servers: addresses
+    ]);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(
+        msg.lease.unwrap().sip_srvs,
+        Some(DhcpV4SipServers::Addresses(vec![
+            Ipv4Addr::new(192, 0, 2, 5),
+            Ipv4Addr::new(192, 0, 2, 6),
+        ]))
+    );
+
+    let mut opt = vec![120, 0, 0];
+    opt.extend_from_slice(b"\x03sip\x07example\x03com\x00");
+    opt.extend_from_slice(b"\x04sip2\x07example\x03com\x00");
+    opt[1] = (opt.len() - 2) as u8;
+    let pkg = gen_offer_pkg(&opt);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(
+        msg.lease.unwrap().sip_srvs,
+        Some(DhcpV4SipServers::Domains(vec![
+            "sip.example.com".to_string(),
+            "sip2.example.com".to_string(),
+        ]))
+    );
+
+    // Unknown encoding
+    let pkg = gen_offer_pkg(&[120, 5, 2, 192, 0, 2, 5]);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(msg.lease.unwrap().sip_srvs, None);
+}
+
+#[test]
+fn test_dhcpv4_compressed_dns_names() {
+    let mut opt = vec![120, 0, 0];
+    opt.extend_from_slice(b"\x03sip\x07example\x03com\x00");
+    // Pointer to `example.com` at offset 4
+    opt.extend_from_slice(b"\x04sip2\xc0\x04");
+    // Pointer to `sip2.example.com` at offset 17
+    opt.extend_from_slice(b"\xc0\x11");
+    opt[1] = (opt.len() - 2) as u8;
+    let pkg = gen_offer_pkg(&opt);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(
+        msg.lease.unwrap().sip_srvs,
+        Some(DhcpV4SipServers::Domains(vec![
+            "sip.example.com".to_string(),
+            "sip2.example.com".to_string(),
+            "sip2.example.com".to_string(),
+        ]))
+    );
+
+    // Pointer to itself, pointer to later data, reserved label type and
+    // truncated pointer
+    for names in [
+        b"\x03sip\xc0\x00".as_slice(),
+        b"\x03sip\xc0\x06\x00\x00",
+        b"\x03sip\x40\x00",
+        b"\x03sip\xc0",
+    ] {
+        let mut opt = vec![120, names.len() as u8 + 1, 0];
+        opt.extend_from_slice(names);
+        let pkg = gen_offer_pkg(&opt);
+        let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+        assert_eq!(msg.lease.unwrap().sip_srvs, None);
+    }
+
+    // Name longer than 255 octets, split into two options
+    let mut names = vec![0u8];
+    for _ in 0..4 {
+        names.push(63);
+        names.extend_from_slice(&[b'a'; 63]);
+    }
+    names.push(0);
+    let mut opt = vec![120, 200];
+    opt.extend_from_slice(&names[..200]);
+    opt.extend_from_slice(&[120, (names.len() - 200) as u8]);
+    opt.extend_from_slice(&names[200..]);
+    let pkg = gen_offer_pkg(&opt);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(msg.lease.unwrap().sip_srvs, None);
+}
+
+#[test]
+fn test_dhcpv4_vendor_specific_infos() {
+    let pkg = gen_offer_pkg(&[
+        125, 19, // V-I Vendor-Specific Information
+        0, 0, 0, 9, 6, 1, 1, 0xa, 2, 1, 0xb, // enterprise 9
+        0, 0, 0x0d, 0xe9, 3, 3, 1, 0xc, // enterprise 3561
+    ]);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(
+        msg.lease.unwrap().vendor_specific_infos,
+        Some(vec![
+            DhcpV4VendorSpecificInfo {
+                enterprise_number: 9,
+                sub_opts: vec![(1, vec![0xa]), (2, vec![0xb])],
+            },
+            DhcpV4VendorSpecificInfo {
+                enterprise_number: 3561,
+                sub_opts: vec![(3, vec![0xc])],
+            },
+        ])
+    );
+
+    // Sub-option exceeding enterprise data length
+    let pkg = gen_offer_pkg(&[125, 8, 0, 0, 0, 9, 3, 1, 2, 0xa]);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(msg.lease.unwrap().vendor_specific_infos, None);
+}
+
+#[test]
+fn test_dhcpv4_request_voip_opts() {
+    let mut config = DhcpV4Config::new("eth1");
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let prl = get_opt(&msg.to_dhcp_pkg().unwrap(), 55).unwrap();
+    assert!(!prl.contains(&120) && !prl.contains(&125));
+
+    config
+        .set_request_sip_srvs(true)
+        .set_request_vendor_specific_infos(true);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let prl = get_opt(&msg.to_dhcp_pkg().unwrap(), 55).unwrap();
+    assert!(prl.contains(&120) && prl.contains(&125));
+}
+
+#[test]
+fn test_dhcpv4_mud_url() {
+    let url = "https://example.com/mud/v1";
+    let mut config = DhcpV4Config::new("eth1");
+    config.set_mud_url(url);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    assert_eq!(
+        get_opt(&msg.to_dhcp_pkg().unwrap(), 161),
+        Some(url.as_bytes().to_vec())
+    );
+
+    config.set_mud_url("http://example.com/mud/v1");
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    assert!(msg.to_dhcp_pkg().is_err());
+
+    let mut opt = vec![161, url.len() as u8];
+    opt.extend_from_slice(url.as_bytes());
+    let pkg = gen_offer_pkg(&opt);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(msg.lease.unwrap().mud_url.as_deref(), Some(url));
+}
+
+#[test]
+fn test_dhcpv4_captive_portal() {
+    let url = "https://portal.example.com/api";
+    let mut opt = vec![114, url.len() as u8];
+    opt.extend_from_slice(url.as_bytes());
+    // Invalid URI should not stop parsing of following options
+    opt.extend_from_slice(&[114, 2, 0xff, 0xfe, 3, 4, 192, 0, 2, 1]);
+    let pkg = gen_offer_pkg(&opt);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    let lease = msg.lease.unwrap();
+    assert_eq!(lease.captive_portal, None);
+    assert_eq!(lease.gateways, Some(vec![Ipv4Addr::new(192, 0, 2, 1)]));
+
+    let mut opt = vec![114, url.len() as u8];
+    opt.extend_from_slice(url.as_bytes());
+    let pkg = gen_offer_pkg(&opt);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(msg.lease.unwrap().captive_portal_url(), Some(url));
+
+    let url = "urn:ietf:params:capport:unrestricted";
+    let mut opt = vec![114, url.len() as u8];
+    opt.extend_from_slice(url.as_bytes());
+    let pkg = gen_offer_pkg(&opt);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    let lease = msg.lease.unwrap();
+    assert_eq!(lease.captive_portal.as_deref(), Some(url));
+    assert_eq!(lease.captive_portal_url(), None);
+
+    let mut config = DhcpV4Config::new("eth1");
+    config.set_request_captive_portal(true);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let prl = get_opt(&msg.to_dhcp_pkg().unwrap(), 55).unwrap();
+    assert!(prl.contains(&114));
+}
+
+#[test]
+fn test_dhcpv4_timezone() {
+    let mut opt = vec![100, 29];
+    opt.extend_from_slice(b"EST5EDT4,M3.2.0/02,M11.1.0/02");
+    opt.extend_from_slice(&[101, 14]);
+    opt.extend_from_slice(b"Europe/Zurich\0");
+    let pkg = gen_offer_pkg(&opt);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    let lease = msg.lease.unwrap();
+    assert_eq!(
+        lease.tz_posix.as_deref(),
+        Some("EST5EDT4,M3.2.0/02,M11.1.0/02")
+    );
+    assert_eq!(lease.tz_database.as_deref(), Some("Europe/Zurich"));
+
+    let pkg = gen_offer_pkg(&[101, 4, b'U', b'T', b' ', b'C']);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(msg.lease.unwrap().tz_database, None);
+
+    let mut config = DhcpV4Config::new("eth1");
+    config.set_request_timezone(true);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let prl = get_opt(&msg.to_dhcp_pkg().unwrap(), 55).unwrap();
+    assert!(prl.contains(&100) && prl.contains(&101));
+}
+
+#[test]
+fn test_dhcpv4_6rd() {
+    let mut opt = vec![212, 26, 8, 32];
+    opt.extend_from_slice(
+        &"2001:db8::".parse::<std::net::Ipv6Addr>().unwrap().octets(),
+    );
+    opt.extend_from_slice(&[192, 0, 2, 10, 192, 0, 2, 11]);
+    let pkg = gen_offer_pkg(&opt);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(
+        msg.lease.unwrap().six_rd,
+        Some(DhcpV4SixRd {
+            ipv4_mask_len: 8,
+            prefix: "2001:db8::".parse().unwrap(),
+            prefix_len: 32,
+            border_relays: vec![
+                Ipv4Addr::new(192, 0, 2, 10),
+                Ipv4Addr::new(192, 0, 2, 11)
+            ],
+        })
+    );
+
+    // Delegated prefix longer than 128 bits
+    opt[2] = 0;
+    opt[3] = 100;
+    let pkg = gen_offer_pkg(&opt);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(msg.lease.unwrap().six_rd, None);
+
+    let mut config = DhcpV4Config::new("eth1");
+    config.set_request_6rd(true);
+    let msg = DhcpV4Message::new(&config, DhcpV4MessageType::Discovery, 1);
+    let prl = get_opt(&msg.to_dhcp_pkg().unwrap(), 55).unwrap();
+    assert!(prl.contains(&212));
+}
+
+#[test]
+fn test_dhcpv4_access_domain() {
+    let mut opt = vec![213, 0];
+    opt.extend_from_slice(b"\x06access\x07example\x03net\x00");
+    opt[1] = (opt.len() - 2) as u8;
+    let pkg = gen_offer_pkg(&opt);
+    let msg = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(
+        msg.lease.unwrap().access_domain.as_deref(),
+        Some("access.example.net")
+    );
+}
+
+#[test]
+fn test_dhcpv4_client_id_echo() {
+    let mut config = DhcpV4Config::new("eth1");
+    config.set_client_id(0, b"host1");
+    let verify = |opts: &[u8], config: &DhcpV4Config| {
+        DhcpV4Message::from_dhcp_pkg(&gen_offer_pkg(opts), NamePolicy::Raw)
+            .unwrap()
+            .is_client_id_mismatch(config)
+    };
+
+    assert!(!verify(&[], &config));
+    assert!(!verify(&[61, 6, 0, b'h', b'o', b's', b't', b'1'], &config));
+    assert!(verify(&[61, 6, 0, b'h', b'o', b's', b't', b'2'], &config));
+
+    config.set_verify_client_id(false);
+    assert!(!verify(&[61, 6, 0, b'h', b'o', b's', b't', b'2'], &config));
+
+    config.set_verify_client_id(true).clear_client_id();
+    assert!(!verify(&[61, 6, 0, b'h', b'o', b's', b't', b'2'], &config));
+}
+
+fn parse_offer_lease(opts: &[u8]) -> DhcpV4Lease {
+    DhcpV4Message::from_dhcp_pkg(&gen_offer_pkg(opts), NamePolicy::Raw)
+        .unwrap()
+        .lease
+        .unwrap()
+}
+
+#[test]
+fn test_dhcpv4_ms_classless_routes() {
+    let lease = parse_offer_lease(&[
+        249, 13, // MS Classless Static Route
+        24, 192, 0, 2, 192, 0, 2, 1, // 192.0.2.0/24 via 192.0.2.1
+        0, 192, 0, 2, 254, // Default route via 192.0.2.254
+    ]);
+    assert_eq!(
+        lease.ms_classless_routes,
+        Some(vec![
+            DhcpV4ClasslessRoute::new(
+                Ipv4Addr::new(192, 0, 2, 0),
+                24,
+                Ipv4Addr::new(192, 0, 2, 1)
+            ),
+            DhcpV4ClasslessRoute::new(
+                Ipv4Addr::UNSPECIFIED,
+                0,
+                Ipv4Addr::new(192, 0, 2, 254)
+            ),
+        ])
+    );
+    assert_eq!(lease.classless_routes, None);
+}
+
+#[test]
+fn test_dhcpv4_ms_classless_routes_malformed() {
+    // Prefix length over 32
+    let lease = parse_offer_lease(&[249, 5, 33, 192, 0, 2, 1]);
+    assert_eq!(lease.ms_classless_routes, None);
+    // Router truncated
+    let lease = parse_offer_lease(&[249, 6, 24, 192, 0, 2, 192, 0]);
+    assert_eq!(lease.ms_classless_routes, None);
+    // Second route truncated invalidates the whole option
+    let lease =
+        parse_offer_lease(&[249, 10, 0, 192, 0, 2, 1, 32, 192, 0, 2, 1]);
+    assert_eq!(lease.ms_classless_routes, None);
+    // Empty option
+    let lease = parse_offer_lease(&[249, 0]);
+    assert_eq!(lease.ms_classless_routes, Some(Vec::new()));
+}
+
+#[test]
+fn test_dhcpv4_effective_routes() {
+    let router_opt = [3, 4, 192, 0, 2, 1];
+    let routes_121 = [121, 8, 24, 198, 51, 100, 192, 0, 2, 2];
+    let routes_249 = [249, 8, 24, 203, 0, 113, 192, 0, 2, 3];
+    let route_121 = DhcpV4ClasslessRoute::new(
+        Ipv4Addr::new(198, 51, 100, 0),
+        24,
+        Ipv4Addr::new(192, 0, 2, 2),
+    );
+    let route_249 = DhcpV4ClasslessRoute::new(
+        Ipv4Addr::new(203, 0, 113, 0),
+        24,
+        Ipv4Addr::new(192, 0, 2, 3),
+    );
+
+    // Option 121 wins over option 249 and Router(3)
+    let lease = parse_offer_lease(
+        &[&router_opt[..], &routes_249, &routes_121].concat(),
+    );
+    assert_eq!(lease.effective_routes(), vec![route_121]);
+
+    // Option 249 wins over Router(3)
+    let lease = parse_offer_lease(&[&router_opt[..], &routes_249].concat());
+    assert_eq!(lease.effective_routes(), vec![route_249]);
+
+    // Default route via router
+    let lease = parse_offer_lease(&router_opt);
+    assert_eq!(
+        lease.effective_routes(),
+        vec![DhcpV4ClasslessRoute::new(
+            Ipv4Addr::UNSPECIFIED,
+            0,
+            Ipv4Addr::new(192, 0, 2, 1)
+        )]
+    );
+
+    assert!(parse_offer_lease(&[]).effective_routes().is_empty());
+}
+
+fn parse_names(
+    opts: &[u8],
+    policy: NamePolicy,
+) -> (Option<String>, Option<String>) {
+    let lease = DhcpV4Message::from_dhcp_pkg(&gen_offer_pkg(opts), policy)
+        .unwrap()
+        .lease
+        .unwrap();
+    (lease.host_name, lease.domain_name)
+}
+
+// Host name "h\xff", domain name "a b" and then Router(3)
+const INVALID_NAME_OPTS: [u8; 15] = [
+    12, 2, b'h', 0xff, // Host Name
+    15, 3, b'a', b' ', b'b', // Domain Name
+    3, 4, 192, 0, 2, 1, // Router
+];
+
+#[test]
+fn test_dhcpv4_name_policy_strict() {
+    assert_eq!(
+        parse_names(
+            &[12, 5, b'h', b'o', b's', b't', b'1', 15, 3, b'a', b'.', b'b'],
+            NamePolicy::Strict
+        ),
+        (Some("host1".to_string()), Some("a.b".to_string()))
+    );
+    assert_eq!(
+        parse_names(&INVALID_NAME_OPTS, NamePolicy::Strict),
+        (None, None)
+    );
+    // Options after invalid name are still parsed
+    let lease = DhcpV4Message::from_dhcp_pkg(
+        &gen_offer_pkg(&INVALID_NAME_OPTS),
+        NamePolicy::Strict,
+    )
+    .unwrap()
+    .lease
+    .unwrap();
+    assert_eq!(lease.gateways, Some(vec![Ipv4Addr::new(192, 0, 2, 1)]));
+}
+
+#[test]
+fn test_dhcpv4_name_policy_lossy() {
+    assert_eq!(
+        parse_names(&INVALID_NAME_OPTS, NamePolicy::Lossy),
+        (Some("h\\255".to_string()), Some("a\\032b".to_string()))
+    );
+}
+
+#[test]
+fn test_dhcpv4_name_policy_raw() {
+    assert_eq!(
+        parse_names(&INVALID_NAME_OPTS, NamePolicy::Raw),
+        (Some("h\u{FFFD}".to_string()), Some("a b".to_string()))
+    );
+}
+
+#[test]
+fn test_dhcpv4_name_in_overloaded_file() {
+    let mut pkg = gen_offer_pkg(&[
+        52, 1, 1, // Option Overload: file
+        12, 2, b'h', b'o', // Host Name
+    ]);
+    pkg[108..113].copy_from_slice(&[12, 2, b's', b't', 255]);
+    let lease = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Strict)
+        .unwrap()
+        .lease
+        .unwrap();
+    assert_eq!(lease.host_name, Some("host".to_string()));
+}
+
+#[test]
+fn test_dhcpv4_name_truncated() {
+    let lease = DhcpV4Message::from_dhcp_pkg(
+        &gen_offer_pkg(&[
+            3, 4, 192, 0, 2, 1, // Router
+            12, 10, b'h', b'o', // Host Name claiming 10 bytes
+        ]),
+        NamePolicy::Raw,
+    )
+    .unwrap()
+    .lease
+    .unwrap();
+    assert_eq!(lease.host_name, None);
+    assert_eq!(lease.gateways, Some(vec![Ipv4Addr::new(192, 0, 2, 1)]));
+}
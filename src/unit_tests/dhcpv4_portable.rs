@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::{Ipv4Addr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use dhcproto::{v4, Decodable, Decoder, Encodable, Encoder};
+
+use crate::{
+    DhcpV4Config, DhcpV4Lease, DhcpV4Message, DhcpV4MessageType,
+    DhcpV4PortableClient, ErrorKind, NamePolicy,
+};
+
+const CLIENT_MAC: &str = "02:00:00:00:00:01";
+const LEASE_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 99);
+const DNS_SRV: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 53);
+
+// Reply to one message received by `srv` with specified message type,
+// returning the received message.
+fn srv_reply(srv: &UdpSocket, msg_type: v4::MessageType) -> v4::Message {
+    let mut buffer = vec![0u8; 1500];
+    let (len, src_addr) = srv.recv_from(&mut buffer).unwrap();
+    let request =
+        v4::Message::decode(&mut Decoder::new(&buffer[..len])).unwrap();
+    let mut msg = v4::Message::default();
+    msg.set_opcode(v4::Opcode::BootReply)
+        .set_xid(request.xid())
+        .set_chaddr(request.chaddr());
+    if request.opts().msg_type() != Some(v4::MessageType::Inform) {
+        msg.set_yiaddr(request.ciaddr());
+        msg.opts_mut()
+            .insert(v4::DhcpOption::AddressLeaseTime(3600));
+    }
+    msg.opts_mut().insert(v4::DhcpOption::MessageType(msg_type));
+    msg.opts_mut()
+        .insert(v4::DhcpOption::ServerIdentifier(Ipv4Addr::LOCALHOST));
+    msg.opts_mut()
+        .insert(v4::DhcpOption::SubnetMask(Ipv4Addr::new(255, 255, 255, 0)));
+    msg.opts_mut()
+        .insert(v4::DhcpOption::DomainNameServer(vec![DNS_SRV]));
+    let mut reply = Vec::new();
+    msg.encode(&mut Encoder::new(&mut reply)).unwrap();
+    srv.send_to(&reply, src_addr).unwrap();
+    request
+}
+
+fn new_portable_client(srv: &UdpSocket) -> DhcpV4PortableClient {
+    let mut config = DhcpV4Config::new("");
+    config.set_timeout(10);
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let mut cli =
+        DhcpV4PortableClient::new_with_socket(config, CLIENT_MAC, socket)
+            .unwrap();
+    cli.srv_port = srv.local_addr().unwrap().port();
+    cli
+}
+
+fn gen_lease() -> DhcpV4Lease {
+    DhcpV4Lease {
+        yiaddr: LEASE_IP,
+        srv_id: Ipv4Addr::LOCALHOST,
+        lease_time: 3600,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_dhcpv4_portable_renew_and_release() {
+    let srv = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let mut cli = new_portable_client(&srv);
+    let handle = std::thread::spawn(move || {
+        let request = srv_reply(&srv, v4::MessageType::Ack);
+        assert_eq!(request.opts().msg_type(), Some(v4::MessageType::Request));
+        assert_eq!(request.ciaddr(), LEASE_IP);
+        assert!(request
+            .opts()
+            .get(v4::OptionCode::RequestedIpAddress)
+            .is_none());
+
+        let mut buffer = vec![0u8; 1500];
+        let (len, _) = srv.recv_from(&mut buffer).unwrap();
+        let release =
+            v4::Message::decode(&mut Decoder::new(&buffer[..len])).unwrap();
+        assert_eq!(release.opts().msg_type(), Some(v4::MessageType::Release));
+    });
+
+    let lease = cli.renew(&gen_lease()).unwrap();
+    assert_eq!(lease.yiaddr, LEASE_IP);
+    assert_eq!(lease.lease_time, 3600);
+    assert_eq!((lease.t1, lease.t2), (1800, 3150));
+    assert_eq!(lease.dns_srvs, Some(vec![DNS_SRV]));
+    cli.release(&lease).unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_dhcpv4_portable_renew_nack() {
+    let srv = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let mut cli = new_portable_client(&srv);
+    let handle = std::thread::spawn(move || {
+        srv_reply(&srv, v4::MessageType::Nak);
+    });
+    let e = cli.renew(&gen_lease()).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::NoLease);
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_dhcpv4_portable_inform() {
+    let srv = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let mut cli = new_portable_client(&srv);
+    let handle = std::thread::spawn(move || {
+        let request = srv_reply(&srv, v4::MessageType::Ack);
+        assert_eq!(request.opts().msg_type(), Some(v4::MessageType::Inform));
+        assert_eq!(request.ciaddr(), LEASE_IP);
+        assert!(!request.flags().broadcast());
+    });
+
+    let lease = cli.inform(LEASE_IP, Some(Ipv4Addr::LOCALHOST)).unwrap();
+    assert_eq!(lease.yiaddr, LEASE_IP);
+    assert_eq!(lease.lease_time, 0);
+    assert_eq!(lease.dns_srvs, Some(vec![DNS_SRV]));
+    handle.join().unwrap();
+
+    // Client address is required
+    let config = DhcpV4Config::new("eth1");
+    let mut msg = DhcpV4Message::new(&config, DhcpV4MessageType::Inform, 1);
+    assert!(msg.to_dhcp_pkg().is_err());
+    msg.load_lease(gen_lease());
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    let parsed = DhcpV4Message::from_dhcp_pkg(&pkg, NamePolicy::Raw).unwrap();
+    assert_eq!(parsed.msg_type, DhcpV4MessageType::Inform);
+}
+
+#[test]
+fn test_dhcpv4_portable_timeout_on_foreign_replies() {
+    let srv = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let mut config = DhcpV4Config::new("");
+    config.set_timeout(2);
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let mut cli =
+        DhcpV4PortableClient::new_with_socket(config, CLIENT_MAC, socket)
+            .unwrap();
+    cli.srv_port = srv.local_addr().unwrap().port();
+
+    // Keep replying with other xid more often than socket timeout
+    let flood = std::thread::spawn(move || {
+        let mut buffer = vec![0u8; 1500];
+        let (len, src_addr) = srv.recv_from(&mut buffer).unwrap();
+        let request =
+            v4::Message::decode(&mut Decoder::new(&buffer[..len])).unwrap();
+        let mut msg = v4::Message::default();
+        msg.set_opcode(v4::Opcode::BootReply)
+            .set_xid(request.xid().wrapping_add(1))
+            .set_yiaddr(LEASE_IP)
+            .set_chaddr(request.chaddr());
+        msg.opts_mut()
+            .insert(v4::DhcpOption::MessageType(v4::MessageType::Ack));
+        let mut reply = Vec::new();
+        msg.encode(&mut Encoder::new(&mut reply)).unwrap();
+        for _ in 0..30 {
+            srv.send_to(&reply, src_addr).ok();
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    });
+
+    let started = Instant::now();
+    assert_eq!(
+        cli.renew(&gen_lease()).unwrap_err().kind(),
+        ErrorKind::Timeout
+    );
+    assert!(started.elapsed() < Duration::from_secs(4));
+    flood.join().unwrap();
+}
@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    iaid_from_iface, Dhcpv6Duid, Dhcpv6DuidEn, Dhcpv6DuidLl, Dhcpv6DuidLlt,
+    Dhcpv6DuidUuid, ErrorKind,
+};
+
+fn tmp_duid_path(name: &str) -> String {
+    let path = std::env::temp_dir()
+        .join(format!("mozim_test_{name}_{}.duid", std::process::id()));
+    std::fs::remove_file(&path).ok();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_dhcpv6_duid_from_slice() {
+    let llt = Dhcpv6Duid::from_slice(&[
+        0, 1, 0, 1, 0x2a, 0x5b, 0x6c, 0x7d, 0x00, 0x23, 0x45, 0x67, 0x89, 0xab,
+    ]);
+    let Dhcpv6Duid::LLT(llt) = llt else {
+        panic!("Expecting DUID-LLT, got {llt:?}");
+    };
+    assert_eq!(llt.hardware_type, 1);
+    assert_eq!(llt.time, 0x2a5b6c7d);
+    assert_eq!(
+        llt.link_layer_address,
+        vec![0x00, 0x23, 0x45, 0x67, 0x89, 0xab]
+    );
+
+    assert_eq!(
+        Dhcpv6Duid::from_slice(&[0, 2, 0, 0, 0x01, 0x37, 0xaa, 0xbb]),
+        Dhcpv6Duid::EN(Dhcpv6DuidEn::new(311, &[0xaa, 0xbb]))
+    );
+    assert_eq!(
+        Dhcpv6Duid::from_slice(&[
+            0, 3, 0, 1, 0x00, 0x23, 0x45, 0x67, 0x89, 0xab
+        ]),
+        Dhcpv6Duid::LL(Dhcpv6DuidLl::new(
+            1,
+            &[0x00, 0x23, 0x45, 0x67, 0x89, 0xab]
+        ))
+    );
+
+    let mut uuid = vec![0, 4];
+    uuid.extend_from_slice(&[0x11; 16]);
+    assert_eq!(
+        Dhcpv6Duid::from_slice(&uuid),
+        Dhcpv6Duid::UUID(Dhcpv6DuidUuid::new(u128::from_be_bytes([0x11; 16])))
+    );
+}
+
+#[test]
+fn test_dhcpv6_duid_from_slice_round_trip() {
+    for duid in [
+        Dhcpv6Duid::LLT(Dhcpv6DuidLlt::new(
+            1,
+            &[0, 0x23, 0x45, 0x67, 0x89, 0xab],
+        )),
+        Dhcpv6Duid::EN(Dhcpv6DuidEn::new(311, &[1, 2, 3, 4])),
+        Dhcpv6Duid::LL(Dhcpv6DuidLl::new(
+            1,
+            &[0, 0x23, 0x45, 0x67, 0x89, 0xab],
+        )),
+        Dhcpv6Duid::UUID(Dhcpv6DuidUuid::new(0x1234)),
+    ] {
+        assert_eq!(Dhcpv6Duid::from_slice(&duid.to_vec()), duid);
+    }
+}
+
+#[test]
+fn test_dhcpv6_duid_from_slice_malformed() {
+    for data in [
+        &[][..],
+        &[0][..],
+        // DUID-LLT without time
+        &[0, 1, 0, 1, 0x2a][..],
+        // DUID-EN without full enterprise number
+        &[0, 2, 0, 0, 1][..],
+        // DUID-LL without hardware type
+        &[0, 3, 0][..],
+        // DUID-UUID with 15 bytes
+        &[0, 4, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15][..],
+        // Unknown type
+        &[0, 9, 1, 2][..],
+    ] {
+        assert_eq!(
+            Dhcpv6Duid::from_slice(data),
+            Dhcpv6Duid::Other(data.to_vec())
+        );
+    }
+}
+
+#[test]
+fn test_dhcpv6_duid_load_or_generate_round_trip() {
+    let path = tmp_duid_path("round_trip");
+
+    let duid = Dhcpv6Duid::load_or_generate(&path).unwrap();
+    assert!(matches!(duid, Dhcpv6Duid::UUID(_)));
+    assert!(!std::path::Path::new(&format!("{path}.tmp")).exists());
+    // Loaded from file instead of generating new one
+    assert_eq!(Dhcpv6Duid::load_or_generate(&path).unwrap(), duid);
+
+    std::fs::write(&path, "00:03:00:01:00:23:45:67:89:ab\n").unwrap();
+    assert_eq!(
+        Dhcpv6Duid::load_or_generate(&path).unwrap(),
+        Dhcpv6Duid::LL(Dhcpv6DuidLl::new(
+            1,
+            &[0x00, 0x23, 0x45, 0x67, 0x89, 0xab]
+        ))
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_dhcpv6_duid_load_invalid_file() {
+    let path = tmp_duid_path("invalid");
+
+    std::fs::write(&path, "00:03:zz\n").unwrap();
+    assert_eq!(
+        Dhcpv6Duid::load_or_generate(&path).unwrap_err().kind(),
+        ErrorKind::InvalidArgument
+    );
+    // Invalid file is not overridden
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "00:03:zz\n");
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+// Expected UUID is HMAC-SHA256 keyed by machine-id over the mozim
+// application ID, computed by Python `hmac` module, with UUIDv4 version and
+// variant bits set.
+#[cfg(feature = "machine-id")]
+#[test]
+fn test_dhcpv6_duid_uuid_from_machine_id() {
+    assert_eq!(
+        Dhcpv6DuidUuid::from_machine_id_str("0123456789abcdef0123456789abcdef")
+            .unwrap(),
+        Dhcpv6DuidUuid::new(0x3f2928192bf94931ab40d761fd63af6a)
+    );
+    for invalid in ["", "0123456789abcdef", "0123456789abcdef0123456789abcdeg"]
+    {
+        assert_eq!(
+            Dhcpv6DuidUuid::from_machine_id_str(invalid)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidArgument
+        );
+    }
+}
+
+// Expected values are computed by reference SipHash-2-4 implementation
+// keyed by systemd `HASH_KEY`.
+#[test]
+fn test_iaid_from_iface_name() {
+    assert_eq!(iaid_from_iface("eth0", "00:23:45:67:89:ab"), 0x9c4e30b3);
+    assert_eq!(iaid_from_iface("eth1", "00:23:45:67:89:ab"), 0x58eadc85);
+    assert_eq!(iaid_from_iface("enp1s0", ""), 0x9429dbcf);
+    // MAC is ignored when interface name is known
+    assert_eq!(
+        iaid_from_iface("eth0", "00:23:45:67:89:ab"),
+        iaid_from_iface("eth0", "02:00:00:00:00:01")
+    );
+}
+
+#[test]
+fn test_iaid_from_iface_mac() {
+    assert_eq!(iaid_from_iface("", "00:23:45:67:89:ab"), 0xc1901f47);
+    // MAC shorter than 4 bytes
+    assert_eq!(iaid_from_iface("", "01:02"), 0x0e01a1a8);
+    assert_eq!(iaid_from_iface("", ""), 0x8962c64e);
+}
@@ -0,0 +1,597 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv6Addr;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use dhcproto::{v6, Decodable, Decoder, Encodable, Encoder, Name};
+
+#[cfg(feature = "client")]
+use crate::dhcpv6::DhcpV6Transaction;
+use crate::{
+    dhcpv6::{DhcpV6Message, DhcpV6MessageType},
+    DhcpV6Config, DhcpV6DnsConfig, DhcpV6DnsDomain, DhcpV6DnsSource,
+    DhcpV6IaType, DhcpV6Lease, DhcpV6OptionNtpServer, ErrorKind,
+};
+
+const TEST_XID: [u8; 3] = [1, 2, 3];
+
+fn gen_lease() -> DhcpV6Lease {
+    DhcpV6Lease {
+        ia_type: DhcpV6IaType::NonTemporaryAddresses,
+        addr: "2001:db8:a::99".parse::<Ipv6Addr>().unwrap(),
+        srv_duid: vec![0, 3, 0, 1, 0, 0x23, 0x45, 0x67, 0x89, 0xab],
+        ..Default::default()
+    }
+}
+
+fn gen_msg(config: &DhcpV6Config, msg_type: DhcpV6MessageType) -> v6::Message {
+    let mut msg = DhcpV6Message::new(config, msg_type, TEST_XID);
+    if msg_type != DhcpV6MessageType::SOLICIT {
+        msg.load_lease(gen_lease()).unwrap();
+    }
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    v6::Message::decode(&mut Decoder::new(&pkg)).unwrap()
+}
+
+fn get_oro(msg: &v6::Message) -> Option<Vec<v6::OptionCode>> {
+    match msg.opts().get(v6::OptionCode::ORO) {
+        Some(v6::DhcpOption::ORO(oro)) => Some(oro.opts.clone()),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_dhcpv6_oro_in_all_client_msgs() {
+    let mut config =
+        DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_request_opts(&[23, 24]);
+    let expected = vec![
+        v6::OptionCode::DomainNameServers,
+        v6::OptionCode::DomainSearchList,
+    ];
+
+    for msg_type in [
+        DhcpV6MessageType::SOLICIT,
+        DhcpV6MessageType::REQUEST,
+        DhcpV6MessageType::RENEW,
+        DhcpV6MessageType::REBIND,
+    ] {
+        assert_eq!(
+            get_oro(&gen_msg(&config, msg_type)).as_ref(),
+            Some(&expected),
+            "ORO mismatch in {msg_type}"
+        );
+    }
+}
+
+#[test]
+fn test_dhcpv6_renew_preserve_default_request_opts() {
+    let config = DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    let solicit_oro = get_oro(&gen_msg(&config, DhcpV6MessageType::SOLICIT));
+    assert!(solicit_oro.is_some());
+    assert_eq!(
+        get_oro(&gen_msg(&config, DhcpV6MessageType::RENEW)),
+        solicit_oro
+    );
+    assert_eq!(
+        get_oro(&gen_msg(&config, DhcpV6MessageType::REBIND)),
+        solicit_oro
+    );
+}
+
+#[test]
+fn test_dhcpv6_no_oro_in_release() {
+    let config = DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    assert_eq!(get_oro(&gen_msg(&config, DhcpV6MessageType::RELEASE)), None);
+}
+
+#[test]
+fn test_dhcpv6_public_builder_round_trip() {
+    let config = DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    let msg = DhcpV6Message::new(&config, DhcpV6MessageType::SOLICIT, TEST_XID);
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    let parsed = DhcpV6Message::from_dhcp_pkg(&pkg).unwrap();
+    assert_eq!(parsed.msg_type(), DhcpV6MessageType::SOLICIT);
+    assert_eq!(parsed.xid(), TEST_XID);
+}
+
+#[test]
+fn test_dhcpv6_builder_unsupported_type() {
+    let config = DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    let msg = DhcpV6Message::new(&config, DhcpV6MessageType::REPLY, TEST_XID);
+    assert!(msg.to_dhcp_pkg().is_err());
+}
+
+fn get_fqdn(msg: &v6::Message) -> Option<Vec<u8>> {
+    match msg.opts().get(v6::OptionCode::ClientFqdn) {
+        Some(v6::DhcpOption::Unknown(opt)) => Some(opt.data().to_vec()),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_dhcpv6_fqdn_encoding() {
+    let mut config =
+        DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_host_name("host-a.example.org");
+    assert_eq!(
+        get_fqdn(&gen_msg(&config, DhcpV6MessageType::SOLICIT)),
+        Some(b"\x01\x06host-a\x07example\x03org\x00".to_vec())
+    );
+
+    // Partial name has no terminating zero length label
+    config.set_host_name("host-a");
+    assert_eq!(
+        get_fqdn(&gen_msg(&config, DhcpV6MessageType::RENEW)),
+        Some(b"\x01\x06host-a".to_vec())
+    );
+    assert_eq!(
+        get_fqdn(&gen_msg(&config, DhcpV6MessageType::RELEASE)),
+        None
+    );
+}
+
+#[test]
+fn test_dhcpv6_no_fqdn_with_anonymity_profile() {
+    let mut config =
+        DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_host_name("host-a").enable_anonymity_profile();
+    assert_eq!(
+        get_fqdn(&gen_msg(&config, DhcpV6MessageType::SOLICIT)),
+        None
+    );
+}
+
+#[test]
+fn test_dhcpv6_invalid_host_name() {
+    let mut config =
+        DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_host_name("host-a..example.org");
+    let msg = DhcpV6Message::new(&config, DhcpV6MessageType::SOLICIT, TEST_XID);
+    assert!(msg.to_dhcp_pkg().is_err());
+}
+
+fn gen_pd_reply(
+    prefix: &str,
+    prefix_len: u8,
+    pd_exclude: &[u8],
+) -> DhcpV6Lease {
+    let mut iaprefix_opts = v6::DhcpOptions::new();
+    iaprefix_opts.insert(v6::DhcpOption::Unknown(v6::UnknownOption::new(
+        v6::OptionCode::PdExclude,
+        pd_exclude.to_vec(),
+    )));
+    let mut iapd_opts = v6::DhcpOptions::new();
+    iapd_opts.insert(v6::DhcpOption::IAPrefix(v6::IAPrefix {
+        preferred_lifetime: 100,
+        valid_lifetime: 200,
+        prefix_len,
+        prefix_ip: prefix.parse::<Ipv6Addr>().unwrap(),
+        opts: iaprefix_opts,
+    }));
+    let mut msg = v6::Message::new_with_id(v6::MessageType::Reply, TEST_XID);
+    msg.opts_mut().insert(v6::DhcpOption::IAPD(v6::IAPD {
+        id: 1,
+        t1: 50,
+        t2: 80,
+        opts: iapd_opts,
+    }));
+    DhcpV6Lease::try_from(&msg).unwrap()
+}
+
+#[test]
+fn test_dhcpv6_pd_exclude() {
+    let lease = gen_pd_reply("2001:db8:0:100::", 56, &[64, 0x05]);
+    assert_eq!(
+        lease.excluded_prefix,
+        Some(("2001:db8:0:105::".parse::<Ipv6Addr>().unwrap(), 64))
+    );
+
+    // IPv6 subnet ID not ending at octet boundary
+    let lease = gen_pd_reply("2001:db8:0:1a0::", 60, &[64, 0x30]);
+    assert_eq!(
+        lease.excluded_prefix,
+        Some(("2001:db8:0:1a3::".parse::<Ipv6Addr>().unwrap(), 64))
+    );
+
+    // Excluded prefix should be longer than delegated prefix
+    let lease = gen_pd_reply("2001:db8:0:100::", 56, &[48, 0x05]);
+    assert_eq!(lease.excluded_prefix, None);
+}
+
+#[test]
+fn test_dhcpv6_pd_exclude_in_oro() {
+    let config = DhcpV6Config::new("eth1", DhcpV6IaType::PrefixDelegation);
+    let oro = get_oro(&gen_msg(&config, DhcpV6MessageType::SOLICIT)).unwrap();
+    assert!(oro.contains(&v6::OptionCode::PdExclude));
+}
+
+fn get_elapsed_time(pkg: &[u8]) -> Option<u16> {
+    let msg = v6::Message::decode(&mut Decoder::new(pkg)).unwrap();
+    match msg.opts().get(v6::OptionCode::ElapsedTime) {
+        Some(v6::DhcpOption::ElapsedTime(t)) => Some(*t),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_dhcpv6_elapsed_time_in_centiseconds() {
+    let config = DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    let mut msg =
+        DhcpV6Message::new(&config, DhcpV6MessageType::SOLICIT, TEST_XID);
+    msg.add_elapsed_time(Instant::now() - Duration::from_millis(1500));
+    let elapsed = get_elapsed_time(&msg.to_dhcp_pkg().unwrap()).unwrap();
+    assert!((150..160).contains(&elapsed));
+    assert_eq!(
+        msg.elapsed_time(),
+        Duration::from_millis(elapsed as u64 * 10)
+    );
+
+    // Saturated to 0xffff beyond 655.35 seconds
+    msg.add_elapsed_time(Instant::now() - Duration::from_secs(700));
+    assert_eq!(
+        get_elapsed_time(&msg.to_dhcp_pkg().unwrap()),
+        Some(u16::MAX)
+    );
+}
+
+#[test]
+#[cfg(feature = "client")]
+fn test_dhcpv6_elapsed_time_increase_on_retransmit() {
+    let config = DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    for msg_type in [DhcpV6MessageType::RENEW, DhcpV6MessageType::REBIND] {
+        let mut trans =
+            DhcpV6Transaction::new(&config, msg_type, Duration::from_secs(60))
+                .unwrap();
+        trans.msg.load_lease(gen_lease()).unwrap();
+
+        // Initial transmission has no elapsed time
+        trans.start_retransmit().unwrap();
+        assert_eq!(get_elapsed_time(&trans.gen_dhcp_pkg().unwrap()), None);
+
+        let mut prev = 0;
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(20));
+            trans.start_retransmit().unwrap();
+            let elapsed =
+                get_elapsed_time(&trans.gen_dhcp_pkg().unwrap()).unwrap();
+            assert!(elapsed > prev, "{msg_type} elapsed time not increased");
+            prev = elapsed;
+        }
+        assert!(trans.elapsed() >= Duration::from_millis(60));
+    }
+}
+
+#[test]
+fn test_dhcpv6_sip_srvs() {
+    let mut domains = b"\x03sip\x07example\x03com\x00".to_vec();
+    domains.extend_from_slice(b"\x04sip2\x07example\x03com\x00");
+    let mut msg = v6::Message::new_with_id(v6::MessageType::Reply, TEST_XID);
+    msg.opts_mut()
+        .insert(v6::DhcpOption::Unknown(v6::UnknownOption::new(
+            v6::OptionCode::SipServerD,
+            domains,
+        )));
+    msg.opts_mut()
+        .insert(v6::DhcpOption::Unknown(v6::UnknownOption::new(
+            v6::OptionCode::SipServerA,
+            "2001:db8::5".parse::<Ipv6Addr>().unwrap().octets().to_vec(),
+        )));
+    let lease = DhcpV6Lease::try_from(&msg).unwrap();
+    assert_eq!(
+        lease.sip_srv_domains,
+        Some(vec![
+            "sip.example.com".to_string(),
+            "sip2.example.com".to_string()
+        ])
+    );
+    assert_eq!(
+        lease.sip_srvs,
+        Some(vec!["2001:db8::5".parse::<Ipv6Addr>().unwrap()])
+    );
+}
+
+#[test]
+fn test_dhcpv6_dns_srvs_and_domain_list() {
+    let dns_srvs = vec![
+        "2001:db8::53".parse::<Ipv6Addr>().unwrap(),
+        "2001:db8::54".parse::<Ipv6Addr>().unwrap(),
+    ];
+    let mut reply = v6::Message::new_with_id(v6::MessageType::Reply, TEST_XID);
+    reply
+        .opts_mut()
+        .insert(v6::DhcpOption::DomainNameServers(dns_srvs.clone()));
+    reply
+        .opts_mut()
+        .insert(v6::DhcpOption::DomainSearchList(vec![
+            Name::from_str("example.com.").unwrap(),
+            Name::from_str("lab.example.org.").unwrap(),
+        ]));
+    let mut pkg = Vec::new();
+    reply.encode(&mut Encoder::new(&mut pkg)).unwrap();
+    let msg = DhcpV6Message::from_dhcp_pkg(&pkg).unwrap();
+    let mut lease = msg.lease().unwrap().clone();
+    assert_eq!(lease.dns_srvs.as_ref(), Some(&dns_srvs));
+    assert_eq!(
+        lease.domain_list,
+        Some(vec![
+            "example.com".to_string(),
+            "lab.example.org".to_string()
+        ])
+    );
+
+    // Trimmed DHCPv6 domain merged with the same RA domain in FQDN form
+    lease.valid_life = 600;
+    let dns = DhcpV6DnsConfig::merge(
+        Some(&lease),
+        &[],
+        &[DhcpV6DnsDomain::new_ra("example.com.", 1200)],
+    );
+    assert_eq!(
+        dns.servers.iter().map(|s| s.addr).collect::<Vec<_>>(),
+        dns_srvs
+    );
+    assert_eq!(dns.domains.len(), 2);
+    assert_eq!(dns.domains[0].domain, "example.com");
+    assert_eq!(dns.domains[0].lifetime, 1200);
+    assert_eq!(dns.domains[0].source, DhcpV6DnsSource::Dhcp);
+    assert_eq!(dns.domains[1].domain, "lab.example.org");
+    assert_eq!(dns.domains[1].lifetime, 600);
+}
+
+#[test]
+fn test_dhcpv6_sntp_srvs() {
+    let srvs = [
+        "2001:db8::123".parse::<Ipv6Addr>().unwrap(),
+        "2001:db8::124".parse::<Ipv6Addr>().unwrap(),
+    ];
+    let mut data: Vec<u8> = srvs.iter().flat_map(|a| a.octets()).collect();
+    let mut reply = v6::Message::new_with_id(v6::MessageType::Reply, TEST_XID);
+    reply
+        .opts_mut()
+        .insert(v6::DhcpOption::Unknown(v6::UnknownOption::new(
+            v6::OptionCode::SntpServers,
+            data.clone(),
+        )));
+    let lease = DhcpV6Lease::try_from(&reply).unwrap();
+    assert_eq!(lease.sntp_srvs.as_deref(), Some(srvs.as_slice()));
+
+    // Trailing bytes not forming a full address are ignored
+    data.extend_from_slice(&[0xfe, 0x80, 0, 0]);
+    let mut reply = v6::Message::new_with_id(v6::MessageType::Reply, TEST_XID);
+    reply
+        .opts_mut()
+        .insert(v6::DhcpOption::Unknown(v6::UnknownOption::new(
+            v6::OptionCode::SntpServers,
+            data,
+        )));
+    let lease = DhcpV6Lease::try_from(&reply).unwrap();
+    assert_eq!(lease.sntp_srvs.as_deref(), Some(srvs.as_slice()));
+}
+
+#[test]
+fn test_dhcpv6_ntp_srvs() {
+    let srv = "2001:db8::123".parse::<Ipv6Addr>().unwrap();
+    let mcast = "ff05::101".parse::<Ipv6Addr>().unwrap();
+    let mut reply = v6::Message::new_with_id(v6::MessageType::Reply, TEST_XID);
+    reply.opts_mut().insert(v6::DhcpOption::NtpServer(vec![
+        v6::NtpSuboption::ServerAddress(srv),
+        v6::NtpSuboption::MulticastAddress(mcast),
+        v6::NtpSuboption::FQDN(Name::from_str("ntp.example.com.").unwrap()),
+    ]));
+    let mut pkg = Vec::new();
+    reply.encode(&mut Encoder::new(&mut pkg)).unwrap();
+    let msg = DhcpV6Message::from_dhcp_pkg(&pkg).unwrap();
+    let ntp_srvs = msg.lease().unwrap().ntp_srvs.clone().unwrap();
+    assert_eq!(
+        ntp_srvs,
+        vec![
+            DhcpV6OptionNtpServer::ServerAddress(srv),
+            DhcpV6OptionNtpServer::MulticastAddress(mcast),
+            DhcpV6OptionNtpServer::Fqdn("ntp.example.com".to_string()),
+        ]
+    );
+    assert_eq!(
+        ntp_srvs.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+        vec!["2001:db8::123", "ff05::101", "ntp.example.com"]
+    );
+}
+
+#[test]
+fn test_dhcpv6_request_sip_srvs() {
+    let mut config =
+        DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    let oro = get_oro(&gen_msg(&config, DhcpV6MessageType::SOLICIT)).unwrap();
+    assert!(!oro.contains(&v6::OptionCode::SipServerD));
+
+    config.set_request_sip_srvs(true).set_request_sip_srvs(true);
+    let oro = get_oro(&gen_msg(&config, DhcpV6MessageType::SOLICIT)).unwrap();
+    assert_eq!(
+        oro.iter()
+            .filter(|c| matches!(
+                c,
+                v6::OptionCode::SipServerD | v6::OptionCode::SipServerA
+            ))
+            .count(),
+        2
+    );
+
+    config.set_request_sip_srvs(false);
+    assert_eq!(config.request_opts(), &[23, 24, 31, 56]);
+}
+
+#[test]
+fn test_dhcpv6_mud_url() {
+    let url = "https://example.com/mud/v1";
+    let mut config =
+        DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_mud_url(url);
+    let msg = gen_msg(&config, DhcpV6MessageType::SOLICIT);
+    assert_eq!(
+        msg.opts().get(v6::OptionCode::MudUrlV6),
+        Some(&v6::DhcpOption::Unknown(v6::UnknownOption::new(
+            v6::OptionCode::MudUrlV6,
+            url.as_bytes().to_vec()
+        )))
+    );
+    let msg = gen_msg(&config, DhcpV6MessageType::RELEASE);
+    assert!(msg.opts().get(v6::OptionCode::MudUrlV6).is_none());
+
+    let mut reply = v6::Message::new_with_id(v6::MessageType::Reply, TEST_XID);
+    reply
+        .opts_mut()
+        .insert(v6::DhcpOption::Unknown(v6::UnknownOption::new(
+            v6::OptionCode::MudUrlV6,
+            url.as_bytes().to_vec(),
+        )));
+    let lease = DhcpV6Lease::try_from(&reply).unwrap();
+    assert_eq!(lease.mud_url.as_deref(), Some(url));
+}
+
+#[test]
+fn test_dhcpv6_captive_portal() {
+    let url = "https://portal.example.com/api";
+    let mut reply = v6::Message::new_with_id(v6::MessageType::Reply, TEST_XID);
+    reply
+        .opts_mut()
+        .insert(v6::DhcpOption::Unknown(v6::UnknownOption::new(
+            v6::OptionCode::DhcpCaptivePortal,
+            url.as_bytes().to_vec(),
+        )));
+    let lease = DhcpV6Lease::try_from(&reply).unwrap();
+    assert_eq!(lease.captive_portal_url(), Some(url));
+
+    let mut config =
+        DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_request_captive_portal(true);
+    let oro = get_oro(&gen_msg(&config, DhcpV6MessageType::SOLICIT)).unwrap();
+    assert!(oro.contains(&v6::OptionCode::DhcpCaptivePortal));
+}
+
+#[test]
+fn test_dhcpv6_lease_check_by_msg_type() {
+    let config = DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    let lease = DhcpV6Lease {
+        ia_type: DhcpV6IaType::NonTemporaryAddresses,
+        ..Default::default()
+    };
+
+    let mut msg =
+        DhcpV6Message::new(&config, DhcpV6MessageType::RENEW, TEST_XID);
+    assert_eq!(
+        msg.load_lease(lease.clone()).unwrap_err().kind(),
+        ErrorKind::InvalidArgument
+    );
+
+    // Rebind carries no Server Identifier, missing address is not fatal
+    let mut msg =
+        DhcpV6Message::new(&config, DhcpV6MessageType::REBIND, TEST_XID);
+    msg.load_lease(lease).unwrap();
+    let pkg = msg.to_dhcp_pkg().unwrap();
+    let msg = v6::Message::decode(&mut Decoder::new(&pkg)).unwrap();
+    match msg.opts().get(v6::OptionCode::IANA) {
+        Some(v6::DhcpOption::IANA(ia)) => {
+            assert!(ia.opts.get(v6::OptionCode::IAAddr).is_none())
+        }
+        o => panic!("Unexpected IA_NA {o:?}"),
+    }
+}
+
+// Captured DHCPv6 REPLY carrying SIP Servers Domain Name List(21) of
+// `sip.example.com` and Domain Search List(24) of `example.com` and
+// `lab.example.org`, all in RFC 1035 label encoding without compression.
+const REPLY_WITH_DOMAINS: [u8; 59] = [
+    0x07, 0x01, 0x02, 0x03, // REPLY, xid
+    0x00, 0x15, 0x00, 0x11, // SIP Servers Domain Name List, length 17
+    0x03, b's', b'i', b'p', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e',
+    0x03, b'c', b'o', b'm', 0x00, // end of SIP Servers Domain Name List
+    0x00, 0x18, 0x00, 0x1e, // Domain Search List, length 30
+    0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm',
+    0x00, 0x03, b'l', b'a', b'b', 0x07, b'e', b'x', b'a', b'm', b'p', b'l',
+    b'e', 0x03, b'o', b'r', b'g', 0x00,
+];
+
+#[test]
+fn test_dhcpv6_parse_multi_label_domains() {
+    let msg = DhcpV6Message::from_dhcp_pkg(&REPLY_WITH_DOMAINS).unwrap();
+    let lease = msg.lease().unwrap();
+    assert_eq!(
+        lease.domain_list.as_deref(),
+        Some(
+            ["example.com".to_string(), "lab.example.org".to_string()]
+                .as_slice()
+        )
+    );
+    assert_eq!(
+        lease.sip_srv_domains.as_deref(),
+        Some(["sip.example.com".to_string()].as_slice())
+    );
+}
+
+#[test]
+fn test_dhcpv6_domains_round_trip() {
+    let msg =
+        v6::Message::decode(&mut Decoder::new(&REPLY_WITH_DOMAINS)).unwrap();
+    let mut buffer = Vec::new();
+    msg.encode(&mut Encoder::new(&mut buffer)).unwrap();
+    assert_eq!(buffer, REPLY_WITH_DOMAINS);
+}
+
+#[test]
+fn test_dhcpv6_unknown_opts_limit() {
+    let mut msg = v6::Message::new_with_id(v6::MessageType::Reply, TEST_XID);
+    msg.opts_mut()
+        .insert(v6::DhcpOption::ServerId(gen_lease().srv_duid));
+    for code in 200..240u16 {
+        msg.opts_mut()
+            .insert(v6::DhcpOption::Unknown(v6::UnknownOption::new(
+                v6::OptionCode::from(code),
+                vec![0; 200],
+            )));
+    }
+    let lease = DhcpV6Lease::try_from(&msg).unwrap();
+    assert_eq!(lease.dhcp_opts.len(), 41);
+
+    let mut config =
+        DhcpV6Config::new("eth1", DhcpV6IaType::NonTemporaryAddresses);
+    let mut truncated = lease.clone();
+    truncated.limit_unknown_opts(&config);
+    // Server ID and 4096 bytes of unknown options
+    assert_eq!(truncated.dhcp_opts.len(), 21);
+    assert!(truncated.unknown_opts_truncated);
+
+    config.set_unknown_opts_limit(5, usize::MAX);
+    let mut truncated = lease.clone();
+    truncated.limit_unknown_opts(&config);
+    assert_eq!(truncated.dhcp_opts.len(), 6);
+    assert!(truncated.unknown_opts_truncated);
+
+    config.set_unknown_opts_limit(usize::MAX, usize::MAX);
+    let mut truncated = lease.clone();
+    truncated.limit_unknown_opts(&config);
+    assert_eq!(truncated, lease);
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn test_dhcpv6_lease_same_options() {
+    let lease = gen_lease();
+    let mut new_lease = lease.clone();
+    new_lease.valid_life = 7200;
+    new_lease.dhcp_opts.push(v6::DhcpOption::Preference(255));
+    assert!(lease.same_options(&new_lease));
+
+    new_lease
+        .dhcp_opts
+        .push(v6::DhcpOption::Unknown(v6::UnknownOption::new(
+            v6::OptionCode::Unknown(65001),
+            vec![1],
+        )));
+    assert!(!lease.same_options(&new_lease));
+
+    let mut new_lease = lease.clone();
+    new_lease.excluded_prefix =
+        Some(("2001:db8:a:1::".parse::<Ipv6Addr>().unwrap(), 64));
+    assert!(!lease.same_options(&new_lease));
+}
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv6Addr;
+
+use crate::{
+    dhcpv6::{gen_router_solicit, parse_router_advert},
+    DhcpV6RaMode,
+};
+
+const ROUTER_LLA: Ipv6Addr = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+
+fn gen_ra(flags: u8) -> Vec<u8> {
+    vec![
+        134, 0, 0, 0, // type, code, checksum
+        64, flags, 0x07, 0x08, // hop limit, flags, router lifetime 1800
+        0, 0, 0, 0, // reachable time
+        0, 0, 0, 0, // retrans timer
+    ]
+}
+
+#[test]
+fn test_parse_router_advert_flags() {
+    for (flags, mode) in [
+        (0x80, DhcpV6RaMode::Stateful),
+        (0xc0, DhcpV6RaMode::Stateful),
+        (0x40, DhcpV6RaMode::Stateless),
+        (0x00, DhcpV6RaMode::Disabled),
+    ] {
+        let ra =
+            parse_router_advert(&gen_ra(flags), ROUTER_LLA, Some(255)).unwrap();
+        assert_eq!(ra.router, ROUTER_LLA);
+        assert_eq!(ra.router_lifetime, 1800);
+        assert_eq!(ra.dhcpv6_mode(), mode);
+    }
+}
+
+#[test]
+fn test_parse_router_advert_invalid() {
+    // Truncated
+    assert!(
+        parse_router_advert(&gen_ra(0x80)[..8], ROUTER_LLA, Some(255))
+            .is_none()
+    );
+    // Neighbor Advertisement
+    let mut data = gen_ra(0x80);
+    data[0] = 136;
+    assert!(parse_router_advert(&data, ROUTER_LLA, Some(255)).is_none());
+    // Not from link-local address
+    assert!(parse_router_advert(
+        &gen_ra(0x80),
+        "2001:db8::1".parse::<Ipv6Addr>().unwrap(),
+        Some(255)
+    )
+    .is_none());
+    // Forwarded by router or hop limit unknown
+    assert!(parse_router_advert(&gen_ra(0x80), ROUTER_LLA, Some(64)).is_none());
+    assert!(parse_router_advert(&gen_ra(0x80), ROUTER_LLA, None).is_none());
+}
+
+#[test]
+fn test_gen_router_solicit() {
+    let mac = [0x00, 0x23, 0x45, 0x67, 0x89, 0xab];
+    assert_eq!(
+        gen_router_solicit(&mac),
+        vec![
+            133, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0x00, 0x23, 0x45, 0x67, 0x89, 0xab
+        ]
+    );
+    assert_eq!(gen_router_solicit(&[]), vec![133, 0, 0, 0, 0, 0, 0, 0]);
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    dhcpv6::{
+        gen_information_refresh_time, gen_request_wait_time,
+        gen_solicit_wait_time,
+    },
+    set_random_seed, DhcpLifetime, DhcpV6Lease,
+};
+
+fn gen_request_wait_times(count: u32) -> Vec<Duration> {
+    let begin = Instant::now();
+    let mut ret = Vec::new();
+    let mut rt = Duration::ZERO;
+    for i in 0..count {
+        rt = gen_request_wait_time(begin, i, rt).unwrap();
+        ret.push(rt);
+    }
+    ret
+}
+
+#[test]
+fn test_dhcpv6_retransmit_seeded() {
+    set_random_seed(42);
+    let first = gen_request_wait_times(8);
+    set_random_seed(42);
+    let second = gen_request_wait_times(8);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_dhcpv6_retransmit_backoff_range() {
+    for seed in 0..100 {
+        set_random_seed(seed);
+        let rts = gen_request_wait_times(8);
+        // REQ_TIMEOUT 1s
+        assert!(rts[0] >= Duration::from_millis(900));
+        assert!(rts[0] <= Duration::from_millis(1100));
+        for i in 1..rts.len() {
+            let prev = rts[i - 1].as_millis();
+            let cur = rts[i].as_millis();
+            // REQ_MAX_RT 30s
+            if cur < 27000 {
+                assert!(cur >= prev * 19 / 10);
+                assert!(cur <= prev * 21 / 10);
+            } else {
+                assert!(cur <= 33000);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_dhcpv6_first_solicit_rand_positive() {
+    for seed in 0..100 {
+        set_random_seed(seed);
+        let rt =
+            gen_solicit_wait_time(Instant::now(), 0, Duration::ZERO).unwrap();
+        // SOL_TIMEOUT 1s
+        assert!(rt > Duration::from_secs(1));
+        assert!(rt <= Duration::from_millis(1100));
+    }
+}
+
+#[test]
+fn test_dhcpv6_request_max_retransmit_count() {
+    let begin = Instant::now();
+    // REQ_MAX_RC 10
+    assert!(gen_request_wait_time(begin, 10, Duration::from_secs(1)).is_ok());
+    assert!(gen_request_wait_time(begin, 11, Duration::from_secs(1)).is_err());
+}
+
+#[test]
+fn test_dhcpv6_information_refresh_time() {
+    // IRT_DEFAULT 24 hours when server sent no Information Refresh Time
+    assert_eq!(
+        gen_information_refresh_time(None),
+        Some(Duration::from_secs(86400))
+    );
+    // IRT_MINIMUM 10 minutes
+    assert_eq!(
+        gen_information_refresh_time(Some(60)),
+        Some(Duration::from_secs(600))
+    );
+    assert_eq!(
+        gen_information_refresh_time(Some(7200)),
+        Some(Duration::from_secs(7200))
+    );
+    assert_eq!(gen_information_refresh_time(Some(u32::MAX)), None);
+}
+
+#[test]
+fn test_dhcp_lifetime_infinite() {
+    assert_eq!(DhcpLifetime::from(u32::MAX), DhcpLifetime::Infinite);
+    assert_eq!(DhcpLifetime::Infinite.as_duration(), None);
+    assert_eq!(u32::from(DhcpLifetime::Infinite), u32::MAX);
+
+    let lifetime = DhcpLifetime::from(3600);
+    assert_eq!(lifetime, DhcpLifetime::Seconds(3600));
+    assert!(!lifetime.is_infinite());
+    assert_eq!(lifetime.as_duration(), Some(Duration::from_secs(3600)));
+
+    let lease = DhcpV6Lease {
+        valid_life: u32::MAX,
+        preferred_life: u32::MAX,
+        t1: 1800,
+        t2: u32::MAX,
+        ..Default::default()
+    };
+    assert!(lease.is_infinite());
+    assert!(lease.preferred_lifetime().is_infinite());
+    assert_eq!(lease.t1_lifetime(), DhcpLifetime::Seconds(1800));
+    assert!(lease.t2_lifetime().is_infinite());
+}
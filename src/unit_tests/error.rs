@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::error::Error;
+
+use crate::{DhcpError, ErrorKind};
+
+#[test]
+fn test_error_io_source() {
+    let e = DhcpError::from(std::io::Error::from(
+        std::io::ErrorKind::PermissionDenied,
+    ));
+    assert_eq!(e.kind(), ErrorKind::PermissionDenied);
+    assert_eq!(
+        e.io_error_kind(),
+        Some(std::io::ErrorKind::PermissionDenied)
+    );
+    assert!(e.source().is_some());
+
+    let e = DhcpError::new(ErrorKind::Bug, "no source".to_string());
+    assert!(e.source().is_none());
+    assert_eq!(e.io_error_kind(), None);
+    assert_eq!(e.raw_os_error(), None);
+}
+
+#[cfg(all(target_os = "linux", feature = "client"))]
+#[test]
+fn test_error_raw_os_error() {
+    let e = DhcpError::new(ErrorKind::InterfaceDown, "down".to_string())
+        .with_source(std::io::Error::from_raw_os_error(libc::ENETDOWN));
+    assert_eq!(e.raw_os_error(), Some(libc::ENETDOWN));
+    // Source is ignored when comparing
+    assert_eq!(
+        e.clone(),
+        DhcpError::new(ErrorKind::InterfaceDown, "down".to_string())
+    );
+
+    // Searching through nested DhcpError
+    let e = DhcpError::new(ErrorKind::Bug, "outer".to_string()).with_source(e);
+    assert_eq!(e.raw_os_error(), Some(libc::ENETDOWN));
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::{
+    DhcpV4Lease, DhcpV4LeaseBuilder, DhcpV6IaType, DhcpV6LeaseBuilder,
+    ErrorKind,
+};
+
+#[test]
+fn test_dhcpv4_lease_builder_default_t1_t2() {
+    let lease = DhcpV4LeaseBuilder::new(
+        Ipv4Addr::new(192, 0, 2, 99),
+        Ipv4Addr::new(255, 255, 255, 0),
+        Ipv4Addr::new(192, 0, 2, 1),
+        3600,
+    )
+    .set_gateways(&[Ipv4Addr::new(192, 0, 2, 1)])
+    .build()
+    .unwrap();
+    assert_eq!(lease.t1, 1800);
+    assert_eq!(lease.t2, 3150);
+    assert_eq!(lease.effective_routes().len(), 1);
+}
+
+#[test]
+fn test_dhcpv4_lease_builder_infinite_t1_t2() {
+    let lease = DhcpV4LeaseBuilder::new(
+        Ipv4Addr::new(192, 0, 2, 99),
+        Ipv4Addr::new(255, 255, 255, 0),
+        Ipv4Addr::new(192, 0, 2, 1),
+        u32::MAX,
+    )
+    .build()
+    .unwrap();
+    assert!(lease.is_infinite());
+    assert_eq!(lease.t1, u32::MAX);
+    assert_eq!(lease.t2, u32::MAX);
+}
+
+#[test]
+fn test_dhcpv4_lease_builder_invalid_t1_t2() {
+    let e = DhcpV4LeaseBuilder::new(
+        Ipv4Addr::new(192, 0, 2, 99),
+        Ipv4Addr::new(255, 255, 255, 0),
+        Ipv4Addr::new(192, 0, 2, 1),
+        3600,
+    )
+    .set_t1(3000)
+    .set_t2(2000)
+    .build()
+    .unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidArgument);
+}
+
+#[test]
+fn test_dhcpv6_lease_builder() {
+    let lease = DhcpV6LeaseBuilder::new(
+        DhcpV6IaType::PrefixDelegation,
+        "2001:db8:a::".parse::<Ipv6Addr>().unwrap(),
+        &[0, 3, 0, 1, 0, 0x23, 0x45, 0x67, 0x89, 0xab],
+        1000,
+    )
+    .set_prefix_len(56)
+    .build()
+    .unwrap();
+    assert_eq!(lease.preferred_life, 1000);
+    assert_eq!(lease.t1, 500);
+    assert_eq!(lease.t2, 800);
+    assert_eq!(lease.prefix_len, 56);
+
+    assert!(DhcpV6LeaseBuilder::new(
+        DhcpV6IaType::NonTemporaryAddresses,
+        "2001:db8:a::99".parse::<Ipv6Addr>().unwrap(),
+        &[],
+        1000,
+    )
+    .build()
+    .is_err());
+}
+
+#[test]
+fn test_dhcpv4_lease_with_server_mac() {
+    let srv_mac = [2, 0, 0, 0, 0, 0xfe];
+    let lease = DhcpV4Lease {
+        yiaddr: Ipv4Addr::new(192, 0, 2, 99),
+        ..Default::default()
+    };
+    assert_eq!(lease.server_mac(), [u8::MAX; 6]);
+    let lease = lease.with_server_mac(srv_mac);
+    assert_eq!(lease.server_mac(), srv_mac);
+    assert_eq!(lease.yiaddr, Ipv4Addr::new(192, 0, 2, 99));
+}
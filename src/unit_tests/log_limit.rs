@@ -0,0 +1,33 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::{Duration, Instant};
+
+use crate::log_limit::DhcpLogLimiter;
+
+#[test]
+fn test_log_limiter_dedup() {
+    let mut limiter = DhcpLogLimiter::default();
+    let now = Instant::now();
+    assert_eq!(limiter.check("a", now), Some(0));
+    assert_eq!(limiter.check("a", now + Duration::from_secs(1)), None);
+    assert_eq!(limiter.check("a", now + Duration::from_secs(2)), None);
+    // Other signature is not affected
+    assert_eq!(limiter.check("b", now + Duration::from_secs(2)), Some(0));
+    assert_eq!(limiter.check("a", now + Duration::from_secs(31)), Some(2));
+    assert_eq!(limiter.check("a", now + Duration::from_secs(32)), None);
+}
+
+#[test]
+fn test_log_limiter_bounded() {
+    let mut limiter = DhcpLogLimiter::default();
+    let now = Instant::now();
+    for i in 0..100u64 {
+        assert_eq!(
+            limiter.check(&i.to_string(), now + Duration::from_millis(i)),
+            Some(0)
+        );
+    }
+    // Oldest signature forgotten, latest one still suppressed
+    assert_eq!(limiter.check("0", now + Duration::from_secs(1)), Some(0));
+    assert_eq!(limiter.check("99", now + Duration::from_secs(1)), None);
+}
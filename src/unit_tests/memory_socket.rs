@@ -0,0 +1,1649 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dhcproto::{v4, v6, Decodable, Decoder, Encodable, Encoder};
+use futures::{executor::block_on, StreamExt};
+
+use crate::{
+    spawn_lease_channel, DhcpDualStackClient, DhcpError, DhcpIdentityProvider,
+    DhcpLeaseEvent, DhcpLifetime, DhcpMemorySocket, DhcpOptionCodec,
+    DhcpOptionValue, DhcpSocket, DhcpStateEvent, DhcpV4Client,
+    DhcpV4ClientAsync, DhcpV4Config, DhcpV4Event, DhcpV4Lease,
+    DhcpV4LeaseValidation, DhcpV4LeaseWarning, DhcpV4Option, DhcpV4Progress,
+    DhcpV4State, DhcpV6Client, DhcpV6Config, DhcpV6Event, DhcpV6IaType,
+    DhcpV6Lease, DhcpV6Message, DhcpV6MessageType, DhcpV6Option,
+    DhcpV6Progress, DhcpV6State, Dhcpv6Duid, ErrorKind, RenewPolicy,
+    RestartPolicy,
+};
+
+const CLIENT_MAC: &str = "02:00:00:00:00:01";
+const SRV_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+const LEASE_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 99);
+const LEASE_IP6: Ipv6Addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x99);
+const SRV_DUID: [u8; 10] = [0, 3, 0, 1, 0, 0x23, 0x45, 0x67, 0x89, 0xab];
+
+fn run_v4_client(cli: &mut DhcpV4Client) -> Option<DhcpV4Lease> {
+    for event in cli.poll(0).unwrap() {
+        if let Some(lease) = cli.process(event).unwrap() {
+            return Some(lease);
+        }
+    }
+    None
+}
+
+// RFC 8415 requires random delay up to 1 second before first SOLICIT
+fn run_v6_client(cli: &mut DhcpV6Client) -> Option<DhcpV6Lease> {
+    for event in cli.poll(1).unwrap() {
+        if let Some(lease) = cli.process(event).unwrap() {
+            return Some(lease);
+        }
+    }
+    None
+}
+
+// Wait client to send out message of specified type
+fn v4_srv_recv(
+    cli: &mut DhcpV4Client,
+    srv: &DhcpMemorySocket,
+    msg_type: v4::MessageType,
+) -> v4::Message {
+    for _ in 0..100 {
+        run_v4_client(cli);
+        if let Ok(data) = srv.recv() {
+            let msg = v4::Message::decode(&mut Decoder::new(&data)).unwrap();
+            if msg.opts().msg_type() == Some(msg_type) {
+                return msg;
+            }
+        }
+    }
+    panic!("DHCPv4 client did not send {msg_type:?}");
+}
+
+// Wait client driven by other thread to send out message of specified type
+fn v4_srv_wait(
+    srv: &DhcpMemorySocket,
+    msg_type: v4::MessageType,
+) -> v4::Message {
+    for _ in 0..100 {
+        if let Ok(data) = srv.recv() {
+            let msg = v4::Message::decode(&mut Decoder::new(&data)).unwrap();
+            if msg.opts().msg_type() == Some(msg_type) {
+                return msg;
+            }
+        } else {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+    panic!("DHCPv4 client did not send {msg_type:?}");
+}
+
+fn v4_srv_reply(
+    srv: &DhcpMemorySocket,
+    request: &v4::Message,
+    msg_type: v4::MessageType,
+) {
+    v4_srv_reply_with_lease_time(srv, request, msg_type, 3600)
+}
+
+fn v4_srv_reply_with_lease_time(
+    srv: &DhcpMemorySocket,
+    request: &v4::Message,
+    msg_type: v4::MessageType,
+    lease_time: u32,
+) {
+    v4_srv_reply_with_timers(
+        srv,
+        request,
+        msg_type,
+        lease_time,
+        Some(lease_time / 2),
+        Some(lease_time * 7 / 8),
+        Some(Ipv4Addr::new(255, 255, 255, 0)),
+    )
+}
+
+fn v4_srv_reply_with_timers(
+    srv: &DhcpMemorySocket,
+    request: &v4::Message,
+    msg_type: v4::MessageType,
+    lease_time: u32,
+    t1: Option<u32>,
+    t2: Option<u32>,
+    subnet_mask: Option<Ipv4Addr>,
+) {
+    let mut msg = v4::Message::default();
+    msg.set_opcode(v4::Opcode::BootReply)
+        .set_xid(request.xid())
+        .set_yiaddr(LEASE_IP)
+        .set_chaddr(request.chaddr());
+    msg.opts_mut().insert(v4::DhcpOption::MessageType(msg_type));
+    msg.opts_mut()
+        .insert(v4::DhcpOption::ServerIdentifier(SRV_IP));
+    if let Some(subnet_mask) = subnet_mask {
+        msg.opts_mut()
+            .insert(v4::DhcpOption::SubnetMask(subnet_mask));
+    }
+    msg.opts_mut()
+        .insert(v4::DhcpOption::AddressLeaseTime(lease_time));
+    if let Some(t1) = t1 {
+        msg.opts_mut().insert(v4::DhcpOption::Renewal(t1));
+    }
+    if let Some(t2) = t2 {
+        msg.opts_mut().insert(v4::DhcpOption::Rebinding(t2));
+    }
+    let mut buffer = Vec::new();
+    msg.encode(&mut Encoder::new(&mut buffer)).unwrap();
+    srv.send(&buffer).unwrap();
+}
+
+#[test]
+fn test_dhcpv4_client_with_memory_socket() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+
+    let discover = v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+    assert_eq!(&discover.chaddr()[..6], &[2, 0, 0, 0, 0, 1]);
+    v4_srv_reply(&srv, &discover, v4::MessageType::Offer);
+
+    let request = v4_srv_recv(&mut cli, &srv, v4::MessageType::Request);
+    assert_eq!(
+        request.opts().get(v4::OptionCode::RequestedIpAddress),
+        Some(&v4::DhcpOption::RequestedIpAddress(LEASE_IP))
+    );
+    v4_srv_reply(&srv, &request, v4::MessageType::Ack);
+
+    let lease = (0..100).find_map(|_| run_v4_client(&mut cli)).unwrap();
+    assert_eq!(lease.yiaddr, LEASE_IP);
+    assert_eq!(lease.srv_id, SRV_IP);
+    assert_eq!(lease.lease_time, 3600);
+
+    // Bound client should not wake up for incoming packets
+    v4_srv_reply(&srv, &request, v4::MessageType::Ack);
+    assert!(cli.poll(0).unwrap().is_empty());
+
+    cli.release(&lease).unwrap();
+    let release = srv.recv().unwrap();
+    let release = v4::Message::decode(&mut Decoder::new(&release)).unwrap();
+    assert_eq!(release.opts().msg_type(), Some(v4::MessageType::Release));
+}
+
+fn v6_srv_recv(
+    cli: &mut DhcpV6Client,
+    srv: &DhcpMemorySocket,
+    msg_type: v6::MessageType,
+) -> v6::Message {
+    for _ in 0..100 {
+        run_v6_client(cli);
+        if let Ok(data) = srv.recv() {
+            let msg = v6::Message::decode(&mut Decoder::new(&data)).unwrap();
+            if msg.msg_type() == msg_type {
+                return msg;
+            }
+        }
+    }
+    panic!("DHCPv6 client did not send {msg_type:?}");
+}
+
+fn v6_srv_reply(
+    srv: &DhcpMemorySocket,
+    request: &v6::Message,
+    msg_type: v6::MessageType,
+) {
+    let mut msg = v6::Message::new_with_id(msg_type, request.xid());
+    let iaid = match request.opts().get(v6::OptionCode::IANA) {
+        Some(v6::DhcpOption::IANA(ia)) => ia.id,
+        _ => panic!("No IA_NA in {request:?}"),
+    };
+    if let Some(cli_id) = request.opts().get(v6::OptionCode::ClientId) {
+        msg.opts_mut().insert(cli_id.clone());
+    }
+    msg.opts_mut()
+        .insert(v6::DhcpOption::ServerId(SRV_DUID.to_vec()));
+    let mut ia_opts = v6::DhcpOptions::new();
+    ia_opts.insert(v6::DhcpOption::IAAddr(v6::IAAddr {
+        addr: LEASE_IP6,
+        preferred_life: 1800,
+        valid_life: 3600,
+        opts: v6::DhcpOptions::new(),
+    }));
+    msg.opts_mut().insert(v6::DhcpOption::IANA(v6::IANA {
+        id: iaid,
+        t1: 900,
+        t2: 1440,
+        opts: ia_opts,
+    }));
+    let mut buffer = Vec::new();
+    msg.encode(&mut Encoder::new(&mut buffer)).unwrap();
+    srv.send_from(
+        SocketAddr::V6(SocketAddrV6::new(
+            "fe80::1".parse().unwrap(),
+            v6::SERVER_PORT,
+            0,
+            0,
+        )),
+        &buffer,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_dhcpv4_lease_channel() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let cli = DhcpV4ClientAsync::init(config, None).unwrap();
+    let receiver = spawn_lease_channel(cli, 1).unwrap();
+
+    let discover = v4_srv_wait(&srv, v4::MessageType::Discover);
+    v4_srv_reply(&srv, &discover, v4::MessageType::Offer);
+    let request = v4_srv_wait(&srv, v4::MessageType::Request);
+    v4_srv_reply(&srv, &request, v4::MessageType::Ack);
+
+    let lease = receiver
+        .recv_timeout(Duration::from_secs(10))
+        .unwrap()
+        .unwrap();
+    assert_eq!(lease.yiaddr, LEASE_IP);
+}
+
+#[test]
+fn test_dhcpv4_lease_channel_close() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let cli = DhcpV4ClientAsync::init(config, None).unwrap();
+    let mut channel = spawn_lease_channel(cli, 1).unwrap();
+    v4_srv_wait(&srv, v4::MessageType::Discover);
+
+    // Closing should not wait for next lease update
+    let now = Instant::now();
+    channel.close();
+    assert!(now.elapsed() < Duration::from_secs(5));
+    assert!(channel.try_recv().is_err());
+    // Client and its socket are dropped
+    assert!(srv.send(&[0]).is_err());
+}
+
+#[test]
+fn test_dhcpv6_client_with_memory_socket() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+    let solicit = v6_srv_recv(&mut cli, &srv, v6::MessageType::Solicit);
+    v6_srv_reply(&srv, &solicit, v6::MessageType::Advertise);
+
+    let request = v6_srv_recv(&mut cli, &srv, v6::MessageType::Request);
+    assert_eq!(
+        request.opts().get(v6::OptionCode::ServerId),
+        Some(&v6::DhcpOption::ServerId(SRV_DUID.to_vec()))
+    );
+    v6_srv_reply(&srv, &request, v6::MessageType::Reply);
+
+    let lease = (0..100).find_map(|_| run_v6_client(&mut cli)).unwrap();
+    assert_eq!(lease.addr, LEASE_IP6);
+    assert_eq!(lease.valid_life, 3600);
+    assert_eq!(lease.srv_duid, SRV_DUID.to_vec());
+}
+
+#[test]
+fn test_dhcpv6_drop_reply_of_other_client() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+    let solicit = v6_srv_recv(&mut cli, &srv, v6::MessageType::Solicit);
+    v6_srv_reply(&srv, &solicit, v6::MessageType::Advertise);
+    let request = v6_srv_recv(&mut cli, &srv, v6::MessageType::Request);
+
+    // Reply without Client Identifier option
+    let mut forged = request.clone();
+    forged.opts_mut().remove(v6::OptionCode::ClientId);
+    v6_srv_reply(&srv, &forged, v6::MessageType::Reply);
+    assert!(run_v6_client(&mut cli).is_none());
+
+    // Reply holding DUID of other client
+    forged
+        .opts_mut()
+        .insert(v6::DhcpOption::ClientId(vec![0, 3, 0, 1, 2, 0, 0, 0, 0, 2]));
+    v6_srv_reply(&srv, &forged, v6::MessageType::Reply);
+    assert!(run_v6_client(&mut cli).is_none());
+
+    v6_srv_reply(&srv, &request, v6::MessageType::Reply);
+    let lease = (0..100).find_map(|_| run_v6_client(&mut cli)).unwrap();
+    assert_eq!(lease.addr, LEASE_IP6);
+}
+
+#[test]
+fn test_dhcpv6_client_remaining_time() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+    let solicit = v6_srv_recv(&mut cli, &srv, v6::MessageType::Solicit);
+    v6_srv_reply(&srv, &solicit, v6::MessageType::Advertise);
+    let request = v6_srv_recv(&mut cli, &srv, v6::MessageType::Request);
+    v6_srv_reply(&srv, &request, v6::MessageType::Reply);
+    (0..100).find_map(|_| run_v6_client(&mut cli)).unwrap();
+
+    let renew = cli.remaining(DhcpV6Event::Renew).unwrap().unwrap();
+    assert!(renew > Duration::from_secs(890));
+    assert!(renew <= Duration::from_secs(900));
+    assert_eq!(cli.remaining(DhcpV6Event::TransmitWait).unwrap(), None);
+    let timers = cli.timers().unwrap();
+    assert_eq!(
+        timers.iter().map(|(e, _)| *e).collect::<Vec<_>>(),
+        vec![
+            DhcpV6Event::Renew,
+            DhcpV6Event::Rebind,
+            DhcpV6Event::LeaseExpired
+        ]
+    );
+}
+
+#[test]
+fn test_dhcpv6_next_retransmit_at() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+    let solicit = v6_srv_recv(&mut cli, &srv, v6::MessageType::Solicit);
+    let (at, rt) = cli.next_retransmit_at().unwrap().unwrap();
+    assert_eq!(rt, cli.retransmit_stats().retrans_timeout);
+    assert!(at > Instant::now());
+    assert!(at <= Instant::now() + rt);
+
+    v6_srv_reply(&srv, &solicit, v6::MessageType::Advertise);
+    let request = v6_srv_recv(&mut cli, &srv, v6::MessageType::Request);
+    v6_srv_reply(&srv, &request, v6::MessageType::Reply);
+    (0..100).find_map(|_| run_v6_client(&mut cli)).unwrap();
+    assert_eq!(cli.next_retransmit_at().unwrap(), None);
+}
+
+#[test]
+fn test_dhcpv6_solicit_hint_of_expired_lease() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let expired_lease = DhcpV6Lease {
+        ia_type: DhcpV6IaType::NonTemporaryAddresses,
+        addr: LEASE_IP6,
+        iaid: 9,
+        srv_duid: SRV_DUID.to_vec(),
+        valid_life: 0,
+        ..Default::default()
+    };
+    let mut cli = DhcpV6Client::init(config, Some(expired_lease)).unwrap();
+
+    let solicit = v6_srv_recv(&mut cli, &srv, v6::MessageType::Solicit);
+    assert!(solicit.opts().get(v6::OptionCode::ServerId).is_none());
+    let ia = match solicit.opts().get(v6::OptionCode::IANA) {
+        Some(v6::DhcpOption::IANA(ia)) => ia,
+        _ => panic!("No IA_NA in {solicit:?}"),
+    };
+    assert_eq!(ia.id, 9);
+    assert_eq!(
+        ia.opts.get(v6::OptionCode::IAAddr),
+        Some(&v6::DhcpOption::IAAddr(v6::IAAddr {
+            addr: LEASE_IP6,
+            preferred_life: 0,
+            valid_life: 0,
+            opts: v6::DhcpOptions::new(),
+        }))
+    );
+}
+
+#[derive(Debug)]
+struct TestIdentity;
+
+impl DhcpIdentityProvider for TestIdentity {
+    fn client_id(
+        &self,
+        iface_name: &str,
+        mac_address: &str,
+    ) -> Result<Option<Vec<u8>>, DhcpError> {
+        assert_eq!(iface_name, "memory");
+        assert_eq!(mac_address, CLIENT_MAC);
+        Ok(Some(b"\x00stored-id".to_vec()))
+    }
+
+    fn duid(
+        &self,
+        _iface_name: &str,
+        _mac_address: &str,
+    ) -> Result<Option<Dhcpv6Duid>, DhcpError> {
+        Ok(Some(Dhcpv6Duid::Other(vec![0, 0xff, 1, 2, 3, 4])))
+    }
+
+    fn iaid(
+        &self,
+        _iface_name: &str,
+        _mac_address: &str,
+    ) -> Result<Option<u32>, DhcpError> {
+        Ok(Some(77))
+    }
+}
+
+#[test]
+fn test_dhcpv4_identity_provider() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_identity_provider(Arc::new(TestIdentity));
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+    assert_eq!(cli.config().client_id(), b"\x00stored-id");
+
+    let discover = v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+    assert_eq!(
+        discover.opts().get(v4::OptionCode::ClientIdentifier),
+        Some(&v4::DhcpOption::ClientIdentifier(b"\x00stored-id".to_vec()))
+    );
+}
+
+#[test]
+fn test_dhcpv6_identity_provider() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_identity_provider(Arc::new(TestIdentity));
+    let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+    let solicit = v6_srv_recv(&mut cli, &srv, v6::MessageType::Solicit);
+    assert_eq!(
+        solicit.opts().get(v6::OptionCode::ClientId),
+        Some(&v6::DhcpOption::ClientId(vec![0, 0xff, 1, 2, 3, 4]))
+    );
+    match solicit.opts().get(v6::OptionCode::IANA) {
+        Some(v6::DhcpOption::IANA(ia)) => assert_eq!(ia.id, 77),
+        _ => panic!("No IA_NA in {solicit:?}"),
+    }
+}
+
+fn gen_v6_lease() -> DhcpV6Lease {
+    DhcpV6Lease {
+        ia_type: DhcpV6IaType::NonTemporaryAddresses,
+        addr: LEASE_IP6,
+        iaid: 9,
+        srv_duid: SRV_DUID.to_vec(),
+        valid_life: 3600,
+        ..Default::default()
+    }
+}
+
+// Wait RELEASE for up to 5 seconds
+fn v6_srv_recv_release(srv: &DhcpMemorySocket) -> Option<v6::Message> {
+    for _ in 0..500 {
+        if let Ok(data) = srv.recv() {
+            let msg = v6::Message::decode(&mut Decoder::new(&data)).unwrap();
+            assert_eq!(msg.msg_type(), v6::MessageType::Release);
+            return Some(msg);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    None
+}
+
+#[test]
+fn test_dhcpv6_release_wait_reply() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+    let srv_thread = std::thread::spawn(move || {
+        // Ignore the initial RELEASE, reply to the retransmission
+        let first = v6_srv_recv_release(&srv).unwrap();
+        let second = v6_srv_recv_release(&srv).unwrap();
+        assert_eq!(first.xid(), second.xid());
+        assert!(second.opts().get(v6::OptionCode::ElapsedTime).is_some());
+        v6_srv_reply(&srv, &second, v6::MessageType::Reply);
+        srv
+    });
+    cli.release(&gen_v6_lease()).unwrap();
+    let srv = srv_thread.join().unwrap();
+    assert_eq!(cli.retransmit_stats().sent, 2);
+    // No more retransmission once replied
+    assert!(srv.recv().is_err());
+}
+
+#[test]
+fn test_dhcpv6_manual_commit_decline() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_manual_commit(true);
+    let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+    let solicit = v6_srv_recv(&mut cli, &srv, v6::MessageType::Solicit);
+    v6_srv_reply(&srv, &solicit, v6::MessageType::Advertise);
+    let request = v6_srv_recv(&mut cli, &srv, v6::MessageType::Request);
+    v6_srv_reply(&srv, &request, v6::MessageType::Reply);
+    let lease = (0..100).find_map(|_| run_v6_client(&mut cli)).unwrap();
+    assert_eq!(cli.uncommitted_lease(), Some(&lease));
+    // Not bound till committed
+    assert_eq!(cli.remaining(DhcpV6Event::Renew).unwrap(), None);
+
+    let srv_thread = std::thread::spawn(move || {
+        let data = (0..500)
+            .find_map(|_| {
+                std::thread::sleep(Duration::from_millis(10));
+                srv.recv().ok()
+            })
+            .unwrap();
+        let decline = v6::Message::decode(&mut Decoder::new(&data)).unwrap();
+        assert_eq!(decline.msg_type(), v6::MessageType::Decline);
+        assert_eq!(
+            decline.opts().get(v6::OptionCode::ServerId),
+            Some(&v6::DhcpOption::ServerId(SRV_DUID.to_vec()))
+        );
+        assert!(decline.opts().get(v6::OptionCode::ORO).is_none());
+        v6_srv_reply(&srv, &decline, v6::MessageType::Reply);
+        srv
+    });
+    cli.decline_lease(&lease).unwrap();
+    let srv = srv_thread.join().unwrap();
+    assert!(cli.uncommitted_lease().is_none());
+    // Solicit again
+    v6_srv_recv(&mut cli, &srv, v6::MessageType::Solicit);
+}
+
+#[test]
+fn test_dhcpv6_manual_commit() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_manual_commit(true);
+    let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+    let solicit = v6_srv_recv(&mut cli, &srv, v6::MessageType::Solicit);
+    v6_srv_reply(&srv, &solicit, v6::MessageType::Advertise);
+    let request = v6_srv_recv(&mut cli, &srv, v6::MessageType::Request);
+    v6_srv_reply(&srv, &request, v6::MessageType::Reply);
+    let lease = (0..100).find_map(|_| run_v6_client(&mut cli)).unwrap();
+    assert!(cli.commit_lease(&gen_v6_lease()).is_err());
+    cli.commit_lease(&lease).unwrap();
+    assert!(cli.remaining(DhcpV6Event::Renew).unwrap().is_some());
+}
+
+#[test]
+fn test_dhcpv6_release_timeout() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_release_timeout(1);
+    let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+    let now = Instant::now();
+    cli.release(&gen_v6_lease()).unwrap();
+    assert!(now.elapsed() >= Duration::from_secs(1));
+    assert!(now.elapsed() < Duration::from_secs(2));
+    assert!(v6_srv_recv_release(&srv).is_some());
+}
+
+#[test]
+fn test_dhcpv6_release_force() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+    cli.release_force(&gen_v6_lease()).unwrap();
+    assert!(v6_srv_recv_release(&srv).is_some());
+    assert!(srv.recv().is_err());
+}
+
+// Acquire lease with manual commit enabled
+fn v4_acquire_uncommitted(
+    cli: &mut DhcpV4Client,
+    srv: &DhcpMemorySocket,
+) -> DhcpV4Lease {
+    let discover = v4_srv_recv(cli, srv, v4::MessageType::Discover);
+    v4_srv_reply(srv, &discover, v4::MessageType::Offer);
+    let request = v4_srv_recv(cli, srv, v4::MessageType::Request);
+    v4_srv_reply(srv, &request, v4::MessageType::Ack);
+    let lease = (0..100).find_map(|_| run_v4_client(cli)).unwrap();
+    assert_eq!(cli.uncommitted_lease(), Some(&lease));
+    // Not bound till committed
+    assert!(cli.timers().unwrap().is_empty());
+    lease
+}
+
+#[test]
+fn test_dhcpv4_manual_commit() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_manual_commit(true);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+    let lease = v4_acquire_uncommitted(&mut cli, &srv);
+
+    let other = DhcpV4Lease {
+        yiaddr: Ipv4Addr::new(192, 0, 2, 100),
+        ..Default::default()
+    };
+    assert_eq!(
+        cli.commit_lease(&other).unwrap_err().kind(),
+        ErrorKind::InvalidArgument
+    );
+    cli.commit_lease(&lease).unwrap();
+    assert!(cli.uncommitted_lease().is_none());
+    assert!(cli.remaining(DhcpV4Event::Renew).unwrap().is_some());
+    assert!(cli.commit_lease(&lease).is_err());
+}
+
+#[test]
+fn test_dhcpv4_manual_commit_decline() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_manual_commit(true);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+    let lease = v4_acquire_uncommitted(&mut cli, &srv);
+
+    cli.decline_lease(&lease).unwrap();
+    let decline = srv.recv().unwrap();
+    let decline = v4::Message::decode(&mut Decoder::new(&decline)).unwrap();
+    assert_eq!(decline.opts().msg_type(), Some(v4::MessageType::Decline));
+    assert_eq!(decline.ciaddr(), Ipv4Addr::UNSPECIFIED);
+    assert_eq!(
+        decline.opts().get(v4::OptionCode::RequestedIpAddress),
+        Some(&v4::DhcpOption::RequestedIpAddress(LEASE_IP))
+    );
+    assert_eq!(
+        decline.opts().get(v4::OptionCode::ServerIdentifier),
+        Some(&v4::DhcpOption::ServerIdentifier(SRV_IP))
+    );
+    assert!(cli.uncommitted_lease().is_none());
+    // Discovery restarts after 10 seconds
+    let wait = cli
+        .remaining(DhcpV4Event::DiscoveryTimeout)
+        .unwrap()
+        .unwrap();
+    assert!(wait > Duration::from_secs(9));
+}
+
+#[test]
+fn test_dhcpv4_release_retransmit() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_release_retransmits(1);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+    // Drop the DISCOVER
+    v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+
+    let lease = DhcpV4Lease {
+        yiaddr: LEASE_IP,
+        srv_id: SRV_IP,
+        ..Default::default()
+    };
+    cli.release(&lease).unwrap();
+    let mut xids = Vec::new();
+    while let Ok(data) = srv.recv() {
+        let msg = v4::Message::decode(&mut Decoder::new(&data)).unwrap();
+        assert_eq!(msg.opts().msg_type(), Some(v4::MessageType::Release));
+        assert_eq!(msg.ciaddr(), LEASE_IP);
+        xids.push(msg.xid());
+    }
+    assert_eq!(xids.len(), 2);
+    assert_eq!(xids[0], xids[1]);
+}
+
+#[test]
+fn test_dhcpv4_release_force() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_release_retransmits(3);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+    v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+
+    let lease = DhcpV4Lease {
+        yiaddr: LEASE_IP,
+        srv_id: SRV_IP,
+        ..Default::default()
+    };
+    let now = Instant::now();
+    cli.release_force(&lease).unwrap();
+    assert!(now.elapsed() < Duration::from_secs(1));
+    let release = srv.recv().unwrap();
+    let release = v4::Message::decode(&mut Decoder::new(&release)).unwrap();
+    assert_eq!(release.opts().msg_type(), Some(v4::MessageType::Release));
+    assert!(srv.recv().is_err());
+}
+
+// Process events till server received message of specified type
+fn v4_srv_recv_detailed(
+    cli: &mut DhcpV4Client,
+    srv: &DhcpMemorySocket,
+    msg_type: v4::MessageType,
+    progress: &mut Vec<DhcpV4Progress>,
+) -> v4::Message {
+    for _ in 0..500 {
+        for event in cli.poll(0).unwrap() {
+            progress.extend(cli.process_detailed(event).unwrap());
+        }
+        if let Ok(data) = srv.recv() {
+            let msg = v4::Message::decode(&mut Decoder::new(&data)).unwrap();
+            if msg.opts().msg_type() == Some(msg_type) {
+                return msg;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("DHCPv4 client did not send {msg_type:?}");
+}
+
+#[test]
+fn test_dhcpv4_process_detailed() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+    let mut progress = Vec::new();
+
+    let discover = v4_srv_recv_detailed(
+        &mut cli,
+        &srv,
+        v4::MessageType::Discover,
+        &mut progress,
+    );
+    v4_srv_reply_with_lease_time(&srv, &discover, v4::MessageType::Offer, 4);
+    let request = v4_srv_recv_detailed(
+        &mut cli,
+        &srv,
+        v4::MessageType::Request,
+        &mut progress,
+    );
+    v4_srv_reply_with_lease_time(&srv, &request, v4::MessageType::Ack, 4);
+
+    // Renew starts at T1(2 seconds) with 1 second jitter
+    let renew = v4_srv_recv_detailed(
+        &mut cli,
+        &srv,
+        v4::MessageType::Request,
+        &mut progress,
+    );
+    assert_eq!(progress.len(), 3, "{progress:?}");
+    let lease = match &progress[1] {
+        DhcpV4Progress::LeaseAcquired(lease) => lease.clone(),
+        p => panic!("Unexpected progress {p:?}"),
+    };
+    assert_eq!(
+        progress[0],
+        DhcpV4Progress::StateChanged(None, DhcpV4State::Bound(lease.clone()))
+    );
+    assert_eq!(
+        progress[2],
+        DhcpV4Progress::StateChanged(
+            Some(DhcpV4State::Bound(lease.clone())),
+            DhcpV4State::Renewing
+        )
+    );
+    assert_eq!(lease.yiaddr, LEASE_IP);
+    progress.clear();
+
+    v4_srv_reply_with_lease_time(&srv, &renew, v4::MessageType::Ack, 4);
+    for _ in 0..100 {
+        for event in cli.poll(0).unwrap() {
+            progress.extend(cli.process_detailed(event).unwrap());
+        }
+        if !progress.is_empty() {
+            break;
+        }
+    }
+    match progress.as_slice() {
+        [DhcpV4Progress::StateChanged(
+            Some(DhcpV4State::Renewing),
+            DhcpV4State::Bound(new_lease),
+        ), DhcpV4Progress::LeaseRenewed { t1, t2 }] => {
+            assert_eq!(new_lease.yiaddr, lease.yiaddr);
+            assert_eq!(*t1, Duration::from_secs(2));
+            assert_eq!(*t2, Duration::from_secs(3));
+        }
+        _ => panic!("Unexpected progress {progress:?}"),
+    }
+}
+
+#[test]
+fn test_dhcpv4_ipv6_only_preferred() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_ipv6_only_preferred(true);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+    cli.track_states();
+
+    let discover = v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+    match discover.opts().get(v4::OptionCode::ParameterRequestList) {
+        Some(v4::DhcpOption::ParameterRequestList(opts)) => {
+            assert!(opts.contains(&v4::OptionCode::Unknown(108)))
+        }
+        o => panic!("Unexpected Parameter Request List {o:?}"),
+    }
+
+    let mut offer = v4::Message::default();
+    offer
+        .set_opcode(v4::Opcode::BootReply)
+        .set_xid(discover.xid())
+        .set_chaddr(discover.chaddr());
+    offer
+        .opts_mut()
+        .insert(v4::DhcpOption::MessageType(v4::MessageType::Offer));
+    offer
+        .opts_mut()
+        .insert(v4::DhcpOption::ServerIdentifier(SRV_IP));
+    // V6ONLY_WAIT lower than MIN_V6ONLY_WAIT(300)
+    offer
+        .opts_mut()
+        .insert(v4::DhcpOption::Unknown(v4::UnknownOption::new(
+            v4::OptionCode::Unknown(108),
+            60u32.to_be_bytes().to_vec(),
+        )));
+    let mut buffer = Vec::new();
+    offer.encode(&mut Encoder::new(&mut buffer)).unwrap();
+    srv.send(&buffer).unwrap();
+
+    let e = (0..100)
+        .find_map(|_| {
+            cli.poll(0)
+                .unwrap()
+                .into_iter()
+                .find_map(|event| cli.process(event).err())
+        })
+        .unwrap();
+    assert_eq!(e.kind(), ErrorKind::Ipv6OnlyPreferred);
+    assert_eq!(
+        cli.take_states(),
+        vec![DhcpV4State::Ipv6OnlyPreferred(Duration::from_secs(300))]
+    );
+}
+
+#[test]
+fn test_dhcpv4_lease_validation_permissive() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+
+    let discover = v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+    v4_srv_reply_with_timers(
+        &srv,
+        &discover,
+        v4::MessageType::Offer,
+        3600,
+        None,
+        None,
+        None,
+    );
+    let request = v4_srv_recv(&mut cli, &srv, v4::MessageType::Request);
+    v4_srv_reply_with_timers(
+        &srv,
+        &request,
+        v4::MessageType::Ack,
+        3600,
+        None,
+        None,
+        None,
+    );
+
+    let lease = (0..100).find_map(|_| run_v4_client(&mut cli)).unwrap();
+    assert_eq!(lease.lease_time, 3600);
+    assert_eq!(lease.t1, 1800);
+    assert_eq!(lease.t2, 3150);
+}
+
+#[test]
+fn test_dhcpv4_lease_timers_normalized() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+
+    let discover = v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+    v4_srv_reply(&srv, &discover, v4::MessageType::Offer);
+    let request = v4_srv_recv(&mut cli, &srv, v4::MessageType::Request);
+    v4_srv_reply_with_timers(
+        &srv,
+        &request,
+        v4::MessageType::Ack,
+        3600,
+        Some(3400),
+        Some(7200),
+        None,
+    );
+
+    let lease = (0..100).find_map(|_| run_v4_client(&mut cli)).unwrap();
+    assert_eq!(lease.t1, 1800);
+    assert_eq!(lease.t2, 3150);
+    assert_eq!(
+        lease.warnings,
+        vec![DhcpV4LeaseWarning::InvalidTimers {
+            t1: 3400,
+            t2: 7200,
+            lease_time: 3600,
+        }]
+    );
+}
+
+#[test]
+fn test_dhcpv4_infinite_lease() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+
+    let discover = v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+    v4_srv_reply_with_timers(
+        &srv,
+        &discover,
+        v4::MessageType::Offer,
+        u32::MAX,
+        None,
+        None,
+        None,
+    );
+    let request = v4_srv_recv(&mut cli, &srv, v4::MessageType::Request);
+    v4_srv_reply_with_timers(
+        &srv,
+        &request,
+        v4::MessageType::Ack,
+        u32::MAX,
+        None,
+        None,
+        None,
+    );
+
+    let lease = (0..100).find_map(|_| run_v4_client(&mut cli)).unwrap();
+    assert!(lease.is_infinite());
+    assert_eq!(lease.lease_lifetime(), DhcpLifetime::Infinite);
+    assert_eq!(lease.t1_lifetime(), DhcpLifetime::Infinite);
+    assert_eq!(lease.t2_lifetime(), DhcpLifetime::Infinite);
+    assert_eq!(lease.t1, u32::MAX);
+    assert_eq!(lease.t2, u32::MAX);
+    assert!(cli.snapshot().unwrap().timers().iter().all(|(e, _)| {
+        !matches!(
+            e,
+            DhcpV4Event::Renew
+                | DhcpV4Event::RenewRetry
+                | DhcpV4Event::Rebind
+                | DhcpV4Event::RebindRetry
+                | DhcpV4Event::LeaseExpired
+        )
+    }));
+}
+
+#[test]
+fn test_dhcpv4_renew_policy() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_renew_policy(RenewPolicy::Percent(25));
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+
+    let discover = v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+    v4_srv_reply(&srv, &discover, v4::MessageType::Offer);
+    let request = v4_srv_recv(&mut cli, &srv, v4::MessageType::Request);
+    v4_srv_reply(&srv, &request, v4::MessageType::Ack);
+
+    let lease = (0..100).find_map(|_| run_v4_client(&mut cli)).unwrap();
+    assert_eq!(lease.t1, 1800);
+    let snapshot = cli.snapshot().unwrap();
+    let renew = snapshot
+        .timers()
+        .iter()
+        .find_map(|(e, t)| (*e == DhcpV4Event::Renew).then_some(*t))
+        .unwrap();
+    assert!(renew > Duration::from_secs(890));
+    assert!(renew <= Duration::from_secs(901));
+}
+
+#[test]
+fn test_dhcpv4_lease_validation_strict() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_lease_validation(DhcpV4LeaseValidation::Strict);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+
+    let discover = v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+    v4_srv_reply_with_timers(
+        &srv,
+        &discover,
+        v4::MessageType::Offer,
+        3600,
+        None,
+        None,
+        None,
+    );
+    assert!((0..20).all(|_| run_v4_client(&mut cli).is_none()));
+    assert!(std::iter::from_fn(|| srv.recv().ok()).all(|data| {
+        v4::Message::decode(&mut Decoder::new(&data))
+            .unwrap()
+            .opts()
+            .msg_type()
+            != Some(v4::MessageType::Request)
+    }));
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TestOptValue(String);
+
+impl DhcpOptionValue for TestOptValue {
+    fn encode(&self) -> Vec<u8> {
+        self.0.as_bytes().to_vec()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn DhcpOptionValue> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug)]
+struct TestOptCodec;
+
+impl DhcpOptionCodec for TestOptCodec {
+    fn decode(
+        &self,
+        data: &[u8],
+    ) -> Result<Box<dyn DhcpOptionValue>, DhcpError> {
+        String::from_utf8(data.to_vec())
+            .map(|s| Box::new(TestOptValue(s)) as Box<dyn DhcpOptionValue>)
+            .map_err(|e| {
+                DhcpError::new(ErrorKind::InvalidDhcpServerReply, e.to_string())
+            })
+    }
+}
+
+#[test]
+fn test_dhcpv4_option_codec() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .register_option_codec(224, Arc::new(TestOptCodec))
+        .add_custom_option(225, Box::new(TestOptValue("site-b".to_string())));
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+
+    let discover = v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+    match discover.opts().get(v4::OptionCode::Unknown(225)) {
+        Some(v4::DhcpOption::Unknown(opt)) => {
+            assert_eq!(opt.data(), b"site-b")
+        }
+        o => panic!("Unexpected custom option {o:?}"),
+    }
+    match discover.opts().get(v4::OptionCode::ParameterRequestList) {
+        Some(v4::DhcpOption::ParameterRequestList(opts)) => {
+            assert!(opts.contains(&v4::OptionCode::Unknown(224)))
+        }
+        o => panic!("Unexpected Parameter Request List {o:?}"),
+    }
+
+    let mut offer = v4::Message::default();
+    offer
+        .set_opcode(v4::Opcode::BootReply)
+        .set_xid(discover.xid())
+        .set_yiaddr(LEASE_IP)
+        .set_chaddr(discover.chaddr());
+    offer
+        .opts_mut()
+        .insert(v4::DhcpOption::MessageType(v4::MessageType::Offer));
+    offer
+        .opts_mut()
+        .insert(v4::DhcpOption::ServerIdentifier(SRV_IP));
+    offer
+        .opts_mut()
+        .insert(v4::DhcpOption::AddressLeaseTime(3600));
+    offer
+        .opts_mut()
+        .insert(v4::DhcpOption::Unknown(v4::UnknownOption::new(
+            v4::OptionCode::Unknown(224),
+            b"site-a".to_vec(),
+        )));
+    let mut buffer = Vec::new();
+    offer.encode(&mut Encoder::new(&mut buffer)).unwrap();
+    srv.send(&buffer).unwrap();
+
+    v4_srv_recv(&mut cli, &srv, v4::MessageType::Request);
+    let lease = cli.pending_lease().unwrap();
+    assert_eq!(lease.custom_opts.len(), 1);
+    let DhcpV4Option::Custom(code, value) = &lease.custom_opts[0];
+    assert_eq!(*code, 224);
+    assert_eq!(
+        value.as_any().downcast_ref::<TestOptValue>(),
+        Some(&TestOptValue("site-a".to_string()))
+    );
+}
+
+#[test]
+fn test_dhcpv6_option_codec() {
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config
+        .register_option_codec(65001, Arc::new(TestOptCodec))
+        // Option already parsed by dhcproto
+        .register_option_codec(
+            u16::from(v6::OptionCode::DomainNameServers),
+            Arc::new(TestOptCodec),
+        );
+    let mut msg = v6::Message::new_with_id(v6::MessageType::Reply, [1, 2, 3]);
+    msg.opts_mut()
+        .insert(v6::DhcpOption::Unknown(v6::UnknownOption::new(
+            v6::OptionCode::Unknown(65001),
+            b"site-a".to_vec(),
+        )));
+    msg.opts_mut()
+        .insert(v6::DhcpOption::DomainNameServers(vec![LEASE_IP6]));
+    let mut lease = DhcpV6Lease::try_from(&msg).unwrap();
+    lease.decode_custom_opts(&config);
+    assert_eq!(
+        lease.custom_opts,
+        vec![DhcpV6Option::Custom(
+            65001,
+            Box::new(TestOptValue("site-a".to_string()))
+        )]
+    );
+}
+
+#[test]
+fn test_dhcpv6_custom_option() {
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config
+        .add_custom_option(65001, Box::new(TestOptValue("site-a".to_string())))
+        .add_custom_option(65001, Box::new(TestOptValue("site-b".to_string())));
+    for (msg_type, expected) in [
+        (DhcpV6MessageType::SOLICIT, Some(b"site-b".to_vec())),
+        (DhcpV6MessageType::RELEASE, None),
+    ] {
+        let mut msg = DhcpV6Message::new(&config, msg_type, [1, 2, 3]);
+        if msg_type == DhcpV6MessageType::RELEASE {
+            msg.load_lease(gen_v6_lease()).unwrap();
+        }
+        let pkg = msg.to_dhcp_pkg().unwrap();
+        let msg = v6::Message::decode(&mut Decoder::new(&pkg)).unwrap();
+        let data = msg
+            .opts()
+            .iter()
+            .filter_map(|opt| match opt {
+                v6::DhcpOption::Unknown(opt)
+                    if opt.code() == v6::OptionCode::Unknown(65001) =>
+                {
+                    Some(opt.data().to_vec())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(data.first().cloned(), expected);
+        assert!(data.len() <= 1);
+    }
+}
+
+#[test]
+fn test_dhcpv4_udp_only() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_udp_only(true);
+    assert_eq!(
+        DhcpV4Client::init(config.clone(), None).unwrap_err().kind(),
+        ErrorKind::RawSocketRequired
+    );
+
+    let lease = DhcpV4Lease {
+        yiaddr: LEASE_IP,
+        srv_id: SRV_IP,
+        lease_time: 3600,
+        t1: 1800,
+        t2: 3150,
+        ..Default::default()
+    };
+    let mut cli = DhcpV4Client::init(config.clone(), Some(lease)).unwrap();
+    // Renew immediately instead of requesting via broadcast
+    let renew = v4_srv_recv(&mut cli, &srv, v4::MessageType::Request);
+    assert_eq!(renew.ciaddr(), LEASE_IP);
+    v4_srv_reply(&srv, &renew, v4::MessageType::Ack);
+    let new_lease = (0..100).find_map(|_| run_v4_client(&mut cli)).unwrap();
+    assert_eq!(new_lease.yiaddr, LEASE_IP);
+    assert!(cli.remaining(DhcpV4Event::Renew).unwrap().is_some());
+
+    // DHCPNAK requires lease acquisition via broadcast
+    let mut cli = DhcpV4Client::init(config, Some(new_lease)).unwrap();
+    let renew = v4_srv_recv(&mut cli, &srv, v4::MessageType::Request);
+    v4_srv_reply(&srv, &renew, v4::MessageType::Nak);
+    let e = (0..100)
+        .find_map(|_| {
+            cli.poll(0)
+                .unwrap()
+                .into_iter()
+                .find_map(|event| cli.process(event).err())
+        })
+        .unwrap();
+    assert_eq!(e.kind(), ErrorKind::RawSocketRequired);
+}
+
+fn v6_srv_reply_information(
+    srv: &DhcpMemorySocket,
+    request: &v6::Message,
+    dns_srv: Ipv6Addr,
+) {
+    let mut msg =
+        v6::Message::new_with_id(v6::MessageType::Reply, request.xid());
+    if let Some(cli_id) = request.opts().get(v6::OptionCode::ClientId) {
+        msg.opts_mut().insert(cli_id.clone());
+    }
+    msg.opts_mut()
+        .insert(v6::DhcpOption::ServerId(SRV_DUID.to_vec()));
+    msg.opts_mut()
+        .insert(v6::DhcpOption::DomainNameServers(vec![dns_srv]));
+    msg.opts_mut()
+        .insert(v6::DhcpOption::InformationRefreshTime(3600));
+    let mut buffer = Vec::new();
+    msg.encode(&mut Encoder::new(&mut buffer)).unwrap();
+    srv.send_from(
+        SocketAddr::V6(SocketAddrV6::new(
+            "fe80::1".parse().unwrap(),
+            v6::SERVER_PORT,
+            0,
+            0,
+        )),
+        &buffer,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_dhcpv6_information_only() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_information_only(true);
+    let mut cli = DhcpV6Client::init(config, None).unwrap();
+
+    let info_req =
+        v6_srv_recv(&mut cli, &srv, v6::MessageType::InformationRequest);
+    assert!(info_req.opts().get(v6::OptionCode::IANA).is_none());
+    match info_req.opts().get(v6::OptionCode::ORO) {
+        Some(v6::DhcpOption::ORO(oro)) => {
+            assert!(oro.opts.contains(&v6::OptionCode::InformationRefreshTime))
+        }
+        o => panic!("Unexpected ORO {o:?}"),
+    }
+    let dns_srv: Ipv6Addr = "2001:db8::53".parse().unwrap();
+    v6_srv_reply_information(&srv, &info_req, dns_srv);
+    // Detailed progress is reported relative to the first lease
+    let lease = (0..100)
+        .find_map(|_| {
+            cli.poll(1).unwrap().into_iter().find_map(|event| {
+                cli.process_detailed(event)
+                    .unwrap()
+                    .into_iter()
+                    .find_map(|p| match p {
+                        DhcpV6Progress::LeaseAcquired(l) => Some(l),
+                        _ => None,
+                    })
+            })
+        })
+        .unwrap();
+    assert_eq!(lease.addr, Ipv6Addr::UNSPECIFIED);
+    assert_eq!(lease.dns_srvs, Some(vec![dns_srv]));
+    assert_eq!(lease.information_refresh_time, Some(3600));
+    let refresh = cli
+        .remaining(DhcpV6Event::InformationRefresh)
+        .unwrap()
+        .unwrap();
+    assert!(refresh > Duration::from_secs(3590));
+
+    // Refresh with changed DNS server
+    let new_dns_srv: Ipv6Addr = "2001:db8::54".parse().unwrap();
+    let progress = v6_refresh_information(&mut cli, &srv, new_dns_srv);
+    let changed: Vec<&DhcpV6Lease> = progress
+        .iter()
+        .filter_map(|p| match p {
+            DhcpV6Progress::OptionsChanged(l) => Some(l),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].dns_srvs, Some(vec![new_dns_srv]));
+    assert!(!progress
+        .iter()
+        .any(|p| matches!(p, DhcpV6Progress::LeaseRenewed { .. })));
+
+    // Refresh without change
+    let progress = v6_refresh_information(&mut cli, &srv, new_dns_srv);
+    assert!(!progress
+        .iter()
+        .any(|p| matches!(p, DhcpV6Progress::OptionsChanged(_))));
+}
+
+// Trigger information refresh and reply with specified DNS server, return
+// progress till bound again
+fn v6_refresh_information(
+    cli: &mut DhcpV6Client,
+    srv: &DhcpMemorySocket,
+    dns_srv: Ipv6Addr,
+) -> Vec<DhcpV6Progress> {
+    let mut progress = cli
+        .process_detailed(DhcpV6Event::InformationRefresh)
+        .unwrap();
+    let info_req = (0..100)
+        .find_map(|_| {
+            for event in cli.poll(1).unwrap() {
+                progress.extend(cli.process_detailed(event).unwrap());
+            }
+            srv.recv().ok()
+        })
+        .unwrap();
+    let info_req = v6::Message::decode(&mut Decoder::new(&info_req)).unwrap();
+    assert_eq!(info_req.msg_type(), v6::MessageType::InformationRequest);
+    v6_srv_reply_information(srv, &info_req, dns_srv);
+    for _ in 0..100 {
+        for event in cli.poll(1).unwrap() {
+            progress.extend(cli.process_detailed(event).unwrap());
+        }
+        if progress.iter().any(|p| {
+            matches!(p, DhcpV6Progress::StateChanged(_, DhcpV6State::Bound(_)))
+        }) {
+            return progress;
+        }
+    }
+    panic!("DHCPv6 client did not bind after information refresh");
+}
+
+// Wait client driven by other thread to send out message of specified type
+fn v6_srv_wait(
+    srv: &DhcpMemorySocket,
+    msg_type: v6::MessageType,
+) -> v6::Message {
+    for _ in 0..100 {
+        if let Ok(data) = srv.recv() {
+            let msg = v6::Message::decode(&mut Decoder::new(&data)).unwrap();
+            if msg.msg_type() == msg_type {
+                return msg;
+            }
+        } else {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+    panic!("DHCPv6 client did not send {msg_type:?}");
+}
+
+fn spawn_v4_srv(srv: DhcpMemorySocket) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let discover = v4_srv_wait(&srv, v4::MessageType::Discover);
+        v4_srv_reply(&srv, &discover, v4::MessageType::Offer);
+        let request = v4_srv_wait(&srv, v4::MessageType::Request);
+        v4_srv_reply(&srv, &request, v4::MessageType::Ack);
+    })
+}
+
+fn spawn_v6_srv(srv: DhcpMemorySocket) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let solicit = v6_srv_wait(&srv, v6::MessageType::Solicit);
+        v6_srv_reply(&srv, &solicit, v6::MessageType::Advertise);
+        let request = v6_srv_wait(&srv, v6::MessageType::Request);
+        v6_srv_reply(&srv, &request, v6::MessageType::Reply);
+    })
+}
+
+fn new_dual_stack_client(
+) -> (DhcpDualStackClient, DhcpMemorySocket, DhcpMemorySocket) {
+    let (v4_socket, v4_srv) = DhcpMemorySocket::new_pair().unwrap();
+    let (v6_socket, v6_srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut v4_config = DhcpV4Config::new("memory");
+    v4_config.set_memory_socket(v4_socket, CLIENT_MAC);
+    let mut v6_config =
+        DhcpV6Config::new("memory", DhcpV6IaType::NonTemporaryAddresses);
+    v6_config.set_memory_socket(v6_socket, CLIENT_MAC);
+    let cli =
+        DhcpDualStackClient::init(v4_config, v6_config, None, None).unwrap();
+    (cli, v4_srv, v6_srv)
+}
+
+#[test]
+fn test_dual_stack_merge_leases() {
+    let (mut cli, v4_srv, v6_srv) = new_dual_stack_client();
+    let v4_thread = spawn_v4_srv(v4_srv);
+    let v6_thread = spawn_v6_srv(v6_srv);
+
+    let mut events = Vec::new();
+    while events.len() < 2 {
+        events.push(block_on(cli.next()).unwrap().unwrap());
+    }
+    v4_thread.join().unwrap();
+    v6_thread.join().unwrap();
+
+    // DHCPv6 waits up to 1 second before first SOLICIT, DHCPv4 lease
+    // should not be held back by it
+    assert!(
+        matches!(&events[0], DhcpLeaseEvent::V4(l) if l.yiaddr == LEASE_IP)
+    );
+    assert!(matches!(&events[1], DhcpLeaseEvent::V6(l) if l.addr == LEASE_IP6));
+    assert_eq!(cli.v4_lease().map(|l| l.yiaddr), Some(LEASE_IP));
+    assert_eq!(cli.v6_lease().map(|l| l.addr), Some(LEASE_IP6));
+
+    cli.close();
+    assert!(block_on(cli.next()).is_none());
+}
+
+#[test]
+fn test_dual_stack_one_family_ended() {
+    let (mut cli, _v4_srv, v6_srv) = new_dual_stack_client();
+    cli.close_v4();
+    let v6_thread = spawn_v6_srv(v6_srv);
+
+    // Ended DHCPv4 stream should not end the DHCPv6 one
+    match block_on(cli.next()) {
+        Some(Ok(DhcpLeaseEvent::V6(lease))) => {
+            assert_eq!(lease.addr, LEASE_IP6)
+        }
+        e => panic!("Expecting DHCPv6 lease, got {e:?}"),
+    }
+    v6_thread.join().unwrap();
+    assert!(cli.v4_lease().is_none());
+
+    cli.close_v6();
+    assert!(block_on(cli.next()).is_none());
+}
+
+#[test]
+fn test_dual_stack_state_stream() {
+    let (cli, v4_srv, v6_srv) = new_dual_stack_client();
+    let mut stream = cli.into_state_stream();
+    let v4_thread = spawn_v4_srv(v4_srv);
+    let v6_thread = spawn_v6_srv(v6_srv);
+
+    let mut v4_bound = false;
+    let mut v6_bound = false;
+    while !(v4_bound && v6_bound) {
+        match block_on(stream.next()).unwrap().unwrap() {
+            DhcpStateEvent::V4(DhcpV4State::Bound(_)) => v4_bound = true,
+            DhcpStateEvent::V6(DhcpV6State::Bound(_)) => v6_bound = true,
+            _ => (),
+        }
+    }
+    v4_thread.join().unwrap();
+    v6_thread.join().unwrap();
+    assert_eq!(stream.v4_lease().map(|l| l.yiaddr), Some(LEASE_IP));
+    assert_eq!(stream.v6_lease().map(|l| l.addr), Some(LEASE_IP6));
+
+    stream.close_v6();
+    stream.close_v4();
+    assert!(block_on(stream.next()).is_none());
+}
+
+// Drive client till error raised
+fn run_v4_client_till_err(cli: &mut DhcpV4Client) -> Option<DhcpError> {
+    for event in cli.poll(0).unwrap() {
+        if let Err(e) = cli.process(event) {
+            return Some(e);
+        }
+    }
+    None
+}
+
+fn v4_nack_request(cli: &mut DhcpV4Client, srv: &DhcpMemorySocket) {
+    let discover = v4_srv_recv(cli, srv, v4::MessageType::Discover);
+    v4_srv_reply(srv, &discover, v4::MessageType::Offer);
+    let request = v4_srv_recv(cli, srv, v4::MessageType::Request);
+    v4_srv_reply(srv, &request, v4::MessageType::Nak);
+}
+
+#[test]
+fn test_dhcpv4_restart_policy_stop() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_restart_policy(RestartPolicy::Stop);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+
+    v4_nack_request(&mut cli, &srv);
+    let e = (0..100)
+        .find_map(|_| run_v4_client_till_err(&mut cli))
+        .unwrap();
+    assert_eq!(e.kind(), ErrorKind::NoLease);
+}
+
+#[test]
+fn test_dhcpv4_restart_policy_limit() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        .set_restart_policy(RestartPolicy::Limit(1));
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+
+    // First DHCPNAK restarts discovery
+    v4_nack_request(&mut cli, &srv);
+    v4_nack_request(&mut cli, &srv);
+    // Second one in a row stops the client
+    let e = (0..100)
+        .find_map(|_| run_v4_client_till_err(&mut cli))
+        .unwrap();
+    assert_eq!(e.kind(), ErrorKind::NoLease);
+}
+
+#[test]
+fn test_dhcpv4_request_retry_limit() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let lease = DhcpV4Lease {
+        yiaddr: LEASE_IP,
+        srv_id: SRV_IP,
+        ..Default::default()
+    };
+    let mut cli = DhcpV4Client::init(config, Some(lease)).unwrap();
+
+    let mut request_count = 0;
+    loop {
+        // Fire the timer instead of waiting for retransmission delay
+        cli.process(DhcpV4Event::RequestTimeout).unwrap();
+        // Nothing sent once fallback to discovery
+        let Ok(data) = srv.recv() else {
+            break;
+        };
+        let msg = v4::Message::decode(&mut Decoder::new(&data)).unwrap();
+        assert_eq!(msg.opts().msg_type(), Some(v4::MessageType::Request));
+        request_count += 1;
+        assert!(request_count <= 5, "Too many REQUEST retransmitted");
+    }
+    // First REQUEST plus 4 retransmissions
+    assert_eq!(request_count, 5);
+    v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+}
+
+#[test]
+fn test_dhcpv4_lease_approver_skip_offer() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let offer_count = Arc::new(AtomicU32::new(0));
+    let counter = offer_count.clone();
+    let mut config = DhcpV4Config::new("memory");
+    config
+        .set_memory_socket(cli_socket, CLIENT_MAC)
+        // Reject the first offer only
+        .set_lease_approver(Arc::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst) > 0
+        }));
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+
+    let discover = v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+    v4_srv_reply(&srv, &discover, v4::MessageType::Offer);
+    for _ in 0..10 {
+        run_v4_client(&mut cli);
+    }
+    assert_eq!(offer_count.load(Ordering::SeqCst), 1);
+    assert!(srv.recv().is_err());
+
+    v4_srv_reply(&srv, &discover, v4::MessageType::Offer);
+    let request = v4_srv_recv(&mut cli, &srv, v4::MessageType::Request);
+    assert_eq!(request.xid(), discover.xid());
+    assert_eq!(offer_count.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_dhcpv4_update_request_opts() {
+    let (cli_socket, srv) = DhcpMemorySocket::new_pair().unwrap();
+    let mut config = DhcpV4Config::new("memory");
+    config.set_memory_socket(cli_socket, CLIENT_MAC);
+    let mut cli = DhcpV4Client::init(config, None).unwrap();
+    v4_srv_recv(&mut cli, &srv, v4::MessageType::Discover);
+
+    let mut new_config = cli.config().clone();
+    new_config.set_request_opts(&[1, 3, 6]);
+    cli.update_config(&new_config).unwrap();
+
+    // Fire the timer instead of waiting for retransmission delay
+    cli.process(DhcpV4Event::DiscoveryTimeout).unwrap();
+    let data = srv.recv().unwrap();
+    let discover = v4::Message::decode(&mut Decoder::new(&data)).unwrap();
+    match discover.opts().get(v4::OptionCode::ParameterRequestList) {
+        Some(v4::DhcpOption::ParameterRequestList(opts)) => {
+            assert!(opts.contains(&v4::OptionCode::DomainNameServer));
+            assert!(!opts.contains(&v4::OptionCode::NtpServers));
+        }
+        o => panic!("Unexpected Parameter Request List {o:?}"),
+    }
+}
@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#[cfg(test)]
+mod capabilities;
+#[cfg(all(test, feature = "chaos"))]
+mod chaos;
+#[cfg(test)]
+mod config_update;
+#[cfg(all(test, feature = "client"))]
+mod dhcpv4_arp;
+#[cfg(test)]
+mod dhcpv4_conformance;
+#[cfg(all(test, feature = "client"))]
+mod dhcpv4_dhcp4o6;
+#[cfg(test)]
+mod dhcpv4_lease;
+#[cfg(test)]
+mod dhcpv4_option;
+#[cfg(test)]
+mod dhcpv4_portable;
+#[cfg(test)]
+mod dhcpv6_duid;
+#[cfg(test)]
+mod dhcpv6_msg;
+#[cfg(all(test, feature = "ra"))]
+mod dhcpv6_ra;
+#[cfg(all(test, feature = "client"))]
+mod dhcpv6_time;
+#[cfg(test)]
+mod error;
+#[cfg(test)]
+mod lease_builder;
+#[cfg(all(test, feature = "client"))]
+mod log_limit;
+#[cfg(all(test, feature = "client"))]
+mod memory_socket;
+#[cfg(all(test, feature = "pcap"))]
+mod pcap;
@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::{Ipv4Addr, SocketAddr};
+
+use crate::pcap::{
+    DhcpPcapWriter, BLOCK_TYPE_EPB, BLOCK_TYPE_IDB, BLOCK_TYPE_SHB,
+    BYTE_ORDER_MAGIC, EPB_FLAG_INBOUND, EPB_FLAG_OUTBOUND, IFACE_ID_ETHERNET,
+    IFACE_ID_IPV4, LINKTYPE_ETHERNET, LINKTYPE_IPV4, LINKTYPE_IPV6,
+};
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    u32::from_ne_bytes(data[pos..pos + 4].try_into().unwrap())
+}
+
+fn read_u16(data: &[u8], pos: usize) -> u16 {
+    u16::from_ne_bytes(data[pos..pos + 2].try_into().unwrap())
+}
+
+// Split pcapng file into (block type, body) after checking block lengths
+fn parse_blocks(data: &[u8]) -> Vec<(u32, &[u8])> {
+    let mut ret = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let block_type = read_u32(data, pos);
+        let total_len = read_u32(data, pos + 4) as usize;
+        assert_eq!(total_len % 4, 0, "Block not padded to 32 bits");
+        assert!(pos + total_len <= data.len(), "Truncated block");
+        assert_eq!(read_u32(data, pos + total_len - 4) as usize, total_len);
+        ret.push((block_type, &data[pos + 8..pos + total_len - 4]));
+        pos += total_len;
+    }
+    ret
+}
+
+// Check Enhanced Packet Block body, return captured data
+fn check_epb(body: &[u8], iface_id: u32, flag: u32) -> &[u8] {
+    assert_eq!(read_u32(body, 0), iface_id);
+    let cap_len = read_u32(body, 12) as usize;
+    assert_eq!(read_u32(body, 16) as usize, cap_len);
+    let padded_len = cap_len.div_ceil(4) * 4;
+    assert!(body[20 + cap_len..20 + padded_len].iter().all(|b| *b == 0));
+    let opts = &body[20 + padded_len..];
+    // epb_flags option followed by opt_endofopt
+    assert_eq!(read_u16(opts, 0), 2);
+    assert_eq!(read_u16(opts, 2), 4);
+    assert_eq!(read_u32(opts, 4), flag);
+    assert_eq!(&opts[8..], &[0, 0, 0, 0]);
+    &body[20..20 + cap_len]
+}
+
+#[test]
+fn test_pcap_writer() {
+    let path = std::env::temp_dir()
+        .join(format!("mozim_test_{}.pcapng", std::process::id()));
+    std::fs::remove_file(&path).ok();
+    let path_str = path.to_str().unwrap();
+
+    let writer = DhcpPcapWriter::open(Some(path_str)).unwrap().unwrap();
+    // Same path shares the same writer
+    let writer2 = DhcpPcapWriter::open(Some(path_str)).unwrap().unwrap();
+    assert!(std::sync::Arc::ptr_eq(&writer, &writer2));
+    // 5 bytes requiring 3 bytes padding
+    writer.write_eth(&[1, 2, 3, 4, 5], true);
+    writer.write_udp(
+        SocketAddr::new(Ipv4Addr::new(192, 0, 2, 99).into(), 68),
+        SocketAddr::new(Ipv4Addr::new(192, 0, 2, 1).into(), 67),
+        &[0xaa, 0xbb],
+        false,
+    );
+    drop(writer);
+    drop(writer2);
+
+    let data = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let blocks = parse_blocks(&data);
+    assert_eq!(
+        blocks.iter().map(|(t, _)| *t).collect::<Vec<u32>>(),
+        vec![
+            BLOCK_TYPE_SHB,
+            BLOCK_TYPE_IDB,
+            BLOCK_TYPE_IDB,
+            BLOCK_TYPE_IDB,
+            BLOCK_TYPE_EPB,
+            BLOCK_TYPE_EPB
+        ]
+    );
+
+    assert_eq!(read_u32(blocks[0].1, 0), BYTE_ORDER_MAGIC);
+    // Version 1.0
+    assert_eq!(read_u16(blocks[0].1, 4), 1);
+    assert_eq!(read_u16(blocks[0].1, 6), 0);
+
+    for ((_, body), link_type) in blocks[1..4].iter().zip([
+        LINKTYPE_ETHERNET,
+        LINKTYPE_IPV4,
+        LINKTYPE_IPV6,
+    ]) {
+        assert_eq!(read_u16(body, 0), link_type);
+    }
+
+    assert_eq!(
+        check_epb(blocks[4].1, IFACE_ID_ETHERNET, EPB_FLAG_INBOUND),
+        &[1, 2, 3, 4, 5]
+    );
+    // IPv4 header(20) + UDP header(8) + payload(2)
+    let pkg = check_epb(blocks[5].1, IFACE_ID_IPV4, EPB_FLAG_OUTBOUND);
+    assert_eq!(pkg.len(), 30);
+    assert_eq!(&pkg[12..16], &[192, 0, 2, 99]);
+    assert_eq!(&pkg[16..20], &[192, 0, 2, 1]);
+    assert_eq!(&pkg[20..22], &68u16.to_be_bytes());
+    assert_eq!(&pkg[28..], &[0xaa, 0xbb]);
+}